@@ -3,6 +3,8 @@
 //! This module defines the error types that can occur when parsing TCSS stylesheets
 //! or resolving variables within them.
 
+use std::path::PathBuf;
+
 use thiserror::Error;
 
 /// Errors that can occur during TCSS parsing and processing.
@@ -35,4 +37,28 @@ pub enum TcssError {
     /// An I/O error occurred while reading a stylesheet file.
     #[error("I/O error reading stylesheet")]
     Io(#[from] std::io::Error),
+
+    /// A stylesheet file (loaded directly or through `@import`) couldn't be
+    /// read.
+    #[error("cannot read stylesheet {}: {source}", path.display())]
+    Read {
+        /// The file, as resolved.
+        path: PathBuf,
+        /// The underlying error.
+        source: std::io::Error,
+    },
+
+    /// A stylesheet imports itself, directly or through other imports.
+    ///
+    /// The paths run from the first file in the cycle back to itself.
+    #[error("@import cycle: {}", display_paths(.0))]
+    ImportCycle(Vec<PathBuf>),
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(" -> ")
 }
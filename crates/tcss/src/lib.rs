@@ -59,6 +59,12 @@
 //! - `:active` - Widget is being pressed
 //! - `:disabled` - Widget is not interactive
 //!
+//! ### At-rules
+//! - `@media (min-width: 100) and (dark) { ... }` - Rules that apply only
+//!   for some terminal sizes, color depths or theme modes
+//! - `@import "base.tcss";` - Rules and variables of another file, resolved
+//!   relative to the importing file (see [`load_stylesheet`](parser::load_stylesheet))
+//!
 //! ## Not Yet Implemented
 //!
 //! - CSS comments (`/* */`)
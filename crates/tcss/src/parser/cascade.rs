@@ -3,15 +3,22 @@
 //! This module implements the CSS cascade algorithm for TCSS:
 //!
 //! - [`compute_style`]: Main entry point for computing a widget's final styles
+//! - [`cascade`]: The same, with `!important` declarations held back so they
+//!   can be applied above inline styles
+//! - [`matching_rules`]: The rules that apply to a widget, in cascade order
 //! - [`WidgetMeta`]: Widget metadata for selector matching
 //! - [`WidgetStates`]: Bitflags for widget pseudo-class states
 //!
 //! ## Cascade Algorithm
 //!
-//! 1. Find all rules whose selectors match the widget
-//! 2. Sort by specificity (IDs > classes > types), then source order
+//! 1. Find all rules whose selectors match the widget (and whose `@media`
+//!    query, if any, matches the stylesheet's terminal environment)
+//! 2. Sort by tier (widget defaults < app), then specificity
+//!    (IDs > classes > types), then source order
 //! 3. Apply declarations in order (later declarations override earlier)
-//! 4. Resolve theme variables to actual colors
+//! 4. Apply `!important` declarations in the same order, above everything
+//!    else (including inline styles when using [`cascade`])
+//! 5. Resolve theme variables to actual colors
 //!
 //! ## Selector Matching
 //!
@@ -20,32 +27,56 @@
 //! - Type selectors match `widget.type_name`
 //! - Class selectors match any class in `widget.classes`
 //! - ID selectors match `widget.id`
-//! - Pseudo-class selectors match `widget.states` (`:focus`, `:hover`, etc.)
-//! - Combinators traverse the ancestor chain
+//! - Pseudo-class selectors match `widget.states` (`:focus`, `:hover`,
+//!   `:focus-within`, `:empty`, etc.)
+//! - Structural pseudo-classes (`:first-child`, `:last-child`,
+//!   `:first-of-type`, `:odd`, `:even`, `:nth-child(an+b)`) match the
+//!   widget's position in `widget.siblings`
+//! - Attribute selectors (`[type=password]`, `[variant^=pri]`) match
+//!   `widget.attributes`
+//! - Descendant and child combinators traverse the ancestor chain
+//! - Sibling combinators (`+`, `~`) traverse `widget.siblings`
+
+use std::sync::Arc;
 
 use bitflags::bitflags;
 
 use crate::{
     parser::{
-        Combinator, ComplexSelector, Declaration, Rule, RuleItem, Selector, Specificity, StyleSheet,
+        Combinator, ComplexSelector, Declaration, MediaEnvironment, NthPattern, Rule, RuleItem,
+        Selector, SelectorList, SelectorPart, Specificity, StyleSheet,
     },
     types::{Border, ComputedStyle, RgbaColor, Theme},
 };
 
-#[derive(Clone, Copy, Debug)]
-struct SelectorContext {
-    is_dark: bool,
-    is_ansi: bool,
-    is_nocolor: bool,
+/// Theme flags that pseudo-classes like `:dark` and `:ansi` match against,
+/// and the terminal that `@media` queries test.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub(crate) struct SelectorContext {
+    pub(crate) is_dark: bool,
+    pub(crate) is_ansi: bool,
+    pub(crate) is_nocolor: bool,
+    pub(crate) media: MediaEnvironment,
 }
 
 impl SelectorContext {
-    fn from_theme(theme: &Theme) -> Self {
+    pub(crate) fn from_theme(theme: &Theme) -> Self {
         let name = theme.name.as_str();
         Self {
             is_dark: theme.is_dark,
             is_ansi: name.contains("ansi"),
             is_nocolor: name.contains("nocolor") || name.contains("no-color"),
+            media: MediaEnvironment::default(),
+        }
+    }
+
+    /// The number of colors styles can use: the terminal's, limited by
+    /// ANSI and no-color themes.
+    pub(crate) fn colors(&self) -> u32 {
+        match (self.is_nocolor, self.is_ansi) {
+            (true, _) => 1,
+            (false, true) => self.media.colors.min(16),
+            (false, false) => self.media.colors,
         }
     }
 }
@@ -79,6 +110,11 @@ bitflags! {
         const ACTIVE   = 0b0000_0100;
         /// Widget is disabled and not interactive
         const DISABLED = 0b0000_1000;
+        /// Widget or one of its descendants has keyboard focus.
+        /// Set by the style resolver.
+        const FOCUS_WITHIN = 0b0001_0000;
+        /// Widget has no children. Set by the style resolver.
+        const EMPTY        = 0b0010_0000;
     }
 }
 
@@ -100,27 +136,35 @@ pub struct WidgetMeta {
     pub classes: Vec<String>,
     /// The widget's current pseudo-class states (focus, hover, active, disabled).
     pub states: WidgetStates,
+    /// All children of the widget's parent in document order, this widget
+    /// included. Filled in by the style resolver for sibling combinators;
+    /// widgets leave it empty.
+    pub siblings: Arc<[WidgetMeta]>,
+    /// The widget's index in `siblings`.
+    pub sibling_index: usize,
+    /// Name/value pairs for attribute selectors (e.g., `("type", "password")`).
+    /// Filled in by the style resolver, like `siblings`.
+    pub attributes: Vec<(&'static str, String)>,
 }
 
 /// A rule that matched a widget, bundled with its priority information.
-#[derive(Debug)]
-struct MatchedRule<'a> {
-    specificity: Specificity,
-    source_order: usize,
-    rule: &'a Rule,
+#[derive(Debug, Clone, Copy)]
+pub struct MatchedRule<'a> {
+    /// Specificity of the selector that matched.
+    pub specificity: Specificity,
+    /// Index of the rule in the stylesheet.
+    pub source_order: usize,
+    /// The first selector in the rule's selector list that matched.
+    pub selector: &'a ComplexSelector,
+    /// The matched rule.
+    pub rule: &'a Rule,
 }
 
 impl WidgetMeta {
     /// Checks if this widget matches a simple selector.
     fn matches_selector(&self, selector: &Selector, ctx: &SelectorContext) -> bool {
         match selector {
-            Selector::Type(name) => {
-                if self.type_names.is_empty() {
-                    self.type_name == *name
-                } else {
-                    self.type_names.iter().any(|t| t == name)
-                }
-            }
+            Selector::Type(name) => self.has_type(name),
             Selector::Id(id) => self.id.as_ref() == Some(id),
             Selector::Class(class) => self.classes.contains(class),
             Selector::Universal => true,
@@ -129,21 +173,43 @@ impl WidgetMeta {
                 "hover" => self.states.contains(WidgetStates::HOVER),
                 "active" => self.states.contains(WidgetStates::ACTIVE),
                 "disabled" => self.states.contains(WidgetStates::DISABLED),
+                "focus-within" => self.states.contains(WidgetStates::FOCUS_WITHIN),
+                "empty" => self.states.contains(WidgetStates::EMPTY),
                 "dark" => ctx.is_dark,
                 "light" => !ctx.is_dark,
                 "ansi" => ctx.is_ansi,
                 "nocolor" => ctx.is_nocolor,
                 _ => false,
             },
+            // Positional; matched by `Element`, which knows the siblings
+            Selector::NthChild(_) => false,
             Selector::Parent => false, // Will be handled by the nesting flattener
-            Selector::Attribute(_name, _value) => {
-                // Placeholder: In a real TUI engine, we'd check the widget's
-                // internal attribute map (e.g., widget.get_attr("type") == "text")
-                false
-            }
+            Selector::Attribute(attribute) => attribute.matches(self.attribute(&attribute.name)),
+        }
+    }
+
+    /// Whether the widget is of type `name` or a subtype of it.
+    pub fn has_type(&self, name: &str) -> bool {
+        if self.type_names.is_empty() {
+            self.type_name == name
+        } else {
+            self.type_names.contains(&name)
         }
     }
 
+    /// The value of the named attribute, if the widget has it.
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attribute, _)| *attribute == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The siblings before this widget, in document order.
+    pub fn previous_siblings(&self) -> &[WidgetMeta] {
+        self.siblings.get(..self.sibling_index).unwrap_or(&[])
+    }
+
     /// Checks if this widget matches a complex selector given its ancestors.
     /// Ancestors should be ordered from immediate parent to root.
     fn matches_complex(
@@ -152,15 +218,40 @@ impl WidgetMeta {
         ancestors: &[WidgetMeta],
         ctx: &SelectorContext,
     ) -> bool {
-        if complex.parts.is_empty() {
-            return false;
-        }
+        let element = Element {
+            meta: self,
+            siblings: &self.siblings,
+            index: self.sibling_index,
+            ancestors,
+        };
+        !complex.parts.is_empty() && element.matches(&complex.parts, ctx)
+    }
+}
 
-        // The rightmost part must match the widget itself.
-        let parts = &complex.parts;
-        let mut current_part_idx = parts.len() - 1;
+/// A widget's position in the tree, as seen by the selector matcher.
+///
+/// Sibling metas carry no sibling lists of their own, so the element's
+/// place among its parent's children is tracked here.
+#[derive(Clone, Copy)]
+struct Element<'a> {
+    meta: &'a WidgetMeta,
+    /// All children of the parent, this element included (empty if unknown).
+    siblings: &'a [WidgetMeta],
+    /// The element's index in `siblings`.
+    index: usize,
+    /// Ancestors from immediate parent to root.
+    ancestors: &'a [WidgetMeta],
+}
 
-        if !parts[current_part_idx]
+impl<'a> Element<'a> {
+    /// Checks if the rightmost part matches this element and the remaining
+    /// parts match the elements its combinators lead to, backtracking over
+    /// every candidate.
+    fn matches(&self, parts: &[SelectorPart], ctx: &SelectorContext) -> bool {
+        let Some((last, rest)) = parts.split_last() else {
+            return true;
+        };
+        if !last
             .compound
             .selectors
             .iter()
@@ -168,111 +259,248 @@ impl WidgetMeta {
         {
             return false;
         }
-
-        // If that was the only part, we matched.
-        if parts.len() == 1 {
+        // A part's combinator relates it to the part on its right.
+        let Some(previous) = rest.last() else {
             return true;
+        };
+        match previous.combinator {
+            Combinator::Child => self.parent().is_some_and(|p| p.matches(rest, ctx)),
+            Combinator::Descendant | Combinator::None => {
+                (0..self.ancestors.len()).any(|i| self.ancestor(i).matches(rest, ctx))
+            }
+            Combinator::AdjacentSibling => self
+                .position()
+                .and_then(|_| self.index.checked_sub(1))
+                .is_some_and(|i| self.sibling(i).matches(rest, ctx)),
+            Combinator::GeneralSibling => (0..self.index.min(self.siblings.len()))
+                .rev()
+                .any(|i| self.sibling(i).matches(rest, ctx)),
+        }
+    }
+
+    /// Checks a simple selector, resolving structural pseudo-classes from
+    /// the element's position.
+    fn matches_selector(&self, selector: &Selector, ctx: &SelectorContext) -> bool {
+        let structural = match selector {
+            Selector::NthChild(nth) => return self.position().is_some_and(|p| nth.matches(p)),
+            Selector::PseudoClass(name) => name.as_str(),
+            _ => return self.meta.matches_selector(selector, ctx),
+        };
+        let same_type = |meta: &WidgetMeta| meta.type_name == self.meta.type_name;
+        match structural {
+            "first-child" => self.position() == Some(1),
+            "last-child" => self.position() == Some(self.siblings.len()),
+            "first-of-type" => {
+                self.position().is_some() && !self.siblings[..self.index].iter().any(same_type)
+            }
+            "odd" => self.position().is_some_and(|p| NthPattern::ODD.matches(p)),
+            "even" => self.position().is_some_and(|p| NthPattern::EVEN.matches(p)),
+            _ => self.meta.matches_selector(selector, ctx),
         }
+    }
 
-        // Match remaining parts against ancestors.
-        let mut ancestor_idx = 0;
-        current_part_idx -= 1;
+    /// The element's 1-based position among its siblings, if known.
+    fn position(&self) -> Option<usize> {
+        (self.index < self.siblings.len()).then_some(self.index + 1)
+    }
 
-        while current_part_idx < parts.len() && ancestor_idx < ancestors.len() {
-            let part = &parts[current_part_idx];
-            let ancestor = &ancestors[ancestor_idx];
+    fn parent(&self) -> Option<Element<'a>> {
+        (!self.ancestors.is_empty()).then(|| self.ancestor(0))
+    }
 
-            let matches = part
-                .compound
-                .selectors
-                .iter()
-                .all(|s| ancestor.matches_selector(s, ctx));
+    fn ancestor(&self, index: usize) -> Element<'a> {
+        let meta = &self.ancestors[index];
+        Element {
+            meta,
+            siblings: &meta.siblings,
+            index: meta.sibling_index,
+            ancestors: &self.ancestors[index + 1..],
+        }
+    }
 
-            match part.combinator {
-                Combinator::Child => {
-                    if matches {
-                        if current_part_idx == 0 {
-                            return true;
-                        }
-                        current_part_idx -= 1;
-                    } else {
-                        return false;
-                    }
-                    ancestor_idx += 1;
+    fn sibling(&self, index: usize) -> Element<'a> {
+        Element {
+            meta: &self.siblings[index],
+            siblings: self.siblings,
+            index,
+            ancestors: self.ancestors,
+        }
+    }
+}
+
+/// Returns the rules that apply to a widget, in the order the cascade
+/// applies them (lowest priority first, so the last rule wins).
+///
+/// Ancestors should be ordered from immediate parent to root.
+pub fn matching_rules<'a>(
+    widget: &WidgetMeta,
+    ancestors: &[WidgetMeta],
+    stylesheet: &'a StyleSheet,
+    theme: &Theme,
+) -> Vec<MatchedRule<'a>> {
+    let ctx = SelectorContext {
+        media: stylesheet.environment(),
+        ..SelectorContext::from_theme(theme)
+    };
+    let rules = stylesheet.rules();
+
+    let matched = stylesheet
+        .cache
+        .matched(rules, widget, ancestors, ctx, |candidates| {
+            // 1. Find all matching rules among the index's candidates
+            let mut matched: Vec<(usize, usize, Specificity)> = Vec::new();
+            for &idx in candidates {
+                let rule = &rules[idx];
+                if let Some(media) = &rule.media
+                    && !media.matches(&ctx)
+                {
+                    continue;
                 }
-                Combinator::Descendant | Combinator::None => {
-                    if matches {
-                        if current_part_idx == 0 {
-                            return true;
-                        }
-                        current_part_idx -= 1;
-                    }
-                    ancestor_idx += 1;
+                // Scoped rules only apply inside a widget of the scope type
+                if let Some(scope) = &rule.scope
+                    && !std::iter::once(widget)
+                        .chain(ancestors)
+                        .any(|meta| meta.has_type(scope))
+                {
+                    continue;
                 }
-                // FIX: Add explicit return to satisfy type checker
-                Combinator::AdjacentSibling | Combinator::GeneralSibling => {
-                    return false;
+                let selectors = &rule.selectors.selectors;
+                // Once one selector in a rule matches, the whole rule applies.
+                if let Some((selector_idx, complex)) = selectors
+                    .iter()
+                    .enumerate()
+                    .find(|(_, complex)| widget.matches_complex(complex, ancestors, &ctx))
+                {
+                    matched.push((idx, selector_idx, complex.specificity()));
                 }
             }
-        }
 
-        current_part_idx == usize::MAX
-            || current_part_idx == 0
-                && ancestor_idx < ancestors.len()
-                && parts[0]
-                    .compound
-                    .selectors
-                    .iter()
-                    .all(|s| ancestors[ancestor_idx].matches_selector(s, ctx))
-    }
+            // 2. Sort by Tier, then Specificity, then Source Order
+            matched.sort_by(|a, b| {
+                (rules[a.0].tier, a.2)
+                    .cmp(&(rules[b.0].tier, b.2))
+                    .then(a.0.cmp(&b.0))
+            });
+            matched
+                .into_iter()
+                .map(|(idx, selector_idx, _)| (idx, selector_idx))
+                .collect()
+        });
+
+    matched
+        .iter()
+        .map(|&(idx, selector_idx)| {
+            let rule = &rules[idx];
+            let selector = &rule.selectors.selectors[selector_idx];
+            MatchedRule {
+                specificity: selector.specificity(),
+                source_order: idx,
+                selector,
+                rule,
+            }
+        })
+        .collect()
+}
+
+/// Whether a widget matches any selector in `selectors`, e.g. to find the
+/// widget a query or message is aimed at.
+///
+/// Ancestors should be ordered from immediate parent to root. `@media`
+/// queries and rule scopes don't apply, since there's no rule.
+pub fn matches_selectors(
+    widget: &WidgetMeta,
+    ancestors: &[WidgetMeta],
+    selectors: &SelectorList,
+    theme: &Theme,
+) -> bool {
+    let ctx = SelectorContext::from_theme(theme);
+    selectors
+        .selectors
+        .iter()
+        .any(|complex| widget.matches_complex(complex, ancestors, &ctx))
 }
 
 /// The core cascade function.
+///
+/// `!important` declarations are applied last, above all normal ones. Use
+/// [`cascade`] to apply an inline style in between.
 pub fn compute_style(
     widget: &WidgetMeta,
     ancestors: &[WidgetMeta],
     stylesheet: &StyleSheet,
     theme: &Theme,
 ) -> ComputedStyle {
-    let mut matched_rules = Vec::new();
-    let ctx = SelectorContext::from_theme(theme);
+    let mut cascaded = cascade(widget, ancestors, stylesheet, theme);
+    let mut computed = std::mem::take(&mut cascaded.style);
+    cascaded.apply_important(&mut computed, theme);
+    computed
+}
 
-    // 1. Find all matching rules
-    for (idx, rule) in stylesheet.rules.iter().enumerate() {
-        for complex in &rule.selectors.selectors {
-            if widget.matches_complex(complex, ancestors, &ctx) {
-                matched_rules.push(MatchedRule {
-                    specificity: complex.specificity(),
-                    source_order: idx,
-                    rule,
-                });
-                // Once one selector in a rule matches, the whole rule applies.
-                break;
-            }
+/// A widget's cascaded style, split into the normal and important tiers.
+#[derive(Debug, Clone)]
+pub struct Cascaded<'a> {
+    /// The style from normal declarations.
+    pub style: ComputedStyle,
+    /// `!important` declarations, in cascade order.
+    pub important: Vec<&'a Declaration>,
+    /// The stylesheet the declarations came from, which resolves their
+    /// variables.
+    stylesheet: &'a StyleSheet,
+}
+
+impl Cascaded<'_> {
+    /// Applies the important tier on top of `style`.
+    ///
+    /// Call this after any inline style, so important declarations win
+    /// over it as in CSS.
+    pub fn apply_important(&self, style: &mut ComputedStyle, theme: &Theme) {
+        for decl in &self.important {
+            apply_declaration(style, decl, self.stylesheet, theme);
         }
     }
+}
 
-    // 2. Sort by Specificity, then Source Order
-    matched_rules.sort_by(|a, b| {
-        a.specificity
-            .cmp(&b.specificity)
-            .then(a.source_order.cmp(&b.source_order))
-    });
+/// Runs the cascade, holding back `!important` declarations.
+///
+/// The caller applies its own tiers (inheritance, inline styles) to
+/// [`Cascaded::style`] and then calls [`Cascaded::apply_important`].
+pub fn cascade<'a>(
+    widget: &WidgetMeta,
+    ancestors: &[WidgetMeta],
+    stylesheet: &'a StyleSheet,
+    theme: &Theme,
+) -> Cascaded<'a> {
+    let matched_rules = matching_rules(widget, ancestors, stylesheet, theme);
 
     // 3. Apply declarations in order (Highest priority wins)
-    let mut computed = ComputedStyle::default();
+    let mut cascaded = Cascaded {
+        style: ComputedStyle::default(),
+        important: Vec::new(),
+        stylesheet,
+    };
     for matched in matched_rules {
         for item in &matched.rule.items {
-            if let RuleItem::Declaration(decl) = item {
-                apply_declaration(&mut computed, decl, theme);
+            match item {
+                RuleItem::Declaration(decl) if decl.is_important() => {
+                    cascaded.important.push(decl);
+                }
+                RuleItem::Declaration(decl) => {
+                    apply_declaration(&mut cascaded.style, decl, stylesheet, theme)
+                }
+                RuleItem::NestedRule(_) => {}
             }
         }
     }
 
-    computed
+    cascaded
 }
 
-fn apply_declaration(style: &mut ComputedStyle, decl: &Declaration, theme: &Theme) {
+fn apply_declaration(
+    style: &mut ComputedStyle,
+    decl: &Declaration,
+    stylesheet: &StyleSheet,
+    theme: &Theme,
+) {
     match decl {
         Declaration::Color(c) => {
             let resolved = resolve_theme_color(c, theme);
@@ -599,6 +827,12 @@ fn apply_declaration(style: &mut ComputedStyle, decl: &Declaration, theme: &Them
             style.offset_y = Some(y.clone());
         }
 
+        Declaration::Important(decl) => apply_declaration(style, decl, stylesheet, theme),
+        Declaration::Variable(variable) => {
+            if let Some(decl) = stylesheet.resolve_declaration(variable) {
+                apply_declaration(style, &decl, stylesheet, theme);
+            }
+        }
         Declaration::Unknown(_) => {}
     }
 }
@@ -687,6 +921,7 @@ Container {
             id: Some("vertical-layout".to_string()),
             classes: vec![],
             states: WidgetStates::empty(),
+            ..Default::default()
         };
 
         let ancestors = vec![];
@@ -721,6 +956,7 @@ Label {
             id: None,
             classes: vec![],
             states: WidgetStates::empty(),
+            ..Default::default()
         };
 
         let ancestors = vec![];
@@ -763,6 +999,7 @@ Label {
             id: Some("zero-opacity".to_string()),
             classes: vec![],
             states: WidgetStates::empty(),
+            ..Default::default()
         };
 
         let ancestors = vec![];
@@ -817,6 +1054,7 @@ Label {
             id: Some("zero-opacity".to_string()),
             classes: vec![],
             states: WidgetStates::empty(),
+            ..Default::default()
         };
 
         let ancestors = vec![];
@@ -844,4 +1082,385 @@ Label {
             color.b
         );
     }
+
+    #[test]
+    fn test_matching_rules_in_cascade_order() {
+        let css = r#"
+#save { color: red; }
+Button.primary { color: blue; }
+Button { color: green; }
+Label { color: white; }
+Button { width: 10; }
+"#;
+
+        let stylesheet = parse_stylesheet(css).expect("Failed to parse CSS");
+        let theme = Theme::new("test", false);
+        let widget = WidgetMeta {
+            type_name: "Button",
+            type_names: vec!["Button", "Widget", "DOMNode"],
+            id: Some("save".to_string()),
+            classes: vec!["primary".to_string()],
+            states: WidgetStates::empty(),
+            ..Default::default()
+        };
+
+        let matched = matching_rules(&widget, &[], &stylesheet, &theme);
+        let selectors: Vec<String> = matched.iter().map(|m| m.selector.to_string()).collect();
+
+        assert_eq!(
+            selectors,
+            ["Button", "Button", "Button.primary", "#save"],
+            "rules should be ordered by specificity, then source order"
+        );
+        assert_eq!(matched[0].source_order, 2);
+        assert_eq!(matched[1].source_order, 4);
+    }
+
+    fn meta(type_name: &'static str, id: Option<&str>) -> WidgetMeta {
+        WidgetMeta {
+            type_name,
+            type_names: vec![type_name, "Widget", "DOMNode"],
+            id: id.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    /// Attach a shared sibling list to each meta, as the style resolver does.
+    fn siblings(metas: Vec<WidgetMeta>) -> Vec<WidgetMeta> {
+        let shared: Arc<[WidgetMeta]> = metas.clone().into();
+        metas
+            .into_iter()
+            .enumerate()
+            .map(|(index, mut meta)| {
+                meta.siblings = shared.clone();
+                meta.sibling_index = index;
+                meta
+            })
+            .collect()
+    }
+
+    fn matches(css: &str, widget: &WidgetMeta, ancestors: &[WidgetMeta]) -> bool {
+        let stylesheet = parse_stylesheet(css).expect("Failed to parse CSS");
+        let theme = Theme::new("test", true);
+        !matching_rules(widget, ancestors, &stylesheet, &theme).is_empty()
+    }
+
+    #[test]
+    fn test_adjacent_sibling_combinator() {
+        let row = siblings(vec![
+            meta("Label", None),
+            meta("Input", Some("first")),
+            meta("Input", Some("second")),
+        ]);
+
+        assert!(matches("Label + Input { color: red; }", &row[1], &[]));
+        assert!(!matches("Label + Input { color: red; }", &row[2], &[]));
+        assert!(matches("Input + Input { color: red; }", &row[2], &[]));
+        assert!(!matches("Label + Input { color: red; }", &row[0], &[]));
+    }
+
+    #[test]
+    fn test_general_sibling_combinator() {
+        let row = siblings(vec![
+            meta("Label", None),
+            meta("Input", Some("first")),
+            meta("Input", Some("second")),
+        ]);
+
+        assert!(matches("Label ~ Input { color: red; }", &row[1], &[]));
+        assert!(matches("Label ~ Input { color: red; }", &row[2], &[]));
+        assert!(matches(
+            "Label ~ #first ~ #second { color: red; }",
+            &row[2],
+            &[]
+        ));
+        assert!(!matches("#second ~ Input { color: red; }", &row[1], &[]));
+    }
+
+    #[test]
+    fn test_matches_selectors_without_a_stylesheet() {
+        let (_, selectors) = crate::parser::parse_selector_list("Form > #second, Label").unwrap();
+        let theme = Theme::new("test", true);
+        let form = [meta("Form", None)];
+        let row = siblings(vec![
+            meta("Input", Some("first")),
+            meta("Input", Some("second")),
+        ]);
+
+        assert!(matches_selectors(&row[1], &form, &selectors, &theme));
+        assert!(!matches_selectors(&row[0], &form, &selectors, &theme));
+        assert!(!matches_selectors(&row[1], &[], &selectors, &theme));
+        assert!(matches_selectors(
+            &meta("Label", None),
+            &[],
+            &selectors,
+            &theme
+        ));
+    }
+
+    #[test]
+    fn test_sibling_combinators_without_siblings_never_match() {
+        let input = meta("Input", None);
+        assert!(!matches("Label + Input { color: red; }", &input, &[]));
+        assert!(!matches("Label ~ Input { color: red; }", &input, &[]));
+    }
+
+    #[test]
+    fn test_sibling_combinators_with_child_and_descendant() {
+        // Screen > [Label, Horizontal#form > [Label, Input]]
+        let screen = meta("Screen", None);
+        let top = siblings(vec![meta("Label", None), meta("Horizontal", Some("form"))]);
+        let form_children = siblings(vec![meta("Label", None), meta("Input", None)]);
+        let input = &form_children[1];
+        let ancestors = [top[1].clone(), screen];
+
+        assert!(matches(
+            "#form > Label + Input { color: red; }",
+            input,
+            &ancestors
+        ));
+        assert!(matches(
+            "Screen Label ~ Input { color: red; }",
+            input,
+            &ancestors
+        ));
+        // The sibling part can also sit on an ancestor
+        assert!(matches(
+            "Label + Horizontal Input { color: red; }",
+            input,
+            &ancestors
+        ));
+        assert!(matches(
+            "Label ~ #form > Input { color: red; }",
+            input,
+            &ancestors
+        ));
+        assert!(!matches(
+            "Input + Horizontal Input { color: red; }",
+            input,
+            &ancestors
+        ));
+        assert!(!matches(
+            "Screen > Label + Input { color: red; }",
+            input,
+            &ancestors
+        ));
+    }
+
+    #[test]
+    fn test_structural_pseudo_classes() {
+        let row = siblings(vec![
+            meta("Label", Some("title")),
+            meta("Input", Some("one")),
+            meta("Input", Some("two")),
+            meta("Button", Some("three")),
+        ]);
+        let matching = |css: &str| {
+            row.iter()
+                .filter(|m| matches(css, m, &[]))
+                .map(|m| m.id.as_deref().unwrap())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(matching(":first-child { color: red; }"), ["title"]);
+        assert_eq!(matching(":last-child { color: red; }"), ["three"]);
+        assert_eq!(
+            matching(":first-of-type { color: red; }"),
+            ["title", "one", "three"]
+        );
+        assert_eq!(matching(":odd { color: red; }"), ["title", "two"]);
+        assert_eq!(matching(":even { color: red; }"), ["one", "three"]);
+        assert_eq!(
+            matching(":nth-child(-n+2) { color: red; }"),
+            ["title", "one"]
+        );
+        assert_eq!(matching("Input:nth-child(3) { color: red; }"), ["two"]);
+        assert_eq!(
+            matching("Label:first-child ~ Input:last-child { color: red; }"),
+            Vec::<&str>::new()
+        );
+        assert_eq!(matching(":first-child + Input { color: red; }"), ["one"]);
+    }
+
+    #[test]
+    fn test_structural_pseudo_classes_need_sibling_info() {
+        let lone = meta("Label", None);
+        assert!(!matches(":first-child { color: red; }", &lone, &[]));
+        assert!(!matches(":odd { color: red; }", &lone, &[]));
+    }
+
+    #[test]
+    fn test_structural_pseudo_classes_on_ancestors() {
+        let rows = siblings(vec![meta("Row", None), meta("Row", None)]);
+        let cell = meta("Label", None);
+
+        assert!(matches("Row:even Label { color: red; }", &cell, &rows[1..]));
+        assert!(!matches(
+            "Row:even Label { color: red; }",
+            &cell,
+            &rows[..1]
+        ));
+    }
+
+    #[test]
+    fn test_focus_within_and_empty_states() {
+        let mut form = meta("Container", Some("form"));
+        assert!(!matches(":focus-within { color: red; }", &form, &[]));
+        form.states |= WidgetStates::FOCUS_WITHIN;
+        assert!(matches(":focus-within { color: red; }", &form, &[]));
+
+        let mut label = meta("Label", None);
+        assert!(!matches("Label:empty { color: red; }", &label, &[]));
+        label.states |= WidgetStates::EMPTY;
+        assert!(matches("Label:empty { color: red; }", &label, &[]));
+    }
+
+    #[test]
+    fn test_stylesheet_reports_sibling_and_focus_within_usage() {
+        let plain = parse_stylesheet("Label { color: red; }").unwrap();
+        assert!(!plain.needs_sibling_context());
+        assert!(!plain.uses_pseudo_class("focus-within"));
+
+        let structural = parse_stylesheet("Row:odd { color: red; }").unwrap();
+        assert!(structural.needs_sibling_context());
+
+        let nth = parse_stylesheet("Row:nth-child(3) { color: red; }").unwrap();
+        assert!(nth.needs_sibling_context());
+
+        let focus = parse_stylesheet("#form:focus-within { color: red; }").unwrap();
+        assert!(focus.uses_pseudo_class("focus-within"));
+        assert!(!focus.needs_sibling_context());
+    }
+
+    #[test]
+    fn test_attribute_selectors_match_widget_attributes() {
+        let mut input = meta("Input", None);
+        input.attributes = vec![("type", "password".to_string())];
+
+        assert!(matches("Input[type=password] { color: red; }", &input, &[]));
+        assert!(matches("[type] { color: red; }", &input, &[]));
+        assert!(matches("Input[type^=pass] { color: red; }", &input, &[]));
+        assert!(matches("Input[type$=word] { color: red; }", &input, &[]));
+        assert!(matches("Input[type*=ssw] { color: red; }", &input, &[]));
+        assert!(!matches("Input[type=text] { color: red; }", &input, &[]));
+        assert!(!matches("Input[value] { color: red; }", &input, &[]));
+        assert!(!matches(
+            "[type] { color: red; }",
+            &meta("Input", None),
+            &[]
+        ));
+    }
+
+    #[test]
+    fn test_attribute_selector_outranks_type() {
+        let stylesheet =
+            parse_stylesheet("Button[variant=error] { color: red; } Button { color: blue; }")
+                .unwrap();
+        let theme = Theme::new("test", true);
+        let mut button = meta("Button", None);
+        button.attributes = vec![("variant", "error".to_string())];
+
+        let style = compute_style(&button, &[], &stylesheet, &theme);
+        assert_eq!(style.color.map(|c| c.r), Some(255));
+    }
+
+    #[test]
+    fn test_matching_rules_reports_first_matching_selector() {
+        let stylesheet = parse_stylesheet("Label, #save.primary, Button { color: red; }").unwrap();
+        let theme = Theme::new("test", true);
+        let mut button = meta("Button", Some("save"));
+        button.classes = vec!["primary".to_string()];
+
+        let matched = matching_rules(&button, &[], &stylesheet, &theme);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].selector.to_string(), "#save.primary");
+        assert_eq!(matched[0].specificity.ids, 1);
+    }
+
+    #[test]
+    fn test_matching_rules_sees_rules_added_after_first_use() {
+        let mut stylesheet = parse_stylesheet("Label { color: red; }").unwrap();
+        let theme = Theme::new("test", true);
+        let label = meta("Label", Some("title"));
+        assert_eq!(matching_rules(&label, &[], &stylesheet, &theme).len(), 1);
+
+        let extra = parse_stylesheet("#title { color: blue; }").unwrap();
+        stylesheet.rules_mut().extend(extra.into_rules());
+        let matched = matching_rules(&label, &[], &stylesheet, &theme);
+        assert_eq!(matched.len(), 2);
+        assert_eq!(matched[1].source_order, 1);
+    }
+
+    #[test]
+    fn test_matching_rules_memo_tracks_states_and_theme() {
+        let stylesheet =
+            parse_stylesheet("Label:focus { color: red; } Label:dark { color: blue; }").unwrap();
+        let dark = Theme::new("dark", true);
+        let light = Theme::new("light", false);
+        let mut label = meta("Label", None);
+
+        assert_eq!(matching_rules(&label, &[], &stylesheet, &dark).len(), 1);
+        assert_eq!(matching_rules(&label, &[], &stylesheet, &light).len(), 0);
+        label.states = WidgetStates::FOCUS;
+        assert_eq!(matching_rules(&label, &[], &stylesheet, &light).len(), 1);
+        assert_eq!(matching_rules(&label, &[], &stylesheet, &dark).len(), 2);
+    }
+
+    fn tiered(default_css: &str, scope: Option<&str>, app_css: &str) -> StyleSheet {
+        let mut stylesheet = parse_stylesheet(default_css)
+            .unwrap()
+            .into_default_tier(scope);
+        stylesheet.merge(parse_stylesheet(app_css).unwrap());
+        stylesheet
+    }
+
+    fn width(style: &ComputedStyle) -> Option<f64> {
+        style.width.as_ref().map(|width| width.value)
+    }
+
+    #[test]
+    fn test_default_tier_ranks_below_app_rules() {
+        let stylesheet = tiered("#save.primary { width: 5; }", None, "Button { width: 10; }");
+        let theme = Theme::new("test", true);
+        let mut button = meta("Button", Some("save"));
+        button.classes = vec!["primary".to_string()];
+
+        let matched = matching_rules(&button, &[], &stylesheet, &theme);
+        assert_eq!(matched[0].selector.to_string(), "#save.primary");
+        let style = compute_style(&button, &[], &stylesheet, &theme);
+        assert_eq!(width(&style), Some(10.0));
+    }
+
+    #[test]
+    fn test_default_tier_important_beats_app_rules() {
+        let stylesheet = tiered(
+            "Button { width: 5 !important; }",
+            None,
+            "#save { width: 10; }",
+        );
+        let theme = Theme::new("test", true);
+        let style = compute_style(&meta("Button", Some("save")), &[], &stylesheet, &theme);
+        assert_eq!(width(&style), Some(5.0));
+    }
+
+    #[test]
+    fn test_scoped_default_rules_stay_inside_their_widget() {
+        let stylesheet = tiered("Label { width: 5; }", Some("Card"), "");
+        let theme = Theme::new("test", true);
+        let label = meta("Label", None);
+
+        let inside = [meta("Container", None), meta("Card", None)];
+        let style = compute_style(&label, &inside, &stylesheet, &theme);
+        assert_eq!(width(&style), Some(5.0));
+
+        let outside = [meta("Container", None)];
+        let style = compute_style(&label, &outside, &stylesheet, &theme);
+        assert_eq!(width(&style), None);
+
+        let style = compute_style(&meta("Card", None), &[], &stylesheet, &theme);
+        assert_eq!(width(&style), None);
+        let stylesheet = tiered("Card { width: 5; }", Some("Card"), "");
+        let style = compute_style(&meta("Card", None), &[], &stylesheet, &theme);
+        assert_eq!(width(&style), Some(5.0));
+    }
 }
@@ -43,7 +43,7 @@ pub fn flatten_stylesheet(raw_rules: Vec<Rule>) -> StyleSheet {
     for rule in raw_rules {
         flatten_rule(&rule, None, &mut flat_rules);
     }
-    StyleSheet { rules: flat_rules }
+    StyleSheet::new(flat_rules)
 }
 
 fn flatten_rule(rule: &Rule, parent_selectors: Option<&[ComplexSelector]>, output: &mut Vec<Rule>) {
@@ -59,6 +59,10 @@ fn flatten_rule(rule: &Rule, parent_selectors: Option<&[ComplexSelector]>, outpu
         output.push(Rule {
             selectors: rule.selectors.clone(),
             items: current_decls,
+            tier: rule.tier,
+            scope: rule.scope.clone(),
+            media: rule.media.clone(),
+            origin: rule.origin.clone(),
         });
     }
 
@@ -70,6 +74,7 @@ fn flatten_rule(rule: &Rule, parent_selectors: Option<&[ComplexSelector]>, outpu
 
             let mut merged = nested.clone();
             merged.selectors = SelectorList::new(combined);
+            merged.media = rule.media.clone();
             // Recursively flatten with the new combined selectors as the parent context
             flatten_rule(&merged, Some(&merged.selectors.selectors), output);
         }
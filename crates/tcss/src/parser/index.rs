@@ -0,0 +1,404 @@
+//! Rule lookup for the cascade.
+//!
+//! Matching every selector in a stylesheet against every widget is the
+//! cascade's hot path: the widget `default_css` strings alone add up to
+//! hundreds of rules. Two structures cut that down:
+//!
+//! - [`RuleIndex`]: buckets rules by the rightmost compound of each of their
+//!   selectors (its id, else a class, else the type, else "universal"), so
+//!   only rules that could possibly match a widget are tried
+//! - A memo of matched rule sets, keyed by everything matching looks at
+//!   (the widget's and its ancestors' metadata, the theme flags and, for
+//!   stylesheets with `@media` rules, the terminal environment), so a
+//!   widget restyled with unchanged classes and states skips matching
+//!   entirely
+//!
+//! Both live in a [`RuleCache`] owned by the [`StyleSheet`](super::StyleSheet),
+//! which drops it whenever its methods change the rules. They are rebuilt on
+//! first use, and when the number of rules changes.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use crate::parser::cascade::{SelectorContext, WidgetMeta};
+use crate::parser::{ComplexSelector, Rule, Selector};
+
+/// Memoized widget contexts kept before the memo is cleared.
+const MEMO_CAPACITY: usize = 4096;
+
+/// A matched rule as `(rule index, selector index within the rule)`, in
+/// cascade order.
+pub(crate) type MatchedIndices = Arc<[(usize, usize)]>;
+
+/// Rules bucketed by the key of their selectors' rightmost compounds.
+#[derive(Debug, Default)]
+pub(crate) struct RuleIndex {
+    /// Number of rules indexed, to notice rules added or removed through
+    /// the public `rules` field.
+    rule_count: usize,
+    by_id: HashMap<String, Vec<usize>>,
+    by_class: HashMap<String, Vec<usize>>,
+    by_type: HashMap<String, Vec<usize>>,
+    /// Rules with a selector whose rightmost compound has no id, class or
+    /// type (`*`, `:focus`, `[type=text]`...).
+    universal: Vec<usize>,
+    /// Whether any selector looks at siblings.
+    pub(crate) uses_siblings: bool,
+    /// Whether any rule has an `@media` query.
+    uses_media: bool,
+    /// Every pseudo-class any selector uses.
+    pseudo_classes: HashSet<String>,
+}
+
+impl RuleIndex {
+    pub(crate) fn new(rules: &[Rule]) -> Self {
+        let mut index = RuleIndex {
+            rule_count: rules.len(),
+            ..Default::default()
+        };
+        for (rule_index, rule) in rules.iter().enumerate() {
+            index.uses_media |= rule.media.is_some();
+            for selector in &rule.selectors.selectors {
+                index.uses_siblings |= selector.uses_siblings();
+                index.pseudo_classes.extend(
+                    selector
+                        .parts
+                        .iter()
+                        .flat_map(|part| &part.compound.selectors)
+                        .filter_map(|s| match s {
+                            Selector::PseudoClass(name) => Some(name.clone()),
+                            _ => None,
+                        }),
+                );
+                let bucket = match rightmost_key(selector) {
+                    Some(Selector::Id(id)) => index.by_id.entry(id.clone()).or_default(),
+                    Some(Selector::Class(class)) => {
+                        index.by_class.entry(class.clone()).or_default()
+                    }
+                    Some(Selector::Type(name)) => index.by_type.entry(name.clone()).or_default(),
+                    _ => &mut index.universal,
+                };
+                // A rule lands in a bucket once even if several of its
+                // selectors share the key
+                if bucket.last() != Some(&rule_index) {
+                    bucket.push(rule_index);
+                }
+            }
+        }
+        index
+    }
+
+    /// Indices of the rules that may match `widget`, ascending.
+    ///
+    /// Every rule left out has no selector whose rightmost compound could
+    /// match the widget.
+    pub(crate) fn candidates(&self, widget: &WidgetMeta) -> Vec<usize> {
+        let single_type = [widget.type_name];
+        let type_names: &[&str] = if widget.type_names.is_empty() {
+            &single_type
+        } else {
+            &widget.type_names
+        };
+
+        let mut candidates = self.universal.clone();
+        let mut extend = |bucket: Option<&Vec<usize>>| {
+            if let Some(bucket) = bucket {
+                candidates.extend_from_slice(bucket);
+            }
+        };
+        if let Some(id) = &widget.id {
+            extend(self.by_id.get(id));
+        }
+        for class in &widget.classes {
+            extend(self.by_class.get(class));
+        }
+        for name in type_names {
+            extend(self.by_type.get(*name));
+        }
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+
+    /// Whether any selector uses the pseudo-class `name`.
+    pub(crate) fn uses_pseudo_class(&self, name: &str) -> bool {
+        self.pseudo_classes.contains(name)
+    }
+}
+
+/// The most selective simple selector of the rightmost compound: an id,
+/// else a class, else a type.
+fn rightmost_key(selector: &ComplexSelector) -> Option<&Selector> {
+    let compound = &selector.parts.last()?.compound;
+    let find = |wanted: fn(&Selector) -> bool| compound.selectors.iter().find(|s| wanted(s));
+    find(|s| matches!(s, Selector::Id(_)))
+        .or_else(|| find(|s| matches!(s, Selector::Class(_))))
+        .or_else(|| find(|s| matches!(s, Selector::Type(_))))
+}
+
+/// A memoized match result and the context it was computed for.
+struct MemoEntry {
+    widget: WidgetMeta,
+    ancestors: Vec<WidgetMeta>,
+    ctx: SelectorContext,
+    matched: MatchedIndices,
+}
+
+#[derive(Default)]
+struct Caches {
+    index: RuleIndex,
+    /// Entries by context hash; colliding contexts share a bucket.
+    memo: HashMap<u64, Vec<MemoEntry>>,
+    memo_len: usize,
+}
+
+/// The rule index and match memo of a stylesheet.
+///
+/// Cloning gives an empty cache, rebuilt on first use.
+#[derive(Default)]
+pub(crate) struct RuleCache(Mutex<Option<Caches>>);
+
+impl RuleCache {
+    /// Calls `f` with the index of `rules`, building it if needed.
+    pub(crate) fn with_index<R>(&self, rules: &[Rule], f: impl FnOnce(&RuleIndex) -> R) -> R {
+        let mut guard = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        f(&Self::caches(&mut guard, rules).index)
+    }
+
+    fn caches<'c>(caches: &'c mut Option<Caches>, rules: &[Rule]) -> &'c mut Caches {
+        if caches
+            .as_ref()
+            .is_some_and(|caches| caches.index.rule_count != rules.len())
+        {
+            *caches = None;
+        }
+        caches.get_or_insert_with(|| Caches {
+            index: RuleIndex::new(rules),
+            ..Default::default()
+        })
+    }
+
+    /// The rules matching `widget`, from the memo or computed by `matcher`.
+    ///
+    /// `matcher` receives the candidate rule indices from the index and
+    /// returns the matched `(rule, selector)` pairs in cascade order.
+    pub(crate) fn matched(
+        &self,
+        rules: &[Rule],
+        widget: &WidgetMeta,
+        ancestors: &[WidgetMeta],
+        ctx: SelectorContext,
+        matcher: impl FnOnce(&[usize]) -> Vec<(usize, usize)>,
+    ) -> MatchedIndices {
+        let mut guard = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let caches = Self::caches(&mut guard, rules);
+
+        let uses_siblings = caches.index.uses_siblings;
+        // Without media queries the terminal can't affect matching, so
+        // leave it out of the key rather than memoizing per terminal size
+        let ctx = if caches.index.uses_media {
+            ctx
+        } else {
+            SelectorContext {
+                media: Default::default(),
+                ..ctx
+            }
+        };
+        let key = context_hash(widget, ancestors, ctx, uses_siblings);
+        if let Some(entry) = caches.memo.get(&key).and_then(|entries| {
+            entries.iter().find(|entry| {
+                entry.ctx == ctx
+                    && meta_eq(&entry.widget, widget, uses_siblings)
+                    && entry.ancestors.len() == ancestors.len()
+                    && entry
+                        .ancestors
+                        .iter()
+                        .zip(ancestors)
+                        .all(|(a, b)| meta_eq(a, b, uses_siblings))
+            })
+        }) {
+            return entry.matched.clone();
+        }
+
+        let matched: MatchedIndices = matcher(&caches.index.candidates(widget)).into();
+        if caches.memo_len >= MEMO_CAPACITY {
+            caches.memo.clear();
+            caches.memo_len = 0;
+        }
+        caches.memo.entry(key).or_default().push(MemoEntry {
+            widget: widget.clone(),
+            ancestors: ancestors.to_vec(),
+            ctx,
+            matched: matched.clone(),
+        });
+        caches.memo_len += 1;
+        matched
+    }
+}
+
+impl Clone for RuleCache {
+    fn clone(&self) -> Self {
+        RuleCache::default()
+    }
+}
+
+impl fmt::Debug for RuleCache {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RuleCache")
+    }
+}
+
+fn context_hash(
+    widget: &WidgetMeta,
+    ancestors: &[WidgetMeta],
+    ctx: SelectorContext,
+    uses_siblings: bool,
+) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    ctx.hash(&mut hasher);
+    for meta in std::iter::once(widget).chain(ancestors) {
+        hash_meta(meta, &mut hasher);
+        if uses_siblings {
+            meta.sibling_index.hash(&mut hasher);
+            meta.siblings.len().hash(&mut hasher);
+            for sibling in meta.siblings.iter() {
+                hash_meta(sibling, &mut hasher);
+            }
+        }
+    }
+    hasher.finish()
+}
+
+/// Hashes the fields selectors look at, other than sibling information.
+fn hash_meta(meta: &WidgetMeta, hasher: &mut impl Hasher) {
+    meta.type_name.hash(hasher);
+    meta.type_names.hash(hasher);
+    meta.id.hash(hasher);
+    meta.classes.hash(hasher);
+    meta.states.bits().hash(hasher);
+    meta.attributes.hash(hasher);
+}
+
+/// Whether `a` and `b` match the same selectors.
+///
+/// Sibling information only counts when some selector uses it.
+fn meta_eq(a: &WidgetMeta, b: &WidgetMeta, uses_siblings: bool) -> bool {
+    shallow_eq(a, b)
+        && (!uses_siblings
+            || (a.sibling_index == b.sibling_index
+                && a.siblings.len() == b.siblings.len()
+                && a.siblings
+                    .iter()
+                    .zip(b.siblings.iter())
+                    .all(|(a, b)| shallow_eq(a, b))))
+}
+
+fn shallow_eq(a: &WidgetMeta, b: &WidgetMeta) -> bool {
+    a.type_name == b.type_name
+        && a.type_names == b.type_names
+        && a.id == b.id
+        && a.classes == b.classes
+        && a.states == b.states
+        && a.attributes == b.attributes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse_stylesheet;
+
+    fn meta(type_name: &'static str, id: Option<&str>, classes: &[&str]) -> WidgetMeta {
+        WidgetMeta {
+            type_name,
+            type_names: vec![type_name, "Widget", "DOMNode"],
+            id: id.map(str::to_string),
+            classes: classes.iter().map(|c| c.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_index_buckets_by_rightmost_compound() {
+        let stylesheet = parse_stylesheet(
+            "Button { color: red; }
+             #save { color: red; }
+             .primary { color: red; }
+             Container Label.title { color: red; }
+             :focus { color: red; }
+             Label, #save { color: red; }",
+        )
+        .unwrap();
+        let index = RuleIndex::new(stylesheet.rules());
+
+        assert_eq!(index.candidates(&meta("Button", None, &[])), [0, 4]);
+        assert_eq!(
+            index.candidates(&meta("Button", Some("save"), &["primary"])),
+            [0, 1, 2, 4, 5]
+        );
+        assert_eq!(index.candidates(&meta("Label", None, &[])), [4, 5]);
+        assert_eq!(
+            index.candidates(&meta("Label", None, &["title"])),
+            [3, 4, 5]
+        );
+    }
+
+    #[test]
+    fn test_index_matches_inherited_type_names() {
+        let stylesheet = parse_stylesheet("Widget { color: red; }").unwrap();
+        let index = RuleIndex::new(stylesheet.rules());
+        assert_eq!(index.candidates(&meta("Label", None, &[])), [0]);
+    }
+
+    #[test]
+    fn test_index_is_rebuilt_after_editing_a_rule_in_place() {
+        let mut stylesheet = parse_stylesheet("Button { color: red; }").unwrap();
+        assert!(!stylesheet.uses_pseudo_class("focus-within"));
+        assert!(!stylesheet.needs_sibling_context());
+
+        // Same number of rules, different selectors
+        let edited = parse_stylesheet(":focus-within > Label:first-child { color: red; }")
+            .unwrap()
+            .into_rules();
+        stylesheet.rules_mut()[0] = edited.into_iter().next().unwrap();
+        assert!(stylesheet.uses_pseudo_class("focus-within"));
+        assert!(stylesheet.needs_sibling_context());
+    }
+
+    #[test]
+    fn test_index_is_rebuilt_after_pushing_to_the_rules_field() {
+        let mut stylesheet = parse_stylesheet("Button { color: red; }").unwrap();
+        assert!(!stylesheet.uses_pseudo_class("focus-within"));
+
+        let added = parse_stylesheet(":focus-within { color: red; }").unwrap();
+        stylesheet.rules.extend(added.rules);
+        assert!(stylesheet.uses_pseudo_class("focus-within"));
+    }
+
+    #[test]
+    fn test_memo_reuses_results_until_context_changes() {
+        let stylesheet = parse_stylesheet("Label { color: red; }").unwrap();
+        let cache = RuleCache::default();
+        let ctx = SelectorContext::default();
+        let parent = [meta("Container", None, &[])];
+        let calls = std::cell::Cell::new(0);
+        let matched = |widget: &WidgetMeta| {
+            cache.matched(stylesheet.rules(), widget, &parent, ctx, |candidates| {
+                calls.set(calls.get() + 1);
+                candidates.iter().map(|&rule| (rule, 0)).collect()
+            })
+        };
+
+        let label = meta("Label", None, &[]);
+        assert_eq!(&*matched(&label), [(0, 0)]);
+        assert_eq!(&*matched(&label.clone()), [(0, 0)]);
+
+        let mut focused = label.clone();
+        focused.states = crate::WidgetStates::FOCUS;
+        matched(&focused);
+        matched(&meta("Label", None, &["marked"]));
+        assert_eq!(calls.get(), 3);
+    }
+}
@@ -0,0 +1,345 @@
+//! `@media` queries on terminal size and capabilities.
+//!
+//! Rules inside an `@media` block only apply while its query matches:
+//!
+//! ```css
+//! @media (max-width: 60) {
+//!     Sidebar { display: none; }
+//! }
+//!
+//! @media (min-width: 100) and (dark), (color: 256) {
+//!     #main { background: $panel; }
+//! }
+//! ```
+//!
+//! A query is a comma-separated list of alternatives, any of which may
+//! match; each alternative is a list of features joined by `and`, all of
+//! which must hold. Supported features:
+//!
+//! - `(min-width: N)`, `(max-width: N)`, `(width: N)`: terminal columns
+//! - `(min-height: N)`, `(max-height: N)`, `(height: N)`: terminal rows
+//! - `(color: N)`: the terminal shows at least `N` colors
+//! - `(dark)`, `(light)`: the theme's mode
+//!
+//! Size and colors come from the stylesheet's [`MediaEnvironment`]; the
+//! theme flags from the same context as `:dark` and `:ansi`.
+
+use std::fmt;
+
+use crate::parser::cascade::SelectorContext;
+
+/// The terminal properties that media queries test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MediaEnvironment {
+    /// Terminal width in columns.
+    pub width: u16,
+    /// Terminal height in rows.
+    pub height: u16,
+    /// Number of colors the terminal can show.
+    pub colors: u32,
+}
+
+impl MediaEnvironment {
+    /// 24-bit color.
+    pub const TRUECOLOR: u32 = 1 << 24;
+
+    /// A truecolor terminal of the given size.
+    pub fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            colors: Self::TRUECOLOR,
+        }
+    }
+
+    /// Sets the number of colors the terminal can show.
+    pub fn with_colors(mut self, colors: u32) -> Self {
+        self.colors = colors;
+        self
+    }
+}
+
+impl Default for MediaEnvironment {
+    /// An 80x24 truecolor terminal.
+    fn default() -> Self {
+        Self::new(80, 24)
+    }
+}
+
+/// A single media feature, such as `(min-width: 100)`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MediaFeature {
+    /// `(min-width: N)`
+    MinWidth(u16),
+    /// `(max-width: N)`
+    MaxWidth(u16),
+    /// `(width: N)`
+    Width(u16),
+    /// `(min-height: N)`
+    MinHeight(u16),
+    /// `(max-height: N)`
+    MaxHeight(u16),
+    /// `(height: N)`
+    Height(u16),
+    /// `(color: N)`: at least `N` colors.
+    Color(u32),
+    /// `(dark)`: a dark theme.
+    Dark,
+    /// `(light)`: a light theme.
+    Light,
+}
+
+impl MediaFeature {
+    /// Parses the text between the parentheses, e.g. `min-width: 100`.
+    pub fn parse(content: &str) -> Option<Self> {
+        let (name, value) = match content.split_once(':') {
+            Some((name, value)) => (name.trim(), Some(value.trim())),
+            None => (content.trim(), None),
+        };
+        let cells = || value?.parse::<u16>().ok();
+        Some(match name {
+            "min-width" => MediaFeature::MinWidth(cells()?),
+            "max-width" => MediaFeature::MaxWidth(cells()?),
+            "width" => MediaFeature::Width(cells()?),
+            "min-height" => MediaFeature::MinHeight(cells()?),
+            "max-height" => MediaFeature::MaxHeight(cells()?),
+            "height" => MediaFeature::Height(cells()?),
+            "color" => MediaFeature::Color(value?.parse().ok()?),
+            "dark" if value.is_none() => MediaFeature::Dark,
+            "light" if value.is_none() => MediaFeature::Light,
+            _ => return None,
+        })
+    }
+
+    pub(crate) fn matches(&self, ctx: &SelectorContext) -> bool {
+        match self {
+            MediaFeature::Dark => ctx.is_dark,
+            MediaFeature::Light => !ctx.is_dark,
+            MediaFeature::Color(colors) => ctx.colors() >= *colors,
+            _ => self.matches_size(&ctx.media),
+        }
+    }
+
+    /// Evaluates the size feature against `environment` (true for
+    /// features that don't depend on size).
+    fn matches_size(&self, environment: &MediaEnvironment) -> bool {
+        let (width, height) = (environment.width, environment.height);
+        match *self {
+            MediaFeature::MinWidth(n) => width >= n,
+            MediaFeature::MaxWidth(n) => width <= n,
+            MediaFeature::Width(n) => width == n,
+            MediaFeature::MinHeight(n) => height >= n,
+            MediaFeature::MaxHeight(n) => height <= n,
+            MediaFeature::Height(n) => height == n,
+            MediaFeature::Color(_) | MediaFeature::Dark | MediaFeature::Light => true,
+        }
+    }
+
+    /// Whether moving from `old` to `new` can change this feature's result.
+    fn changes(&self, old: &MediaEnvironment, new: &MediaEnvironment) -> bool {
+        match self {
+            MediaFeature::Color(_) => old.colors != new.colors,
+            _ => self.matches_size(old) != self.matches_size(new),
+        }
+    }
+}
+
+/// A media query: alternatives separated by commas, each a list of
+/// features joined by `and`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MediaQuery {
+    /// The query matches if all features of any alternative match.
+    pub alternatives: Vec<Vec<MediaFeature>>,
+}
+
+impl MediaQuery {
+    /// Creates a query with a single alternative.
+    pub fn all(features: Vec<MediaFeature>) -> Self {
+        Self {
+            alternatives: vec![features],
+        }
+    }
+
+    /// Parses the query between `@media` and the opening brace, e.g.
+    /// `(min-width: 100) and (dark), (color: 256)`.
+    pub fn parse(source: &str) -> Option<Self> {
+        let source = source.split_whitespace().collect::<Vec<_>>().join(" ");
+        let alternatives = source
+            .split(',')
+            .map(|alternative| {
+                alternative
+                    .split(" and ")
+                    .map(|feature| {
+                        let feature = feature.trim();
+                        MediaFeature::parse(feature.strip_prefix('(')?.strip_suffix(')')?)
+                    })
+                    .collect::<Option<Vec<_>>>()
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self { alternatives })
+    }
+
+    /// The query matching when both `self` and `other` do, as for an
+    /// `@media` block nested in another.
+    pub fn and(&self, other: &MediaQuery) -> MediaQuery {
+        let alternatives = self
+            .alternatives
+            .iter()
+            .flat_map(|outer| {
+                other.alternatives.iter().map(move |inner| {
+                    let mut features = outer.clone();
+                    features.extend_from_slice(inner);
+                    features
+                })
+            })
+            .collect();
+        MediaQuery { alternatives }
+    }
+
+    pub(crate) fn matches(&self, ctx: &SelectorContext) -> bool {
+        self.alternatives
+            .iter()
+            .any(|features| features.iter().all(|feature| feature.matches(ctx)))
+    }
+
+    /// Whether moving from `old` to `new` can change whether the query
+    /// matches.
+    pub fn changes(&self, old: &MediaEnvironment, new: &MediaEnvironment) -> bool {
+        self.alternatives
+            .iter()
+            .flatten()
+            .any(|feature| feature.changes(old, new))
+    }
+}
+
+impl fmt::Display for MediaFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MediaFeature::MinWidth(n) => write!(f, "(min-width: {n})"),
+            MediaFeature::MaxWidth(n) => write!(f, "(max-width: {n})"),
+            MediaFeature::Width(n) => write!(f, "(width: {n})"),
+            MediaFeature::MinHeight(n) => write!(f, "(min-height: {n})"),
+            MediaFeature::MaxHeight(n) => write!(f, "(max-height: {n})"),
+            MediaFeature::Height(n) => write!(f, "(height: {n})"),
+            MediaFeature::Color(n) => write!(f, "(color: {n})"),
+            MediaFeature::Dark => write!(f, "(dark)"),
+            MediaFeature::Light => write!(f, "(light)"),
+        }
+    }
+}
+
+impl fmt::Display for MediaQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, features) in self.alternatives.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            for (j, feature) in features.iter().enumerate() {
+                if j > 0 {
+                    write!(f, " and ")?;
+                }
+                write!(f, "{feature}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(width: u16, height: u16, is_dark: bool) -> SelectorContext {
+        SelectorContext {
+            is_dark,
+            media: MediaEnvironment::new(width, height),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_media_query() {
+        let query = MediaQuery::parse("(min-width: 100) and (dark), (color:256)").unwrap();
+        assert_eq!(
+            query.alternatives,
+            [
+                vec![MediaFeature::MinWidth(100), MediaFeature::Dark],
+                vec![MediaFeature::Color(256)],
+            ]
+        );
+        assert_eq!(
+            query.to_string(),
+            "(min-width: 100) and (dark), (color: 256)"
+        );
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_queries() {
+        for source in [
+            "",
+            "min-width: 100",
+            "(min-width)",
+            "(min-width: wide)",
+            "(dark: 1)",
+            "(orientation: landscape)",
+            "(dark) or (light)",
+        ] {
+            assert_eq!(MediaQuery::parse(source), None, "{source:?}");
+        }
+    }
+
+    #[test]
+    fn test_size_features() {
+        let query = MediaQuery::parse("(min-width: 100) and (max-height: 30)").unwrap();
+        assert!(query.matches(&ctx(100, 30, true)));
+        assert!(!query.matches(&ctx(99, 30, true)));
+        assert!(!query.matches(&ctx(120, 31, true)));
+
+        let exact = MediaQuery::parse("(width: 80), (height: 10)").unwrap();
+        assert!(exact.matches(&ctx(80, 24, true)));
+        assert!(exact.matches(&ctx(40, 10, true)));
+        assert!(!exact.matches(&ctx(81, 24, true)));
+    }
+
+    #[test]
+    fn test_theme_and_color_features() {
+        let dark = MediaQuery::parse("(dark)").unwrap();
+        let light = MediaQuery::parse("(light)").unwrap();
+        assert!(dark.matches(&ctx(80, 24, true)));
+        assert!(light.matches(&ctx(80, 24, false)));
+        assert!(!light.matches(&ctx(80, 24, true)));
+
+        let colors = MediaQuery::parse("(color: 256)").unwrap();
+        let mut context = ctx(80, 24, true);
+        assert!(colors.matches(&context));
+        context.media = context.media.with_colors(16);
+        assert!(!colors.matches(&context));
+        context.media = context.media.with_colors(256);
+        assert!(colors.matches(&context));
+        context.is_ansi = true;
+        assert!(!colors.matches(&context));
+    }
+
+    #[test]
+    fn test_nested_queries_combine() {
+        let outer = MediaQuery::parse("(min-width: 100), (dark)").unwrap();
+        let inner = MediaQuery::parse("(max-height: 30)").unwrap();
+        assert_eq!(
+            outer.and(&inner).to_string(),
+            "(min-width: 100) and (max-height: 30), (dark) and (max-height: 30)"
+        );
+    }
+
+    #[test]
+    fn test_changes_only_when_result_can_change() {
+        let query = MediaQuery::parse("(min-width: 100) and (dark)").unwrap();
+        let narrow = MediaEnvironment::new(80, 24);
+        assert!(!query.changes(&narrow, &MediaEnvironment::new(90, 50)));
+        assert!(query.changes(&narrow, &MediaEnvironment::new(100, 24)));
+        assert!(
+            !MediaQuery::parse("(dark)")
+                .unwrap()
+                .changes(&narrow, &MediaEnvironment::new(100, 24))
+        );
+    }
+}
@@ -4,6 +4,7 @@
 //! including:
 //!
 //! - [`parse_stylesheet`]: Main entry point for parsing TCSS source
+//! - [`load_stylesheet`]: Parses a stylesheet file and its `@import`s
 //! - [`StyleSheet`]: Represents a complete parsed stylesheet
 //! - [`Rule`]: A CSS rule with selectors and declarations
 //! - [`Declaration`]: A property-value pair like `color: red`
@@ -18,6 +19,8 @@
 //! - [`values`]: Color, border, and other value parsing
 //! - [`variables`]: CSS variable extraction and resolution
 //! - [`flatten`]: Nested rule flattening (for `&` parent selector support)
+//! - [`media`]: `@media` queries on terminal size and capabilities
+//! - `index`: Rule index and matched-rule memo used by the cascade
 //!
 //! ## Example
 //!
@@ -36,6 +39,8 @@
 
 pub mod cascade;
 pub mod flatten;
+mod index;
+pub mod media;
 pub mod selectors;
 pub mod stylesheet;
 pub mod units;
@@ -43,22 +48,29 @@ pub mod values;
 pub mod variables;
 
 pub use crate::parser::flatten::flatten_stylesheet;
+pub use crate::parser::media::{MediaEnvironment, MediaFeature, MediaQuery};
 pub use crate::parser::stylesheet::{
-    Combinator, ComplexSelector, CompoundSelector, Declaration, Rule, RuleItem, Selector,
-    SelectorList, SelectorPart, Specificity, StyleSheet,
+    AttributeOperator, AttributeSelector, CascadeTier, Combinator, ComplexSelector,
+    CompoundSelector, Declaration, NthPattern, Rule, RuleItem, RuleOrigin, Selector, SelectorList,
+    SelectorPart, Specificity, StyleSheet, VariableDeclaration,
 };
-pub use crate::parser::variables::{extract_variables, resolve_variables};
+pub use crate::parser::variables::{
+    extract_variables, resolve_variables, strip_definitions, substitute_variables,
+    variable_references,
+};
+
+use std::path::{Path, PathBuf};
 
 use crate::TcssError;
 use crate::parser::selectors::parse_complex_selector;
 use crate::parser::values::parse_ident;
 
 use nom::branch::alt;
-use nom::bytes::complete::tag;
+use nom::bytes::complete::{tag, take_until};
 use nom::{
     IResult,
     character::complete::{char, multispace0},
-    combinator::{map, opt},
+    combinator::{map, map_opt, opt},
     multi::many0,
     sequence::{delimited, preceded, tuple},
 };
@@ -83,6 +95,9 @@ fn strip_comments(input: &str) -> String {
                             result.push(' '); // preserve spacing
                             break;
                         }
+                        if c2 == '\n' {
+                            result.push('\n'); // preserve line numbers
+                        }
                     }
                 }
                 Some('/') => {
@@ -105,15 +120,69 @@ fn strip_comments(input: &str) -> String {
 }
 
 /// Parses a full TCSS stylesheet, including variable resolution.
+///
+/// Variable references stay in the parsed declarations (see
+/// [`Declaration::Variable`]), to be resolved by the cascade. Declarations
+/// whose variables `source` defines must resolve to a valid value.
+///
+/// `@import` paths are resolved against the current directory; use
+/// [`load_stylesheet`] to resolve them relative to a file.
 pub fn parse_stylesheet(source: &str) -> Result<StyleSheet, TcssError> {
+    parse_source(source, None, &mut Vec::new())
+}
+
+/// Reads and parses the stylesheet file at `path`.
+///
+/// `@import "other.tcss";` paths are resolved relative to the importing
+/// file. Imported rules take the place of the `@import` in source order, and
+/// imported variables are visible to the importing file (whose own
+/// definitions win). Every rule's [`origin`](Rule::origin) names the file it
+/// was written in.
+pub fn load_stylesheet(path: impl AsRef<Path>) -> Result<StyleSheet, TcssError> {
+    load_file(path.as_ref(), &mut Vec::new())
+}
+
+/// Loads `path`, with `importing` holding the files whose imports led here.
+fn load_file(path: &Path, importing: &mut Vec<PathBuf>) -> Result<StyleSheet, TcssError> {
+    let read_error = |source| TcssError::Read {
+        path: path.to_path_buf(),
+        source,
+    };
+    let canonical = path.canonicalize().map_err(read_error)?;
+    if let Some(start) = importing.iter().position(|file| *file == canonical) {
+        let mut cycle = importing[start..].to_vec();
+        cycle.push(canonical);
+        return Err(TcssError::ImportCycle(cycle));
+    }
+    let source = std::fs::read_to_string(&canonical).map_err(read_error)?;
+
+    importing.push(canonical);
+    let stylesheet = parse_source(&source, Some(path), importing);
+    importing.pop();
+
+    let stylesheet = stylesheet.map_err(|e| match e {
+        TcssError::InvalidSyntax(message) => {
+            TcssError::InvalidSyntax(format!("{}: {message}", path.display()))
+        }
+        e => e,
+    })?;
+    Ok(stylesheet.with_source(&path.display().to_string()))
+}
+
+/// Parses `source`, read from `file` if it came from one.
+fn parse_source(
+    source: &str,
+    file: Option<&Path>,
+    importing: &mut Vec<PathBuf>,
+) -> Result<StyleSheet, TcssError> {
     // Strip comments first (before variable extraction)
     let source_no_comments = strip_comments(source);
 
     let vars = extract_variables(&source_no_comments);
-    let resolved_source = resolve_variables(&source_no_comments, &vars)?;
+    let source = strip_definitions(&source_no_comments);
 
-    let (remaining, raw_rules) =
-        many0(parse_rule)(&resolved_source).map_err(|e| TcssError::InvalidSyntax(e.to_string()))?;
+    let (remaining, items) = many0(|input| parse_top_level_item(&source, input))(&source)
+        .map_err(|e| TcssError::InvalidSyntax(e.to_string()))?;
 
     if !remaining.trim().is_empty() {
         return Err(TcssError::InvalidSyntax(format!(
@@ -122,28 +191,121 @@ pub fn parse_stylesheet(source: &str) -> Result<StyleSheet, TcssError> {
         )));
     }
 
-    Ok(flatten_stylesheet(raw_rules))
+    // Imported stylesheets are merged in where they were imported, so
+    // source order runs through them
+    let mut stylesheet = StyleSheet::default();
+    let mut raw_rules = Vec::new();
+    for item in items {
+        match item {
+            TopLevelItem::Rules(rules) => raw_rules.extend(rules),
+            TopLevelItem::Import(import) => {
+                let path = match file.and_then(Path::parent) {
+                    Some(dir) => dir.join(&import),
+                    None => PathBuf::from(&import),
+                };
+                let imported = load_file(&path, importing)?;
+                stylesheet.merge(flatten_stylesheet(std::mem::take(&mut raw_rules)));
+                stylesheet.merge(imported);
+            }
+        }
+    }
+    let flat = flatten_stylesheet(raw_rules);
+    stylesheet.merge(StyleSheet::with_variables(flat.into_rules(), vars));
+    stylesheet.check_variables()?;
+    Ok(stylesheet)
+}
+
+/// A top-level statement of a stylesheet.
+enum TopLevelItem {
+    /// A rule or the rules of an `@media` block.
+    Rules(Vec<Rule>),
+    /// `@import "path";`
+    Import(String),
+}
+
+fn parse_top_level_item<'a>(source: &'a str, input: &'a str) -> IResult<&'a str, TopLevelItem> {
+    alt((
+        map(parse_import, TopLevelItem::Import),
+        map(|input| parse_rules(source, input), TopLevelItem::Rules),
+    ))(input)
+}
+
+/// Parses `@import "path";` (or with single quotes).
+fn parse_import(input: &str) -> IResult<&str, String> {
+    let (input, _) = tuple((multispace0, tag("@import"), multispace0))(input)?;
+    let (input, path) = alt((
+        delimited(char('"'), take_until("\""), char('"')),
+        delimited(char('\''), take_until("'"), char('\'')),
+    ))(input)?;
+    let (input, _) = tuple((multispace0, char(';')))(input)?;
+    Ok((input, path.to_string()))
+}
+
+/// Parses a rule or an `@media` block of rules.
+fn parse_rules<'a>(source: &'a str, input: &'a str) -> IResult<&'a str, Vec<Rule>> {
+    alt((
+        |input| parse_media_block(source, input),
+        map(|input| parse_rule_in(source, input), |rule| vec![rule]),
+    ))(input)
+}
+
+/// Parses `@media <query> { ... }`, attaching the query to the rules inside.
+///
+/// Nested blocks apply when both queries match.
+fn parse_media_block<'a>(source: &'a str, input: &'a str) -> IResult<&'a str, Vec<Rule>> {
+    let (input, _) = multispace0(input)?;
+    let (input, query) =
+        map_opt(preceded(tag("@media"), take_until("{")), MediaQuery::parse)(input)?;
+    let (input, rules) = delimited(
+        char('{'),
+        many0(|input| parse_rules(source, input)),
+        preceded(multispace0, char('}')),
+    )(input)?;
+
+    let rules = rules
+        .into_iter()
+        .flatten()
+        .map(|mut rule| {
+            rule.media = Some(match &rule.media {
+                Some(inner) => query.and(inner),
+                None => query.clone(),
+            });
+            rule
+        })
+        .collect();
+    Ok((input, rules))
 }
 
 /// Top-level parser for a CSS rule (e.g., "Button { color: red; }").
+///
+/// The rule's [`origin`](Rule::origin) line counts from the start of `input`.
 pub fn parse_rule(input: &str) -> IResult<&str, Rule> {
-    let (input, _) = multispace0(input)?;
-    let (input, selectors) = parse_selector_list(input)?;
+    parse_rule_in(input, input)
+}
+
+/// Parses a rule from `input`, a suffix of `source`, recording the line of
+/// `source` it starts on.
+fn parse_rule_in<'a>(source: &'a str, input: &'a str) -> IResult<&'a str, Rule> {
+    let (start, _) = multispace0(input)?;
+    let (input, selectors) = parse_selector_list(start)?;
     let (input, _) = multispace0(input)?;
 
     let (input, items) = delimited(
         char('{'),
-        parse_rule_items,
+        |input| parse_rule_items(source, input),
         preceded(multispace0, char('}')),
     )(input)?;
 
-    Ok((input, Rule::new(selectors, items)))
+    let line = source[..source.len() - start.len()].matches('\n').count() + 1;
+    let mut rule = Rule::new(selectors, items);
+    rule.origin = Some(RuleOrigin { source: None, line });
+    Ok((input, rule))
 }
 
 /// Parses either a declaration (color: red) or a nested rule (&:hover { ... })
-fn parse_rule_items(input: &str) -> IResult<&str, Vec<RuleItem>> {
+fn parse_rule_items<'a>(source: &'a str, input: &'a str) -> IResult<&'a str, Vec<RuleItem>> {
     many0(alt((
-        map(parse_rule, RuleItem::NestedRule),
+        map(|input| parse_rule_in(source, input), RuleItem::NestedRule),
         map(parse_single_declaration, RuleItem::Declaration),
     )))(input)
 }
@@ -186,6 +348,46 @@ fn parse_single_declaration(input: &str) -> IResult<&str, Declaration> {
     let (input, property) = parse_ident(input)?;
     let (input, _) = tuple((multispace0, char(':'), multispace0))(input)?;
 
+    // Values referencing variables are kept as written, to be resolved
+    // against the stylesheet's variables
+    let (input, declaration) = match variable_value(input) {
+        Some((input, value)) => (
+            input,
+            Declaration::Variable(VariableDeclaration::new(property, value)),
+        ),
+        None => parse_property(property, input)?,
+    };
+
+    let (input, important) = opt(preceded(multispace0, tag("!important")))(input)?;
+    let declaration = match important {
+        Some(_) => declaration.important(),
+        None => declaration,
+    };
+
+    let (input, _) = multispace0(input)?;
+    let (input, _) = opt(char(';'))(input)?;
+    Ok((input, declaration))
+}
+
+/// If the value at the start of `input` references a variable, splits off
+/// the value (up to `;`, `}` or `!important`).
+fn variable_value(input: &str) -> Option<(&str, &str)> {
+    let end = input.find([';', '}']).unwrap_or(input.len());
+    let end = input[..end].find("!important").unwrap_or(end);
+    let value = input[..end].trim();
+    value.contains('$').then(|| (&input[end..], value))
+}
+
+/// Parses `value` as the value of `property`, which must consume all of it.
+pub(crate) fn parse_declaration_value(property: &str, value: &str) -> Option<Declaration> {
+    match parse_property(property, value.trim()) {
+        Ok((rest, declaration)) if rest.trim().is_empty() => Some(declaration),
+        _ => None,
+    }
+}
+
+/// Parses the value of `property`.
+fn parse_property<'a>(property: &str, input: &'a str) -> IResult<&'a str, Declaration> {
     let (input, declaration) = match property {
         "color" => map(values::parse_color, Declaration::Color)(input)?,
         "background" => map(values::parse_color, Declaration::Background)(input)?,
@@ -379,12 +581,6 @@ fn parse_single_declaration(input: &str) -> IResult<&str, Declaration> {
             (input, Declaration::Unknown(property.to_string()))
         }
     };
-
-    // Consume !important if present (we'll just skip it for now to pass parsing)
-    let (input, _) = opt(preceded(multispace0, tag("!important")))(input)?;
-
-    let (input, _) = multispace0(input)?;
-    let (input, _) = opt(char(';'))(input)?;
     Ok((input, declaration))
 }
 
@@ -3,6 +3,7 @@
 //! This module handles parsing of CSS selectors from source text:
 //!
 //! - Simple selectors: `Button`, `.class`, `#id`, `*`, `:hover`, `[attr=value]`
+//! - Attribute operators: `[attr]`, `[attr=v]`, `[attr^=v]`, `[attr$=v]`, `[attr*=v]`
 //! - Compound selectors: `Button.primary#submit`
 //! - Complex selectors with combinators: `Container > Button`
 //!
@@ -23,21 +24,27 @@
 //! ```
 
 use crate::parser::{
-    Combinator, ComplexSelector, CompoundSelector, Selector, SelectorPart, values::parse_ident,
+    AttributeSelector, Combinator, ComplexSelector, CompoundSelector, NthPattern, Selector,
+    SelectorPart, values::parse_ident,
 };
 use nom::{
     IResult,
     branch::alt,
-    bytes::complete::take_until,
+    bytes::complete::{tag, take_until},
     character::complete::{char, multispace0},
-    combinator::map,
+    combinator::{map, map_opt},
     multi::many0,
     sequence::{delimited, preceded},
 };
 
-/// Parses a simple selector: `Type`, `.class`, `#id`, `*`, `:pseudo`, `&`, or `[attr]`.
+/// Parses a simple selector: `Type`, `.class`, `#id`, `*`, `:pseudo`,
+/// `:nth-child(an+b)`, `&`, or `[attr]`.
 pub fn parse_simple_selector(input: &str) -> IResult<&str, Selector> {
     alt((
+        map_opt(
+            delimited(tag(":nth-child("), take_until(")"), char(')')),
+            |pattern| parse_nth_pattern(pattern).map(Selector::NthChild),
+        ),
         map(preceded(char('#'), parse_ident), |s| {
             Selector::Id(s.to_string())
         }),
@@ -119,21 +126,38 @@ pub fn parse_complex_selector(input: &str) -> IResult<&str, ComplexSelector> {
     Ok((input, ComplexSelector::new(parts)))
 }
 
-fn parse_attribute_selector(input: &str) -> IResult<&str, Selector> {
-    let (input, content) = delimited(char('['), take_until("]"), char(']'))(input)?;
-
-    if let Some(idx) = content.find('=') {
-        let name = content[..idx].trim();
-        let value = content[idx + 1..].trim();
-        Ok((
-            input,
-            Selector::Attribute(name.to_string(), value.to_string()),
-        ))
-    } else {
-        // Handle [attr] existence selector
-        Ok((
-            input,
-            Selector::Attribute(content.trim().to_string(), "".to_string()),
-        ))
+/// Parses the argument of `:nth-child()`: `odd`, `even`, `B`, `An`, or
+/// `An+B` (whitespace around the sign is allowed).
+pub fn parse_nth_pattern(input: &str) -> Option<NthPattern> {
+    let pattern: String = input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match pattern.as_str() {
+        "odd" => return Some(NthPattern::ODD),
+        "even" => return Some(NthPattern::EVEN),
+        _ => {}
     }
+    let Some((a, b)) = pattern.split_once('n') else {
+        return pattern.parse().ok().map(|b| NthPattern::new(0, b));
+    };
+    let a = match a {
+        "" | "+" => 1,
+        "-" => -1,
+        a => a.parse().ok()?,
+    };
+    let b = match b {
+        "" => 0,
+        b if b.starts_with(['+', '-']) => b.parse().ok()?,
+        _ => return None,
+    };
+    Some(NthPattern::new(a, b))
+}
+
+fn parse_attribute_selector(input: &str) -> IResult<&str, Selector> {
+    map_opt(
+        delimited(char('['), take_until("]"), char(']')),
+        |content| AttributeSelector::parse(content).map(Selector::Attribute),
+    )(input)
 }
@@ -15,13 +15,35 @@
 //! SelectorList         = ComplexSelector (',' ComplexSelector)*
 //! ComplexSelector      = CompoundSelector (Combinator CompoundSelector)*
 //! CompoundSelector     = SimpleSelector+
-//! SimpleSelector       = Type | Class | Id | Universal | PseudoClass
+//! SimpleSelector       = Type | Class | Id | Universal | PseudoClass | NthChild | Attribute
 //! ```
 //!
 //! For example, `Container > Button.primary, #submit`:
 //! - Two complex selectors (comma-separated)
 //! - First has two compound selectors with child combinator
 //! - Second is a single ID selector
+//!
+//! ## Printing
+//!
+//! Selectors, declarations, rules and whole stylesheets implement
+//! [`Display`](fmt::Display), writing TCSS that [`parse_stylesheet`]
+//! parses back to the same rules. Values are normalized (`red` prints as
+//! `#ff0000`) and nested rules print flattened, as the parser stores them.
+//!
+//! [`parse_stylesheet`]: crate::parser::parse_stylesheet
+
+use std::fmt;
+use std::sync::Arc;
+
+use crate::TcssError;
+
+use crate::parser::index::RuleCache;
+use crate::parser::media::{MediaEnvironment, MediaQuery};
+use crate::parser::parse_declaration_value;
+use crate::parser::variables::{
+    MAX_VARIABLE_DEPTH, StylesheetVariables, VariableCache, substitute_variables,
+    variable_references,
+};
 
 use crate::types::{
     AlignHorizontal, AlignVertical, BorderEdge, BoxSizing, Display, Dock, Hatch, Keyline, Layout,
@@ -66,12 +88,165 @@ pub enum Selector {
     /// Matches elements in a specific state (e.g., `:hover`, `:focus`).
     /// Note: Pseudo-class matching requires runtime state information.
     PseudoClass(String),
+    /// Matches elements by their position among their siblings
+    /// (`:nth-child(2n+1)`).
+    NthChild(NthPattern),
     /// The parent selector (`&`) used in nested rules.
     /// Resolves to the parent rule's selector during flattening.
     Parent,
-    /// Matches elements by attribute (e.g., `[type=text]`).
-    /// The tuple contains (attribute-name, expected-value).
-    Attribute(String, String),
+    /// Matches elements by attribute (e.g., `[type=text]`, `[variant^=pri]`).
+    Attribute(AttributeSelector),
+}
+
+/// How an attribute selector compares a widget's attribute value.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeOperator {
+    /// `[name]`: the attribute is present, whatever its value.
+    Exists,
+    /// `[name=value]`
+    Equals,
+    /// `[name^=value]`: the value starts with the given text.
+    Prefix,
+    /// `[name$=value]`: the value ends with the given text.
+    Suffix,
+    /// `[name*=value]`: the value contains the given text.
+    Contains,
+}
+
+impl AttributeOperator {
+    /// The operator as written in a selector (empty for [`Exists`](Self::Exists)).
+    pub fn symbol(self) -> &'static str {
+        match self {
+            AttributeOperator::Exists => "",
+            AttributeOperator::Equals => "=",
+            AttributeOperator::Prefix => "^=",
+            AttributeOperator::Suffix => "$=",
+            AttributeOperator::Contains => "*=",
+        }
+    }
+}
+
+/// An attribute selector: `[name]` or `[name<op>value]`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AttributeSelector {
+    /// The attribute name.
+    pub name: String,
+    /// How `value` is compared.
+    pub operator: AttributeOperator,
+    /// The expected value (empty for [`AttributeOperator::Exists`]).
+    pub value: String,
+}
+
+impl AttributeSelector {
+    /// Creates an `[name]` presence selector.
+    pub fn exists(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            operator: AttributeOperator::Exists,
+            value: String::new(),
+        }
+    }
+
+    /// Creates a selector comparing the attribute with `value`.
+    pub fn new(
+        name: impl Into<String>,
+        operator: AttributeOperator,
+        value: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            operator,
+            value: value.into(),
+        }
+    }
+
+    /// Parses the text between the brackets, e.g. `type=password` or
+    /// `label*="Save as"`. Quotes around the value are optional.
+    pub fn parse(content: &str) -> Option<Self> {
+        let content = content.trim();
+        let Some(eq) = content.find('=') else {
+            return is_attribute_name(content).then(|| Self::exists(content));
+        };
+        let (name, operator) = match content[..eq].strip_suffix(['^', '$', '*']) {
+            Some(name) => {
+                let operator = match content.as_bytes()[eq - 1] {
+                    b'^' => AttributeOperator::Prefix,
+                    b'$' => AttributeOperator::Suffix,
+                    _ => AttributeOperator::Contains,
+                };
+                (name.trim(), operator)
+            }
+            None => (content[..eq].trim(), AttributeOperator::Equals),
+        };
+        if !is_attribute_name(name) {
+            return None;
+        }
+        let value = content[eq + 1..].trim();
+        let value = ['"', '\'']
+            .iter()
+            .find_map(|&quote| value.strip_prefix(quote)?.strip_suffix(quote))
+            .unwrap_or(value);
+        Some(Self::new(name, operator, value))
+    }
+
+    /// Checks a widget's value for the attribute (`None` if it has none).
+    pub fn matches(&self, actual: Option<&str>) -> bool {
+        let Some(actual) = actual else {
+            return false;
+        };
+        let expected = self.value.as_str();
+        match self.operator {
+            AttributeOperator::Exists => true,
+            AttributeOperator::Equals => actual == expected,
+            AttributeOperator::Prefix => actual.starts_with(expected),
+            AttributeOperator::Suffix => actual.ends_with(expected),
+            AttributeOperator::Contains => actual.contains(expected),
+        }
+    }
+}
+
+fn is_attribute_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Pseudo-classes that depend on a widget's position among its siblings.
+pub const STRUCTURAL_PSEUDO_CLASSES: &[&str] =
+    &["first-child", "last-child", "first-of-type", "odd", "even"];
+
+/// An `An+B` pattern from `:nth-child()`.
+///
+/// Matches the 1-based sibling positions `A*n + B` for any `n >= 0`, so
+/// `2n+1` matches the 1st, 3rd, 5th... child and `-n+3` the first three.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NthPattern {
+    /// Step between matched positions.
+    pub a: i32,
+    /// Offset of the first matched position.
+    pub b: i32,
+}
+
+impl NthPattern {
+    /// `odd`: positions 1, 3, 5...
+    pub const ODD: NthPattern = NthPattern { a: 2, b: 1 };
+    /// `even`: positions 2, 4, 6...
+    pub const EVEN: NthPattern = NthPattern { a: 2, b: 0 };
+
+    /// Creates a pattern matching positions `a*n + b`.
+    pub fn new(a: i32, b: i32) -> Self {
+        Self { a, b }
+    }
+
+    /// Checks if the 1-based `position` is matched.
+    pub fn matches(&self, position: usize) -> bool {
+        let offset = position as i64 - i64::from(self.b);
+        match i64::from(self.a) {
+            0 => offset == 0,
+            a => offset % a == 0 && offset / a >= 0,
+        }
+    }
 }
 
 /// A compound selector: one or more simple selectors without combinators.
@@ -97,7 +272,10 @@ impl CompoundSelector {
         for s in &self.selectors {
             match s {
                 Selector::Id(_) => spec.ids += 1,
-                Selector::Class(_) | Selector::PseudoClass(_) | Selector::Attribute(_, _) => {
+                Selector::Class(_)
+                | Selector::PseudoClass(_)
+                | Selector::NthChild(_)
+                | Selector::Attribute(_) => {
                     spec.classes += 1;
                 }
                 Selector::Type(_) => spec.types += 1,
@@ -172,6 +350,31 @@ impl ComplexSelector {
             },
         )
     }
+
+    /// Whether matching this selector looks at the widget's siblings, through
+    /// a sibling combinator or a structural pseudo-class.
+    pub fn uses_siblings(&self) -> bool {
+        self.parts.iter().any(|p| {
+            matches!(
+                p.combinator,
+                Combinator::AdjacentSibling | Combinator::GeneralSibling
+            ) || p.compound.selectors.iter().any(|s| match s {
+                Selector::NthChild(_) => true,
+                Selector::PseudoClass(name) => STRUCTURAL_PSEUDO_CLASSES.contains(&name.as_str()),
+                _ => false,
+            })
+        })
+    }
+
+    /// Whether any part of this selector uses the pseudo-class `name`.
+    pub fn uses_pseudo_class(&self, name: &str) -> bool {
+        self.parts.iter().any(|p| {
+            p.compound
+                .selectors
+                .iter()
+                .any(|s| matches!(s, Selector::PseudoClass(n) if n == name))
+        })
+    }
 }
 
 /// A comma-separated list of complex selectors.
@@ -191,6 +394,102 @@ impl SelectorList {
     }
 }
 
+impl fmt::Display for Specificity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}, {})", self.ids, self.classes, self.types)
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selector::Type(name) => write!(f, "{name}"),
+            Selector::Class(class) => write!(f, ".{class}"),
+            Selector::Id(id) => write!(f, "#{id}"),
+            Selector::Universal => write!(f, "*"),
+            Selector::PseudoClass(name) => write!(f, ":{name}"),
+            Selector::NthChild(nth) => write!(f, ":nth-child({nth})"),
+            Selector::Parent => write!(f, "&"),
+            Selector::Attribute(attribute) => write!(f, "{attribute}"),
+        }
+    }
+}
+
+impl fmt::Display for AttributeSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (name, operator, value) = (&self.name, self.operator.symbol(), &self.value);
+        if self.operator == AttributeOperator::Exists {
+            write!(f, "[{name}]")
+        } else if is_attribute_name(value) {
+            write!(f, "[{name}{operator}{value}]")
+        } else {
+            write!(f, "[{name}{operator}\"{value}\"]")
+        }
+    }
+}
+
+impl fmt::Display for NthPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.a, self.b) {
+            (0, b) => write!(f, "{b}"),
+            (a, b) => {
+                match a {
+                    1 => write!(f, "n")?,
+                    -1 => write!(f, "-n")?,
+                    a => write!(f, "{a}n")?,
+                }
+                match b {
+                    0 => Ok(()),
+                    b if b > 0 => write!(f, "+{b}"),
+                    b => write!(f, "{b}"),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for CompoundSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for selector in &self.selectors {
+            write!(f, "{selector}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Combinator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Combinator::None => Ok(()),
+            Combinator::Descendant => write!(f, " "),
+            Combinator::Child => write!(f, " > "),
+            Combinator::AdjacentSibling => write!(f, " + "),
+            Combinator::GeneralSibling => write!(f, " ~ "),
+        }
+    }
+}
+
+impl fmt::Display for ComplexSelector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            write!(f, "{}{}", part.compound, part.combinator)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SelectorList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, selector) in self.selectors.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{selector}")?;
+        }
+        Ok(())
+    }
+}
+
 /// A CSS declaration (property-value pair).
 ///
 /// Each variant represents a supported CSS property with its parsed value.
@@ -421,6 +720,195 @@ pub enum Declaration {
 
     /// An unrecognized property (stored for forward compatibility).
     Unknown(String),
+
+    /// A declaration marked `!important`, which the cascade applies above
+    /// all normal declarations and inline styles.
+    Important(Box<Declaration>),
+
+    /// A declaration whose value references variables (`width: $sidebar`),
+    /// resolved against the stylesheet's variable scope.
+    Variable(VariableDeclaration),
+}
+
+/// A declaration whose value references `$variables`.
+///
+/// The value is kept as written and resolved by the cascade (see
+/// [`StyleSheet::resolve_declaration`]), so variables can change at
+/// runtime.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VariableDeclaration {
+    /// The property name, e.g. `width`.
+    pub property: String,
+    /// The value as written, e.g. `$sidebar-width`.
+    pub value: String,
+}
+
+impl VariableDeclaration {
+    /// Creates the declaration.
+    pub fn new(property: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            property: property.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Substitutes the variables `lookup` defines and parses the result.
+    ///
+    /// References `lookup` doesn't define are left for the typed parser, so
+    /// `$primary` becomes a theme color. Returns `None` if the value doesn't
+    /// parse (e.g. an undefined variable where a number is expected).
+    pub fn resolve<'v>(&self, lookup: &dyn Fn(&str) -> Option<&'v str>) -> Option<Declaration> {
+        parse_declaration_value(&self.property, &substitute_variables(&self.value, lookup))
+    }
+
+    /// The names of the variables the value references, in order.
+    pub fn references(&self) -> impl Iterator<Item = &str> {
+        variable_references(&self.value)
+    }
+}
+
+impl Declaration {
+    /// Marks this declaration `!important`.
+    pub fn important(self) -> Self {
+        match self {
+            Declaration::Important(_) => self,
+            decl => Declaration::Important(Box::new(decl)),
+        }
+    }
+
+    /// Whether this declaration is marked `!important`.
+    pub fn is_important(&self) -> bool {
+        matches!(self, Declaration::Important(_))
+    }
+
+    /// The declaration without its `!important` flag.
+    pub fn inner(&self) -> &Declaration {
+        match self {
+            Declaration::Important(decl) => decl,
+            decl => decl,
+        }
+    }
+
+    /// The property this declaration sets, e.g. `max-width`.
+    pub fn property(&self) -> &str {
+        match self {
+            Declaration::Color(..) => "color",
+            Declaration::Background(..) => "background",
+            Declaration::Tint(..) => "tint",
+            Declaration::BackgroundTint(..) => "background-tint",
+            Declaration::Width(..) => "width",
+            Declaration::Height(..) => "height",
+            Declaration::MaxHeight(..) => "max-height",
+            Declaration::MaxWidth(..) => "max-width",
+            Declaration::MinHeight(..) => "min-height",
+            Declaration::MinWidth(..) => "min-width",
+            Declaration::Margin(..) => "margin",
+            Declaration::MarginTop(..) => "margin-top",
+            Declaration::MarginRight(..) => "margin-right",
+            Declaration::MarginBottom(..) => "margin-bottom",
+            Declaration::MarginLeft(..) => "margin-left",
+            Declaration::Padding(..) => "padding",
+            Declaration::PaddingTop(..) => "padding-top",
+            Declaration::PaddingRight(..) => "padding-right",
+            Declaration::PaddingBottom(..) => "padding-bottom",
+            Declaration::PaddingLeft(..) => "padding-left",
+            Declaration::Border(..) => "border",
+            Declaration::ScrollbarColor(..) => "scrollbar-color",
+            Declaration::ScrollbarColorHover(..) => "scrollbar-color-hover",
+            Declaration::ScrollbarColorActive(..) => "scrollbar-color-active",
+            Declaration::ScrollbarBackground(..) => "scrollbar-background",
+            Declaration::ScrollbarBackgroundHover(..) => "scrollbar-background-hover",
+            Declaration::ScrollbarBackgroundActive(..) => "scrollbar-background-active",
+            Declaration::ScrollbarCornerColor(..) => "scrollbar-corner-color",
+            Declaration::ScrollbarSize(..) => "scrollbar-size",
+            Declaration::ScrollbarSizeHorizontal(..) => "scrollbar-size-horizontal",
+            Declaration::ScrollbarSizeVertical(..) => "scrollbar-size-vertical",
+            Declaration::ScrollbarGutter(..) => "scrollbar-gutter",
+            Declaration::ScrollbarVisibility(..) => "scrollbar-visibility",
+            Declaration::BoxSizing(..) => "box-sizing",
+            Declaration::Display(..) => "display",
+            Declaration::Visibility(..) => "visibility",
+            Declaration::Opacity(..) => "opacity",
+            Declaration::Position(..) => "position",
+            Declaration::OverflowX(..) => "overflow-x",
+            Declaration::OverflowY(..) => "overflow-y",
+            Declaration::Overflow(..) => "overflow",
+            Declaration::Layout(..) => "layout",
+            Declaration::Dock(..) => "dock",
+            Declaration::Layers(..) => "layers",
+            Declaration::Layer(..) => "layer",
+            Declaration::GridSize(..) => "grid-size",
+            Declaration::GridColumns(..) => "grid-columns",
+            Declaration::GridRows(..) => "grid-rows",
+            Declaration::GridGutter(..) => "grid-gutter",
+            Declaration::ColumnSpan(..) => "column-span",
+            Declaration::RowSpan(..) => "row-span",
+            Declaration::LinkColor(..) => "link-color",
+            Declaration::LinkColorHover(..) => "link-color-hover",
+            Declaration::LinkBackground(..) => "link-background",
+            Declaration::LinkBackgroundHover(..) => "link-background-hover",
+            Declaration::LinkStyle(..) => "link-style",
+            Declaration::LinkStyleHover(..) => "link-style-hover",
+            Declaration::TextAlign(..) => "text-align",
+            Declaration::TextOverflow(..) => "text-overflow",
+            Declaration::TextWrap(..) => "text-wrap",
+            Declaration::LinePad(..) => "line-pad",
+            Declaration::TextOpacity(..) => "text-opacity",
+            Declaration::TextStyle(..) => "text-style",
+            Declaration::ContentAlignHorizontal(..) => "content-align-horizontal",
+            Declaration::ContentAlignVertical(..) => "content-align-vertical",
+            Declaration::ContentAlign(..) => "content-align",
+            Declaration::AlignHorizontal(..) => "align-horizontal",
+            Declaration::AlignVertical(..) => "align-vertical",
+            Declaration::Align(..) => "align",
+            Declaration::BorderTitleAlign(..) => "border-title-align",
+            Declaration::BorderSubtitleAlign(..) => "border-subtitle-align",
+            Declaration::BorderTitleColor(..) => "border-title-color",
+            Declaration::BorderSubtitleColor(..) => "border-subtitle-color",
+            Declaration::BorderTitleBackground(..) => "border-title-background",
+            Declaration::BorderSubtitleBackground(..) => "border-subtitle-background",
+            Declaration::BorderTitleStyle(..) => "border-title-style",
+            Declaration::BorderSubtitleStyle(..) => "border-subtitle-style",
+            Declaration::BorderTop(..) => "border-top",
+            Declaration::BorderBottom(..) => "border-bottom",
+            Declaration::BorderLeft(..) => "border-left",
+            Declaration::BorderRight(..) => "border-right",
+            Declaration::Outline(..) => "outline",
+            Declaration::OutlineTop(..) => "outline-top",
+            Declaration::OutlineRight(..) => "outline-right",
+            Declaration::OutlineBottom(..) => "outline-bottom",
+            Declaration::OutlineLeft(..) => "outline-left",
+            Declaration::Hatch(..) => "hatch",
+            Declaration::Keyline(..) => "keyline",
+            Declaration::Offset(..) => "offset",
+            Declaration::OffsetX(..) => "offset-x",
+            Declaration::OffsetY(..) => "offset-y",
+            Declaration::Unknown(property) => property,
+            Declaration::Important(decl) => decl.property(),
+            Declaration::Variable(variable) => &variable.property,
+        }
+    }
+}
+
+impl fmt::Display for Declaration {
+    /// Writes the declaration as TCSS, without the trailing semicolon:
+    /// `max-width: 50%`, `color: #ff0000 !important`.
+    ///
+    /// Variable declarations keep their value as written. Unknown
+    /// properties, whose values aren't kept, are written as a comment.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Declaration::Important(decl) => write!(f, "{decl} !important"),
+            Declaration::Variable(variable) => {
+                write!(f, "{}: {}", variable.property, variable.value)
+            }
+            Declaration::Unknown(property) => write!(f, "/* {property} */"),
+            decl => {
+                write!(f, "{}: ", decl.property())?;
+                write_value(decl, f)
+            }
+        }
+    }
 }
 
 /// An item inside a rule block: either a declaration or a nested rule.
@@ -447,12 +935,61 @@ pub struct Rule {
     pub selectors: SelectorList,
     /// The declarations and nested rules inside this rule.
     pub items: Vec<RuleItem>,
+    /// The cascade tier; any app rule beats any widget default rule.
+    pub tier: CascadeTier,
+    /// If set, the rule only matches widgets of this type and their
+    /// descendants (used for scoped widget default CSS).
+    pub scope: Option<String>,
+    /// If set, the rule only applies while this `@media` query matches.
+    pub media: Option<MediaQuery>,
+    /// Where the rule was written, if it was parsed.
+    pub origin: Option<RuleOrigin>,
+}
+
+/// Where a rule was written: its source and line.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RuleOrigin {
+    /// The file the rule came from, or a name for a stylesheet given as a
+    /// string (see [`StyleSheet::with_source`]). `None` if unnamed.
+    pub source: Option<Arc<str>>,
+    /// The 1-based line of the rule's selector.
+    pub line: usize,
+}
+
+impl fmt::Display for RuleOrigin {
+    /// Writes `source:line`, or `line N` for an unnamed source.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{source}:{}", self.line),
+            None => write!(f, "line {}", self.line),
+        }
+    }
+}
+
+/// Which layer of the cascade a rule belongs to.
+///
+/// Tiers are compared before specificity, so a widget default rule never
+/// overrides an app rule, however specific its selector.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CascadeTier {
+    /// A widget's `default_css`.
+    Default,
+    /// The app's stylesheet.
+    #[default]
+    App,
 }
 
 impl Rule {
-    /// Creates a new rule with the given selectors and items.
+    /// Creates a new app-tier rule with the given selectors and items.
     pub fn new(selectors: SelectorList, items: Vec<RuleItem>) -> Self {
-        Self { selectors, items }
+        Self {
+            selectors,
+            items,
+            tier: CascadeTier::App,
+            scope: None,
+            media: None,
+            origin: None,
+        }
     }
 
     /// Returns only the declarations from this rule (excludes nested rules).
@@ -473,5 +1010,456 @@ impl Rule {
 #[derive(Clone, Debug, Default)]
 pub struct StyleSheet {
     /// The rules in this stylesheet, in source order.
+    ///
+    /// The cascade indexes these on first use and rebuilds the index when
+    /// rules are added or removed; after editing a rule in place through
+    /// this field, call [`StyleSheet::invalidate_cache`], or edit through
+    /// [`rules_mut`](Self::rules_mut), which does so itself.
     pub rules: Vec<Rule>,
+    /// Rule index and matched-rule memo for the cascade.
+    pub(crate) cache: RuleCache,
+    /// The terminal that `@media` queries are evaluated against.
+    environment: MediaEnvironment,
+    /// `$name: value;` definitions from the stylesheet source.
+    variables: StylesheetVariables,
+    /// Variables set by the app, which take precedence over definitions.
+    overrides: StylesheetVariables,
+    /// Parsed values of variable declarations.
+    variable_cache: VariableCache,
+}
+
+impl StyleSheet {
+    /// Creates a stylesheet from flattened rules.
+    pub fn new(rules: Vec<Rule>) -> Self {
+        Self {
+            rules,
+            cache: RuleCache::default(),
+            environment: MediaEnvironment::default(),
+            variables: StylesheetVariables::default(),
+            overrides: StylesheetVariables::default(),
+            variable_cache: VariableCache::default(),
+        }
+    }
+
+    /// Creates a stylesheet from flattened rules and the `$` variables
+    /// defined in their source.
+    pub fn with_variables(rules: Vec<Rule>, variables: StylesheetVariables) -> Self {
+        let mut stylesheet = Self::new(rules);
+        stylesheet.variables = variables;
+        stylesheet
+    }
+
+    /// The rules in this stylesheet, in source order.
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Mutable access to the rules, e.g. to add, remove or edit them.
+    ///
+    /// The cascade's rule index and matched-rule memo are dropped, to be
+    /// rebuilt from the edited rules on first use.
+    pub fn rules_mut(&mut self) -> &mut Vec<Rule> {
+        self.invalidate_cache();
+        &mut self.rules
+    }
+
+    /// Consumes the stylesheet, returning its rules.
+    pub fn into_rules(self) -> Vec<Rule> {
+        self.rules
+    }
+
+    /// The value of variable `name`: set by the app, else defined in the
+    /// stylesheet. Theme variables aren't included.
+    pub fn variable(&self, name: &str) -> Option<&str> {
+        self.overrides
+            .get(name)
+            .or_else(|| self.variables.get(name))
+    }
+
+    /// Sets variable `name`, overriding any stylesheet definition (and
+    /// theme variable of the same name).
+    ///
+    /// Returns whether any rule uses the variable, in which case widgets
+    /// those rules match ([`rules_using_variable`](Self::rules_using_variable))
+    /// should be restyled.
+    pub fn set_variable(&mut self, name: &str, value: &str) -> bool {
+        if self.overrides.get(name) == Some(value) {
+            return false;
+        }
+        self.overrides.define(name.to_string(), value.to_string());
+        self.uses_variable(name)
+    }
+
+    /// Removes an app-set variable, restoring the stylesheet's definition or
+    /// theme variable. Returns whether any rule uses the variable.
+    pub fn remove_variable(&mut self, name: &str) -> bool {
+        self.overrides.remove(name) && self.uses_variable(name)
+    }
+
+    /// Indices of the rules with a declaration referencing variable `name`,
+    /// directly or through other variables.
+    pub fn rules_using_variable(&self, name: &str) -> Vec<usize> {
+        self.rules
+            .iter()
+            .enumerate()
+            .filter(|(_, rule)| self.rule_uses_variable(rule, name))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    fn uses_variable(&self, name: &str) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| self.rule_uses_variable(rule, name))
+    }
+
+    fn rule_uses_variable(&self, rule: &Rule, name: &str) -> bool {
+        rule.items.iter().any(|item| match item {
+            RuleItem::Declaration(declaration) => match declaration.inner() {
+                Declaration::Variable(variable) => {
+                    self.references_variable(&variable.value, name, 0)
+                }
+                _ => false,
+            },
+            RuleItem::NestedRule(_) => false,
+        })
+    }
+
+    /// Whether `value` references `name`, directly or through the values of
+    /// the variables it references.
+    fn references_variable(&self, value: &str, name: &str, depth: usize) -> bool {
+        variable_references(value).any(|reference| {
+            reference == name
+                || (depth < MAX_VARIABLE_DEPTH
+                    && self
+                        .variable(reference)
+                        .is_some_and(|value| self.references_variable(value, name, depth + 1)))
+        })
+    }
+
+    /// The declaration `declaration` resolves to, with variables
+    /// substituted from app overrides, then stylesheet definitions.
+    ///
+    /// References to neither are left for the typed parser, so `$primary`
+    /// becomes a theme color the cascade resolves against the current
+    /// theme. Returns `None`, logging a warning, if the value doesn't parse,
+    /// e.g. when a variable is undefined.
+    pub fn resolve_declaration(&self, declaration: &VariableDeclaration) -> Option<Declaration> {
+        let value = substitute_variables(&declaration.value, &|name| self.variable(name));
+        self.variable_cache.parse(declaration, &value)
+    }
+
+    /// Checks that variable declarations whose variables are all defined by
+    /// the stylesheet resolve to valid values.
+    ///
+    /// Declarations referencing other variables can only be checked once
+    /// the app's variables and theme are known, by the cascade.
+    pub(crate) fn check_variables(&self) -> Result<(), TcssError> {
+        let declarations = self.rules.iter().flat_map(|rule| &rule.items);
+        for item in declarations {
+            let RuleItem::Declaration(declaration) = item else {
+                continue;
+            };
+            let Declaration::Variable(variable) = declaration.inner() else {
+                continue;
+            };
+            let value = substitute_variables(&variable.value, &|name| self.variable(name));
+            if variable_references(&value).next().is_none()
+                && parse_declaration_value(&variable.property, &value).is_none()
+            {
+                return Err(TcssError::InvalidSyntax(format!(
+                    "invalid value for {}: `{}` resolves to `{value}`",
+                    variable.property, variable.value
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The terminal that `@media` queries are evaluated against.
+    pub fn environment(&self) -> MediaEnvironment {
+        self.environment
+    }
+
+    /// Sets the terminal that `@media` queries are evaluated against.
+    ///
+    /// Returns whether the result of any query may have changed, in which
+    /// case styles should be resolved again.
+    pub fn set_environment(&mut self, environment: MediaEnvironment) -> bool {
+        let old = std::mem::replace(&mut self.environment, environment);
+        self.rules
+            .iter()
+            .filter_map(|rule| rule.media.as_ref())
+            .any(|media| media.changes(&old, &environment))
+    }
+
+    /// Drops the rule index and matched-rule memo, so they are rebuilt from
+    /// the current rules.
+    ///
+    /// Methods that change how rules match call this themselves.
+    pub fn invalidate_cache(&mut self) {
+        self.cache = RuleCache::default();
+    }
+
+    /// Moves every rule to the widget default tier, optionally scoped to
+    /// widgets of type `scope` and their descendants.
+    pub fn into_default_tier(mut self, scope: Option<&str>) -> Self {
+        for rule in &mut self.rules {
+            rule.tier = CascadeTier::Default;
+            rule.scope = scope.map(str::to_string);
+        }
+        self.invalidate_cache();
+        self
+    }
+
+    /// Names the source of rules that don't have one yet, e.g. the file or
+    /// widget a stylesheet string came from, for diagnostics.
+    pub fn with_source(mut self, source: &str) -> Self {
+        let source: Arc<str> = source.into();
+        for origin in self
+            .rules
+            .iter_mut()
+            .filter_map(|rule| rule.origin.as_mut())
+        {
+            origin.source.get_or_insert_with(|| source.clone());
+        }
+        self
+    }
+
+    /// Appends `other`'s rules after this stylesheet's, so they come later
+    /// in source order. Rules keep their [`origin`](Rule::origin), so
+    /// diagnostics still point at the file and line they were written on.
+    ///
+    /// Variables of both share one scope; `other`'s definitions win.
+    pub fn merge(&mut self, other: StyleSheet) {
+        self.rules.extend(other.rules);
+        self.variables.extend(other.variables);
+        self.overrides.extend(other.overrides);
+        self.invalidate_cache();
+    }
+
+    /// Whether any selector needs sibling metadata
+    /// ([`WidgetMeta::siblings`](crate::parser::cascade::WidgetMeta::siblings))
+    /// to match.
+    ///
+    /// Style resolvers can skip collecting siblings when this is false.
+    ///
+    /// Answered from the cascade's rule index, so it is cheap to ask on
+    /// every resolve.
+    pub fn needs_sibling_context(&self) -> bool {
+        self.cache
+            .with_index(&self.rules, |index| index.uses_siblings)
+    }
+
+    /// Whether any selector uses the pseudo-class `name` (e.g. `focus-within`).
+    ///
+    /// Answered from the cascade's rule index, like
+    /// [`needs_sibling_context`](Self::needs_sibling_context).
+    pub fn uses_pseudo_class(&self, name: &str) -> bool {
+        self.cache
+            .with_index(&self.rules, |index| index.uses_pseudo_class(name))
+    }
+}
+
+impl fmt::Display for Rule {
+    /// Writes the rule as a TCSS block, wrapped in its `@media` query if it
+    /// has one. The cascade tier and scope have no TCSS syntax and are not
+    /// written.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_rules(f, std::slice::from_ref(self))
+    }
+}
+
+impl fmt::Display for StyleSheet {
+    /// Writes the stylesheet as TCSS that parses back to the same rules:
+    /// variable definitions (with app overrides applied), then the rules,
+    /// with consecutive rules under the same `@media` query sharing a
+    /// block.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut names: Vec<&str> = self
+            .variables
+            .names()
+            .chain(self.overrides.names())
+            .collect();
+        names.sort_unstable();
+        names.dedup();
+        for name in &names {
+            if let Some(value) = self.variable(name) {
+                writeln!(f, "${name}: {value};")?;
+            }
+        }
+        if !names.is_empty() && !self.rules.is_empty() {
+            writeln!(f)?;
+        }
+        write_rules(f, &self.rules)
+    }
+}
+
+/// Writes `rules` separated by blank lines, grouping consecutive rules with
+/// the same `@media` query into one block.
+fn write_rules(f: &mut fmt::Formatter<'_>, rules: &[Rule]) -> fmt::Result {
+    for (i, group) in rules.chunk_by(|a, b| a.media == b.media).enumerate() {
+        if i > 0 {
+            writeln!(f)?;
+        }
+        match &group[0].media {
+            Some(media) => {
+                writeln!(f, "@media {media} {{")?;
+                for (j, rule) in group.iter().enumerate() {
+                    if j > 0 {
+                        writeln!(f)?;
+                    }
+                    write_rule(f, rule, 1)?;
+                }
+                writeln!(f, "}}")?;
+            }
+            None => {
+                for (j, rule) in group.iter().enumerate() {
+                    if j > 0 {
+                        writeln!(f)?;
+                    }
+                    write_rule(f, rule, 0)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes a rule block and its nested rules, indented `depth` levels.
+fn write_rule(f: &mut fmt::Formatter<'_>, rule: &Rule, depth: usize) -> fmt::Result {
+    let indent = "    ".repeat(depth);
+    if rule.items.is_empty() {
+        return writeln!(f, "{indent}{} {{}}", rule.selectors);
+    }
+    writeln!(f, "{indent}{} {{", rule.selectors)?;
+    for item in &rule.items {
+        match item {
+            RuleItem::Declaration(Declaration::Unknown(property)) => {
+                writeln!(f, "{indent}    /* {property} */")?
+            }
+            RuleItem::Declaration(decl) => writeln!(f, "{indent}    {decl};")?,
+            RuleItem::NestedRule(nested) => write_rule(f, nested, depth + 1)?,
+        }
+    }
+    writeln!(f, "{indent}}}")
+}
+
+/// Writes a declaration's value as TCSS.
+fn write_value(declaration: &Declaration, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match declaration {
+        Declaration::Color(value)
+        | Declaration::Background(value)
+        | Declaration::Tint(value)
+        | Declaration::BackgroundTint(value)
+        | Declaration::ScrollbarColor(value)
+        | Declaration::ScrollbarColorHover(value)
+        | Declaration::ScrollbarColorActive(value)
+        | Declaration::ScrollbarBackground(value)
+        | Declaration::ScrollbarBackgroundHover(value)
+        | Declaration::ScrollbarBackgroundActive(value)
+        | Declaration::ScrollbarCornerColor(value)
+        | Declaration::LinkColor(value)
+        | Declaration::LinkColorHover(value)
+        | Declaration::LinkBackground(value)
+        | Declaration::LinkBackgroundHover(value)
+        | Declaration::BorderTitleColor(value)
+        | Declaration::BorderSubtitleColor(value)
+        | Declaration::BorderTitleBackground(value)
+        | Declaration::BorderSubtitleBackground(value) => write!(f, "{value}"),
+        Declaration::Width(value)
+        | Declaration::Height(value)
+        | Declaration::MaxHeight(value)
+        | Declaration::MaxWidth(value)
+        | Declaration::MinHeight(value)
+        | Declaration::MinWidth(value)
+        | Declaration::MarginTop(value)
+        | Declaration::MarginRight(value)
+        | Declaration::MarginBottom(value)
+        | Declaration::MarginLeft(value)
+        | Declaration::PaddingTop(value)
+        | Declaration::PaddingRight(value)
+        | Declaration::PaddingBottom(value)
+        | Declaration::PaddingLeft(value)
+        | Declaration::OffsetX(value)
+        | Declaration::OffsetY(value) => write!(f, "{value}"),
+        Declaration::Margin(value) | Declaration::Padding(value) => write!(f, "{value}"),
+        Declaration::Border(value)
+        | Declaration::BorderTop(value)
+        | Declaration::BorderBottom(value)
+        | Declaration::BorderLeft(value)
+        | Declaration::BorderRight(value)
+        | Declaration::Outline(value)
+        | Declaration::OutlineTop(value)
+        | Declaration::OutlineRight(value)
+        | Declaration::OutlineBottom(value)
+        | Declaration::OutlineLeft(value) => write!(f, "{value}"),
+        Declaration::LinkStyle(value)
+        | Declaration::LinkStyleHover(value)
+        | Declaration::TextStyle(value)
+        | Declaration::BorderTitleStyle(value)
+        | Declaration::BorderSubtitleStyle(value) => write!(f, "{value}"),
+        Declaration::ContentAlignHorizontal(value)
+        | Declaration::AlignHorizontal(value)
+        | Declaration::BorderTitleAlign(value)
+        | Declaration::BorderSubtitleAlign(value) => write!(f, "{value}"),
+        Declaration::ScrollbarSizeHorizontal(value)
+        | Declaration::ScrollbarSizeVertical(value)
+        | Declaration::ColumnSpan(value)
+        | Declaration::RowSpan(value)
+        | Declaration::LinePad(value) => write!(f, "{value}"),
+        Declaration::Opacity(value) | Declaration::TextOpacity(value) => write!(f, "{value}"),
+        Declaration::OverflowX(value) | Declaration::OverflowY(value) => write!(f, "{value}"),
+        Declaration::ScrollbarSize(value) => write!(f, "{value}"),
+        Declaration::ScrollbarGutter(value) => write!(f, "{value}"),
+        Declaration::ScrollbarVisibility(value) => write!(f, "{value}"),
+        Declaration::BoxSizing(value) => write!(f, "{value}"),
+        Declaration::Display(value) => write!(f, "{value}"),
+        Declaration::Visibility(value) => write!(f, "{value}"),
+        Declaration::Layout(value) => write!(f, "{value}"),
+        Declaration::Position(value) => write!(f, "{value}"),
+        Declaration::Dock(value) => write!(f, "{value}"),
+        Declaration::Layer(value) => write!(f, "{value}"),
+        Declaration::TextAlign(value) => write!(f, "{value}"),
+        Declaration::TextOverflow(value) => write!(f, "{value}"),
+        Declaration::TextWrap(value) => write!(f, "{value}"),
+        Declaration::ContentAlignVertical(value) => write!(f, "{value}"),
+        Declaration::AlignVertical(value) => write!(f, "{value}"),
+        Declaration::Hatch(value) => write!(f, "{value}"),
+        Declaration::Keyline(value) => write!(f, "{value}"),
+        Declaration::Overflow(x, y) if x == y => write!(f, "{x}"),
+        Declaration::Overflow(x, y) => write!(f, "{x} {y}"),
+        Declaration::ContentAlign(h, v) | Declaration::Align(h, v) => write!(f, "{h} {v}"),
+        Declaration::Offset(x, y) => write!(f, "{x} {y}"),
+        Declaration::GridSize(columns, rows) => {
+            write!(f, "{columns}")?;
+            match rows {
+                Some(rows) => write!(f, " {rows}"),
+                None => Ok(()),
+            }
+        }
+        Declaration::GridGutter(vertical, horizontal) => {
+            write!(f, "{vertical}")?;
+            match horizontal {
+                Some(horizontal) => write!(f, " {horizontal}"),
+                None => Ok(()),
+            }
+        }
+        Declaration::GridColumns(scalars) | Declaration::GridRows(scalars) => {
+            write_separated(f, scalars)
+        }
+        Declaration::Layers(names) => write_separated(f, names),
+        Declaration::Unknown(_) | Declaration::Important(_) | Declaration::Variable(_) => Ok(()),
+    }
+}
+
+/// Writes `items` separated by spaces.
+fn write_separated<T: fmt::Display>(f: &mut fmt::Formatter<'_>, items: &[T]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, " ")?;
+        }
+        write!(f, "{item}")?;
+    }
+    Ok(())
 }
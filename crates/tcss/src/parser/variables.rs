@@ -3,7 +3,10 @@
 //! This module handles TCSS variables (SCSS-style `$variable` syntax):
 //!
 //! - [`extract_variables`]: Scans source for variable definitions
-//! - [`resolve_variables`]: Replaces variable references with values
+//! - [`strip_definitions`]: Removes definition lines before parsing
+//! - [`substitute_variables`]: Replaces variable references in a value
+//! - [`variable_references`]: Lists the variables a value references
+//! - [`resolve_variables`]: Replaces variable references in a whole source
 //!
 //! ## Variable Syntax
 //!
@@ -22,12 +25,25 @@
 //! ## Processing Steps
 //!
 //! 1. Block comments (`/* */`) are stripped
-//! 2. Variable definitions (`$name: value;`) are extracted
-//! 3. Variable references (`$name`) are replaced with values
-//! 4. Definition lines are removed from output
+//! 2. Variable definitions (`$name: value;`) are extracted and their lines
+//!    removed
+//! 3. Declarations referencing variables are parsed as
+//!    [`Declaration::Variable`](crate::parser::Declaration::Variable), keeping
+//!    the value as written
+//! 4. The cascade resolves them against the variable scope: app overrides,
+//!    then stylesheet definitions. References neither defines are left for
+//!    theme variables, resolved against the current theme
+//!
+//! Declarations whose variables the stylesheet defines are checked when it
+//! is parsed; others resolving to an invalid value are logged and ignored.
+//!
+//! Because references stay in the parsed stylesheet, variables can change
+//! at runtime ([`StyleSheet::set_variable`](crate::parser::StyleSheet::set_variable)).
 
 use crate::error::TcssError;
+use crate::parser::{Declaration, VariableDeclaration, parse_declaration_value};
 use std::collections::HashMap;
+use std::sync::{Mutex, PoisonError};
 
 /// Storage for stylesheet-defined variables.
 ///
@@ -53,6 +69,145 @@ impl StylesheetVariables {
     pub fn resolve(&self, name: &str) -> Option<String> {
         self.variables.get(name).cloned()
     }
+
+    /// The value of a variable, if defined.
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.variables.get(name).map(String::as_str)
+    }
+
+    /// The names of the defined variables, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.variables.keys().map(String::as_str)
+    }
+
+    /// Removes a variable, returning whether it was defined.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.variables.remove(name).is_some()
+    }
+
+    /// Whether no variables are defined.
+    pub fn is_empty(&self) -> bool {
+        self.variables.is_empty()
+    }
+
+    /// Defines all of `other`'s variables, replacing existing ones.
+    pub fn extend(&mut self, other: StylesheetVariables) {
+        self.variables.extend(other.variables);
+    }
+}
+
+/// Nesting limit for variables defined in terms of other variables, which
+/// also stops reference cycles.
+pub(crate) const MAX_VARIABLE_DEPTH: usize = 8;
+
+/// Replaces `$name` references in a declaration value with their values
+/// from `lookup`, recursively. Undefined references are kept as written.
+pub fn substitute_variables<'v>(value: &str, lookup: &dyn Fn(&str) -> Option<&'v str>) -> String {
+    substitute(value, lookup, 0)
+}
+
+fn substitute<'v>(value: &str, lookup: &dyn Fn(&str) -> Option<&'v str>, depth: usize) -> String {
+    let mut output = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('$') {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let end = name_end(after);
+        let name = &after[..end];
+        match lookup(name) {
+            Some(replacement) if depth < MAX_VARIABLE_DEPTH => {
+                output.push_str(&substitute(replacement, lookup, depth + 1));
+            }
+            _ => {
+                output.push('$');
+                output.push_str(name);
+            }
+        }
+        rest = &after[end..];
+    }
+    output.push_str(rest);
+    output
+}
+
+/// The names of the variables `value` references, in order.
+pub fn variable_references(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split('$')
+        .skip(1)
+        .map(|after| &after[..name_end(after)])
+}
+
+/// The length of the variable name at the start of `after` (just past a
+/// `$`).
+fn name_end(after: &str) -> usize {
+    after
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(after.len())
+}
+
+/// Distinct variable values parsed before the cache is cleared.
+const CACHE_CAPACITY: usize = 1024;
+
+/// Parsed values of variable declarations, so the cascade doesn't parse the
+/// same substituted value for every widget.
+///
+/// Cloning gives an empty cache.
+#[derive(Default)]
+pub(crate) struct VariableCache(Mutex<HashMap<(String, String), Option<Declaration>>>);
+
+impl VariableCache {
+    /// Parses `value`, `declaration`'s value with its variables substituted.
+    ///
+    /// Returns `None` if it isn't valid for the property, logging a warning
+    /// the first time.
+    pub(crate) fn parse(
+        &self,
+        declaration: &VariableDeclaration,
+        value: &str,
+    ) -> Option<Declaration> {
+        let mut cache = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        let key = (declaration.property.clone(), value.to_string());
+        if let Some(parsed) = cache.get(&key) {
+            return parsed.clone();
+        }
+        if cache.len() >= CACHE_CAPACITY {
+            cache.clear();
+        }
+        let parsed = parse_declaration_value(&declaration.property, value);
+        if parsed.is_none() {
+            log::warn!(
+                "CSS: ignoring `{}: {}`, which resolves to `{value}`",
+                declaration.property,
+                declaration.value
+            );
+        }
+        cache.insert(key, parsed.clone());
+        parsed
+    }
+}
+
+impl Clone for VariableCache {
+    fn clone(&self) -> Self {
+        VariableCache::default()
+    }
+}
+
+impl std::fmt::Debug for VariableCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("VariableCache")
+    }
+}
+
+/// Removes variable definition lines (`$name: value;`) from `source`.
+pub fn strip_definitions(source: &str) -> String {
+    let mut output = String::with_capacity(source.len());
+    for line in source.lines() {
+        if !line.trim_start().starts_with('$') {
+            output.push_str(line);
+        }
+        output.push('\n');
+    }
+    output
 }
 
 pub fn resolve_variables(source: &str, vars: &StylesheetVariables) -> Result<String, TcssError> {
@@ -29,6 +29,8 @@
 //! }
 //! ```
 
+use std::fmt;
+
 use crate::types::color::RgbaColor;
 
 /// The visual style of a border edge.
@@ -135,3 +137,39 @@ impl Border {
             && self.left.kind == BorderKind::None
     }
 }
+
+impl fmt::Display for BorderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BorderKind::None => "none",
+            BorderKind::Ascii => "ascii",
+            BorderKind::Blank => "blank",
+            BorderKind::Block => "block",
+            BorderKind::Double => "double",
+            BorderKind::Dashed => "dashed",
+            BorderKind::Heavy => "heavy",
+            BorderKind::Hidden => "hidden",
+            BorderKind::Hkey => "hkey",
+            BorderKind::Outer => "outer",
+            BorderKind::Inner => "inner",
+            BorderKind::Panel => "panel",
+            BorderKind::Solid => "solid",
+            BorderKind::Round => "round",
+            BorderKind::Tall => "tall",
+            BorderKind::Thick => "thick",
+            BorderKind::Vkey => "vkey",
+            BorderKind::Wide => "wide",
+        })
+    }
+}
+
+impl fmt::Display for BorderEdge {
+    /// Writes the edge as TCSS, e.g. `solid` or `round #ff0000`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+        if let Some(color) = &self.color {
+            write!(f, " {color}")?;
+        }
+        Ok(())
+    }
+}
@@ -938,6 +938,36 @@ impl RgbaColor {
     }
 }
 
+impl fmt::Display for RgbaColor {
+    /// Writes the color as TCSS: `$name` for theme variables, `auto`,
+    /// `#rrggbb` when opaque and `rgba(r, g, b, a)` otherwise. Theme and
+    /// auto colors write their alpha as a percentage (`$primary 40%`).
+    ///
+    /// ANSI palette colors have no TCSS syntax and are written as their RGB
+    /// value.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keyword = match &self.theme_var {
+            Some(name) => format!("${name}"),
+            None if self.auto => "auto".to_string(),
+            None if self.a >= 1.0 => {
+                return write!(f, "#{:02x}{:02x}{:02x}", self.r, self.g, self.b);
+            }
+            None if self.a <= 0.0 && (self.r, self.g, self.b) == (0, 0, 0) => {
+                return write!(f, "transparent");
+            }
+            None => {
+                return write!(f, "rgba({}, {}, {}, {})", self.r, self.g, self.b, self.a);
+            }
+        };
+        write!(f, "{keyword}")?;
+        if self.a < 1.0 {
+            write!(f, " ")?;
+            crate::types::write_percent(f, self.a)?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
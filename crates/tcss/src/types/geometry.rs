@@ -6,6 +6,8 @@
 //! - [`Scalar`]: A numeric value with a unit
 //! - [`Spacing`]: Four-sided spacing for margins and padding
 
+use std::fmt;
+
 /// Units of measurement for dimension values.
 ///
 /// TCSS extends CSS units with terminal-specific units like cells.
@@ -110,3 +112,41 @@ impl Spacing {
         }
     }
 }
+
+impl fmt::Display for Scalar {
+    /// Writes the scalar as TCSS, e.g. `10`, `50%`, `1fr` or `auto`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let suffix = match self.unit {
+            Unit::Auto => return write!(f, "auto"),
+            Unit::Cells => "",
+            Unit::Percent => "%",
+            Unit::Width => "w",
+            Unit::Height => "h",
+            Unit::ViewWidth => "vw",
+            Unit::ViewHeight => "vh",
+            Unit::Fraction => "fr",
+        };
+        write!(f, "{}{suffix}", self.value)
+    }
+}
+
+impl fmt::Display for Spacing {
+    /// Writes the shortest of the one, two and four value forms.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Spacing {
+            top,
+            right,
+            bottom,
+            left,
+        } = self;
+        if top == bottom && left == right {
+            if top == left {
+                write!(f, "{top}")
+            } else {
+                write!(f, "{top} {left}")
+            }
+        } else {
+            write!(f, "{top} {right} {bottom} {left}")
+        }
+    }
+}
@@ -3,6 +3,8 @@
 //! Hatch patterns are used to fill areas with repeating characters,
 //! creating visual textures similar to cross-hatching in art.
 
+use std::fmt;
+
 use crate::types::RgbaColor;
 
 /// Hatch pattern character mapping (matches Python Textual exactly).
@@ -126,3 +128,30 @@ impl Default for Hatch {
         }
     }
 }
+
+impl fmt::Display for HatchPattern {
+    /// Writes the pattern's name, or a custom character in quotes.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HatchPattern::Left => write!(f, "left"),
+            HatchPattern::Right => write!(f, "right"),
+            HatchPattern::Cross => write!(f, "cross"),
+            HatchPattern::Horizontal => write!(f, "horizontal"),
+            HatchPattern::Vertical => write!(f, "vertical"),
+            HatchPattern::Custom('"') => write!(f, "'\"'"),
+            HatchPattern::Custom(c) => write!(f, "\"{c}\""),
+        }
+    }
+}
+
+impl fmt::Display for Hatch {
+    /// Writes `<pattern> <color> [opacity%]`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.pattern, self.color)?;
+        if self.opacity < 1.0 {
+            write!(f, " ")?;
+            crate::types::write_percent(f, self.opacity)?;
+        }
+        Ok(())
+    }
+}
@@ -71,6 +71,16 @@ impl Keyline {
     }
 }
 
+impl std::fmt::Display for Keyline {
+    /// Writes `<style> <color>`, or just `none` for a transparent `none`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.style == KeylineStyle::None && self.color == RgbaColor::transparent() {
+            return write!(f, "none");
+        }
+        write!(f, "{} {}", self.style, self.color)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
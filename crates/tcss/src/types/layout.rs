@@ -22,6 +22,8 @@
 //! .grid-container { layout: grid; }
 //! ```
 
+use std::fmt;
+
 /// Controls whether a widget is rendered and participates in layout.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum Display {
@@ -127,3 +129,70 @@ pub enum Position {
     /// Widget is removed from layout flow and positioned absolutely.
     Absolute,
 }
+
+impl fmt::Display for Display {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Display::Block => "block",
+            Display::None => "none",
+        })
+    }
+}
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Layout::Vertical => "vertical",
+            Layout::Horizontal => "horizontal",
+            Layout::Grid => "grid",
+        })
+    }
+}
+
+impl fmt::Display for Visibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Visibility::Visible => "visible",
+            Visibility::Hidden => "hidden",
+        })
+    }
+}
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Overflow::Hidden => "hidden",
+            Overflow::Auto => "auto",
+            Overflow::Scroll => "scroll",
+        })
+    }
+}
+
+impl fmt::Display for BoxSizing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BoxSizing::ContentBox => "content-box",
+            BoxSizing::BorderBox => "border-box",
+        })
+    }
+}
+
+impl fmt::Display for Dock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Dock::Top => "top",
+            Dock::Bottom => "bottom",
+            Dock::Left => "left",
+            Dock::Right => "right",
+        })
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Position::Relative => "relative",
+            Position::Absolute => "absolute",
+        })
+    }
+}
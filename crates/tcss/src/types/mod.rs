@@ -51,6 +51,21 @@ pub use scrollbar::{ScrollbarGutter, ScrollbarSize, ScrollbarStyle, ScrollbarVis
 pub use text::{AlignHorizontal, AlignVertical, TextAlign, TextOverflow, TextStyle, TextWrap};
 pub use theme::{ColorSystem, Theme};
 
+use std::fmt;
+
+/// Writes `fraction` as a TCSS percentage (`0.4` as `40%`), with as few
+/// decimals as parse back to the same value.
+pub(crate) fn write_percent(f: &mut fmt::Formatter<'_>, fraction: f32) -> fmt::Result {
+    let percent = f64::from(fraction) * 100.0;
+    for precision in 0..=6 {
+        let text = format!("{percent:.precision$}");
+        if text.parse::<f32>().is_ok_and(|p| p / 100.0 == fraction) {
+            return write!(f, "{text}%");
+        }
+    }
+    write!(f, "{percent}%")
+}
+
 /// The final computed style for a widget after cascade resolution.
 ///
 /// This struct contains all resolved style properties that can be
@@ -1,5 +1,7 @@
 //! Scrollbar CSS types.
 
+use std::fmt;
+
 use super::RgbaColor;
 
 /// Scrollbar size configuration.
@@ -147,6 +149,35 @@ impl ScrollbarStyle {
     }
 }
 
+impl fmt::Display for ScrollbarSize {
+    /// Writes `horizontal vertical`, or one value if they're equal.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.horizontal == self.vertical {
+            write!(f, "{}", self.horizontal)
+        } else {
+            write!(f, "{} {}", self.horizontal, self.vertical)
+        }
+    }
+}
+
+impl fmt::Display for ScrollbarGutter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ScrollbarGutter::Auto => "auto",
+            ScrollbarGutter::Stable => "stable",
+        })
+    }
+}
+
+impl fmt::Display for ScrollbarVisibility {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ScrollbarVisibility::Visible => "visible",
+            ScrollbarVisibility::Hidden => "hidden",
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -29,6 +29,8 @@
 //! }
 //! ```
 
+use std::fmt;
+
 /// Text style modifiers for terminal rendering.
 ///
 /// Combines multiple ANSI text attributes that can be applied
@@ -232,3 +234,85 @@ pub enum AlignVertical {
     /// Align content to the bottom.
     Bottom,
 }
+
+impl fmt::Display for TextStyle {
+    /// Writes the style's keywords (`bold italic`), `none` or `$name` for a
+    /// theme variable.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.theme_var {
+            return write!(f, "${name}");
+        }
+        let keywords = [
+            (self.bold, "bold"),
+            (self.dim, "dim"),
+            (self.italic, "italic"),
+            (self.underline, "underline"),
+            (self.underline2, "underline2"),
+            (self.blink, "blink"),
+            (self.blink2, "blink2"),
+            (self.reverse, "reverse"),
+            (self.strike, "strike"),
+            (self.overline, "overline"),
+        ];
+        let mut keywords = keywords.iter().filter(|(set, _)| *set).map(|(_, k)| k);
+        match keywords.next() {
+            Some(first) => {
+                write!(f, "{first}")?;
+                keywords.try_for_each(|keyword| write!(f, " {keyword}"))
+            }
+            None => write!(f, "none"),
+        }
+    }
+}
+
+impl fmt::Display for TextAlign {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TextAlign::Start => "start",
+            TextAlign::End => "end",
+            TextAlign::Left => "left",
+            TextAlign::Center => "center",
+            TextAlign::Right => "right",
+            TextAlign::Justify => "justify",
+        })
+    }
+}
+
+impl fmt::Display for TextOverflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TextOverflow::Clip => "clip",
+            TextOverflow::Fold => "fold",
+            TextOverflow::Ellipsis => "ellipsis",
+        })
+    }
+}
+
+impl fmt::Display for TextWrap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TextWrap::Wrap => "wrap",
+            TextWrap::NoWrap => "nowrap",
+        })
+    }
+}
+
+impl fmt::Display for AlignHorizontal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AlignHorizontal::Left => "left",
+            AlignHorizontal::Center => "center",
+            AlignHorizontal::Right => "right",
+        })
+    }
+}
+
+impl fmt::Display for AlignVertical {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AlignVertical::Top => "top",
+            AlignVertical::Middle => "middle",
+            AlignVertical::Bottom => "bottom",
+        })
+    }
+}
@@ -0,0 +1,213 @@
+//! Tests for `@import`, stylesheet files and rule origins.
+
+use std::fs;
+use std::path::PathBuf;
+
+use tcss::TcssError;
+use tcss::parser::{Declaration, StyleSheet, load_stylesheet, parse_stylesheet};
+use tcss::types::Scalar;
+
+// ============================================================================
+// HELPER FUNCTIONS
+// ============================================================================
+
+/// Writes `files` (relative path, contents) into a fresh directory named
+/// after the test and returns it.
+fn write_files(test: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("tcss-{test}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    for (path, contents) in files {
+        let path = dir.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, contents).unwrap();
+    }
+    dir
+}
+
+/// Each rule's first selector with the file name and line it came from.
+fn origins(stylesheet: &StyleSheet) -> Vec<(String, String, usize)> {
+    stylesheet
+        .rules
+        .iter()
+        .map(|rule| {
+            let origin = rule.origin.as_ref().expect("parsed rules have an origin");
+            let file = origin.source.as_deref().unwrap_or_default();
+            let name = file.rsplit(['/', '\\']).next().unwrap_or_default();
+            (rule.selectors.to_string(), name.to_string(), origin.line)
+        })
+        .collect()
+}
+
+// ============================================================================
+// ORIGINS
+// ============================================================================
+
+#[test]
+fn test_rules_record_their_line() {
+    let stylesheet = parse_stylesheet(
+        "/* header
+          * comment */
+         Button { color: red; }
+
+         Label {
+             width: 1;
+             &:hover { width: 2; }
+         }
+         @media (dark) {
+             Input { width: 3; }
+         }",
+    )
+    .unwrap();
+    let lines: Vec<_> = stylesheet
+        .rules
+        .iter()
+        .map(|rule| rule.origin.as_ref().unwrap().line)
+        .collect();
+    assert_eq!(lines, [3, 5, 7, 10]);
+    assert_eq!(
+        stylesheet.rules[0].origin.as_ref().unwrap().to_string(),
+        "line 3"
+    );
+}
+
+#[test]
+fn test_named_sources_survive_merge() {
+    let mut stylesheet = parse_stylesheet("Button { color: red; }")
+        .unwrap()
+        .with_source("Button");
+    stylesheet.merge(
+        parse_stylesheet("\nLabel { color: red; }")
+            .unwrap()
+            .with_source("app.tcss"),
+    );
+    let origins: Vec<_> = stylesheet
+        .rules
+        .iter()
+        .map(|rule| rule.origin.as_ref().unwrap().to_string())
+        .collect();
+    assert_eq!(origins, ["Button:1", "app.tcss:2"]);
+}
+
+// ============================================================================
+// IMPORTS
+// ============================================================================
+
+#[test]
+fn test_import_resolves_relative_to_importing_file() {
+    let dir = write_files(
+        "relative",
+        &[
+            (
+                "app.tcss",
+                "@import \"shared/base.tcss\";\nButton { width: $gap; }\n",
+            ),
+            (
+                "shared/base.tcss",
+                "$gap: 3;\n@import 'colors.tcss';\nLabel { width: 1; }\n",
+            ),
+            ("shared/colors.tcss", "\n\nScreen { color: red; }\n"),
+        ],
+    );
+
+    let stylesheet = load_stylesheet(dir.join("app.tcss")).unwrap();
+    assert_eq!(
+        origins(&stylesheet),
+        [
+            ("Screen".to_string(), "colors.tcss".to_string(), 3),
+            ("Label".to_string(), "base.tcss".to_string(), 3),
+            ("Button".to_string(), "app.tcss".to_string(), 2),
+        ]
+    );
+
+    // Imported variables are visible to the importing file
+    let Declaration::Variable(width) = &stylesheet.rules[2].declarations()[0] else {
+        panic!("expected a variable declaration");
+    };
+    assert_eq!(
+        stylesheet.resolve_declaration(width),
+        Some(Declaration::Width(Scalar::cells(3.0)))
+    );
+}
+
+#[test]
+fn test_imported_rules_keep_their_place_in_source_order() {
+    let dir = write_files(
+        "order",
+        &[
+            (
+                "app.tcss",
+                "$gap: 1;\nButton { width: 1; }\n@import \"theme.tcss\";\nButton { width: 3; }\n",
+            ),
+            ("theme.tcss", "$gap: 2;\nButton { width: 2; }\n"),
+        ],
+    );
+
+    let stylesheet = load_stylesheet(dir.join("app.tcss")).unwrap();
+    let widths: Vec<_> = stylesheet
+        .rules
+        .iter()
+        .map(|rule| rule.declarations()[0].clone())
+        .collect();
+    assert_eq!(
+        widths,
+        [1.0, 2.0, 3.0].map(|w| Declaration::Width(Scalar::cells(w)))
+    );
+    // The importing file's own definitions win
+    assert_eq!(stylesheet.variable("gap"), Some("1"));
+}
+
+#[test]
+fn test_import_cycles_are_reported() {
+    let dir = write_files(
+        "cycle",
+        &[
+            ("a.tcss", "@import \"b.tcss\";\nA { width: 1; }\n"),
+            ("b.tcss", "@import \"a.tcss\";\nB { width: 1; }\n"),
+        ],
+    );
+
+    match load_stylesheet(dir.join("a.tcss")) {
+        Err(TcssError::ImportCycle(cycle)) => {
+            let names: Vec<_> = cycle
+                .iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect();
+            assert_eq!(names, ["a.tcss", "b.tcss", "a.tcss"]);
+        }
+        other => panic!("expected an import cycle, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_the_same_file_can_be_imported_twice() {
+    let dir = write_files(
+        "diamond",
+        &[
+            ("app.tcss", "@import \"a.tcss\";\n@import \"b.tcss\";\n"),
+            ("a.tcss", "@import \"base.tcss\";\n"),
+            ("b.tcss", "@import \"base.tcss\";\n"),
+            ("base.tcss", "Base { width: 1; }\n"),
+        ],
+    );
+    let stylesheet = load_stylesheet(dir.join("app.tcss")).unwrap();
+    assert_eq!(stylesheet.rules.len(), 2);
+}
+
+#[test]
+fn test_import_errors_name_the_file() {
+    let dir = write_files(
+        "errors",
+        &[
+            ("app.tcss", "@import \"missing.tcss\";\n"),
+            ("broken.tcss", "Button { color: red; } }\n"),
+            ("uses-broken.tcss", "@import \"broken.tcss\";\n"),
+        ],
+    );
+
+    match load_stylesheet(dir.join("app.tcss")) {
+        Err(TcssError::Read { path, .. }) => assert!(path.ends_with("missing.tcss")),
+        other => panic!("expected a read error, got {other:?}"),
+    }
+    let error = load_stylesheet(dir.join("uses-broken.tcss")).unwrap_err();
+    assert!(error.to_string().contains("broken.tcss"), "{error}");
+}
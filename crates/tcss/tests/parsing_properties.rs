@@ -1099,9 +1099,19 @@ fn test_link_multiple_properties() {
     }
 }
 
+/// The declaration a variable reference currently resolves to.
+fn resolved(decl: Declaration) -> Declaration {
+    match decl {
+        Declaration::Variable(variable) => {
+            variable.resolve(&|_| None).expect("unresolved variable")
+        }
+        decl => panic!("expected Variable declaration, got {:?}", decl),
+    }
+}
+
 #[test]
 fn test_property_link_style_theme_variable() {
-    let decl = parse_first_declaration("Link { link-style: $link-style; }");
+    let decl = resolved(parse_first_declaration("Link { link-style: $link-style; }"));
     if let Declaration::LinkStyle(s) = decl {
         assert_eq!(s.theme_var, Some("link-style".to_string()));
         // When using a theme variable, the style flags should be default (false)
@@ -1114,7 +1124,9 @@ fn test_property_link_style_theme_variable() {
 
 #[test]
 fn test_property_link_style_hover_theme_variable() {
-    let decl = parse_first_declaration("Link { link-style-hover: $link-style-hover; }");
+    let decl = resolved(parse_first_declaration(
+        "Link { link-style-hover: $link-style-hover; }",
+    ));
     if let Declaration::LinkStyleHover(s) = decl {
         assert_eq!(s.theme_var, Some("link-style-hover".to_string()));
     } else {
@@ -1151,6 +1163,7 @@ Vertical {
         id: Some("tint1".to_string()),
         classes: vec![],
         states: WidgetStates::empty(),
+        ..Default::default()
     };
 
     let style = compute_style(&meta, &[], &stylesheet, &theme);
@@ -4,11 +4,17 @@
 //! - Full rule parsing (selector + declaration block)
 //! - Multiple rules
 //! - Comments
-//! - Variables (TODO)
+//! - Variables
 //! - Nested rules (TODO)
-//! - !important (TODO)
-
-use tcss::parser::{Combinator, Declaration, Selector, parse_rule};
+//! - !important
+//! - @media blocks
+
+use tcss::StyleOverride;
+use tcss::parser::cascade::{WidgetMeta, cascade, compute_style};
+use tcss::parser::{
+    Combinator, Declaration, MediaEnvironment, Selector, parse_rule, parse_stylesheet,
+};
+use tcss::types::Theme;
 use tcss::types::border::BorderKind;
 use tcss::types::color::RgbaColor;
 use tcss::types::geometry::Unit;
@@ -369,16 +375,6 @@ fn test_rule_inline_comment() {
     assert_eq!(rule.declarations().len(), 1);
 }
 
-#[test]
-#[ignore = "CSS variables not yet implemented"]
-fn test_rule_with_variable() {
-    // $primary: red;
-    // Button { color: $primary; }
-    let (_, rule) = parse_rule("Button { color: $primary; }").unwrap();
-    // Should expand variable
-    assert!(matches!(rule.declarations()[0], Declaration::Color(_)));
-}
-
 #[test]
 fn test_nested_rule() {
     let input = "
@@ -407,14 +403,6 @@ fn test_nesting_selector() {
     let _ = parse_rule(input).unwrap();
 }
 
-#[test]
-#[ignore = "!important not yet implemented"]
-fn test_important_declaration() {
-    let (_, rule) = parse_rule("Button { color: red !important; }").unwrap();
-    // Declaration should be marked as important
-    assert_eq!(rule.declarations().len(), 1);
-}
-
 #[test]
 #[ignore = "pseudo-classes not yet implemented"]
 fn test_rule_with_pseudo_class() {
@@ -431,6 +419,302 @@ fn test_initial_value() {
     assert_eq!(rule.declarations().len(), 1);
 }
 
+// ============================================================================
+// !IMPORTANT
+// ============================================================================
+
+fn red() -> RgbaColor {
+    RgbaColor::rgb(255, 0, 0)
+}
+
+fn blue() -> RgbaColor {
+    RgbaColor::rgb(0, 0, 255)
+}
+
+fn button() -> WidgetMeta {
+    WidgetMeta {
+        type_name: "Button",
+        type_names: vec!["Button", "Widget", "DOMNode"],
+        id: Some("submit".to_string()),
+        classes: vec!["primary".to_string()],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_important_declaration() {
+    let (_, rule) = parse_rule("Button { color: red !important; }").unwrap();
+    let decls = rule.declarations();
+    assert_eq!(decls.len(), 1);
+    assert!(decls[0].is_important());
+    assert_eq!(decls[0].inner(), &Declaration::Color(red()));
+}
+
+#[test]
+fn test_important_only_marks_its_declaration() {
+    let (_, rule) =
+        parse_rule("Button { color: red !important; background: blue; width: 10 !important }")
+            .unwrap();
+    let decls = rule.declarations();
+    assert_eq!(decls.len(), 3);
+    assert!(decls[0].is_important());
+    assert!(!decls[1].is_important());
+    assert!(decls[2].is_important());
+    assert_eq!(decls[1].inner(), &Declaration::Background(blue()));
+}
+
+#[test]
+fn test_important_is_idempotent() {
+    let decl = Declaration::Color(red()).important().important();
+    assert!(decl.is_important());
+    assert_eq!(decl.inner(), &Declaration::Color(red()));
+}
+
+#[test]
+fn test_important_beats_higher_specificity() {
+    let stylesheet =
+        parse_stylesheet("Button { color: red !important; } #submit.primary { color: blue; }")
+            .unwrap();
+    let theme = Theme::new("test", true);
+    let style = compute_style(&button(), &[], &stylesheet, &theme);
+    assert_eq!(style.color, Some(red()));
+}
+
+#[test]
+fn test_important_declarations_cascade_among_themselves() {
+    let stylesheet =
+        parse_stylesheet("#submit { color: blue !important; } Button { color: red !important; }")
+            .unwrap();
+    let theme = Theme::new("test", true);
+    let style = compute_style(&button(), &[], &stylesheet, &theme);
+    assert_eq!(style.color, Some(blue()));
+}
+
+#[test]
+fn test_important_outranks_inline_style() {
+    let stylesheet =
+        parse_stylesheet("Button { color: red !important; background: red; }").unwrap();
+    let theme = Theme::new("test", true);
+    let inline = StyleOverride::new().color(blue()).background(blue());
+
+    let mut cascaded = cascade(&button(), &[], &stylesheet, &theme);
+    assert_eq!(cascaded.important.len(), 1);
+    let mut style = std::mem::take(&mut cascaded.style);
+    inline.apply_to(&mut style);
+    cascaded.apply_important(&mut style, &theme);
+
+    assert_eq!(style.color, Some(red()));
+    assert_eq!(style.background, Some(blue()));
+}
+
+// ============================================================================
+// @MEDIA
+// ============================================================================
+
+const MEDIA_CSS: &str = "
+    Button { color: red; }
+    @media (min-width: 100) {
+        Button { color: blue; }
+        #submit { &.primary { background: blue; } }
+    }
+    @media (max-height: 20), (light) {
+        Button { width: 10; }
+        @media (color: 256) {
+            Button { height: 3; }
+        }
+    }
+";
+
+#[test]
+fn test_media_block_attaches_query_to_rules() {
+    let stylesheet = parse_stylesheet(MEDIA_CSS).unwrap();
+    let queries: Vec<Option<String>> = stylesheet
+        .rules
+        .iter()
+        .map(|rule| rule.media.as_ref().map(ToString::to_string))
+        .collect();
+    assert_eq!(
+        queries,
+        [
+            None,
+            Some("(min-width: 100)".to_string()),
+            Some("(min-width: 100)".to_string()),
+            Some("(max-height: 20), (light)".to_string()),
+            Some("(max-height: 20) and (color: 256), (light) and (color: 256)".to_string()),
+        ]
+    );
+    assert_eq!(stylesheet.rules[2].selectors.to_string(), "#submit.primary");
+}
+
+#[test]
+fn test_media_rules_follow_the_environment() {
+    let mut stylesheet = parse_stylesheet(MEDIA_CSS).unwrap();
+    let dark = Theme::new("dark", true);
+
+    assert!(stylesheet.set_environment(MediaEnvironment::new(120, 40)));
+    let style = compute_style(&button(), &[], &stylesheet, &dark);
+    assert_eq!(style.color, Some(blue()));
+    assert_eq!(style.background, Some(blue()));
+    assert_eq!(style.width, None);
+
+    assert!(stylesheet.set_environment(MediaEnvironment::new(80, 20).with_colors(16)));
+    let style = compute_style(&button(), &[], &stylesheet, &dark);
+    assert_eq!(style.color, Some(red()));
+    assert_eq!(style.width.map(|width| width.value), Some(10.0));
+    assert_eq!(style.height, None);
+
+    let light = Theme::new("light", false);
+    assert!(stylesheet.set_environment(MediaEnvironment::new(80, 40)));
+    let style = compute_style(&button(), &[], &stylesheet, &light);
+    assert_eq!(style.width.map(|width| width.value), Some(10.0));
+    assert_eq!(style.height.map(|height| height.value), Some(3.0));
+}
+
+#[test]
+fn test_set_environment_reports_query_changes() {
+    let mut stylesheet = parse_stylesheet(MEDIA_CSS).unwrap();
+    assert!(!stylesheet.set_environment(MediaEnvironment::new(90, 30)));
+    assert!(stylesheet.set_environment(MediaEnvironment::new(100, 30)));
+
+    let mut plain = parse_stylesheet("Button { color: red; }").unwrap();
+    assert!(!plain.set_environment(MediaEnvironment::new(200, 5)));
+}
+
+#[test]
+fn test_invalid_media_query_is_an_error() {
+    assert!(
+        parse_stylesheet("@media (orientation: landscape) { Button { color: red; } }").is_err()
+    );
+    assert!(parse_stylesheet("@media min-width: 10 { Button { color: red; } }").is_err());
+}
+
+// ============================================================================
+// VARIABLES
+// ============================================================================
+
+fn width(style: &tcss::ComputedStyle) -> Option<f64> {
+    style.width.map(|width| width.value)
+}
+
+#[test]
+fn test_variable_reference_kept_in_declaration() {
+    let (_, rule) = parse_rule("Button { width: $sidebar !important; color: $primary }").unwrap();
+    let decls = rule.declarations();
+    let Declaration::Variable(variable) = decls[0].inner() else {
+        panic!("expected Variable declaration, got {:?}", decls[0]);
+    };
+    assert!(decls[0].is_important());
+    assert_eq!(variable.property, "width");
+    assert_eq!(variable.value, "$sidebar");
+    assert_eq!(variable.resolve(&|_| None), None);
+
+    // Theme variables are left for the cascade
+    let Declaration::Variable(variable) = &decls[1] else {
+        panic!("expected Variable declaration, got {:?}", decls[1]);
+    };
+    assert_eq!(
+        variable.resolve(&|_| None),
+        Some(Declaration::Color(RgbaColor::theme_variable("primary")))
+    );
+}
+
+#[test]
+fn test_rule_with_variable() {
+    let stylesheet = parse_stylesheet("$primary: red;\nButton { color: $primary; }").unwrap();
+    let theme = Theme::new("test", true);
+    let style = compute_style(&button(), &[], &stylesheet, &theme);
+    assert_eq!(style.color, Some(red()));
+}
+
+#[test]
+fn test_app_variables_override_stylesheet_and_theme() {
+    let mut stylesheet = parse_stylesheet(
+        "$accent: red;\nButton { color: $accent; background: $panel; width: $sidebar; }",
+    )
+    .unwrap();
+    let mut theme = Theme::new("test", true);
+    theme.colors.insert("panel".into(), blue());
+
+    let style = compute_style(&button(), &[], &stylesheet, &theme);
+    assert_eq!(style.color, Some(red()));
+    assert_eq!(style.background, Some(blue()));
+    assert_eq!(width(&style), None);
+
+    assert!(stylesheet.set_variable("accent", "blue"));
+    assert!(stylesheet.set_variable("panel", "red"));
+    assert!(stylesheet.set_variable("sidebar", "30"));
+    assert!(!stylesheet.set_variable("sidebar", "30"));
+    assert_eq!(stylesheet.variable("accent"), Some("blue"));
+    let style = compute_style(&button(), &[], &stylesheet, &theme);
+    assert_eq!(style.color, Some(blue()));
+    assert_eq!(style.background, Some(red()));
+    assert_eq!(width(&style), Some(30.0));
+
+    assert!(stylesheet.remove_variable("accent"));
+    assert!(!stylesheet.remove_variable("accent"));
+    assert_eq!(stylesheet.variable("accent"), Some("red"));
+    let style = compute_style(&button(), &[], &stylesheet, &theme);
+    assert_eq!(style.color, Some(red()));
+}
+
+#[test]
+fn test_unrelated_variable_changes_nothing() {
+    let mut stylesheet = parse_stylesheet("Button { color: $accent; }").unwrap();
+    assert!(!stylesheet.set_variable("sidebar", "30"));
+}
+
+#[test]
+fn test_variables_defined_in_terms_of_variables() {
+    let mut stylesheet =
+        parse_stylesheet("$a: $b;\n$b: red;\nButton { color: $a; width: $x; }").unwrap();
+    let theme = Theme::new("test", true);
+    assert_eq!(
+        compute_style(&button(), &[], &stylesheet, &theme).color,
+        Some(red())
+    );
+
+    // A cycle never resolves, but doesn't hang either
+    stylesheet.set_variable("x", "$y");
+    stylesheet.set_variable("y", "$x");
+    assert_eq!(
+        width(&compute_style(&button(), &[], &stylesheet, &theme)),
+        None
+    );
+}
+
+#[test]
+fn test_variable_resolving_to_an_invalid_value_is_an_error() {
+    assert!(parse_stylesheet("$sidebar: wide;\nButton { width: $sidebar; }").is_err());
+    // Undefined here, so it may yet be defined by the app or theme
+    assert!(parse_stylesheet("Button { width: $sidebar; }").is_ok());
+}
+
+#[test]
+fn test_rules_using_variable() {
+    let stylesheet = parse_stylesheet(
+        "$accent: $tint;\n\
+         Button { color: $accent; }\n\
+         Label { width: $sidebar !important; }\n\
+         Static { color: red; }",
+    )
+    .unwrap();
+    assert_eq!(stylesheet.rules_using_variable("accent"), [0]);
+    assert_eq!(stylesheet.rules_using_variable("tint"), [0]);
+    assert_eq!(stylesheet.rules_using_variable("sidebar"), [1]);
+    assert!(stylesheet.rules_using_variable("primary").is_empty());
+}
+
+#[test]
+fn test_merged_stylesheets_share_variables() {
+    let mut stylesheet = parse_stylesheet("Button { width: $sidebar; }").unwrap();
+    stylesheet.merge(parse_stylesheet("$sidebar: 20;\nLabel { color: red; }").unwrap());
+    let theme = Theme::new("test", true);
+    assert_eq!(
+        width(&compute_style(&button(), &[], &stylesheet, &theme)),
+        Some(20.0)
+    );
+}
+
 // ============================================================================
 // MULTIPLE RULES (STYLESHEET)
 // ============================================================================
@@ -9,7 +9,10 @@
 //! - Combinators: descendant (space), child (`>`)
 //! - Selector lists: `Button, .primary`
 
-use tcss::parser::{Combinator, Selector, Specificity, parse_selector_list};
+use tcss::parser::{
+    AttributeOperator, AttributeSelector, Combinator, NthPattern, Selector, Specificity,
+    parse_selector_list,
+};
 
 // ============================================================================
 // TYPE SELECTORS
@@ -558,19 +561,67 @@ fn test_pseudo_class_dark_light() {
 }
 
 #[test]
-#[ignore = "pseudo-classes not yet implemented"]
 fn test_pseudo_class_first_last_child() {
-    let _ = parse_selector_list("ListItem:first-child").unwrap();
+    let (_, list) = parse_selector_list("ListItem:first-child").unwrap();
+    assert_eq!(
+        list.selectors[0].parts[0].compound.selectors[1],
+        Selector::PseudoClass("first-child".to_string())
+    );
     let _ = parse_selector_list("ListItem:last-child").unwrap();
 }
 
 #[test]
-#[ignore = "pseudo-classes not yet implemented"]
 fn test_pseudo_class_even_odd() {
     let _ = parse_selector_list("Row:even").unwrap();
     let _ = parse_selector_list("Row:odd").unwrap();
 }
 
+#[test]
+fn test_nth_child_patterns() {
+    for (source, a, b) in [
+        ("2n+1", 2, 1),
+        ("2n + 1", 2, 1),
+        ("odd", 2, 1),
+        ("even", 2, 0),
+        ("3", 0, 3),
+        ("n", 1, 0),
+        ("-n+3", -1, 3),
+        ("+3n-2", 3, -2),
+    ] {
+        let css = format!("Row:nth-child({source})");
+        let (rest, list) = parse_selector_list(&css).unwrap();
+        assert!(rest.is_empty(), "unparsed input for {source}: {rest:?}");
+        assert_eq!(
+            list.selectors[0].parts[0].compound.selectors[1],
+            Selector::NthChild(NthPattern::new(a, b)),
+            "{source}"
+        );
+    }
+}
+
+#[test]
+fn test_nth_child_specificity_counts_as_pseudo_class() {
+    let (_, list) = parse_selector_list("Row:nth-child(2n)").unwrap();
+    assert_eq!(
+        list.selectors[0].specificity(),
+        Specificity {
+            ids: 0,
+            classes: 1,
+            types: 1
+        }
+    );
+}
+
+#[test]
+fn test_nth_pattern_matches_positions() {
+    let matched = |pattern: NthPattern| (1..=7).filter(|&p| pattern.matches(p)).collect::<Vec<_>>();
+    assert_eq!(matched(NthPattern::ODD), [1, 3, 5, 7]);
+    assert_eq!(matched(NthPattern::EVEN), [2, 4, 6]);
+    assert_eq!(matched(NthPattern::new(0, 3)), [3]);
+    assert_eq!(matched(NthPattern::new(-1, 3)), [1, 2, 3]);
+    assert_eq!(matched(NthPattern::new(3, -2)), [1, 4, 7]);
+}
+
 #[test]
 #[ignore = "pseudo-classes not yet implemented"]
 fn test_multiple_pseudo_classes() {
@@ -578,6 +629,82 @@ fn test_multiple_pseudo_classes() {
     let _ = parse_selector_list("Button:hover:focus").unwrap();
 }
 
+// ============================================================================
+// ATTRIBUTE SELECTORS
+// ============================================================================
+
+fn attribute_of(source: &str) -> AttributeSelector {
+    let (rest, list) = parse_selector_list(source).unwrap();
+    assert!(rest.is_empty(), "unparsed input: {rest:?}");
+    match list.selectors[0].parts[0].compound.selectors.last() {
+        Some(Selector::Attribute(attribute)) => attribute.clone(),
+        other => panic!("expected an attribute selector, got {other:?}"),
+    }
+}
+
+#[test]
+fn test_attribute_selector_operators() {
+    for (source, operator) in [
+        ("Input[type=password]", AttributeOperator::Equals),
+        ("Button[variant^=pri]", AttributeOperator::Prefix),
+        ("Button[variant$=ary]", AttributeOperator::Suffix),
+        ("Button[variant*=ima]", AttributeOperator::Contains),
+    ] {
+        let attribute = attribute_of(source);
+        assert_eq!(attribute.operator, operator, "{source}");
+        assert!(!attribute.value.is_empty(), "{source}");
+    }
+    assert_eq!(
+        attribute_of("Switch[value]"),
+        AttributeSelector::exists("value")
+    );
+}
+
+#[test]
+fn test_attribute_selector_quoted_value_and_spacing() {
+    assert_eq!(
+        attribute_of(r#"Button[ label *= "Save as" ]"#),
+        AttributeSelector::new("label", AttributeOperator::Contains, "Save as")
+    );
+    assert_eq!(
+        attribute_of("Input[type='password']"),
+        AttributeSelector::new("type", AttributeOperator::Equals, "password")
+    );
+}
+
+#[test]
+fn test_attribute_selector_rejects_invalid_name() {
+    assert!(AttributeSelector::parse("").is_none());
+    assert!(AttributeSelector::parse("=x").is_none());
+    assert!(AttributeSelector::parse("a b=x").is_none());
+}
+
+#[test]
+fn test_attribute_selector_matches_values() {
+    let check = |op, value: &str, actual| AttributeSelector::new("a", op, value).matches(actual);
+    assert!(check(AttributeOperator::Equals, "on", Some("on")));
+    assert!(!check(AttributeOperator::Equals, "on", Some("only")));
+    assert!(check(AttributeOperator::Prefix, "on", Some("only")));
+    assert!(check(AttributeOperator::Suffix, "ly", Some("only")));
+    assert!(check(AttributeOperator::Contains, "nl", Some("only")));
+    assert!(check(AttributeOperator::Exists, "", Some("")));
+    assert!(!check(AttributeOperator::Exists, "", None));
+    assert!(!check(AttributeOperator::Contains, "", None));
+}
+
+#[test]
+fn test_attribute_selector_specificity_counts_as_class() {
+    let (_, list) = parse_selector_list("Input[type=password]").unwrap();
+    assert_eq!(
+        list.selectors[0].specificity(),
+        Specificity {
+            ids: 0,
+            classes: 1,
+            types: 1
+        }
+    );
+}
+
 // ============================================================================
 // WHITESPACE HANDLING
 // ============================================================================
@@ -601,3 +728,34 @@ fn test_multiple_spaces_in_descendant() {
         Combinator::Descendant
     );
 }
+
+// ============================================================================
+// DISPLAY
+// ============================================================================
+
+#[test]
+fn test_selector_list_display_round_trips() {
+    for source in [
+        "Button",
+        "Button.primary#submit",
+        "Container > Button:hover",
+        "Screen Vertical .item",
+        "*",
+        "Input[type=password], #header",
+        "Row:nth-child(2n+1), Row:nth-child(-n+3), Row:nth-child(4)",
+        "Switch[value], Button[variant^=pri], Button[variant$=or], Button[label*=\"Save as\"]",
+    ] {
+        let (_, list) = parse_selector_list(source).unwrap();
+        assert_eq!(list.to_string(), source);
+    }
+}
+
+#[test]
+fn test_specificity_display() {
+    let spec = Specificity {
+        ids: 1,
+        classes: 2,
+        types: 3,
+    };
+    assert_eq!(spec.to_string(), "(1, 2, 3)");
+}
@@ -0,0 +1,256 @@
+//! Tests for writing parsed stylesheets back as TCSS.
+//!
+//! Printing a stylesheet and parsing the output must give back the same
+//! rules, so formatters and debugging tools can rely on the text.
+
+use tcss::parser::{Declaration, Rule, StyleSheet, parse_stylesheet};
+use tcss::types::{Hatch, HatchPattern, RgbaColor, Scalar, Spacing};
+
+// ============================================================================
+// HELPER FUNCTIONS
+// ============================================================================
+
+/// The rules of `stylesheet`, without the lines they were parsed from.
+fn rules(stylesheet: &StyleSheet) -> Vec<Rule> {
+    let mut rules = stylesheet.rules.clone();
+    for rule in &mut rules {
+        rule.origin = None;
+    }
+    rules
+}
+
+/// Parses `source`, prints it, parses the output and checks both parses
+/// agree. Returns the printed text.
+fn round_trip(source: &str) -> String {
+    let stylesheet = parse_stylesheet(source).expect("source should parse");
+    let printed = stylesheet.to_string();
+    let reparsed = parse_stylesheet(&printed)
+        .unwrap_or_else(|e| panic!("printed stylesheet should parse: {e:?}\n\n{printed}"));
+    assert_eq!(rules(&reparsed), rules(&stylesheet), "\n{printed}");
+    assert_eq!(reparsed.to_string(), printed);
+    printed
+}
+
+fn declaration(source: &str) -> Declaration {
+    let stylesheet = parse_stylesheet(&format!("* {{ {source}; }}")).expect("should parse");
+    stylesheet.rules[0].declarations().remove(0)
+}
+
+// ============================================================================
+// VALUES
+// ============================================================================
+
+#[test]
+fn test_declarations_print_normalized_values() {
+    for (source, printed) in [
+        ("color: red", "color: #ff0000"),
+        (
+            "background: rgba(255, 0, 0, 0.25)",
+            "background: rgba(255, 0, 0, 0.25)",
+        ),
+        ("background: transparent", "background: transparent"),
+        ("color: auto 87%", "color: auto 87%"),
+        ("tint: magenta 40%", "tint: rgba(255, 0, 255, 0.4)"),
+        ("width: 50%", "width: 50%"),
+        ("height: 1.5fr", "height: 1.5fr"),
+        ("max-width: 80vw", "max-width: 80vw"),
+        ("min-height: auto", "min-height: auto"),
+        ("margin: 1", "margin: 1"),
+        ("margin: 1 2 1 2", "margin: 1 2"),
+        ("padding: 1 2 3 4", "padding: 1 2 3 4"),
+        ("border: round red", "border: round #ff0000"),
+        ("border-top: solid", "border-top: solid"),
+        ("text-style: italic bold", "text-style: bold italic"),
+        ("text-style: none", "text-style: none"),
+        ("overflow: auto", "overflow: auto"),
+        ("overflow: hidden scroll", "overflow: hidden scroll"),
+        ("scrollbar-size: 1 1", "scrollbar-size: 1"),
+        (
+            "content-align: center middle",
+            "content-align: center middle",
+        ),
+        ("grid-size: 3", "grid-size: 3"),
+        ("grid-columns: 1fr 2fr 10", "grid-columns: 1fr 2fr 10"),
+        ("layers: below above", "layers: below above"),
+        ("hatch: \"T\" blue", "hatch: \"T\" #0000ff"),
+        ("keyline: none", "keyline: none"),
+        ("opacity: 50%", "opacity: 0.5"),
+        ("box-sizing: content-box", "box-sizing: content-box"),
+        ("offset: -1 2", "offset: -1 2"),
+        ("width: 10 !important", "width: 10 !important"),
+        ("width: $sidebar", "width: $sidebar"),
+        (
+            "color: $primary 50% !important",
+            "color: $primary 50% !important",
+        ),
+    ] {
+        assert_eq!(declaration(source).to_string(), printed, "{source}");
+    }
+}
+
+#[test]
+fn test_programmatic_values_print_as_tcss() {
+    let theme = Declaration::Background(RgbaColor::theme_variable("panel").with_alpha(0.3));
+    assert_eq!(theme.to_string(), "background: $panel 30%");
+    assert_eq!(
+        declaration(&theme.to_string()),
+        declaration("background: $panel 30%")
+    );
+
+    let hatch = Hatch::new(HatchPattern::Cross, RgbaColor::rgb(0, 128, 0)).with_opacity(0.25);
+    assert_eq!(
+        Declaration::Hatch(hatch).to_string(),
+        "hatch: cross #008000 25%"
+    );
+
+    let spacing = Spacing {
+        top: Scalar::cells(1.0),
+        right: Scalar::percent(10.0),
+        bottom: Scalar::cells(1.0),
+        left: Scalar::AUTO,
+    };
+    assert_eq!(
+        Declaration::Padding(spacing).to_string(),
+        "padding: 1 10% 1 auto"
+    );
+    assert_eq!(
+        Declaration::Unknown("cursor".into()).to_string(),
+        "/* cursor */"
+    );
+}
+
+#[test]
+fn test_property_names_match_the_parser() {
+    for property in [
+        "scrollbar-color-hover",
+        "border-subtitle-style",
+        "offset-x",
+        "align",
+    ] {
+        let value = match property {
+            "scrollbar-color-hover" => "red",
+            "border-subtitle-style" => "bold",
+            "offset-x" => "2",
+            _ => "center middle",
+        };
+        assert_eq!(
+            declaration(&format!("{property}: {value}")).property(),
+            property
+        );
+    }
+    assert_eq!(declaration("width: 1 !important").property(), "width");
+    assert_eq!(declaration("width: $w").property(), "width");
+}
+
+// ============================================================================
+// STYLESHEETS
+// ============================================================================
+
+#[test]
+fn test_stylesheet_prints_rules_as_blocks() {
+    let printed = round_trip("Screen>Button.primary,#save{color:red;width:50%;}Label{dock:top}");
+    assert_eq!(
+        printed,
+        "Screen > Button.primary, #save {\n    color: #ff0000;\n    width: 50%;\n}\n\n\
+         Label {\n    dock: top;\n}\n"
+    );
+
+    // Unflattened rules print their nested rules inside the block
+    let (_, rule) = tcss::parser::parse_rule("Button { &:hover {} color: red; }").unwrap();
+    assert_eq!(
+        rule.to_string(),
+        "Button {\n    &:hover {}\n    color: #ff0000;\n}\n"
+    );
+}
+
+#[test]
+fn test_stylesheet_prints_flattened_nesting() {
+    let printed = round_trip(
+        "Button {
+            color: white;
+            &:hover { color: red; }
+            & > .icon { width: 2; }
+        }",
+    );
+    assert_eq!(
+        printed,
+        "Button {\n    color: #ffffff;\n}\n\n\
+         Button:hover {\n    color: #ff0000;\n}\n\n\
+         Button > .icon {\n    width: 2;\n}\n"
+    );
+}
+
+#[test]
+fn test_stylesheet_prints_variables_and_media_blocks() {
+    let printed = round_trip(
+        "$gap: 2;
+         $accent: $primary;
+         Label { margin: $gap; color: $accent; }
+         @media (max-width: 40) {
+             Sidebar { display: none; }
+             Label { margin: 0; }
+         }
+         @media (dark) { Label { background: black; } }",
+    );
+    assert_eq!(
+        printed,
+        "$accent: $primary;\n$gap: 2;\n\n\
+         Label {\n    margin: $gap;\n    color: $accent;\n}\n\n\
+         @media (max-width: 40) {\n    Sidebar {\n        display: none;\n    }\n\n    \
+         Label {\n        margin: 0;\n    }\n}\n\n\
+         @media (dark) {\n    Label {\n        background: #000000;\n    }\n}\n"
+    );
+}
+
+#[test]
+fn test_stylesheet_prints_app_variable_overrides() {
+    let mut stylesheet = parse_stylesheet("$gap: 2;\nLabel { margin: $gap; }").unwrap();
+    stylesheet.set_variable("gap", "4");
+    let reparsed = parse_stylesheet(&stylesheet.to_string()).unwrap();
+    assert_eq!(reparsed.variable("gap"), Some("4"));
+    assert_eq!(rules(&reparsed), rules(&stylesheet));
+}
+
+#[test]
+fn test_every_property_round_trips() {
+    round_trip(
+        "Widget {
+            color: red; background: #102030; tint: rgb(1, 2, 3, 0.5);
+            background-tint: white 50%; width: 10; height: 50%; max-height: 10h;
+            max-width: 20w; min-height: 1; min-width: 1fr; margin: 1 2;
+            margin-top: 1; margin-right: 2; margin-bottom: 3; margin-left: 4;
+            padding: 1 2 3 4; padding-top: 1; padding-right: 2; padding-bottom: 3;
+            padding-left: 4; border: heavy blue;
+            scrollbar-color: red; scrollbar-color-hover: red; scrollbar-color-active: red;
+            scrollbar-background: red; scrollbar-background-hover: red;
+            scrollbar-background-active: red; scrollbar-corner-color: red;
+            scrollbar-size: 1 2; scrollbar-size-horizontal: 1; scrollbar-size-vertical: 2;
+            scrollbar-gutter: stable; scrollbar-visibility: hidden;
+            box-sizing: border-box; display: block; visibility: hidden; opacity: 0.25;
+            position: absolute; overflow-x: scroll; overflow-y: auto; overflow: auto hidden;
+            layout: grid; dock: right; layers: a b; layer: b;
+            grid-size: 2 3; grid-columns: 1fr 2; grid-rows: auto 50%; grid-gutter: 1 2;
+            column-span: 2; row-span: 3;
+            link-color: red; link-color-hover: red; link-background: red;
+            link-background-hover: red; link-style: underline; link-style-hover: bold reverse;
+            text-align: justify; text-overflow: ellipsis; text-wrap: nowrap; line-pad: 1;
+            text-opacity: 50%; text-style: strike overline;
+            content-align-horizontal: right; content-align-vertical: bottom;
+            content-align: center middle; align-horizontal: center; align-vertical: middle;
+            align: right bottom; border-title-align: center; border-subtitle-align: right;
+            border-title-color: red; border-subtitle-color: red;
+            border-title-background: red; border-subtitle-background: red;
+            border-title-style: italic; border-subtitle-style: dim;
+            border-top: solid red; border-bottom: dashed; border-left: tall $accent;
+            border-right: none; outline: wide red; outline-top: round; outline-right: thick;
+            outline-bottom: panel; outline-left: vkey;
+            hatch: horizontal red 80%; keyline: thin green; offset: 1 -2;
+            offset-x: 50%; offset-y: -1;
+        }",
+    );
+}
+
+#[test]
+fn test_default_stylesheet_prints_empty() {
+    assert_eq!(StyleSheet::default().to_string(), "");
+}
@@ -20,6 +20,7 @@ fn computes_text_opacity_for_label() {
         id: Some("quarter-opacity".to_string()),
         classes: Vec::new(),
         states: WidgetStates::empty(),
+        ..Default::default()
     };
 
     let style = compute_style(&meta, &[], &stylesheet, &Theme::new("test", true));
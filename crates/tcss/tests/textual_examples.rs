@@ -169,11 +169,8 @@ fn test_multiple_rules_with_box_model() {
 }
 
 #[test]
-#[ignore = "variable definition lines are incorrectly expanded - bug in resolve_variables"]
 fn test_variables_defined_in_stylesheet() {
     // Variables that are defined within the stylesheet should work
-    // BUG: The resolver replaces $my-color in "$my-color: red;" with "red",
-    //      resulting in "red: red;" which is invalid syntax.
     let source = r#"
     $my-color: red;
     $my-width: 100;
@@ -9,6 +9,7 @@ use std::collections::VecDeque;
 
 use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
 use textual::canvas::{Canvas, Region, Size};
+use textual::layouts;
 use textual::style_resolver::resolve_styles;
 use textual::testing::{build_combined_css, render_to_canvas};
 use textual::tree::WidgetTree;
@@ -322,7 +323,7 @@ fn bench_canvas_to_snapshot(c: &mut Criterion) {
 
 /// Benchmark layout caching - renders same widget tree multiple times.
 /// This tests the optimization where layout is cached and reused when
-/// region/viewport haven't changed.
+/// no layout input (region, viewport, styles, content) has changed.
 fn bench_layout_caching(c: &mut Criterion) {
     let mut group = c.benchmark_group("layout_caching");
 
@@ -361,6 +362,24 @@ fn bench_layout_caching(c: &mut Criterion) {
         })
     });
 
+    // Every container arranges its children once per render, so after the
+    // first frame all lookups should be served from the layout caches.
+    {
+        let mut canvas = Canvas::new(80, 24);
+        tree.root().render(&mut canvas, region);
+        layouts::reset_layout_cache_stats();
+        for _ in 0..10 {
+            tree.root().render(&mut canvas, region);
+        }
+        let stats = layouts::layout_cache_stats();
+        assert!(stats.lookups() > 0, "layout caches were never consulted");
+        assert_eq!(
+            stats.hit_rate(),
+            1.0,
+            "unchanged tree should hit layout caches: {stats:?}"
+        );
+    }
+
     // Multiple renders of same tree (cache hits after first)
     group.bench_function("grid_10_cached_renders", |b| {
         b.iter(|| {
@@ -0,0 +1,117 @@
+//! Devtools console for textual apps.
+//!
+//! Connects to an app started with `TEXTUAL_DEVTOOLS` set and prints the
+//! streamed log records, messages, input events and frame timings.
+//!
+//! Run with: `cargo run -p textual --bin textual-console -- [OPTIONS]`
+
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::process::ExitCode;
+use std::thread;
+use std::time::Duration;
+
+use crossterm::style::{Color, Stylize};
+use textual::devtools::{DEFAULT_ADDR, DevtoolsEvent, DevtoolsFilter, DevtoolsRecord};
+use textual::log::{Level, LevelFilter};
+
+const USAGE: &str = "\
+Usage: textual-console [OPTIONS]
+
+Options:
+  --addr <HOST:PORT>   Devtools server address [default: 127.0.0.1:8081]
+  --level <LEVEL>      Most verbose log level to show: error, warn, info, debug, trace
+  --sender <TYPE>      Only show messages from this widget type (repeatable)
+  --no-input           Hide key and mouse events
+  --no-frames          Hide frame timings
+  -h, --help           Print this help";
+
+struct Options {
+    addr: String,
+    filter: DevtoolsFilter,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Options>, String> {
+    let mut options = Options {
+        addr: DEFAULT_ADDR.to_string(),
+        filter: DevtoolsFilter::default(),
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name} requires a value"));
+        match arg.as_str() {
+            "--addr" => options.addr = value("--addr")?,
+            "--level" => {
+                let level = value("--level")?;
+                options.filter.level = level
+                    .parse::<LevelFilter>()
+                    .map_err(|_| format!("invalid log level: {level}"))?;
+            }
+            "--sender" => options.filter.sender_types.push(value("--sender")?),
+            "--no-input" => options.filter.input = false,
+            "--no-frames" => options.filter.frames = false,
+            "-h" | "--help" => return Ok(None),
+            other => return Err(format!("unknown argument: {other}")),
+        }
+    }
+
+    Ok(Some(options))
+}
+
+fn color_for(event: &DevtoolsEvent) -> Color {
+    match event {
+        DevtoolsEvent::Log { level, .. } => match level {
+            Level::Error => Color::Red,
+            Level::Warn => Color::Yellow,
+            Level::Info => Color::Green,
+            Level::Debug => Color::Blue,
+            Level::Trace => Color::DarkGrey,
+        },
+        DevtoolsEvent::Message { .. } => Color::Magenta,
+        DevtoolsEvent::Key { .. } | DevtoolsEvent::Mouse { .. } => Color::Cyan,
+        DevtoolsEvent::Frame { .. } => Color::DarkGrey,
+    }
+}
+
+/// Stream records from one app session until it disconnects.
+fn stream(connection: TcpStream, filter: &DevtoolsFilter) -> io::Result<()> {
+    let mut stdout = io::stdout().lock();
+    for line in BufReader::new(connection).lines() {
+        let Some(record) = DevtoolsRecord::decode(&line?) else {
+            continue;
+        };
+        if filter.matches(&record.event) {
+            let color = color_for(&record.event);
+            writeln!(stdout, "{}", record.to_string().with(color))?;
+        }
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let options = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => options,
+        Ok(None) => {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("error: {e}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    println!("Waiting for app on {} (Ctrl+C to quit)", options.addr);
+    loop {
+        match TcpStream::connect(&options.addr) {
+            Ok(connection) => {
+                println!("{}", "--- app connected ---".bold());
+                if let Err(e) = stream(connection, &options.filter) {
+                    eprintln!("connection error: {e}");
+                }
+                println!("{}", "--- app disconnected ---".bold());
+            }
+            Err(_) => thread::sleep(Duration::from_millis(250)),
+        }
+    }
+}
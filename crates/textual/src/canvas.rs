@@ -2,6 +2,7 @@ use crossterm::{
     cursor, execute,
     style::{Attribute, Color, SetAttribute, SetBackgroundColor, SetForegroundColor},
 };
+use std::collections::HashMap;
 use std::io::{BufWriter, Write};
 use tcss::types::RgbaColor;
 
@@ -21,7 +22,7 @@ pub struct TextAttributes {
 }
 
 /// The physical dimensions of a widget or terminal.
-#[derive(Clone, Copy, Debug, Default)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Size {
     pub width: u16,
     pub height: u16,
@@ -106,6 +107,28 @@ impl Region {
     pub fn is_empty(&self) -> bool {
         self.width <= 0 || self.height <= 0
     }
+
+    /// Returns the smallest region containing both this region and another.
+    /// Empty regions are ignored.
+    pub fn union(&self, other: &Region) -> Region {
+        if self.is_empty() {
+            return *other;
+        }
+        if other.is_empty() {
+            return *self;
+        }
+        let x1 = self.x.min(other.x);
+        let y1 = self.y.min(other.y);
+        let x2 = self
+            .x
+            .saturating_add(self.width)
+            .max(other.x.saturating_add(other.width));
+        let y2 = self
+            .y
+            .saturating_add(self.height)
+            .max(other.y.saturating_add(other.height));
+        Region::new(x1, y1, x2 - x1, y2 - y1)
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -128,6 +151,16 @@ pub struct Canvas {
     first_flush: bool,
     /// Stack of clipping regions. The active clip is the intersection of all.
     clip_stack: Vec<Region>,
+    /// Where each boxed widget was painted, and its desired size then,
+    /// keyed by widget identity. Used to turn widget repaints into damaged
+    /// screen regions.
+    widget_regions: HashMap<usize, (Region, Size)>,
+    /// The damaged region being re-rendered during a partial frame.
+    /// Widgets entirely outside it are skipped.
+    damage_clip: Option<Region>,
+    /// Whether `prev_cells` holds a complete frame that a partial
+    /// render can build on.
+    has_previous_frame: bool,
 }
 
 impl Canvas {
@@ -147,6 +180,9 @@ impl Canvas {
             prev_cells: vec![blank_cell; cell_count],
             first_flush: true,
             clip_stack: Vec::new(),
+            widget_regions: HashMap::new(),
+            damage_clip: None,
+            has_previous_frame: false,
         }
     }
 
@@ -379,20 +415,40 @@ impl Canvas {
         let stdout = std::io::stdout();
         let mut out = BufWriter::with_capacity(8192, stdout.lock());
 
+        self.flush_into(&mut out)?;
+
+        // Single flush writes entire buffer to stdout
+        out.flush()?;
+        Ok(())
+    }
+
+    /// Writes the frame's ANSI output to `out` instead of stdout.
+    ///
+    /// Emits exactly what [`flush`](Self::flush) would (a full redraw on the
+    /// first frame, a diff afterwards) and ends the frame. Used to record
+    /// sessions.
+    pub fn flush_into<W: Write>(&mut self, out: &mut W) -> std::io::Result<()> {
         // First flush requires full redraw
         if self.first_flush {
-            self.flush_full(&mut out)?;
+            self.flush_full(out)?;
             self.first_flush = false;
         } else {
-            self.flush_diff(&mut out)?;
+            self.flush_diff(out)?;
         }
 
+        self.end_frame();
+        Ok(())
+    }
+
+    /// Finishes the current frame without writing it anywhere.
+    ///
+    /// The current cells become the previous frame, which the next diff
+    /// compares against and partial renders build on. Called by `flush`;
+    /// headless renderers call it directly.
+    pub fn end_frame(&mut self) {
         // Swap buffers: current becomes previous for next frame
         std::mem::swap(&mut self.cells, &mut self.prev_cells);
-
-        // Single flush writes entire buffer to stdout
-        out.flush()?;
-        Ok(())
+        self.has_previous_frame = true;
     }
 
     /// Full flush - redraws entire screen (used on first render).
@@ -564,6 +620,97 @@ impl Canvas {
         self.clip_stack.clear();
     }
 
+    /// Blanks the cells inside `region`, leaving the rest of the frame intact.
+    pub fn clear_region(&mut self, region: Region) {
+        let bounds = Region::from_u16(0, 0, self.size.width, self.size.height);
+        let region = region.intersection(&bounds);
+        if region.is_empty() {
+            return;
+        }
+        let width = self.size.width as usize;
+        for y in region.y..region.y + region.height {
+            let start = y as usize * width + region.x as usize;
+            let end = start + region.width as usize;
+            for cell in &mut self.cells[start..end] {
+                *cell = Cell {
+                    symbol: " ".to_string(),
+                    width: 1,
+                    continuation: false,
+                    fg: None,
+                    bg: None,
+                    attrs: TextAttributes::default(),
+                };
+            }
+        }
+    }
+
+    // === Damage tracking ===
+
+    /// Returns true if the last flushed frame can be reused by a partial render.
+    pub fn has_previous_frame(&self) -> bool {
+        self.has_previous_frame
+    }
+
+    /// Starts a full frame: clears every cell and forgets widget regions,
+    /// which are re-recorded as the tree renders.
+    pub fn begin_full_frame(&mut self) {
+        self.clear();
+        self.widget_regions.clear();
+        self.damage_clip = None;
+    }
+
+    /// Starts a partial frame: restores the last flushed frame so that only
+    /// damaged regions need to be re-rendered on top of it.
+    pub fn begin_partial_frame(&mut self) {
+        self.cells.clone_from(&self.prev_cells);
+        self.clip_stack.clear();
+        self.damage_clip = None;
+    }
+
+    /// Restricts rendering to `region` (or lifts the restriction with `None`).
+    ///
+    /// The region is also pushed as a clip so widgets that straddle its
+    /// edges only overwrite damaged cells.
+    pub fn set_damage_clip(&mut self, region: Option<Region>) {
+        if self.damage_clip.take().is_some() {
+            self.pop_clip();
+        }
+        if let Some(region) = region {
+            self.push_clip(region);
+            self.damage_clip = Some(region);
+        }
+    }
+
+    /// Returns true if a widget occupying `region` must be rendered this pass.
+    pub fn is_damaged(&self, region: &Region) -> bool {
+        match &self.damage_clip {
+            Some(clip) => !clip.intersection(region).is_empty(),
+            None => true,
+        }
+    }
+
+    /// Records where the widget identified by `key` was painted, and the
+    /// size it asked for.
+    pub(crate) fn record_widget_region(&mut self, key: usize, region: Region, size: Size) {
+        self.widget_regions.insert(key, (region, size));
+    }
+
+    /// Returns where the widget identified by `key` was last painted.
+    pub(crate) fn widget_region(&self, key: usize) -> Option<Region> {
+        self.widget_regions.get(&key).map(|&(region, _)| region)
+    }
+
+    /// Returns the desired size of the widget identified by `key` when it
+    /// was last painted.
+    pub(crate) fn widget_size(&self, key: usize) -> Option<Size> {
+        self.widget_regions.get(&key).map(|&(_, size)| size)
+    }
+
+    /// Forgets where widgets for which `keep` returns false were painted.
+    pub(crate) fn retain_widget_regions(&mut self, mut keep: impl FnMut(usize) -> bool) {
+        self.widget_regions.retain(|&key, _| keep(key));
+    }
+
     /// Force a full redraw on the next flush.
     /// Call this after resize or when the terminal state is unknown.
     pub fn invalidate(&mut self) {
@@ -934,6 +1081,32 @@ mod tests {
         assert_eq!(a.intersection(&b), b.intersection(&a));
     }
 
+    // =========================================================================
+    // Region::union tests
+    // =========================================================================
+
+    #[test]
+    fn union_disjoint_regions_is_bounding_box() {
+        let a = Region::new(0, 0, 10, 5);
+        let b = Region::new(20, 10, 5, 5);
+        assert_eq!(a.union(&b), Region::new(0, 0, 25, 15));
+    }
+
+    #[test]
+    fn union_ignores_empty_region() {
+        let a = Region::new(5, 5, 10, 10);
+        let empty = Region::new(100, 100, 0, 0);
+        assert_eq!(a.union(&empty), a);
+        assert_eq!(empty.union(&a), a);
+    }
+
+    #[test]
+    fn union_is_commutative() {
+        let a = Region::new(-5, 20, 100, 80);
+        let b = Region::new(50, -40, 80, 100);
+        assert_eq!(a.union(&b), b.union(&a));
+    }
+
     // =========================================================================
     // Region::contains_point tests
     // =========================================================================
@@ -1279,4 +1452,38 @@ mod tests {
         canvas.put_str(10, 10, "Inside", None, None, TextAttributes::default());
         assert_eq!(canvas.get_char(10, 10), 'I');
     }
+
+    // =========================================================================
+    // Partial frame tests
+    // =========================================================================
+
+    #[test]
+    fn partial_frame_keeps_previous_cells() {
+        let mut canvas = Canvas::new(10, 3);
+        canvas.begin_full_frame();
+        canvas.put_str(0, 0, "Top", None, None, TextAttributes::default());
+        canvas.put_str(0, 2, "Bottom", None, None, TextAttributes::default());
+        canvas.end_frame();
+        assert!(canvas.has_previous_frame());
+
+        canvas.begin_partial_frame();
+        canvas.clear_region(Region::new(0, 2, 10, 1));
+        canvas.set_damage_clip(Some(Region::new(0, 2, 10, 1)));
+        canvas.put_str(0, 0, "Skipped", None, None, TextAttributes::default());
+        canvas.put_str(0, 2, "New", None, None, TextAttributes::default());
+        canvas.set_damage_clip(None);
+
+        assert_eq!(canvas.row_str(0).trim_end(), "Top");
+        assert_eq!(canvas.row_str(2).trim_end(), "New");
+    }
+
+    #[test]
+    fn damage_clip_limits_damaged_check() {
+        let mut canvas = Canvas::new(10, 10);
+        assert!(canvas.is_damaged(&Region::new(0, 0, 1, 1)));
+
+        canvas.set_damage_clip(Some(Region::new(0, 5, 10, 2)));
+        assert!(canvas.is_damaged(&Region::new(0, 6, 3, 3)));
+        assert!(!canvas.is_damaged(&Region::new(0, 0, 10, 5)));
+    }
 }
@@ -10,7 +10,9 @@ pub mod vertical_scroll;
 use crate::KeyCode;
 use crate::MouseEvent;
 use crate::canvas::{Canvas, Region, Size};
+use crate::layouts::Viewport;
 use crate::render_cache::RenderCache;
+use crate::scroll::ScrollState;
 use crate::widget::Widget;
 use tcss::ComputedStyle;
 
@@ -60,6 +62,29 @@ pub fn inner_region_for_container(region: Region, style: &ComputedStyle) -> Regi
     inner_region_from_cache(region, &cache, inner_width as i32, inner_height as i32)
 }
 
+/// `region` of scrolled content, moved by the scroll offset.
+pub(crate) fn scrolled_region(region: Region, scroll: &ScrollState) -> Region {
+    Region {
+        x: region.x - scroll.offset_x,
+        y: region.y - scroll.offset_y,
+        ..region
+    }
+}
+
+/// Scroll so `target` is visible in `content_region`, returning how far the
+/// content moved. See [`ScrollState::offset_to_show`].
+pub(crate) fn scroll_to_show(
+    scroll: &mut ScrollState,
+    content_region: Region,
+    target: Region,
+    center: bool,
+) -> (i32, i32) {
+    let before = (scroll.offset_x, scroll.offset_y);
+    let (x, y) = scroll.offset_to_show(content_region, target, center);
+    scroll.scroll_to(x, y);
+    (scroll.offset_x - before.0, scroll.offset_y - before.1)
+}
+
 fn inner_region_from_cache(
     region: Region,
     cache: &RenderCache,
@@ -204,6 +229,13 @@ impl<M> Widget<M> for Center<M> {
         self.child_mut().on_mouse(event, centered)
     }
 
+    fn child_regions(&self, region: Region, _viewport: Viewport) -> Vec<(usize, Region)> {
+        if !self.child().is_visible() {
+            return Vec::new();
+        }
+        vec![(0, self.centered_region(region))]
+    }
+
     fn clear_hover(&mut self) {
         if self.child().is_visible() {
             self.child_mut().clear_hover();
@@ -346,6 +378,13 @@ impl<M> Widget<M> for Middle<M> {
         self.child_mut().on_mouse(event, middled)
     }
 
+    fn child_regions(&self, region: Region, _viewport: Viewport) -> Vec<(usize, Region)> {
+        if !self.child().is_visible() {
+            return Vec::new();
+        }
+        vec![(0, self.middled_region(region))]
+    }
+
     fn clear_hover(&mut self) {
         if self.child().is_visible() {
             self.child_mut().clear_hover();
@@ -17,7 +17,7 @@ use tcss::{ComputedStyle, StyleOverride, WidgetMeta, WidgetStates};
 use crate::canvas::{Canvas, Region, Size};
 use crate::content::Content;
 use crate::keyline_canvas::KeylineCanvas;
-use crate::layouts::{self, Layout, Viewport, WidgetPlacement};
+use crate::layouts::{self, Layout, LayoutCache, LayoutCacheStats, Viewport, WidgetPlacement};
 use crate::render_cache::RenderCache;
 use crate::scroll::ScrollState;
 use crate::scrollbar::ScrollBarRender;
@@ -25,15 +25,6 @@ use crate::segment::Style;
 use crate::widget::Widget;
 use crate::{KeyCode, MouseEvent, MouseEventKind};
 
-/// Cached layout computation result.
-/// Stores the computed placements along with the region/viewport they were computed for.
-#[derive(Debug, Clone)]
-struct CachedLayout {
-    placements: Vec<WidgetPlacement>,
-    region: Region,
-    viewport: Viewport,
-}
-
 // Re-export for use by Horizontal/Vertical
 pub use tcss::types::Layout as ContainerLayoutDirection;
 
@@ -65,8 +56,7 @@ pub struct Container<M> {
     /// Used for content_height_for_scroll to match layout calculations.
     viewport: Size,
     /// Cached layout placements to avoid recomputing on every render.
-    /// Uses RefCell for interior mutability since render takes &self.
-    cached_layout: RefCell<Option<CachedLayout>>,
+    layout_cache: LayoutCache,
     /// Scroll state for overflow scrolling.
     /// Uses RefCell for interior mutability since render takes &self.
     scroll: RefCell<ScrollState>,
@@ -74,6 +64,10 @@ pub struct Container<M> {
     scrollbar_hover: Option<bool>,
     /// Scrollbar drag state: (is_vertical, grab_offset).
     scrollbar_drag: Option<(bool, i32)>,
+    /// Whether Tab cycles inside this container while it holds focus.
+    focus_trap: bool,
+    /// Whether Tab visits descendants.
+    can_focus_children: bool,
 }
 
 impl<M> Container<M> {
@@ -90,10 +84,12 @@ impl<M> Container<M> {
             border_subtitle: None,
             layout_override: None,
             viewport: Size::new(80, 24), // Default until on_resize is called
-            cached_layout: RefCell::new(None),
+            layout_cache: LayoutCache::new(),
             scroll: RefCell::new(ScrollState::default()),
             scrollbar_hover: None,
             scrollbar_drag: None,
+            focus_trap: false,
+            can_focus_children: true,
         }
     }
 
@@ -122,10 +118,23 @@ impl<M> Container<M> {
         self
     }
 
+    /// Keep Tab cycling inside this container while it holds focus, as for
+    /// a modal dialog.
+    pub fn with_focus_trap(mut self, focus_trap: bool) -> Self {
+        self.focus_trap = focus_trap;
+        self
+    }
+
+    /// Set whether Tab visits this container's descendants.
+    pub fn with_can_focus_children(mut self, can_focus_children: bool) -> Self {
+        self.can_focus_children = can_focus_children;
+        self
+    }
+
     /// Replace all children with a new list.
     pub fn set_children(&mut self, children: Vec<Box<dyn Widget<M>>>) {
         self.children = children;
-        self.cached_layout = RefCell::new(None);
+        self.layout_cache.invalidate();
         self.scroll = RefCell::new(ScrollState::default());
         self.dirty = true;
     }
@@ -327,19 +336,8 @@ impl<M> Container<M> {
     }
 
     /// Compute child placements using the appropriate layout algorithm.
-    /// Uses caching to avoid recomputation when region/viewport haven't changed.
+    /// Uses caching to avoid recomputation when no layout input has changed.
     fn compute_child_placements(&self, region: Region, viewport: Viewport) -> Vec<WidgetPlacement> {
-        // Check cache first - if region and viewport match, return cached placements
-        {
-            let cache = self.cached_layout.borrow();
-            if let Some(ref cached) = *cache {
-                if cached.region == region && cached.viewport == viewport {
-                    return cached.placements.clone();
-                }
-            }
-        }
-
-        // Cache miss - compute fresh placements
         let children_with_styles: Vec<layouts::LayoutChild> = self
             .children
             .iter()
@@ -358,21 +356,116 @@ impl<M> Container<M> {
         effective_style.layout = self.effective_layout();
 
         // Dispatch to layout (handles layers internally when needed)
-        let placements = layouts::arrange_children_with_layers(
+        self.layout_cache.arrange(
             &effective_style,
             &children_with_styles,
             region,
             viewport,
-        );
+            |children| {
+                layouts::arrange_children_with_layers(&effective_style, children, region, viewport)
+            },
+        )
+    }
 
-        // Store in cache
-        *self.cached_layout.borrow_mut() = Some(CachedLayout {
-            placements: placements.clone(),
-            region,
-            viewport,
-        });
+    /// Lay children out in `inner_region` (the region inside border and
+    /// padding), updating the scroll state. Returns the region left for
+    /// content once scrollbars are placed, and the unscrolled placements.
+    fn layout_content(
+        &self,
+        inner_region: Region,
+        viewport: Viewport,
+    ) -> (Region, Vec<WidgetPlacement>) {
+        // First compute placements to determine virtual content height
+        let initial_placements = self.compute_child_placements(inner_region, viewport);
 
-        placements
+        // Calculate virtual content dimensions from placements (max extent relative to inner_region)
+        let virtual_height = initial_placements
+            .iter()
+            .map(|p| {
+                let margin_bottom = self
+                    .children
+                    .get(p.child_index)
+                    .map(|child| child.get_style().margin.bottom.value as i32)
+                    .unwrap_or(0);
+                (p.region.y - inner_region.y) + p.region.height + margin_bottom
+            })
+            .max()
+            .unwrap_or(0);
+
+        let virtual_width = initial_placements
+            .iter()
+            .map(|p| {
+                let margin_right = self
+                    .children
+                    .get(p.child_index)
+                    .map(|child| child.get_style().margin.right.value as i32)
+                    .unwrap_or(0);
+                (p.region.x - inner_region.x) + p.region.width + margin_right
+            })
+            .max()
+            .unwrap_or(0);
+
+        // Update scroll state with virtual size and viewport
+        {
+            let mut scroll = self.scroll.borrow_mut();
+            scroll.set_virtual_size(virtual_width, virtual_height);
+            scroll.set_viewport(inner_region.width, inner_region.height);
+        }
+
+        // Determine if we need scrollbars and adjust content region
+        let show_v_scrollbar = self.show_vertical_scrollbar();
+        let content_region = self.content_region_for_scroll(inner_region);
+
+        // If scrollbar visibility changed the content width, we need to recompute placements
+        // and update virtual dimensions (important for width-based units like `w` in min-height: 40w)
+        let placements = if show_v_scrollbar && content_region.width < inner_region.width {
+            let new_placements = self.compute_child_placements(content_region, viewport);
+
+            // Recalculate virtual dimensions with the new placements (content_region based)
+            let new_virtual_height = new_placements
+                .iter()
+                .map(|p| {
+                    let margin_bottom = self
+                        .children
+                        .get(p.child_index)
+                        .map(|child| child.get_style().margin.bottom.value as i32)
+                        .unwrap_or(0);
+                    (p.region.y - content_region.y) + p.region.height + margin_bottom
+                })
+                .max()
+                .unwrap_or(0);
+
+            let new_virtual_width = new_placements
+                .iter()
+                .map(|p| {
+                    let margin_right = self
+                        .children
+                        .get(p.child_index)
+                        .map(|child| child.get_style().margin.right.value as i32)
+                        .unwrap_or(0);
+                    (p.region.x - content_region.x) + p.region.width + margin_right
+                })
+                .max()
+                .unwrap_or(0);
+
+            // Update scroll state with corrected virtual dimensions
+            {
+                let mut scroll = self.scroll.borrow_mut();
+                scroll.set_virtual_size(new_virtual_width, new_virtual_height);
+                scroll.set_viewport(content_region.width, content_region.height);
+            }
+
+            new_placements
+        } else {
+            initial_placements
+        };
+
+        (content_region, placements)
+    }
+
+    /// Hit/miss counts for this container's layout cache.
+    pub fn layout_cache_stats(&self) -> LayoutCacheStats {
+        self.layout_cache.stats()
     }
 
     /// Render keylines for the container.
@@ -814,93 +907,7 @@ Container {
 
         // 5. Update scroll state and compute content region
         let viewport = canvas.viewport();
-
-        // First compute placements to determine virtual content height
-        let initial_placements = self.compute_child_placements(inner_region, viewport);
-
-        // Calculate virtual content dimensions from placements (max extent relative to inner_region)
-        let virtual_height = initial_placements
-            .iter()
-            .map(|p| {
-                let margin_bottom = self
-                    .children
-                    .get(p.child_index)
-                    .map(|child| child.get_style().margin.bottom.value as i32)
-                    .unwrap_or(0);
-                (p.region.y - inner_region.y) + p.region.height + margin_bottom
-            })
-            .max()
-            .unwrap_or(0);
-
-        let virtual_width = initial_placements
-            .iter()
-            .map(|p| {
-                let margin_right = self
-                    .children
-                    .get(p.child_index)
-                    .map(|child| child.get_style().margin.right.value as i32)
-                    .unwrap_or(0);
-                (p.region.x - inner_region.x) + p.region.width + margin_right
-            })
-            .max()
-            .unwrap_or(0);
-
-        // Update scroll state with virtual size and viewport
-        {
-            let mut scroll = self.scroll.borrow_mut();
-            scroll.set_virtual_size(virtual_width, virtual_height);
-            scroll.set_viewport(inner_region.width, inner_region.height);
-        }
-
-        // Determine if we need scrollbars and adjust content region
-        let show_v_scrollbar = self.show_vertical_scrollbar();
-        let content_region = self.content_region_for_scroll(inner_region);
-
-        // If scrollbar visibility changed the content width, we need to recompute placements
-        // and update virtual dimensions (important for width-based units like `w` in min-height: 40w)
-        let placements = if show_v_scrollbar && content_region.width < inner_region.width {
-            // Invalidate cache since we need to recalculate with smaller width
-            *self.cached_layout.borrow_mut() = None;
-            let new_placements = self.compute_child_placements(content_region, viewport);
-
-            // Recalculate virtual dimensions with the new placements (content_region based)
-            let new_virtual_height = new_placements
-                .iter()
-                .map(|p| {
-                    let margin_bottom = self
-                        .children
-                        .get(p.child_index)
-                        .map(|child| child.get_style().margin.bottom.value as i32)
-                        .unwrap_or(0);
-                    (p.region.y - content_region.y) + p.region.height + margin_bottom
-                })
-                .max()
-                .unwrap_or(0);
-
-            let new_virtual_width = new_placements
-                .iter()
-                .map(|p| {
-                    let margin_right = self
-                        .children
-                        .get(p.child_index)
-                        .map(|child| child.get_style().margin.right.value as i32)
-                        .unwrap_or(0);
-                    (p.region.x - content_region.x) + p.region.width + margin_right
-                })
-                .max()
-                .unwrap_or(0);
-
-            // Update scroll state with corrected virtual dimensions
-            {
-                let mut scroll = self.scroll.borrow_mut();
-                scroll.set_virtual_size(new_virtual_width, new_virtual_height);
-                scroll.set_viewport(content_region.width, content_region.height);
-            }
-
-            new_placements
-        } else {
-            initial_placements
-        };
+        let (content_region, placements) = self.layout_content(inner_region, viewport);
 
         // Get scroll offsets for rendering
         let (offset_x, offset_y) = {
@@ -1362,13 +1369,12 @@ Container {
             id: self.id.clone(),
             classes: self.classes.clone(),
             states: WidgetStates::empty(),
+            ..Default::default()
         }
     }
 
     fn set_style(&mut self, style: ComputedStyle) {
         self.style = style;
-        // Invalidate layout cache since style may affect layout
-        *self.cached_layout.borrow_mut() = None;
     }
 
     fn get_style(&self) -> ComputedStyle {
@@ -1378,7 +1384,6 @@ Container {
     fn set_inline_style(&mut self, style: StyleOverride) {
         self.inline_style = style;
         self.dirty = true;
-        *self.cached_layout.borrow_mut() = None;
     }
 
     fn inline_style(&self) -> Option<&StyleOverride> {
@@ -1392,7 +1397,6 @@ Container {
     fn clear_inline_style(&mut self) {
         self.inline_style = StyleOverride::default();
         self.dirty = true;
-        *self.cached_layout.borrow_mut() = None;
     }
 
     fn is_dirty(&self) -> bool {
@@ -1401,8 +1405,6 @@ Container {
 
     fn mark_dirty(&mut self) {
         self.dirty = true;
-        // Invalidate layout cache when container is marked dirty
-        *self.cached_layout.borrow_mut() = None;
     }
 
     fn mark_clean(&mut self) {
@@ -1423,8 +1425,6 @@ Container {
         // Store viewport for content_height_for_scroll calculations
         log::debug!("Container::on_resize: size={}x{}", size.width, size.height);
         self.viewport = size;
-        // Invalidate layout cache since viewport changed
-        *self.cached_layout.borrow_mut() = None;
         for child in &mut self.children {
             child.on_resize(size);
         }
@@ -1568,6 +1568,14 @@ Container {
         None
     }
 
+    fn can_focus_children(&self) -> bool {
+        self.can_focus_children
+    }
+
+    fn is_focus_trap(&self) -> bool {
+        self.focus_trap
+    }
+
     fn count_focusable(&self) -> usize {
         self.children
             .iter()
@@ -1618,6 +1626,62 @@ Container {
         }
     }
 
+    fn insert_child(
+        &mut self,
+        index: usize,
+        child: Box<dyn Widget<M>>,
+    ) -> Result<(), Box<dyn Widget<M>>> {
+        self.children.insert(index.min(self.children.len()), child);
+        self.layout_cache.invalidate();
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn remove_child(&mut self, index: usize) -> Option<Box<dyn Widget<M>>> {
+        if index >= self.children.len() {
+            return None;
+        }
+        let child = self.children.remove(index);
+        self.layout_cache.invalidate();
+        self.dirty = true;
+        Some(child)
+    }
+
+    fn child_regions(&self, region: Region, viewport: Viewport) -> Vec<(usize, Region)> {
+        let inner_region = crate::containers::inner_region_for_container(region, &self.style);
+        let (_, placements) = self.layout_content(inner_region, viewport);
+        let scroll = self.scroll.borrow();
+        placements
+            .iter()
+            .map(|p| {
+                let region = crate::containers::scrolled_region(p.region, &scroll);
+                (p.child_index, region)
+            })
+            .collect()
+    }
+
+    fn scroll_to_region(
+        &mut self,
+        region: Region,
+        viewport: Viewport,
+        target: Region,
+        _animate: bool,
+        center: bool,
+    ) -> Option<(i32, i32)> {
+        let inner_region = crate::containers::inner_region_for_container(region, &self.style);
+        let (content_region, _) = self.layout_content(inner_region, viewport);
+        let moved = crate::containers::scroll_to_show(
+            &mut self.scroll.borrow_mut(),
+            content_region,
+            target,
+            center,
+        );
+        if moved != (0, 0) {
+            self.dirty = true;
+        }
+        Some(moved)
+    }
+
     fn border_title(&self) -> Option<&str> {
         self.border_title.as_deref()
     }
@@ -31,7 +31,7 @@ use tcss::{
 };
 
 use crate::keyline_canvas::KeylineCanvas;
-use crate::layouts::{self, GridLayout, Layout};
+use crate::layouts::{self, GridLayout, Layout, LayoutCache};
 use crate::{Canvas, KeyCode, MouseEvent, Region, Size, Widget};
 
 /// A grid container that arranges children in a 2D grid.
@@ -46,6 +46,8 @@ pub struct Grid<M> {
     inline_style: StyleOverride,
     dirty: bool,
     id: Option<String>,
+    /// Cached layout placements (reused while no layout input changes).
+    layout_cache: LayoutCache,
 }
 
 impl<M> Grid<M> {
@@ -57,6 +59,7 @@ impl<M> Grid<M> {
             inline_style: StyleOverride::default(),
             dirty: true,
             id: None,
+            layout_cache: LayoutCache::new(),
         }
     }
 
@@ -100,23 +103,30 @@ impl<M> Grid<M> {
             (region, (0, 0))
         };
 
-        // Force grid layout regardless of CSS
-        let mut layout = layouts::GridLayout::default();
-        let mut placements =
-            layout.arrange(&self.style, &children_with_styles, layout_region, viewport);
-
-        // Offset all placements when keylines are enabled
-        if keyline_offset != (0, 0) {
-            for placement in &mut placements {
-                placement.region.x += keyline_offset.0;
-                placement.region.y += keyline_offset.1;
-            }
-        }
+        self.layout_cache.arrange(
+            &self.style,
+            &children_with_styles,
+            region,
+            viewport,
+            |children| {
+                // Force grid layout regardless of CSS
+                let mut layout = layouts::GridLayout::default();
+                let mut placements = layout.arrange(&self.style, children, layout_region, viewport);
+
+                // Offset all placements when keylines are enabled
+                if keyline_offset != (0, 0) {
+                    for placement in &mut placements {
+                        placement.region.x += keyline_offset.0;
+                        placement.region.y += keyline_offset.1;
+                    }
+                }
 
-        // Apply post-layout alignment to match Textual's container-level align behavior.
-        layouts::apply_alignment(&mut placements, &children_with_styles, &self.style, region);
+                // Apply post-layout alignment to match Textual's container-level align behavior.
+                layouts::apply_alignment(&mut placements, children, &self.style, region);
 
-        placements
+                placements
+            },
+        )
     }
 
     /// Render keylines for the grid layout.
@@ -291,6 +301,7 @@ Grid {
             id: self.id.clone(),
             classes: Vec::new(),
             states: WidgetStates::empty(),
+            ..Default::default()
         }
     }
 
@@ -467,6 +478,35 @@ Grid {
         }
     }
 
+    fn insert_child(
+        &mut self,
+        index: usize,
+        child: Box<dyn Widget<M>>,
+    ) -> Result<(), Box<dyn Widget<M>>> {
+        self.children.insert(index.min(self.children.len()), child);
+        self.layout_cache.invalidate();
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn remove_child(&mut self, index: usize) -> Option<Box<dyn Widget<M>>> {
+        if index >= self.children.len() {
+            return None;
+        }
+        let child = self.children.remove(index);
+        self.layout_cache.invalidate();
+        self.dirty = true;
+        Some(child)
+    }
+
+    fn child_regions(&self, region: Region, viewport: layouts::Viewport) -> Vec<(usize, Region)> {
+        let inner_region = crate::containers::inner_region_for_container(region, &self.style);
+        self.compute_child_placements(inner_region, viewport)
+            .into_iter()
+            .map(|p| (p.child_index, p.region))
+            .collect()
+    }
+
     fn pre_layout(&mut self, _layout: &mut dyn Layout) {
         // Grid container doesn't configure layout at runtime
         // Override in ItemGrid for min_column_width, etc.
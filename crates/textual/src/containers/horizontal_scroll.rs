@@ -5,6 +5,8 @@
 
 use crate::canvas::{Canvas, Region, Size};
 use crate::containers::scrollable::ScrollableContainer;
+use crate::damage::Invalidation;
+use crate::layouts::Viewport;
 use crate::widget::Widget;
 use crate::{KeyCode, MessageEnvelope, MouseEvent};
 use tcss::{ComputedStyle, WidgetMeta, WidgetStates};
@@ -105,6 +107,7 @@ HorizontalScroll {
             id: self.id.clone(),
             classes: self.classes.clone(),
             states: self.inner.get_state(),
+            ..Default::default()
         }
     }
 
@@ -144,6 +147,10 @@ HorizontalScroll {
         self.inner.mark_clean()
     }
 
+    fn invalidation(&self) -> Option<Invalidation> {
+        self.inner.invalidation()
+    }
+
     fn on_event(&mut self, key: KeyCode) -> Option<M> {
         self.inner.on_event(key)
     }
@@ -160,6 +167,10 @@ HorizontalScroll {
         self.inner.on_mouse_with_sender(event, region)
     }
 
+    fn on_pointer(&mut self, event: &crate::mouse::PointerEvent) -> Option<M> {
+        self.inner.on_pointer(event)
+    }
+
     fn set_hover(&mut self, is_hovered: bool) -> bool {
         self.inner.set_hover(is_hovered)
     }
@@ -176,6 +187,18 @@ HorizontalScroll {
         self.inner.is_focusable()
     }
 
+    fn tab_index(&self) -> i32 {
+        self.inner.tab_index()
+    }
+
+    fn can_focus_children(&self) -> bool {
+        self.inner.can_focus_children()
+    }
+
+    fn is_focus_trap(&self) -> bool {
+        self.inner.is_focus_trap()
+    }
+
     fn is_visible(&self) -> bool {
         self.inner.is_visible()
     }
@@ -228,10 +251,46 @@ HorizontalScroll {
         self.inner.get_child_mut(index)
     }
 
+    fn insert_child(
+        &mut self,
+        index: usize,
+        child: Box<dyn Widget<M>>,
+    ) -> Result<(), Box<dyn Widget<M>>> {
+        self.inner.insert_child(index, child)
+    }
+
+    fn remove_child(&mut self, index: usize) -> Option<Box<dyn Widget<M>>> {
+        self.inner.remove_child(index)
+    }
+
+    fn child_regions(&self, region: Region, viewport: Viewport) -> Vec<(usize, Region)> {
+        self.inner.child_regions(region, viewport)
+    }
+
+    fn scroll_to_region(
+        &mut self,
+        region: Region,
+        viewport: Viewport,
+        target: Region,
+        animate: bool,
+        center: bool,
+    ) -> Option<(i32, i32)> {
+        self.inner
+            .scroll_to_region(region, viewport, target, animate, center)
+    }
+
     fn handle_message(&mut self, envelope: &mut MessageEnvelope<M>) -> Option<M> {
         self.inner.handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut MessageEnvelope<M>,
+        ctx: &crate::AppContext<M>,
+    ) -> Option<M> {
+        self.inner.on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.id.as_deref()
     }
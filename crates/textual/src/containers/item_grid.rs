@@ -9,7 +9,7 @@
 use tcss::{ComputedStyle, StyleOverride, WidgetMeta, WidgetStates, types::Visibility};
 
 use crate::canvas::{Canvas, Region, Size};
-use crate::layouts::{self, GridLayout, Layout};
+use crate::layouts::{self, GridLayout, Layout, LayoutCache};
 use crate::widget::Widget;
 use crate::{KeyCode, MouseEvent};
 
@@ -42,6 +42,9 @@ pub struct ItemGrid<M> {
     max_column_width: Option<u16>,
     stretch_height: bool,
     regular: bool,
+
+    /// Cached layout placements (reused while no layout input changes).
+    layout_cache: LayoutCache,
 }
 
 impl<M> ItemGrid<M> {
@@ -57,6 +60,7 @@ impl<M> ItemGrid<M> {
             max_column_width: None,
             stretch_height: true,
             regular: false,
+            layout_cache: LayoutCache::new(),
         }
     }
 
@@ -121,16 +125,24 @@ impl<M> ItemGrid<M> {
             })
             .collect();
 
-        // Create and configure GridLayout
-        let mut layout = GridLayout::default();
-        layout.min_column_width = self.min_column_width;
-        layout.max_column_width = self.max_column_width;
-        layout.stretch_height = self.stretch_height;
-        layout.regular = self.regular;
-
-        let mut placements = layout.arrange(&self.style, &children_with_styles, region, viewport);
-        layouts::apply_alignment(&mut placements, &children_with_styles, &self.style, region);
-        placements
+        self.layout_cache.arrange(
+            &self.style,
+            &children_with_styles,
+            region,
+            viewport,
+            |children| {
+                // Create and configure GridLayout
+                let mut layout = GridLayout::default();
+                layout.min_column_width = self.min_column_width;
+                layout.max_column_width = self.max_column_width;
+                layout.stretch_height = self.stretch_height;
+                layout.regular = self.regular;
+
+                let mut placements = layout.arrange(&self.style, children, region, viewport);
+                layouts::apply_alignment(&mut placements, children, &self.style, region);
+                placements
+            },
+        )
     }
 }
 
@@ -175,6 +187,7 @@ impl<M> Widget<M> for ItemGrid<M> {
             id: self.id.clone(),
             classes: Vec::new(),
             states: WidgetStates::empty(),
+            ..Default::default()
         }
     }
 
@@ -348,6 +361,35 @@ impl<M> Widget<M> for ItemGrid<M> {
         }
     }
 
+    fn insert_child(
+        &mut self,
+        index: usize,
+        child: Box<dyn Widget<M>>,
+    ) -> Result<(), Box<dyn Widget<M>>> {
+        self.children.insert(index.min(self.children.len()), child);
+        self.layout_cache.invalidate();
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn remove_child(&mut self, index: usize) -> Option<Box<dyn Widget<M>>> {
+        if index >= self.children.len() {
+            return None;
+        }
+        let child = self.children.remove(index);
+        self.layout_cache.invalidate();
+        self.dirty = true;
+        Some(child)
+    }
+
+    fn child_regions(&self, region: Region, viewport: layouts::Viewport) -> Vec<(usize, Region)> {
+        let inner_region = crate::containers::inner_region_for_container(region, &self.style);
+        self.compute_child_placements(inner_region, viewport)
+            .into_iter()
+            .map(|p| (p.child_index, p.region))
+            .collect()
+    }
+
     fn pre_layout(&mut self, layout: &mut dyn Layout) {
         // Configure GridLayout with our runtime properties
         if let Some(grid) = layout.as_grid_mut() {
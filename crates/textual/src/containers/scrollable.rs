@@ -10,7 +10,8 @@
 use std::cell::RefCell;
 
 use crate::canvas::{Canvas, Region, Size};
-use crate::layouts::{self, Viewport};
+use crate::damage::Invalidation;
+use crate::layouts::{self, LayoutCache, Viewport};
 use crate::render_cache::RenderCache;
 use crate::scroll::{ScrollMessage, ScrollState};
 use crate::scrollbar::ScrollBarRender;
@@ -54,6 +55,8 @@ pub struct ScrollableContainer<M> {
     id: Option<String>,
     /// CSS classes for styling
     classes: Vec<String>,
+    /// Cached layout placements (reused while no layout input changes)
+    layout_cache: LayoutCache,
 }
 
 struct ScrollLayout {
@@ -78,6 +81,7 @@ impl<M> ScrollableContainer<M> {
             scrollbar_drag: None,
             id: None,
             classes: Vec::new(),
+            layout_cache: LayoutCache::new(),
         }
     }
 
@@ -304,7 +308,15 @@ impl<M> ScrollableContainer<M> {
             })
             .collect();
 
-        layouts::arrange_children_with_layers(&self.style, &children_with_styles, region, viewport)
+        self.layout_cache.arrange(
+            &self.style,
+            &children_with_styles,
+            region,
+            viewport,
+            |children| {
+                layouts::arrange_children_with_layers(&self.style, children, region, viewport)
+            },
+        )
     }
 
     fn compute_virtual_size(
@@ -842,6 +854,12 @@ ScrollableContainer {
         }
     }
 
+    fn invalidation(&self) -> Option<Invalidation> {
+        // Only our own state: dirty children report themselves. Scrolling
+        // moves children within our region without changing its size.
+        self.dirty.then_some(Invalidation::Repaint)
+    }
+
     fn for_each_child(&mut self, f: &mut dyn FnMut(&mut dyn Widget<M>)) {
         for child in &mut self.children {
             f(child.as_mut());
@@ -855,6 +873,7 @@ ScrollableContainer {
             id: self.id.clone(),
             classes: self.classes.clone(),
             states: WidgetStates::empty(),
+            ..Default::default()
         }
     }
 
@@ -1418,6 +1437,61 @@ ScrollableContainer {
             .map(|c| c.as_mut() as &mut dyn Widget<M>)
     }
 
+    fn insert_child(
+        &mut self,
+        index: usize,
+        child: Box<dyn Widget<M>>,
+    ) -> Result<(), Box<dyn Widget<M>>> {
+        self.children.insert(index.min(self.children.len()), child);
+        self.layout_cache.invalidate();
+        self.dirty = true;
+        Ok(())
+    }
+
+    fn remove_child(&mut self, index: usize) -> Option<Box<dyn Widget<M>>> {
+        if index >= self.children.len() {
+            return None;
+        }
+        let child = self.children.remove(index);
+        self.layout_cache.invalidate();
+        self.dirty = true;
+        Some(child)
+    }
+
+    fn child_regions(&self, region: Region, viewport: Viewport) -> Vec<(usize, Region)> {
+        let inner_region = crate::containers::inner_region_for_container(region, &self.style);
+        let layout = self.compute_scroll_layout(inner_region, viewport);
+        let scroll = self.scroll.borrow();
+        layout
+            .placements
+            .iter()
+            .map(|p| {
+                let region = crate::containers::scrolled_region(p.region, &scroll);
+                (p.child_index, region)
+            })
+            .collect()
+    }
+
+    fn scroll_to_region(
+        &mut self,
+        region: Region,
+        viewport: Viewport,
+        target: Region,
+        animate: bool,
+        center: bool,
+    ) -> Option<(i32, i32)> {
+        let inner_region = crate::containers::inner_region_for_container(region, &self.style);
+        let layout = self.compute_scroll_layout(inner_region, viewport);
+        let (before, (x, y)) = {
+            let scroll = self.scroll.borrow();
+            let offsets = scroll.offset_to_show(layout.content_region, target, center);
+            ((scroll.offset_x, scroll.offset_y), offsets)
+        };
+        self.handle_scroll(ScrollMessage::ScrollTo { x, y, animate });
+        let scroll = self.scroll.borrow();
+        Some((scroll.offset_x - before.0, scroll.offset_y - before.1))
+    }
+
     fn clear_hover(&mut self) {
         self.scrollbar_hover = None;
         for child in &mut self.children {
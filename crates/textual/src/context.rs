@@ -5,17 +5,26 @@
 //! - Post messages to be processed in the next event loop tick
 //! - Set timers and intervals for delayed/periodic messages
 //! - Spawn async tasks that can send messages back
+//! - Run cancellable background [workers](crate::worker)
+//! - Start [timers](crate::timer) that call back into the widget
+//! - [Suspend](crate::suspend) the app to run another terminal program
 //!
 //! The `MountContext` extends `AppContext` with widget tree access for querying
 //! and modifying widgets during the `on_mount` lifecycle hook.
 
+use std::fmt;
+use std::future::Future;
 use std::time::Duration;
 
 use tokio::sync::mpsc;
 
 use crate::Widget;
+use crate::error::Result;
 use crate::message::MessageEnvelope;
-use crate::tree::{DOMQuery, WidgetTree};
+use crate::suspend::TerminalControl;
+use crate::timer::{TimerHandle, TimerManager};
+use crate::tree::{DOMQuery, MountPosition, WidgetTree};
+use crate::worker::{self, WorkerHandle, WorkerManager, WorkerSender, WorkerStateChanged};
 
 /// Context provided to widgets for posting messages and spawning async tasks.
 ///
@@ -24,13 +33,35 @@ use crate::tree::{DOMQuery, WidgetTree};
 ///
 /// When bound to a widget via `with_sender_info`, messages sent through this
 /// context will include the widget's ID and type for attribution.
-#[derive(Clone)]
 pub struct AppContext<M> {
     sender: mpsc::UnboundedSender<MessageEnvelope<M>>,
     /// The widget's string ID (from `Widget::id()`).
     sender_id: Option<String>,
     /// The widget's type name (from `Widget::type_name()`).
     sender_type: String,
+    /// Workers started through this context and its clones.
+    workers: WorkerManager,
+    /// Widget timers started through this context and its clones.
+    timers: TimerManager,
+    /// The app's hold on the terminal, given up while suspended.
+    terminal: TerminalControl,
+    /// Identity of the widget whose lifecycle hook this context was made
+    /// for; its workers and timers are cancelled when it is removed.
+    owner: Option<usize>,
+}
+
+impl<M> Clone for AppContext<M> {
+    fn clone(&self) -> Self {
+        Self {
+            sender: self.sender.clone(),
+            sender_id: self.sender_id.clone(),
+            sender_type: self.sender_type.clone(),
+            workers: self.workers.clone(),
+            timers: self.timers.clone(),
+            terminal: self.terminal.clone(),
+            owner: self.owner,
+        }
+    }
 }
 
 impl<M> AppContext<M> {
@@ -40,6 +71,10 @@ impl<M> AppContext<M> {
             sender,
             sender_id: None,
             sender_type: "AppContext".to_string(),
+            workers: WorkerManager::default(),
+            timers: TimerManager::default(),
+            terminal: TerminalControl::default(),
+            owner: None,
         }
     }
 
@@ -76,6 +111,94 @@ impl<M> AppContext<M> {
     pub fn sender(&self) -> mpsc::UnboundedSender<MessageEnvelope<M>> {
         self.sender.clone()
     }
+
+    /// The app's workers, shared by every clone of this context.
+    pub fn workers(&self) -> &WorkerManager {
+        &self.workers
+    }
+
+    /// The app's widget timers and the clock they run on, shared by every
+    /// clone of this context.
+    pub fn timers(&self) -> &TimerManager {
+        &self.timers
+    }
+
+    /// Give up the terminal while `f` runs, e.g. to open `$EDITOR` or a
+    /// pager, returning what `f` returns.
+    ///
+    /// Leaves raw mode and the alternate screen, stops reading input and
+    /// pauses the app's [clock](crate::timer::Clock), then restores all of
+    /// them (even if `f` panics) and redraws the whole screen before the
+    /// next frame. Fails if the terminal can't be switched. See
+    /// [`suspend`](crate::suspend).
+    ///
+    /// # Example
+    /// ```ignore
+    /// let editor = std::env::var("EDITOR").unwrap_or_else(|_| "vi".into());
+    /// let status = ctx.suspend(|| Command::new(editor).arg(&path).status())??;
+    /// ```
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> Result<R> {
+        Ok(self.terminal.suspend(self.timers.clock(), f)?)
+    }
+
+    /// Share `terminal` with the event loop, instead of a detached one.
+    pub(crate) fn with_terminal(mut self, terminal: TerminalControl) -> Self {
+        self.terminal = terminal;
+        self
+    }
+
+    /// Tie workers and timers started through this context to the widget
+    /// with identity `owner`.
+    pub(crate) fn with_owner(mut self, owner: usize) -> Self {
+        self.owner = Some(owner);
+        self
+    }
+
+    /// Start a one-shot [timer](crate::timer) that calls this context's
+    /// widget's [`on_timer`](crate::Widget::on_timer) after `delay`.
+    ///
+    /// Unlike [`set_timer`](Self::set_timer), the timer is paused while the
+    /// widget is hidden and cancelled when it is removed. Only contexts
+    /// handed to a widget's hooks can start timers; elsewhere this logs a
+    /// warning and returns `None`.
+    ///
+    /// # Example
+    /// ```ignore
+    /// fn on_mount(&mut self, ctx: &AppContext<M>) {
+    ///     self.blink = ctx.start_timer(Duration::from_millis(500));
+    /// }
+    /// ```
+    pub fn start_timer(&self, delay: Duration) -> Option<TimerHandle> {
+        self.start_widget_timer(delay, false)
+    }
+
+    /// Start a repeating [timer](crate::timer) that calls this context's
+    /// widget's [`on_timer`](crate::Widget::on_timer) every `period`.
+    ///
+    /// See [`start_timer`](Self::start_timer) for how it differs from
+    /// [`set_interval`](Self::set_interval).
+    pub fn start_interval(&self, period: Duration) -> Option<TimerHandle> {
+        self.start_widget_timer(period, true)
+    }
+
+    fn start_widget_timer(&self, delay: Duration, repeat: bool) -> Option<TimerHandle> {
+        let Some(owner) = self.owner else {
+            log::warn!(
+                "TIMER: {} has no widget to call back, timer not started",
+                self.sender_type
+            );
+            return None;
+        };
+        Some(self.timers.start(owner, delay, repeat))
+    }
+
+    fn worker_sender(&self) -> WorkerSender<M> {
+        WorkerSender {
+            sender: self.sender.clone(),
+            sender_id: self.sender_id.clone(),
+            sender_type: self.sender_type.clone(),
+        }
+    }
 }
 
 impl<M: Send + 'static> AppContext<M> {
@@ -91,6 +214,25 @@ impl<M: Send + 'static> AppContext<M> {
         let _ = self.sender.send(envelope);
     }
 
+    /// Post a message to the first widget matching `selector`, processed in
+    /// the next event loop tick.
+    ///
+    /// The widget's [`on_message`](crate::widget::Widget::on_message) sees
+    /// it first, then it bubbles through the widget's ancestors and finally
+    /// reaches `App::handle_message`, unless a handler prevents that with
+    /// [`MessageEnvelope::prevent_default`]. The envelope's `target` is the
+    /// selector; if nothing matches, a warning is logged and the message
+    /// goes straight to the app.
+    ///
+    /// `selector` is a TCSS selector list such as `#sidebar`,
+    /// `.panel > Button` or `Input:focus`, matched as in a stylesheet. An
+    /// invalid selector matches nothing.
+    pub fn post_to(&self, selector: impl Into<String>, message: M) {
+        let envelope = MessageEnvelope::new(message, self.sender_id.as_deref(), &self.sender_type)
+            .with_target(selector);
+        let _ = self.sender.send(envelope);
+    }
+
     /// Set a one-shot timer that fires a message after a delay.
     ///
     /// If this context is bound to a widget, the timer message will include
@@ -168,6 +310,76 @@ impl<M: Send + 'static> AppContext<M> {
             cancel_tx: Some(cancel_tx),
         }
     }
+
+    /// Run `work` as a background [worker](crate::worker).
+    ///
+    /// Each state change is posted as a [`WorkerStateChanged`]; when `work`
+    /// succeeds its message is posted just before the `Success` state, and
+    /// an error is reported in the `Error` state. If `exclusive`, workers
+    /// this context's owner started earlier in `group` are cancelled first.
+    ///
+    /// Workers started from a widget's lifecycle hook are cancelled when the
+    /// widget is removed; all workers are cancelled when the app exits.
+    ///
+    /// # Example
+    /// ```ignore
+    /// ctx.run_worker("search", "search", true, async move {
+    ///     let results = search(&query).await?;
+    ///     Ok::<_, SearchError>(Message::Results(results))
+    /// });
+    /// ```
+    pub fn run_worker<F, E>(
+        &self,
+        name: &str,
+        group: &str,
+        exclusive: bool,
+        work: F,
+    ) -> WorkerHandle
+    where
+        M: From<WorkerStateChanged>,
+        F: Future<Output = std::result::Result<M, E>> + Send + 'static,
+        E: fmt::Display,
+    {
+        worker::spawn_worker(
+            &self.workers,
+            self.worker_sender(),
+            name,
+            group,
+            exclusive,
+            self.owner,
+            move |_| worker::spawn_future(work),
+        )
+    }
+
+    /// Run blocking `work` on a background thread as a
+    /// [worker](crate::worker).
+    ///
+    /// Behaves like [`run_worker`](Self::run_worker), except that a
+    /// cancelled thread can't be interrupted: `work` receives its worker's
+    /// handle and should return early once
+    /// [`is_cancelled`](WorkerHandle::is_cancelled).
+    pub fn run_worker_thread<F, E>(
+        &self,
+        name: &str,
+        group: &str,
+        exclusive: bool,
+        work: F,
+    ) -> WorkerHandle
+    where
+        M: From<WorkerStateChanged>,
+        F: FnOnce(&WorkerHandle) -> std::result::Result<M, E> + Send + 'static,
+        E: fmt::Display,
+    {
+        worker::spawn_worker(
+            &self.workers,
+            self.worker_sender(),
+            name,
+            group,
+            exclusive,
+            self.owner,
+            move |handle| worker::spawn_thread(handle, work),
+        )
+    }
 }
 
 /// Handle to cancel a running interval.
@@ -328,6 +540,38 @@ impl<'a, M: 'static> MountContext<'a, M> {
         self.query_one_as::<crate::widget::screen::Screen<M>, _, _>("Screen", f)
     }
 
+    /// Set CSS variable `$name` for the app, overriding the stylesheet's
+    /// definition and any theme variable of the same name.
+    pub fn set_css_variable(&mut self, name: &str, value: &str) {
+        self.tree.set_css_variable(name, value);
+    }
+
+    /// Remove a CSS variable set with
+    /// [`set_css_variable`](Self::set_css_variable).
+    pub fn remove_css_variable(&mut self, name: &str) {
+        self.tree.remove_css_variable(name);
+    }
+
+    /// Mount `widget` as a child of the first widget matching
+    /// `parent_selector`, without recomposing the tree.
+    ///
+    /// Untouched widgets keep their state and the focused widget keeps
+    /// focus. See [`WidgetTree::mount`].
+    pub fn mount(
+        &mut self,
+        parent_selector: &str,
+        widget: Box<dyn Widget<M>>,
+        position: MountPosition,
+    ) -> Result<()> {
+        self.tree.mount(parent_selector, widget, position)
+    }
+
+    /// Remove every widget matching `selector`, with its descendants,
+    /// returning how many were removed. See [`WidgetTree::remove`].
+    pub fn remove(&mut self, selector: &str) -> usize {
+        self.tree.remove(selector)
+    }
+
     /// Query for multiple widgets matching a selector.
     ///
     /// Returns a `DOMQuery` that enables bulk operations on all matching widgets.
@@ -436,6 +680,15 @@ impl<'a, M: 'static> EventContext<'a, M> {
         self.tree.set_focus_index(index)
     }
 
+    /// Scroll every scrollable ancestor of the first widget matching
+    /// `selector` so the widget is visible, centering it if `center`.
+    ///
+    /// Returns `false` if nothing matches. See
+    /// [`WidgetTree::scroll_to_widget`].
+    pub fn scroll_to_widget(&mut self, selector: &str, animate: bool, center: bool) -> bool {
+        self.tree.scroll_to_widget(selector, animate, center)
+    }
+
     /// Get the underlying AppContext for timer/interval operations.
     pub fn app_context(&self) -> &AppContext<M> {
         &self.app_ctx
@@ -448,6 +701,41 @@ impl<'a, M: 'static> EventContext<'a, M> {
     {
         self.query_one_as::<crate::widget::screen::Screen<M>, _, _>("Screen", f)
     }
+
+    /// Set CSS variable `$name` for the app, overriding the stylesheet's
+    /// definition and any theme variable of the same name.
+    ///
+    /// Widgets whose styles use the variable are restyled before the next
+    /// frame.
+    pub fn set_css_variable(&mut self, name: &str, value: &str) {
+        self.tree.set_css_variable(name, value);
+    }
+
+    /// Remove a CSS variable set with
+    /// [`set_css_variable`](Self::set_css_variable).
+    pub fn remove_css_variable(&mut self, name: &str) {
+        self.tree.remove_css_variable(name);
+    }
+
+    /// Mount `widget` as a child of the first widget matching
+    /// `parent_selector`, without recomposing the tree.
+    ///
+    /// Untouched widgets keep their state and the focused widget keeps
+    /// focus. See [`WidgetTree::mount`].
+    pub fn mount(
+        &mut self,
+        parent_selector: &str,
+        widget: Box<dyn Widget<M>>,
+        position: MountPosition,
+    ) -> Result<()> {
+        self.tree.mount(parent_selector, widget, position)
+    }
+
+    /// Remove every widget matching `selector`, with its descendants,
+    /// returning how many were removed. See [`WidgetTree::remove`].
+    pub fn remove(&mut self, selector: &str) -> usize {
+        self.tree.remove(selector)
+    }
 }
 
 // Delegate AppContext methods to EventContext
@@ -459,6 +747,13 @@ impl<'a, M: Send + 'static> EventContext<'a, M> {
         self.app_ctx.post(message);
     }
 
+    /// Post a message to the first widget matching `selector`.
+    ///
+    /// See [`AppContext::post_to`] for details.
+    pub fn post_to(&self, selector: impl Into<String>, message: M) {
+        self.app_ctx.post_to(selector, message);
+    }
+
     /// Set a one-shot timer that fires a message after a delay.
     ///
     /// See [`AppContext::set_timer`] for details.
@@ -482,6 +777,63 @@ impl<'a, M: Send + 'static> EventContext<'a, M> {
     pub fn sender(&self) -> mpsc::UnboundedSender<MessageEnvelope<M>> {
         self.app_ctx.sender()
     }
+
+    /// Run `work` as a background worker.
+    ///
+    /// See [`AppContext::run_worker`] for details.
+    pub fn run_worker<F, E>(
+        &self,
+        name: &str,
+        group: &str,
+        exclusive: bool,
+        work: F,
+    ) -> WorkerHandle
+    where
+        M: From<WorkerStateChanged>,
+        F: Future<Output = std::result::Result<M, E>> + Send + 'static,
+        E: fmt::Display,
+    {
+        self.app_ctx.run_worker(name, group, exclusive, work)
+    }
+
+    /// Run blocking `work` on a background thread as a worker.
+    ///
+    /// See [`AppContext::run_worker_thread`] for details.
+    pub fn run_worker_thread<F, E>(
+        &self,
+        name: &str,
+        group: &str,
+        exclusive: bool,
+        work: F,
+    ) -> WorkerHandle
+    where
+        M: From<WorkerStateChanged>,
+        F: FnOnce(&WorkerHandle) -> std::result::Result<M, E> + Send + 'static,
+        E: fmt::Display,
+    {
+        self.app_ctx.run_worker_thread(name, group, exclusive, work)
+    }
+
+    /// The app's workers.
+    ///
+    /// See [`AppContext::workers`] for details.
+    pub fn workers(&self) -> &WorkerManager {
+        self.app_ctx.workers()
+    }
+
+    /// The app's widget timers.
+    ///
+    /// See [`AppContext::timers`] for details.
+    pub fn timers(&self) -> &TimerManager {
+        self.app_ctx.timers()
+    }
+
+    /// Give up the terminal while `f` runs.
+    ///
+    /// See [`AppContext::suspend`] for details.
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> Result<R> {
+        self.app_ctx.suspend(f)
+    }
 }
 
 // Delegate AppContext methods to MountContext
@@ -493,6 +845,13 @@ impl<'a, M: Send + 'static> MountContext<'a, M> {
         self.app_ctx.post(message);
     }
 
+    /// Post a message to the first widget matching `selector`.
+    ///
+    /// See [`AppContext::post_to`] for details.
+    pub fn post_to(&self, selector: impl Into<String>, message: M) {
+        self.app_ctx.post_to(selector, message);
+    }
+
     /// Set a one-shot timer that fires a message after a delay.
     ///
     /// See [`AppContext::set_timer`] for details.
@@ -516,6 +875,63 @@ impl<'a, M: Send + 'static> MountContext<'a, M> {
     pub fn sender(&self) -> mpsc::UnboundedSender<MessageEnvelope<M>> {
         self.app_ctx.sender()
     }
+
+    /// Run `work` as a background worker.
+    ///
+    /// See [`AppContext::run_worker`] for details.
+    pub fn run_worker<F, E>(
+        &self,
+        name: &str,
+        group: &str,
+        exclusive: bool,
+        work: F,
+    ) -> WorkerHandle
+    where
+        M: From<WorkerStateChanged>,
+        F: Future<Output = std::result::Result<M, E>> + Send + 'static,
+        E: fmt::Display,
+    {
+        self.app_ctx.run_worker(name, group, exclusive, work)
+    }
+
+    /// Run blocking `work` on a background thread as a worker.
+    ///
+    /// See [`AppContext::run_worker_thread`] for details.
+    pub fn run_worker_thread<F, E>(
+        &self,
+        name: &str,
+        group: &str,
+        exclusive: bool,
+        work: F,
+    ) -> WorkerHandle
+    where
+        M: From<WorkerStateChanged>,
+        F: FnOnce(&WorkerHandle) -> std::result::Result<M, E> + Send + 'static,
+        E: fmt::Display,
+    {
+        self.app_ctx.run_worker_thread(name, group, exclusive, work)
+    }
+
+    /// The app's workers.
+    ///
+    /// See [`AppContext::workers`] for details.
+    pub fn workers(&self) -> &WorkerManager {
+        self.app_ctx.workers()
+    }
+
+    /// The app's widget timers.
+    ///
+    /// See [`AppContext::timers`] for details.
+    pub fn timers(&self) -> &TimerManager {
+        self.app_ctx.timers()
+    }
+
+    /// Give up the terminal while `f` runs.
+    ///
+    /// See [`AppContext::suspend`] for details.
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> Result<R> {
+        self.app_ctx.suspend(f)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,322 @@
+//! Damage tracking for partial re-rendering.
+//!
+//! Widgets report what they need before the next frame via
+//! [`Widget::invalidation`](crate::Widget::invalidation):
+//!
+//! - [`Invalidation::Repaint`]: the widget's cells changed but its size and
+//!   position did not, so only the area it occupied last frame is redrawn.
+//!   This is the default; it becomes a relayout if the widget's desired
+//!   size changed since it was painted.
+//! - [`Invalidation::Relayout`]: the widget may have changed size or
+//!   visibility, so placements of its siblings may shift and the whole
+//!   screen is redrawn.
+//!
+//! The [`WidgetTree`](crate::tree::WidgetTree) accumulates damage into
+//! [`DirtyRegions`] while styles are resolved. Rendering then re-runs only
+//! for widgets intersecting those regions; every other cell is carried over
+//! from the previous frame.
+
+use tcss::types::ComputedStyle;
+
+use crate::canvas::{Canvas, Region};
+use crate::widget::Widget;
+
+/// Maximum number of disjoint regions tracked before collapsing them into
+/// a single bounding region. Keeps the per-region render passes bounded.
+const MAX_REGIONS: usize = 8;
+
+/// What a widget needs redone before the next frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Invalidation {
+    /// Redraw the widget in place; its layout is unchanged.
+    Repaint,
+    /// Re-run layout; the widget's size or visibility may have changed.
+    Relayout,
+}
+
+/// A set of screen regions that must be re-rendered on the next frame.
+///
+/// Overlapping regions are unioned as they are added so the set stays
+/// small. A "full" damage set covers the entire screen.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DirtyRegions {
+    regions: Vec<Region>,
+    full: bool,
+}
+
+impl DirtyRegions {
+    /// Create an empty damage set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a damage set covering the entire screen.
+    pub fn full() -> Self {
+        Self {
+            regions: Vec::new(),
+            full: true,
+        }
+    }
+
+    /// Mark a region as damaged, merging it with any regions it overlaps.
+    pub fn add(&mut self, region: Region) {
+        if self.full || region.is_empty() {
+            return;
+        }
+
+        let mut merged = region;
+        loop {
+            let before = self.regions.len();
+            self.regions.retain(|existing| {
+                if existing.intersection(&merged).is_empty() {
+                    true
+                } else {
+                    merged = merged.union(existing);
+                    false
+                }
+            });
+            if self.regions.len() == before {
+                break;
+            }
+        }
+        self.regions.push(merged);
+
+        if self.regions.len() > MAX_REGIONS {
+            let bounds = self
+                .regions
+                .iter()
+                .skip(1)
+                .fold(self.regions[0], |acc, r| acc.union(r));
+            self.regions.clear();
+            self.regions.push(bounds);
+        }
+    }
+
+    /// Mark the entire screen as damaged.
+    pub fn mark_full(&mut self) {
+        self.full = true;
+        self.regions.clear();
+    }
+
+    /// Returns true if the entire screen must be re-rendered.
+    pub fn is_full(&self) -> bool {
+        self.full
+    }
+
+    /// Returns true if nothing needs to be re-rendered.
+    pub fn is_empty(&self) -> bool {
+        !self.full && self.regions.is_empty()
+    }
+
+    /// The disjoint damaged regions (empty when [`is_full`](Self::is_full)).
+    pub fn regions(&self) -> &[Region] {
+        &self.regions
+    }
+
+    /// Reset to an empty damage set.
+    pub fn clear(&mut self) {
+        self.regions.clear();
+        self.full = false;
+    }
+}
+
+/// Identity of a widget for damage bookkeeping.
+///
+/// Boxed widgets keep a stable heap address for as long as they live in the
+/// tree, so the data pointer identifies the same widget across frames.
+pub(crate) fn widget_key<M>(widget: &dyn Widget<M>) -> usize {
+    widget as *const dyn Widget<M> as *const () as usize
+}
+
+/// Converts widget invalidations into damaged regions, using where each
+/// widget was painted on the previous frame.
+pub(crate) struct DamageCollector<'a> {
+    canvas: &'a Canvas,
+    dirty: &'a mut DirtyRegions,
+}
+
+impl<'a> DamageCollector<'a> {
+    pub(crate) fn new(canvas: &'a Canvas, dirty: &'a mut DirtyRegions) -> Self {
+        Self { canvas, dirty }
+    }
+
+    /// Record that `widget` needs `kind` before the next frame.
+    ///
+    /// A repaint of a widget that was not painted last frame escalates to a
+    /// full redraw, since its on-screen area is unknown, and so does one of
+    /// a widget whose desired size changed since.
+    pub(crate) fn invalidate<M>(&mut self, widget: &dyn Widget<M>, kind: Invalidation) {
+        if self.dirty.is_full() {
+            return;
+        }
+        let key = widget_key(widget);
+        let resized = self
+            .canvas
+            .widget_size(key)
+            .is_some_and(|size| size != widget.desired_size());
+        match kind {
+            Invalidation::Relayout => self.dirty.mark_full(),
+            Invalidation::Repaint if resized => self.dirty.mark_full(),
+            Invalidation::Repaint => self.repaint_key(key),
+        }
+    }
+
+    /// Record that the widget identified by `key` must be repainted.
+    pub(crate) fn repaint_key(&mut self, key: usize) {
+        if self.dirty.is_full() {
+            return;
+        }
+        match self.canvas.widget_region(key) {
+            Some(region) => self.dirty.add(region),
+            None => self.dirty.mark_full(),
+        }
+    }
+
+    /// Record that a hidden widget changed state.
+    ///
+    /// If it was painted last frame, the space it occupied must be
+    /// reclaimed by a relayout.
+    pub(crate) fn hidden<M>(&mut self, widget: &dyn Widget<M>) {
+        if self.canvas.widget_region(widget_key(widget)).is_some() {
+            self.dirty.mark_full();
+        }
+    }
+}
+
+/// Returns how a style change affects the widget.
+///
+/// Properties that influence size or placement require a relayout; purely
+/// visual properties (colors, text style, etc.) only require a repaint.
+pub fn style_invalidation(old: &ComputedStyle, new: &ComputedStyle) -> Option<Invalidation> {
+    if old == new {
+        return None;
+    }
+
+    let border_kinds = |s: &ComputedStyle| {
+        [
+            s.border.top.kind,
+            s.border.right.kind,
+            s.border.bottom.kind,
+            s.border.left.kind,
+        ]
+    };
+
+    let layout_changed = old.width != new.width
+        || old.height != new.height
+        || old.min_width != new.min_width
+        || old.max_width != new.max_width
+        || old.min_height != new.min_height
+        || old.max_height != new.max_height
+        || old.margin != new.margin
+        || old.padding != new.padding
+        || border_kinds(old) != border_kinds(new)
+        || old.box_sizing != new.box_sizing
+        || old.display != new.display
+        || old.layout != new.layout
+        || old.position != new.position
+        || old.dock != new.dock
+        || old.layers != new.layers
+        || old.layer != new.layer
+        || old.grid != new.grid
+        || old.grid_placement != new.grid_placement
+        || old.overflow_x != new.overflow_x
+        || old.overflow_y != new.overflow_y
+        || old.scrollbar.size != new.scrollbar.size
+        || old.scrollbar.gutter != new.scrollbar.gutter
+        || old.scrollbar.visibility != new.scrollbar.visibility
+        || old.align_horizontal != new.align_horizontal
+        || old.align_vertical != new.align_vertical
+        || old.offset_x != new.offset_x
+        || old.offset_y != new.offset_y
+        || old.text_wrap != new.text_wrap
+        || old.text_overflow != new.text_overflow
+        || old.line_pad != new.line_pad;
+
+    if layout_changed {
+        Some(Invalidation::Relayout)
+    } else {
+        Some(Invalidation::Repaint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tcss::types::{Display, RgbaColor};
+
+    #[test]
+    fn overlapping_regions_are_unioned() {
+        let mut dirty = DirtyRegions::new();
+        dirty.add(Region::new(0, 0, 4, 2));
+        dirty.add(Region::new(2, 1, 4, 2));
+
+        assert_eq!(dirty.regions(), &[Region::new(0, 0, 6, 3)]);
+    }
+
+    #[test]
+    fn disjoint_regions_stay_separate() {
+        let mut dirty = DirtyRegions::new();
+        dirty.add(Region::new(0, 0, 2, 2));
+        dirty.add(Region::new(10, 10, 2, 2));
+
+        assert_eq!(dirty.regions().len(), 2);
+    }
+
+    #[test]
+    fn bridging_region_merges_transitively() {
+        let mut dirty = DirtyRegions::new();
+        dirty.add(Region::new(0, 0, 2, 1));
+        dirty.add(Region::new(4, 0, 2, 1));
+        dirty.add(Region::new(1, 0, 4, 1));
+
+        assert_eq!(dirty.regions(), &[Region::new(0, 0, 6, 1)]);
+    }
+
+    #[test]
+    fn full_damage_absorbs_regions() {
+        let mut dirty = DirtyRegions::new();
+        dirty.add(Region::new(0, 0, 2, 2));
+        dirty.mark_full();
+        dirty.add(Region::new(5, 5, 1, 1));
+
+        assert!(dirty.is_full());
+        assert!(dirty.regions().is_empty());
+        assert!(!dirty.is_empty());
+    }
+
+    #[test]
+    fn too_many_regions_collapse_to_bounds() {
+        let mut dirty = DirtyRegions::new();
+        for i in 0..=MAX_REGIONS as i32 {
+            dirty.add(Region::new(i * 3, 0, 1, 1));
+        }
+
+        assert_eq!(
+            dirty.regions(),
+            &[Region::new(0, 0, MAX_REGIONS as i32 * 3 + 1, 1)]
+        );
+    }
+
+    #[test]
+    fn color_change_is_repaint() {
+        let old = ComputedStyle::default();
+        let new = ComputedStyle {
+            background: Some(RgbaColor::rgb(10, 20, 30)),
+            ..ComputedStyle::default()
+        };
+
+        assert_eq!(style_invalidation(&old, &new), Some(Invalidation::Repaint));
+        assert_eq!(style_invalidation(&old, &old.clone()), None);
+    }
+
+    #[test]
+    fn display_change_is_relayout() {
+        let old = ComputedStyle::default();
+        let new = ComputedStyle {
+            display: Display::None,
+            ..ComputedStyle::default()
+        };
+
+        assert_eq!(style_invalidation(&old, &new), Some(Invalidation::Relayout));
+    }
+}
@@ -0,0 +1,711 @@
+//! Devtools server for inspecting a running app from a second terminal.
+//!
+//! A running app owns stdout, so diagnostics are streamed over a local TCP
+//! socket instead. When the `TEXTUAL_DEVTOOLS` environment variable is set,
+//! the event loop starts a [`DevtoolsServer`] and forwards:
+//!
+//! - `log` records (from [`init_logger`](crate::init_logger), or from
+//!   [`forward_logs`] when `TEXTUAL_DEVTOOLS_LOG` is set),
+//! - messages delivered to [`App::handle_message`](crate::App::handle_message),
+//! - key and mouse events,
+//! - frame timings.
+//!
+//! Connect with the `textual-console` binary:
+//!
+//! ```text
+//! # terminal 1
+//! textual-console --level debug --sender Button
+//! # terminal 2
+//! TEXTUAL_DEVTOOLS=1 cargo run --example my_app
+//! ```
+//!
+//! `TEXTUAL_DEVTOOLS` accepts `1`/`true` (listen on [`DEFAULT_ADDR`]), a port
+//! number, or a full `host:port` address. The stream is unauthenticated, so
+//! addresses that aren't on the loopback interface are refused.
+//!
+//! Apps without a logger of their own can set `TEXTUAL_DEVTOOLS_LOG` to a
+//! level (`info`, `debug`, ...) to forward `log` records at that level and
+//! above; otherwise the app's logging is left alone.
+//!
+//! Records are sent as one line each: elapsed microseconds, a kind tag and
+//! the kind's fields, separated by tabs (see [`DevtoolsRecord::encode`]).
+
+use std::fmt;
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use log::{Level, LevelFilter, Metadata, Record};
+
+/// Address the server listens on when `TEXTUAL_DEVTOOLS=1`.
+pub const DEFAULT_ADDR: &str = "127.0.0.1:8081";
+
+/// Environment variable that enables the devtools server.
+pub const ENV_VAR: &str = "TEXTUAL_DEVTOOLS";
+
+/// Environment variable holding the level at which to forward `log`
+/// records (see [`forward_logs`]).
+pub const LOG_ENV_VAR: &str = "TEXTUAL_DEVTOOLS_LOG";
+
+/// Records queued for a console before it is considered stalled and
+/// dropped.
+const CLIENT_QUEUE: usize = 1024;
+
+/// How long a write to a console may block before the console is dropped.
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// A single event streamed to the console.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DevtoolsEvent {
+    /// A `log` record.
+    Log {
+        level: Level,
+        target: String,
+        message: String,
+    },
+    /// A message delivered to the app.
+    Message {
+        sender_type: String,
+        sender_id: Option<String>,
+        message: String,
+    },
+    /// A key press (e.g. `ctrl+p`, `enter`, `a`).
+    Key { key: String },
+    /// A mouse event at terminal coordinates.
+    Mouse { kind: String, column: u16, row: u16 },
+    /// A rendered frame.
+    Frame {
+        /// Time spent resolving styles, rendering and flushing.
+        duration: Duration,
+        /// Whether the whole screen was redrawn.
+        full: bool,
+        /// Number of damaged regions redrawn (0 for a full redraw).
+        regions: usize,
+    },
+}
+
+/// A [`DevtoolsEvent`] stamped with the time since the server started.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DevtoolsRecord {
+    pub elapsed: Duration,
+    pub event: DevtoolsEvent,
+}
+
+impl DevtoolsRecord {
+    /// Encode as a single protocol line (without the trailing newline).
+    pub fn encode(&self) -> String {
+        let mut fields: Vec<String> = vec![self.elapsed.as_micros().to_string()];
+        match &self.event {
+            DevtoolsEvent::Log {
+                level,
+                target,
+                message,
+            } => {
+                fields.push("log".into());
+                fields.push(level.to_string());
+                fields.push(escape(target));
+                fields.push(escape(message));
+            }
+            DevtoolsEvent::Message {
+                sender_type,
+                sender_id,
+                message,
+            } => {
+                fields.push("message".into());
+                fields.push(escape(sender_type));
+                fields.push(sender_id.as_deref().map(escape).unwrap_or_default());
+                fields.push(escape(message));
+            }
+            DevtoolsEvent::Key { key } => {
+                fields.push("key".into());
+                fields.push(escape(key));
+            }
+            DevtoolsEvent::Mouse { kind, column, row } => {
+                fields.push("mouse".into());
+                fields.push(escape(kind));
+                fields.push(column.to_string());
+                fields.push(row.to_string());
+            }
+            DevtoolsEvent::Frame {
+                duration,
+                full,
+                regions,
+            } => {
+                fields.push("frame".into());
+                fields.push(duration.as_micros().to_string());
+                fields.push(if *full { "full" } else { "partial" }.into());
+                fields.push(regions.to_string());
+            }
+        }
+        fields.join("\t")
+    }
+
+    /// Decode a protocol line produced by [`encode`](Self::encode).
+    ///
+    /// Returns `None` for malformed or unknown lines.
+    pub fn decode(line: &str) -> Option<Self> {
+        let fields: Vec<&str> = line.trim_end_matches(['\r', '\n']).split('\t').collect();
+        let (elapsed, kind, rest) = match fields.as_slice() {
+            [elapsed, kind, rest @ ..] => (elapsed, *kind, rest),
+            _ => return None,
+        };
+        let elapsed = Duration::from_micros(elapsed.parse().ok()?);
+
+        let event = match (kind, rest) {
+            ("log", [level, target, message]) => DevtoolsEvent::Log {
+                level: level.parse().ok()?,
+                target: unescape(target),
+                message: unescape(message),
+            },
+            ("message", [sender_type, sender_id, message]) => DevtoolsEvent::Message {
+                sender_type: unescape(sender_type),
+                sender_id: (!sender_id.is_empty()).then(|| unescape(sender_id)),
+                message: unescape(message),
+            },
+            ("key", [key]) => DevtoolsEvent::Key { key: unescape(key) },
+            ("mouse", [kind, column, row]) => DevtoolsEvent::Mouse {
+                kind: unescape(kind),
+                column: column.parse().ok()?,
+                row: row.parse().ok()?,
+            },
+            ("frame", [duration, full, regions]) => DevtoolsEvent::Frame {
+                duration: Duration::from_micros(duration.parse().ok()?),
+                full: match *full {
+                    "full" => true,
+                    "partial" => false,
+                    _ => return None,
+                },
+                regions: regions.parse().ok()?,
+            },
+            _ => return None,
+        };
+
+        Some(Self { elapsed, event })
+    }
+}
+
+impl fmt::Display for DevtoolsRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:>10.3}s] ", self.elapsed.as_secs_f64())?;
+        match &self.event {
+            DevtoolsEvent::Log {
+                level,
+                target,
+                message,
+            } => write!(f, "LOG     {level:<5} {target}: {message}"),
+            DevtoolsEvent::Message {
+                sender_type,
+                sender_id,
+                message,
+            } => {
+                write!(f, "MESSAGE {sender_type}")?;
+                if let Some(id) = sender_id {
+                    write!(f, "#{id}")?;
+                }
+                write!(f, " {message}")
+            }
+            DevtoolsEvent::Key { key } => write!(f, "KEY     {key}"),
+            DevtoolsEvent::Mouse { kind, column, row } => {
+                write!(f, "MOUSE   {kind} at ({column}, {row})")
+            }
+            DevtoolsEvent::Frame {
+                duration,
+                full,
+                regions,
+            } => {
+                let ms = duration.as_secs_f64() * 1000.0;
+                if *full {
+                    write!(f, "FRAME   {ms:.2}ms full redraw")
+                } else {
+                    write!(f, "FRAME   {ms:.2}ms {regions} region(s)")
+                }
+            }
+        }
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => out.push('\t'),
+            Some('n') => out.push('\n'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// Describe a key event in binding notation (e.g. `ctrl+shift+left`).
+pub fn describe_key(event: &KeyEvent) -> String {
+    let mut parts = Vec::new();
+    if event.modifiers.contains(KeyModifiers::CONTROL) {
+        parts.push("ctrl".to_string());
+    }
+    if event.modifiers.contains(KeyModifiers::ALT) {
+        parts.push("alt".to_string());
+    }
+    if event.modifiers.contains(KeyModifiers::SHIFT) && !matches!(event.code, KeyCode::Char(_)) {
+        parts.push("shift".to_string());
+    }
+    parts.push(match event.code {
+        KeyCode::Char(' ') => "space".to_string(),
+        KeyCode::Char(c) => c.to_string(),
+        KeyCode::F(n) => format!("f{n}"),
+        other => format!("{other:?}").to_lowercase(),
+    });
+    parts.join("+")
+}
+
+/// Build a [`DevtoolsEvent::Mouse`] from a crossterm mouse event.
+pub fn mouse_event(event: &MouseEvent) -> DevtoolsEvent {
+    let kind = match event.kind {
+        MouseEventKind::Down(button) => format!("down({button:?})"),
+        MouseEventKind::Up(button) => format!("up({button:?})"),
+        MouseEventKind::Drag(button) => format!("drag({button:?})"),
+        other => format!("{other:?}"),
+    };
+    DevtoolsEvent::Mouse {
+        kind: kind.to_lowercase(),
+        column: event.column,
+        row: event.row,
+    }
+}
+
+/// Console-side filter over streamed records.
+#[derive(Debug, Clone)]
+pub struct DevtoolsFilter {
+    /// Most verbose log level to show.
+    pub level: LevelFilter,
+    /// Only show messages from these widget types (all if empty).
+    pub sender_types: Vec<String>,
+    /// Show key and mouse events.
+    pub input: bool,
+    /// Show frame timings.
+    pub frames: bool,
+}
+
+impl Default for DevtoolsFilter {
+    fn default() -> Self {
+        Self {
+            level: LevelFilter::Trace,
+            sender_types: Vec::new(),
+            input: true,
+            frames: true,
+        }
+    }
+}
+
+impl DevtoolsFilter {
+    /// Returns true if the event passes the filter.
+    pub fn matches(&self, event: &DevtoolsEvent) -> bool {
+        match event {
+            DevtoolsEvent::Log { level, .. } => *level <= self.level,
+            DevtoolsEvent::Message { sender_type, .. } => {
+                self.sender_types.is_empty() || self.sender_types.iter().any(|t| t == sender_type)
+            }
+            DevtoolsEvent::Key { .. } | DevtoolsEvent::Mouse { .. } => self.input,
+            DevtoolsEvent::Frame { .. } => self.frames,
+        }
+    }
+}
+
+/// Streams [`DevtoolsRecord`]s to every connected console.
+///
+/// Accepting connections and writing to sockets happen on background
+/// threads, so [`send`](Self::send) never blocks the event loop. Each
+/// console has its own writer thread and bounded queue; a console that
+/// stops reading is dropped once its queue fills up or a write times out.
+pub struct DevtoolsServer {
+    addr: SocketAddr,
+    started: Instant,
+    clients: Arc<Mutex<Vec<SyncSender<Arc<str>>>>>,
+    client_count: Arc<AtomicUsize>,
+}
+
+impl DevtoolsServer {
+    /// Bind the server to `addr` and start accepting consoles.
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let addr = listener.local_addr()?;
+        let clients: Arc<Mutex<Vec<SyncSender<Arc<str>>>>> = Arc::default();
+        let client_count = Arc::new(AtomicUsize::new(0));
+
+        {
+            let clients = Arc::clone(&clients);
+            let client_count = Arc::clone(&client_count);
+            thread::Builder::new()
+                .name("textual-devtools-accept".into())
+                .spawn(move || {
+                    for stream in listener.incoming().flatten() {
+                        let Ok(sender) = spawn_writer(stream) else {
+                            continue;
+                        };
+                        let mut clients = clients.lock().unwrap_or_else(|e| e.into_inner());
+                        clients.push(sender);
+                        client_count.store(clients.len(), Ordering::Release);
+                    }
+                })?;
+        }
+
+        Ok(Self {
+            addr,
+            started: Instant::now(),
+            clients,
+            client_count,
+        })
+    }
+
+    /// The address the server is listening on.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns true if at least one console is connected.
+    pub fn has_clients(&self) -> bool {
+        self.client_count.load(Ordering::Acquire) > 0
+    }
+
+    /// Stream an event to all connected consoles.
+    pub fn send(&self, event: DevtoolsEvent) {
+        let record = DevtoolsRecord {
+            elapsed: self.started.elapsed(),
+            event,
+        };
+        let mut line = record.encode();
+        line.push('\n');
+        let line: Arc<str> = line.into();
+        let mut stalled = 0;
+        {
+            let mut clients = self.clients.lock().unwrap_or_else(|e| e.into_inner());
+            clients.retain(|client| match client.try_send(Arc::clone(&line)) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    stalled += 1;
+                    false
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            });
+            self.client_count.store(clients.len(), Ordering::Release);
+        }
+        // Logged after releasing the lock: the record may come back here
+        if stalled > 0 {
+            log::warn!("Disconnected {stalled} devtools console(s) that stopped reading");
+        }
+    }
+}
+
+/// Start a thread writing queued lines to `stream`, returning its queue.
+///
+/// The thread exits, closing the queue, when a write fails or times out.
+fn spawn_writer(mut stream: TcpStream) -> io::Result<SyncSender<Arc<str>>> {
+    stream.set_nodelay(true)?;
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let (sender, receiver) = mpsc::sync_channel::<Arc<str>>(CLIENT_QUEUE);
+    thread::Builder::new()
+        .name("textual-devtools-write".into())
+        .spawn(move || {
+            for line in receiver {
+                if stream.write_all(line.as_bytes()).is_err() {
+                    break;
+                }
+            }
+        })?;
+    Ok(sender)
+}
+
+static SERVER: OnceLock<DevtoolsServer> = OnceLock::new();
+
+/// Start the process-wide devtools server on `addr`.
+///
+/// Returns the bound address; calling this again returns the address of the
+/// already running server. Anyone who can reach `addr` can read the stream,
+/// so keep it on a loopback address.
+pub fn start(addr: impl ToSocketAddrs) -> io::Result<SocketAddr> {
+    if let Some(server) = SERVER.get() {
+        return Ok(server.local_addr());
+    }
+    let server = DevtoolsServer::bind(addr)?;
+    let addr = server.local_addr();
+    let _ = SERVER.set(server);
+    Ok(addr)
+}
+
+/// Install a logger forwarding `log` records at `level` and above to the
+/// console, and set the global max level to `level`.
+///
+/// Does nothing (returning false) if a logger is already installed; use
+/// [`forward_log`] from it instead. Forwarding formats every enabled record,
+/// so pick the least verbose level that's useful.
+pub fn forward_logs(level: LevelFilter) -> bool {
+    let installed = log::set_boxed_logger(Box::new(DevtoolsLogger)).is_ok();
+    if installed {
+        log::set_max_level(level);
+    }
+    installed
+}
+
+/// Start the devtools server if [`ENV_VAR`] is set.
+///
+/// Returns the bound address, or `None` if devtools are disabled or the
+/// server could not be started (the failure is logged).
+pub fn start_from_env() -> Option<SocketAddr> {
+    let value = std::env::var(ENV_VAR).ok()?;
+    let addr = match value.trim() {
+        "" | "0" | "false" => return None,
+        "1" | "true" => DEFAULT_ADDR.to_string(),
+        port if port.parse::<u16>().is_ok() => format!("127.0.0.1:{port}"),
+        addr => addr.to_string(),
+    };
+    if !is_loopback(&addr) {
+        log::error!("Refusing to start devtools on {addr}: only loopback addresses are allowed");
+        return None;
+    }
+    match start(addr.as_str()) {
+        Ok(addr) => {
+            log::info!("Devtools listening on {addr}");
+            if let Ok(level) = std::env::var(LOG_ENV_VAR) {
+                match level.trim().parse() {
+                    Ok(level) => {
+                        forward_logs(level);
+                    }
+                    Err(_) => log::warn!("Ignoring {LOG_ENV_VAR}={level:?}: not a log level"),
+                }
+            }
+            Some(addr)
+        }
+        Err(e) => {
+            log::warn!("Failed to start devtools on {addr}: {e}");
+            None
+        }
+    }
+}
+
+/// Whether every address `addr` resolves to is on the loopback interface.
+fn is_loopback(addr: &str) -> bool {
+    addr.to_socket_addrs()
+        .map(|mut addrs| addrs.all(|addr| addr.ip().is_loopback()))
+        .unwrap_or(false)
+}
+
+/// Returns true if the devtools server is running with a console attached.
+pub fn is_connected() -> bool {
+    SERVER.get().is_some_and(DevtoolsServer::has_clients)
+}
+
+/// Stream the event built by `event` if a console is attached.
+///
+/// The closure is not called otherwise, so formatting costs nothing when
+/// devtools are off.
+pub fn emit(event: impl FnOnce() -> DevtoolsEvent) {
+    if let Some(server) = SERVER.get()
+        && server.has_clients()
+    {
+        server.send(event());
+    }
+}
+
+/// Forward a `log` record to the console, if attached.
+pub fn forward_log(record: &Record) {
+    emit(|| DevtoolsEvent::Log {
+        level: record.level(),
+        target: record.target().to_string(),
+        message: record.args().to_string(),
+    });
+}
+
+/// Logger installed by [`forward_logs`].
+struct DevtoolsLogger;
+
+impl log::Log for DevtoolsLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            forward_log(record);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+
+    fn record(event: DevtoolsEvent) -> DevtoolsRecord {
+        DevtoolsRecord {
+            elapsed: Duration::from_micros(1_234_567),
+            event,
+        }
+    }
+
+    #[test]
+    fn records_round_trip() {
+        let events = [
+            DevtoolsEvent::Log {
+                level: Level::Warn,
+                target: "textual::tree".into(),
+                message: "tab\there\nnewline \\ slash".into(),
+            },
+            DevtoolsEvent::Message {
+                sender_type: "Button".into(),
+                sender_id: Some("ok".into()),
+                message: "Pressed".into(),
+            },
+            DevtoolsEvent::Message {
+                sender_type: "AppContext".into(),
+                sender_id: None,
+                message: String::new(),
+            },
+            DevtoolsEvent::Key {
+                key: "ctrl+p".into(),
+            },
+            DevtoolsEvent::Mouse {
+                kind: "down(left)".into(),
+                column: 3,
+                row: 7,
+            },
+            DevtoolsEvent::Frame {
+                duration: Duration::from_micros(850),
+                full: false,
+                regions: 2,
+            },
+        ];
+
+        for event in events {
+            let record = record(event);
+            let line = record.encode();
+            assert!(!line.contains('\n'));
+            assert_eq!(DevtoolsRecord::decode(&line), Some(record));
+        }
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        assert_eq!(DevtoolsRecord::decode(""), None);
+        assert_eq!(DevtoolsRecord::decode("12\tunknown\tx"), None);
+        assert_eq!(DevtoolsRecord::decode("abc\tkey\tq"), None);
+        assert_eq!(DevtoolsRecord::decode("12\tframe\t5\tmaybe\t0"), None);
+    }
+
+    #[test]
+    fn describe_key_uses_binding_notation() {
+        let key = |code, modifiers| describe_key(&KeyEvent::new(code, modifiers));
+
+        assert_eq!(key(KeyCode::Char('p'), KeyModifiers::CONTROL), "ctrl+p");
+        assert_eq!(key(KeyCode::Char('A'), KeyModifiers::SHIFT), "A");
+        assert_eq!(key(KeyCode::Left, KeyModifiers::SHIFT), "shift+left");
+        assert_eq!(key(KeyCode::F(5), KeyModifiers::NONE), "f5");
+        assert_eq!(key(KeyCode::Char(' '), KeyModifiers::NONE), "space");
+    }
+
+    #[test]
+    fn filter_by_level_and_sender_type() {
+        let filter = DevtoolsFilter {
+            level: LevelFilter::Info,
+            sender_types: vec!["Button".into()],
+            input: false,
+            frames: true,
+        };
+        let log = |level| DevtoolsEvent::Log {
+            level,
+            target: String::new(),
+            message: String::new(),
+        };
+        let message = |sender: &str| DevtoolsEvent::Message {
+            sender_type: sender.into(),
+            sender_id: None,
+            message: String::new(),
+        };
+
+        assert!(filter.matches(&log(Level::Warn)));
+        assert!(!filter.matches(&log(Level::Debug)));
+        assert!(filter.matches(&message("Button")));
+        assert!(!filter.matches(&message("Switch")));
+        assert!(!filter.matches(&DevtoolsEvent::Key { key: "a".into() }));
+    }
+
+    #[test]
+    fn server_streams_to_connected_console() {
+        let server = DevtoolsServer::bind("127.0.0.1:0").unwrap();
+        assert!(!server.has_clients());
+
+        let stream = TcpStream::connect(server.local_addr()).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !server.has_clients() {
+            assert!(Instant::now() < deadline, "console never registered");
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        server.send(DevtoolsEvent::Key { key: "q".into() });
+
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        let record = DevtoolsRecord::decode(&line).unwrap();
+        assert_eq!(record.event, DevtoolsEvent::Key { key: "q".into() });
+    }
+
+    #[test]
+    fn server_drops_console_that_stops_reading() {
+        let server = DevtoolsServer::bind("127.0.0.1:0").unwrap();
+        let _stream = TcpStream::connect(server.local_addr()).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !server.has_clients() {
+            assert!(Instant::now() < deadline, "console never registered");
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        // Fill the socket buffers, then the console's queue
+        let message = "x".repeat(4096);
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while server.has_clients() {
+            assert!(
+                Instant::now() < deadline,
+                "stalled console was never dropped"
+            );
+            server.send(DevtoolsEvent::Log {
+                level: Level::Info,
+                target: "test".into(),
+                message: message.clone(),
+            });
+        }
+    }
+
+    #[test]
+    fn only_loopback_addresses_are_allowed() {
+        assert!(is_loopback("127.0.0.1:8081"));
+        assert!(is_loopback("[::1]:8081"));
+        assert!(!is_loopback("0.0.0.0:8081"));
+        assert!(!is_loopback("not an address"));
+    }
+}
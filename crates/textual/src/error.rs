@@ -16,6 +16,12 @@ pub enum TextualError {
 
     #[error("Failed to initialize async runtime: {0}")]
     RuntimeInit(String),
+
+    #[error("Invalid session recording: {0}")]
+    InvalidRecording(String),
+
+    #[error("Mount error: {0}")]
+    Mount(String),
 }
 
 // Create a type alias for convenience
@@ -0,0 +1,398 @@
+//! Per-container caching of layout placements.
+//!
+//! Containers arrange their children on every render and on every mouse
+//! hit-test. Most of the time nothing relevant has changed since the last
+//! arrangement, so [`LayoutCache`] memoizes the placements and only re-runs
+//! the layout algorithm when one of its inputs changes:
+//!
+//! - the available region or viewport,
+//! - the container's own style,
+//! - the number of participating children, or any child's style,
+//! - any child's desired size or intrinsic height (content changes).
+//!
+//! Intrinsic heights are validated by replaying the exact
+//! `intrinsic_height_for_width` queries the layout made when the entry was
+//! computed, so content changes that only affect wrapped height are caught.
+//!
+//! Hit/miss counters are kept per cache and process-wide; the latter are
+//! exposed through [`layout_cache_stats`] for benchmarks.
+
+use std::cell::{Cell, RefCell};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use tcss::types::ComputedStyle;
+
+use super::{LayoutChild, LayoutNode, Viewport, WidgetPlacement};
+use crate::canvas::{Region, Size};
+
+/// Number of (region, viewport) entries kept per container.
+///
+/// Containers commonly arrange against more than one region per frame
+/// (e.g. before and after reserving space for a scrollbar).
+const MAX_ENTRIES: usize = 4;
+
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// Hit/miss counts for layout cache lookups.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LayoutCacheStats {
+    /// Lookups answered from the cache.
+    pub hits: u64,
+    /// Lookups that re-ran the layout algorithm.
+    pub misses: u64,
+}
+
+impl LayoutCacheStats {
+    /// Total number of lookups.
+    pub fn lookups(&self) -> u64 {
+        self.hits + self.misses
+    }
+
+    /// Fraction of lookups answered from the cache (0.0 when there were none).
+    pub fn hit_rate(&self) -> f64 {
+        match self.lookups() {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+/// Process-wide layout cache counters, summed over every container.
+pub fn layout_cache_stats() -> LayoutCacheStats {
+    LayoutCacheStats {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+    }
+}
+
+/// Reset the process-wide layout cache counters to zero.
+pub fn reset_layout_cache_stats() {
+    HITS.store(0, Ordering::Relaxed);
+    MISSES.store(0, Ordering::Relaxed);
+}
+
+/// Layout inputs recorded for a single child.
+#[derive(Debug, Clone)]
+struct ChildKey {
+    index: usize,
+    style: ComputedStyle,
+    desired_size: Size,
+    /// `(width, height)` pairs returned by `intrinsic_height_for_width`.
+    probes: Vec<(u16, u16)>,
+}
+
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    region: Region,
+    viewport: Viewport,
+    parent_style: ComputedStyle,
+    children: Vec<ChildKey>,
+    placements: Vec<WidgetPlacement>,
+}
+
+impl CacheEntry {
+    fn is_valid_for(&self, parent_style: &ComputedStyle, children: &[LayoutChild]) -> bool {
+        self.parent_style == *parent_style
+            && self.children.len() == children.len()
+            && self.children.iter().zip(children).all(|(key, child)| {
+                key.index == child.index
+                    && key.desired_size == child.desired_size
+                    && key.style == child.style
+                    && key
+                        .probes
+                        .iter()
+                        .all(|&(w, h)| child.node.intrinsic_height_for_width(w) == h)
+            })
+    }
+}
+
+/// A [`LayoutNode`] that records intrinsic height queries.
+struct ProbedNode<'a> {
+    node: &'a dyn LayoutNode,
+    probes: RefCell<Vec<(u16, u16)>>,
+}
+
+impl LayoutNode for ProbedNode<'_> {
+    fn desired_size(&self) -> Size {
+        self.node.desired_size()
+    }
+
+    fn intrinsic_height_for_width(&self, width: u16) -> u16 {
+        let height = self.node.intrinsic_height_for_width(width);
+        let mut probes = self.probes.borrow_mut();
+        if !probes.iter().any(|&(w, _)| w == width) {
+            probes.push((width, height));
+        }
+        height
+    }
+}
+
+/// Memoized child placements for one container.
+///
+/// Uses interior mutability since containers arrange children from `&self`
+/// (in `render` and hit-testing).
+#[derive(Debug, Default)]
+pub struct LayoutCache {
+    entries: RefCell<Vec<CacheEntry>>,
+    hits: Cell<u64>,
+    misses: Cell<u64>,
+}
+
+impl LayoutCache {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Drop all cached placements.
+    pub fn invalidate(&self) {
+        self.entries.borrow_mut().clear();
+    }
+
+    /// Hit/miss counts for this cache.
+    pub fn stats(&self) -> LayoutCacheStats {
+        LayoutCacheStats {
+            hits: self.hits.get(),
+            misses: self.misses.get(),
+        }
+    }
+
+    /// Return the placements for `children`, running `arrange` only if the
+    /// cached result is stale.
+    ///
+    /// `arrange` receives the children to lay out and must be a pure
+    /// function of them, `parent_style`, `region` and `viewport`.
+    pub fn arrange(
+        &self,
+        parent_style: &ComputedStyle,
+        children: &[LayoutChild],
+        region: Region,
+        viewport: Viewport,
+        arrange: impl FnOnce(&[LayoutChild]) -> Vec<WidgetPlacement>,
+    ) -> Vec<WidgetPlacement> {
+        {
+            let entries = self.entries.borrow();
+            if let Some(entry) = entries
+                .iter()
+                .find(|e| e.region == region && e.viewport == viewport)
+                && entry.is_valid_for(parent_style, children)
+            {
+                self.hits.set(self.hits.get() + 1);
+                HITS.fetch_add(1, Ordering::Relaxed);
+                return entry.placements.clone();
+            }
+        }
+
+        self.misses.set(self.misses.get() + 1);
+        MISSES.fetch_add(1, Ordering::Relaxed);
+
+        let probed: Vec<ProbedNode> = children
+            .iter()
+            .map(|child| ProbedNode {
+                node: child.node,
+                probes: RefCell::new(Vec::new()),
+            })
+            .collect();
+        let probed_children: Vec<LayoutChild> = children
+            .iter()
+            .zip(&probed)
+            .map(|(child, node)| LayoutChild {
+                index: child.index,
+                style: child.style.clone(),
+                desired_size: child.desired_size,
+                node,
+            })
+            .collect();
+
+        let placements = arrange(&probed_children);
+
+        let keys = probed_children
+            .into_iter()
+            .zip(&probed)
+            .map(|(child, node)| ChildKey {
+                index: child.index,
+                style: child.style,
+                desired_size: child.desired_size,
+                probes: node.probes.take(),
+            })
+            .collect();
+
+        let mut entries = self.entries.borrow_mut();
+        entries.retain(|e| e.region != region || e.viewport != viewport);
+        if entries.len() >= MAX_ENTRIES {
+            entries.remove(0);
+        }
+        entries.push(CacheEntry {
+            region,
+            viewport,
+            parent_style: parent_style.clone(),
+            children: keys,
+            placements: placements.clone(),
+        });
+
+        placements
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tcss::types::Scalar;
+
+    struct TextNode {
+        lines: Cell<u16>,
+    }
+
+    impl LayoutNode for TextNode {
+        fn desired_size(&self) -> Size {
+            Size::new(10, 1)
+        }
+
+        fn intrinsic_height_for_width(&self, _width: u16) -> u16 {
+            self.lines.get()
+        }
+    }
+
+    fn auto_height_style() -> ComputedStyle {
+        ComputedStyle {
+            height: Some(Scalar::AUTO),
+            ..ComputedStyle::default()
+        }
+    }
+
+    fn arrange(cache: &LayoutCache, nodes: &[&TextNode], style: &ComputedStyle) -> Vec<Region> {
+        let children: Vec<LayoutChild> = nodes
+            .iter()
+            .enumerate()
+            .map(|(index, node)| LayoutChild {
+                index,
+                style: style.clone(),
+                desired_size: node.desired_size(),
+                node: *node,
+            })
+            .collect();
+        let parent = ComputedStyle::default();
+        let region = Region::new(0, 0, 20, 10);
+        let viewport = Viewport::from(region);
+        cache
+            .arrange(&parent, &children, region, viewport, |children| {
+                super::super::arrange_children_with_layers(&parent, children, region, viewport)
+            })
+            .into_iter()
+            .map(|p| p.region)
+            .collect()
+    }
+
+    #[test]
+    fn unchanged_inputs_hit_cache() {
+        let cache = LayoutCache::new();
+        let node = TextNode {
+            lines: Cell::new(1),
+        };
+        let style = auto_height_style();
+
+        let first = arrange(&cache, &[&node], &style);
+        let second = arrange(&cache, &[&node], &style);
+
+        assert_eq!(first, second);
+        assert_eq!(cache.stats(), LayoutCacheStats { hits: 1, misses: 1 });
+    }
+
+    #[test]
+    fn child_style_change_misses() {
+        let cache = LayoutCache::new();
+        let node = TextNode {
+            lines: Cell::new(1),
+        };
+
+        arrange(&cache, &[&node], &auto_height_style());
+        let taller = ComputedStyle {
+            height: Some(Scalar::cells(3.0)),
+            ..ComputedStyle::default()
+        };
+        let regions = arrange(&cache, &[&node], &taller);
+
+        assert_eq!(regions[0].height, 3);
+        assert_eq!(cache.stats().misses, 2);
+    }
+
+    #[test]
+    fn child_count_change_misses() {
+        let cache = LayoutCache::new();
+        let a = TextNode {
+            lines: Cell::new(1),
+        };
+        let b = TextNode {
+            lines: Cell::new(1),
+        };
+        let style = auto_height_style();
+
+        arrange(&cache, &[&a], &style);
+        let regions = arrange(&cache, &[&a, &b], &style);
+
+        assert_eq!(regions.len(), 2);
+        assert_eq!(cache.stats().misses, 2);
+    }
+
+    #[test]
+    fn content_height_change_misses() {
+        let cache = LayoutCache::new();
+        let node = TextNode {
+            lines: Cell::new(1),
+        };
+        let style = auto_height_style();
+
+        arrange(&cache, &[&node], &style);
+        node.lines.set(4);
+        let regions = arrange(&cache, &[&node], &style);
+
+        assert_eq!(regions[0].height, 4);
+        assert_eq!(cache.stats(), LayoutCacheStats { hits: 0, misses: 2 });
+    }
+
+    #[test]
+    fn region_change_misses_and_keeps_other_entries() {
+        let cache = LayoutCache::new();
+        let node = TextNode {
+            lines: Cell::new(1),
+        };
+        let children = [LayoutChild {
+            index: 0,
+            style: auto_height_style(),
+            desired_size: node.desired_size(),
+            node: &node,
+        }];
+        let parent = ComputedStyle::default();
+        let wide = Region::new(0, 0, 20, 10);
+        let narrow = Region::new(0, 0, 18, 10);
+        let viewport = Viewport::from(wide);
+        let run = |region: Region| {
+            cache.arrange(&parent, &children, region, viewport, |children| {
+                super::super::arrange_children_with_layers(&parent, children, region, viewport)
+            })
+        };
+
+        run(wide);
+        run(narrow);
+        run(wide);
+        run(narrow);
+
+        assert_eq!(cache.stats(), LayoutCacheStats { hits: 2, misses: 2 });
+    }
+
+    #[test]
+    fn invalidate_forces_recompute() {
+        let cache = LayoutCache::new();
+        let node = TextNode {
+            lines: Cell::new(1),
+        };
+        let style = auto_height_style();
+
+        arrange(&cache, &[&node], &style);
+        cache.invalidate();
+        arrange(&cache, &[&node], &style);
+
+        assert_eq!(cache.stats().misses, 2);
+    }
+}
@@ -13,13 +13,16 @@
 //! ## Usage
 //!
 //! Containers call `arrange_children()` which dispatches to the appropriate layout
-//! based on the container's `style.layout` CSS property.
+//! based on the container's `style.layout` CSS property. Results are memoized
+//! per container with a [`LayoutCache`].
 
+mod cache;
 mod grid;
 mod horizontal;
 pub mod size_resolver;
 mod vertical;
 
+pub use cache::{LayoutCache, LayoutCacheStats, layout_cache_stats, reset_layout_cache_stats};
 pub use grid::{GridLayout, GridTrackInfo};
 pub use horizontal::HorizontalLayout;
 pub use size_resolver::{
@@ -7,6 +7,8 @@ pub mod command;
 pub mod containers;
 pub mod content;
 pub mod context;
+pub mod damage;
+pub mod devtools;
 pub mod error;
 pub mod fraction;
 pub mod fuzzy;
@@ -16,26 +18,30 @@ pub mod layouts;
 mod log_init;
 mod macros;
 pub mod message;
+pub mod mouse;
+pub mod recording;
 pub mod render_cache;
 pub mod scroll;
 pub mod scrollbar;
 pub mod segment;
 pub mod strip;
 pub mod style_resolver;
+pub mod suspend;
 pub mod svg;
 pub mod testing;
+pub mod timer;
 pub mod tree;
 pub mod visual;
 pub mod widget;
+pub mod worker;
 
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture, EventStream};
 pub use crossterm::event::{
     Event, KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
 };
-use crossterm::{cursor, execute, terminal};
-use futures::StreamExt;
+use crossterm::terminal;
 use std::cell::Cell;
-use std::collections::{HashSet, VecDeque};
+use std::collections::VecDeque;
+use std::io::Write;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
@@ -55,19 +61,26 @@ pub use fraction::Fraction;
 pub use fuzzy::Matcher;
 pub use log_init::init_logger;
 pub use message::MessageEnvelope;
+pub use mouse::{PointerAction, PointerEvent, PointerTracker};
 pub use scroll::{ScrollMessage, ScrollState};
 pub use scrollbar::{ScrollBarRender, ScrollbarGlyphs};
 pub use tcss::TcssError;
-pub use tree::{DOMQuery, clear_all_hover, collect_pending_actions_mut, find_hovered_tooltip_mut};
+pub use timer::{Clock, TimerHandle, TimerId, TimerManager};
+pub use tree::{
+    DOMQuery, FocusEvent, MountPosition, clear_all_hover, collect_pending_actions_mut,
+    find_hovered_tooltip_mut,
+};
 pub use visual::VisualType;
 pub use widget::command_palette::CommandPalette;
 pub use widget::header::Header;
 pub use widget::input::Input;
+pub use widget::inspector::Inspector;
 pub use widget::label::{Label, LabelVariant};
 pub use widget::loading_indicator::LoadingIndicator;
 pub use widget::option_list::OptionList;
 pub use widget::static_widget::Static;
 pub use widget::tooltip::Tooltip;
+pub use worker::{WorkerHandle, WorkerManager, WorkerState, WorkerStateChanged};
 
 // Re-export the log crate so users can use textual::log::info!, etc.
 pub use log;
@@ -108,47 +121,10 @@ where
 
 use crate::{
     error::TextualError,
-    style_resolver::{InheritedContext, resolve_dirty_styles, resolve_styles},
+    style_resolver::{build_app_stylesheet, media_environment, resolve_styles},
     tree::WidgetTree,
 };
 
-/// Collect default CSS from a widget and all its descendants.
-///
-/// Walks the widget tree and collects unique `default_css()` strings from all widgets.
-/// These are prepended to the app's CSS to ensure widget defaults are applied with
-/// lower specificity than app-level styles.
-fn collect_default_css<M>(widget: &mut dyn Widget<M>, collected: &mut HashSet<&'static str>) {
-    let default_css = widget.default_css();
-    if !default_css.is_empty() {
-        collected.insert(default_css);
-    }
-    widget.for_each_child(&mut |child| {
-        collect_default_css(child, collected);
-    });
-}
-
-/// Build a combined stylesheet from widget defaults and app CSS.
-///
-/// Widget default CSS is prepended (lower specificity), so app CSS can override.
-fn build_combined_css<M: 'static>(root: &mut dyn Widget<M>, app_css: &str) -> String {
-    let mut defaults: HashSet<&'static str> = HashSet::new();
-    collect_default_css(root, &mut defaults);
-
-    // Concatenate defaults (order doesn't matter, app CSS will override)
-    let mut combined = String::new();
-
-    // First add base widget CSS (universal defaults like scrollbar and link styling)
-    combined.push_str(widget::screen::Screen::<M>::base_widget_css());
-    combined.push('\n');
-
-    for css in defaults {
-        combined.push_str(css);
-        combined.push('\n');
-    }
-    combined.push_str(app_css);
-    combined
-}
-
 fn matches_binding(key_event: &KeyEvent, binding: &str) -> bool {
     binding
         .split(',')
@@ -212,6 +188,12 @@ fn parse_key_token(token: &str) -> Option<KeyCode> {
         "pageup" => Some(KeyCode::PageUp),
         "pagedown" => Some(KeyCode::PageDown),
         "backslash" => Some(KeyCode::Char('\\')),
+        f if f.len() > 1 && f.starts_with('f') && f[1..].bytes().all(|b| b.is_ascii_digit()) => f
+            [1..]
+            .parse()
+            .ok()
+            .filter(|n| (1..=24).contains(n))
+            .map(KeyCode::F),
         _ => {
             let mut chars = token.chars();
             let ch = chars.next()?;
@@ -236,7 +218,7 @@ fn key_matches(expected: &KeyCode, actual: &KeyCode) -> bool {
 fn drain_command_palette<M, A>(
     app: &mut A,
     tree: &mut WidgetTree<M>,
-    tx: &mpsc::UnboundedSender<MessageEnvelope<M>>,
+    app_ctx: &AppContext<M>,
 ) -> bool
 where
     A: App<Message = M> + ?Sized,
@@ -252,27 +234,32 @@ where
         needs_render |= changed;
     }
 
-    if let Some(changed) =
-        tree.query_one_as::<CommandPalette<M>, _, _>("CommandPalette", |palette| palette.tick())
-    {
-        needs_render |= changed;
-    }
-
     let events = tree
         .query_one_as::<CommandPalette<M>, _, _>("CommandPalette", |palette| palette.take_events())
         .unwrap_or_default();
 
     if !events.is_empty() {
-        let event_ctx = AppContext::new(tx.clone());
-        let mut ctx = EventContext::new(event_ctx, tree);
+        let mut ctx = EventContext::new(app_ctx.clone(), tree);
         for event in events {
             app.on_command_palette_event(event, &mut ctx);
         }
+        needs_render = true;
     }
 
     needs_render
 }
 
+/// Stream a message about to be handled by the app to the devtools console.
+fn trace_message<A: App + ?Sized>(app: &A, envelope: &MessageEnvelope<A::Message>) {
+    devtools::emit(|| devtools::DevtoolsEvent::Message {
+        sender_type: envelope.sender_type.clone(),
+        sender_id: envelope.sender_id.clone(),
+        message: app
+            .describe_message(&envelope.message)
+            .unwrap_or_else(|| std::any::type_name::<A::Message>().to_string()),
+    });
+}
+
 /// The main application trait. Implement this to create a TUI application.
 ///
 /// The `Message` associated type (from `Compose`) defines the events your UI can produce.
@@ -281,12 +268,31 @@ pub trait App {
     type Message: Send + 'static;
 
     const CSS: &'static str = "";
+    /// Stylesheet files loaded before [`CSS`](Self::CSS), in order.
+    ///
+    /// Relative paths are resolved against the current directory (prefix
+    /// them with `env!("CARGO_MANIFEST_DIR")` to keep them next to the
+    /// crate); `@import`s inside the files are resolved relative to the
+    /// importing file.
+    const CSS_PATH: &'static [&'static str] = &[];
     /// Enable the system command palette overlay.
     const ENABLE_COMMAND_PALETTE: bool = true;
     /// The key that launches the command palette (if enabled).
     const COMMAND_PALETTE_BINDING: &'static str = "ctrl+p";
     /// Optional display override for the command palette binding (unused for now).
     const COMMAND_PALETTE_DISPLAY: Option<&'static str> = None;
+    /// Enable the DOM inspector overlay.
+    ///
+    /// Off by default: the inspector is a node in the DOM, so enabling it
+    /// can change how `*` and structural selectors match.
+    const ENABLE_INSPECTOR: bool = false;
+    /// The key that toggles the DOM inspector (if enabled).
+    const INSPECTOR_BINDING: &'static str = "f12";
+    /// Let the user suspend the app to the shell (Unix only); `SIGTSTP`
+    /// is handled the same way.
+    const ENABLE_SUSPEND: bool = true;
+    /// The key that suspends the app to the shell (if enabled).
+    const SUSPEND_BINDING: &'static str = "ctrl+z";
 
     /// Returns a vector of widgets that make up this composition.
     ///
@@ -315,6 +321,15 @@ pub trait App {
     ) {
     }
 
+    /// Describe a message for the devtools console.
+    ///
+    /// Messages are not required to implement `Debug`, so by default only the
+    /// message type name is shown. Override to show the payload, e.g.
+    /// `Some(format!("{message:?}"))`.
+    fn describe_message(&self, _message: &Self::Message) -> Option<String> {
+        None
+    }
+
     /// Handle command palette lifecycle events.
     fn on_command_palette_event(
         &mut self,
@@ -323,6 +338,13 @@ pub trait App {
     ) {
     }
 
+    /// Handle focus moving between widgets.
+    ///
+    /// Called with [`FocusEvent::FocusLost`] for the widget that had focus,
+    /// then [`FocusEvent::FocusGained`] for the one that has it now, after
+    /// the handler that moved focus returns.
+    fn on_focus_event(&mut self, _event: FocusEvent, _ctx: &mut EventContext<Self::Message>) {}
+
     /// Handle global key events (e.g., 'q' to quit).
     /// Called after widget event handling.
     ///
@@ -397,6 +419,19 @@ pub trait App {
         Duration::from_millis(500)
     }
 
+    /// Maximum time between clicks that count as a double (or triple) click.
+    fn click_interval(&self) -> Duration {
+        mouse::DEFAULT_CLICK_INTERVAL
+    }
+
+    /// Whether to record the session to, or replay it from, an asciicast file.
+    ///
+    /// Defaults to [`SessionMode::from_env`](recording::SessionMode::from_env)
+    /// (`TEXTUAL_RECORD` / `TEXTUAL_REPLAY`).
+    fn session_mode(&self) -> recording::SessionMode {
+        recording::SessionMode::from_env()
+    }
+
     /// Handle an action string from a link click (e.g., "app.bell", "app.quit").
     ///
     /// Override this to handle custom actions. Return `true` if the action was handled.
@@ -480,14 +515,13 @@ pub trait App {
             }
             "app.command_palette" | "command_palette" => {
                 if Self::ENABLE_COMMAND_PALETTE {
-                    let focus_index = ctx.current_focus_index();
                     let providers = self.command_providers();
                     let opened = ctx
                         .query_one_as::<CommandPalette<Self::Message>, _, _>(
                             "CommandPalette",
                             |palette| {
                                 palette.set_providers(providers);
-                                palette.open_with_focus(focus_index);
+                                palette.open();
                             },
                         )
                         .is_some();
@@ -507,6 +541,11 @@ pub trait App {
 
     /// Returns the current focus index for the widget tree.
     /// The run loop uses this to set focus on the nth focusable widget.
+    ///
+    /// Focus is only moved when the returned index changes. The tree also
+    /// moves focus itself (Tab, mounting and removing widgets), so apps that
+    /// compute the next index should start from
+    /// [`EventContext::current_focus_index`].
     fn focus_index(&self) -> usize {
         0
     }
@@ -557,6 +596,8 @@ pub trait App {
     ///
     /// Breakpoints are (threshold, class_name) pairs. The class is applied
     /// when width >= threshold. The last matching breakpoint wins.
+    /// For styling alone, `@media (min-width: N)` blocks in the app CSS
+    /// do the same without code.
     ///
     /// Default: `[(0, "-narrow"), (80, "-wide")]`
     ///
@@ -587,7 +628,7 @@ pub trait App {
     /// own state and mark themselves dirty for restyling.
     ///
     /// The event loop uses `tokio::select!` to poll:
-    /// - Terminal events via `crossterm::event::EventStream`
+    /// - Terminal events via `crossterm::event::EventStream` (or a replayed recording)
     /// - Async messages via `tokio::sync::mpsc` channel
     ///
     /// Runtime handling:
@@ -647,26 +688,13 @@ pub trait App {
     /// Inner async run logic, separated for runtime flexibility.
     fn run_inner(&mut self) -> impl std::future::Future<Output = Result<()>> + '_ {
         async move {
-            let mut stdout = std::io::stdout();
             // Enable raw mode, mouse capture, and enter alternate screen
-            terminal::enable_raw_mode()?;
-            execute!(
-                stdout,
-                terminal::EnterAlternateScreen,
-                cursor::Hide,
-                EnableMouseCapture
-            )?;
+            suspend::enter_app_mode()?;
 
             let result = self.event_loop_async().await;
 
             // Cleanup: Restore terminal state on exit
-            execute!(
-                stdout,
-                DisableMouseCapture,
-                cursor::Show,
-                terminal::LeaveAlternateScreen
-            )?;
-            terminal::disable_raw_mode()?;
+            suspend::leave_app_mode()?;
 
             result
         }
@@ -685,6 +713,23 @@ pub trait App {
             let (mut cols, mut rows) = terminal::size()?;
             let mut canvas = Canvas::new(cols, rows);
 
+            // Stream diagnostics to `textual-console` when TEXTUAL_DEVTOOLS is set
+            devtools::start_from_env();
+
+            // Record frames and input, or replay recorded input, if requested
+            let terminal_control = suspend::TerminalControl::attached();
+            let mut input = recording::InputSource::live(terminal_control.clone());
+            let mut recorder = None;
+            match self.session_mode() {
+                recording::SessionMode::Live => {}
+                recording::SessionMode::Record(path) => {
+                    recorder = Some(recording::Recorder::create(path, cols, rows)?);
+                }
+                recording::SessionMode::Replay(path) => {
+                    input = input.with_replay(recording::Replay::load(path)?);
+                }
+            }
+
             // 2. Build the widget tree ONCE (persistent tree)
             // Use WidgetTree for O(d) focus-targeted dispatch and message bubbling
             // DOM hierarchy: App > Screen > user widgets (matches Python Textual)
@@ -696,17 +741,20 @@ pub trait App {
             if Self::ENABLE_COMMAND_PALETTE {
                 root_children.push(Box::new(CommandPalette::new()));
             }
+            if Self::ENABLE_INSPECTOR {
+                root_children.push(Box::new(Inspector::new()));
+            }
             let root = Box::new(widget::app_widget::AppWidget::new(root_children));
             let mut tree = WidgetTree::new(root);
 
             // Initialize Screen with current terminal size for breakpoints
-            tree.root_mut().on_resize(Size::new(cols, rows));
+            tree.resize(Size::new(cols, rows));
 
             // 3. Collect widget default CSS and combine with app CSS
-            // Widget defaults are prepended (lower specificity), app CSS overrides
-            let combined_css = build_combined_css(tree.root_mut(), Self::CSS);
-            let stylesheet = tcss::parser::parse_stylesheet(&combined_css)
-                .map_err(|e| TextualError::InvalidCss(e.to_string()))?;
+            // Widget defaults form a lower cascade tier, so app CSS overrides
+            let mut stylesheet = build_app_stylesheet(tree.root_mut(), Self::CSS_PATH, Self::CSS)?;
+            let mut environment = media_environment(Size::new(cols, rows));
+            stylesheet.set_environment(environment);
 
             // Set initial focus and cache the focus path
             tree.root_mut().clear_focus();
@@ -723,15 +771,18 @@ pub trait App {
             // 3. Create message channel for async communication
             let (tx, mut rx) = mpsc::unbounded_channel::<MessageEnvelope<Self::Message>>();
 
+            // Every context handed out shares this one's workers, timers,
+            // clock and terminal
+            let app_ctx = AppContext::new(tx).with_terminal(terminal_control.clone());
+
+            // Mount widgets before the app, so `App::on_mount` sees them
+            // running
+            tree.attach(app_ctx.clone());
+
             // Call lifecycle hook with MountContext (provides widget tree access)
-            // MountContext takes ownership of an AppContext, so we create one specifically for it
-            let mount_app_ctx = AppContext::new(tx.clone());
-            let mut mount_ctx = MountContext::new(mount_app_ctx, &mut tree);
+            let mut mount_ctx = MountContext::new(app_ctx.clone(), &mut tree);
             self.on_mount(&mut mount_ctx);
 
-            // 4. Create async event stream
-            let mut event_stream = EventStream::new();
-
             // Track the previous focus index to detect changes
             let mut last_focus_index = self.focus_index();
 
@@ -744,10 +795,47 @@ pub trait App {
             let mut tooltip_text: Option<String> = None;
             let mut tooltip_visible = false;
             let mut last_mouse_pos: Option<(i32, i32)> = None;
+            // Hover, press and click state for synthesized mouse events
+            let mut pointer = PointerTracker::new(self.click_interval());
             let mut tooltip_tick = tokio::time::interval(Duration::from_millis(50));
             let mut palette_tick = tokio::time::interval(Duration::from_millis(50));
+            let mut inspector_open = false;
+            // Only claim SIGTSTP if the app can be suspended
+            let mut stop_signals = Self::ENABLE_SUSPEND.then(suspend::StopSignals::new);
 
             while !self.should_quit() {
+                // Another program had the terminal while the app was
+                // suspended: redraw all of it, at whatever size it has now
+                if terminal_control.take_resumed() {
+                    let (width, height) = terminal::size()?;
+                    if (width, height) != (cols, rows) {
+                        cols = width;
+                        rows = height;
+                        canvas = Canvas::new(cols, rows);
+                        tree.resize(Size::new(cols, rows));
+                        environment.width = cols;
+                        environment.height = rows;
+                        if stylesheet.set_environment(environment) {
+                            tree.root_mut().mark_dirty();
+                        }
+                    }
+                    canvas.invalidate();
+                    tree.invalidate_all();
+                    needs_render = true;
+                }
+
+                // Apply CSS variables set by handlers before the tree holding
+                // them can be rebuilt
+                if tree.apply_css_variables(&mut stylesheet, &theme) {
+                    needs_render = true;
+                }
+
+                // Style widgets mounted by handlers, and redraw what
+                // mounting or removing widgets damaged
+                if tree.apply_mounted_css(&mut stylesheet)? || !tree.damage().is_empty() {
+                    needs_render = true;
+                }
+
                 // Rebuild widget tree if app state changed
                 if needs_recompose {
                     // DOM hierarchy: App > Screen > user widgets (matches Python Textual)
@@ -760,20 +848,45 @@ pub trait App {
                     if Self::ENABLE_COMMAND_PALETTE {
                         root_children.push(Box::new(CommandPalette::new()));
                     }
+                    if Self::ENABLE_INSPECTOR {
+                        root_children.push(Box::new(Inspector::new()));
+                    }
                     let root = Box::new(widget::app_widget::AppWidget::new(root_children));
+                    // Keep focus where the tree moved it (Tab, mounting,
+                    // removing), unless the app changed its focus index
+                    let focus_index = if self.focus_index() == last_focus_index {
+                        tree.current_focus_index()
+                    } else {
+                        self.focus_index()
+                    };
+                    tree.detach();
+                    let focus_memory = tree.take_focus_memory();
                     tree = WidgetTree::new(root);
+                    tree.set_focus_memory(focus_memory);
+                    pointer.reset();
 
                     // Re-apply resize to new tree so breakpoints are correct
-                    tree.root_mut().on_resize(Size::new(cols, rows));
+                    tree.resize(Size::new(cols, rows));
 
                     tree.root_mut().clear_focus();
-                    tree.root_mut().focus_nth(self.focus_index());
-                    tree.update_focus(self.focus_index());
+                    tree.root_mut().focus_nth(focus_index);
+                    tree.update_focus(focus_index);
                     last_focus_index = self.focus_index();
 
+                    // Keep the inspector open across rebuilds
+                    if inspector_open {
+                        tree.query_one_as::<Inspector<Self::Message>, _, _>(
+                            "Inspector",
+                            |inspector| {
+                                inspector.toggle();
+                            },
+                        );
+                    }
+
                     // Full style resolution for new tree
                     let mut ancestors = VecDeque::new();
                     resolve_styles(tree.root_mut(), &stylesheet, &theme, &mut ancestors);
+                    tree.attach(app_ctx.clone());
 
                     needs_recompose = false;
                     needs_render = true;
@@ -789,33 +902,75 @@ pub trait App {
                     needs_render = true;
                 }
 
-                // If the currently focused widget is no longer focusable (e.g. hidden modal),
-                // fall back to the first available focusable widget.
-                if let Some(is_focusable) = tree.with_focused(|widget| widget.is_focusable()) {
-                    if !is_focusable {
-                        if tree.set_focus_index(0) {
-                            last_focus_index = self.focus_index();
-                            needs_render = true;
-                        }
+                // If the currently focused widget can no longer take focus (e.g. a
+                // hidden modal), return to where focus was before it, or the first
+                // focusable widget.
+                if tree.restore_focus() {
+                    last_focus_index = self.focus_index();
+                    needs_render = true;
+                }
+
+                // Report focus moving between widgets
+                let focus_events = tree.take_focus_events();
+                if !focus_events.is_empty() {
+                    let mut ctx = EventContext::new(app_ctx.clone(), &mut tree);
+                    for event in focus_events {
+                        self.on_focus_event(event, &mut ctx);
                     }
                 }
 
                 if needs_render {
-                    // Resolve styles only for dirty widgets
-                    let mut ancestors = VecDeque::new();
-                    resolve_dirty_styles(
-                        tree.root_mut(),
-                        &stylesheet,
-                        &theme,
-                        &mut ancestors,
-                        false,
-                        &InheritedContext::from_theme(&theme),
-                    );
-
-                    canvas.clear();
+                    // Resolve styles only for dirty widgets, collecting the
+                    // regions their changes damage
+                    let frame_start = Instant::now();
+                    tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+
+                    // Refresh the inspector overlay from the just-resolved styles
+                    if inspector_open {
+                        let snapshot = widget::inspector::inspect(
+                            tree.root_mut(),
+                            &canvas,
+                            &stylesheet,
+                            &theme,
+                            last_mouse_pos,
+                        );
+                        let screen = Region::from_u16(0, 0, cols, rows);
+                        let damaged = tree
+                            .query_one_as::<Inspector<Self::Message>, _, _>(
+                                "Inspector",
+                                |inspector| inspector.refresh(snapshot, screen),
+                            )
+                            .unwrap_or_default();
+                        for region in damaged {
+                            tree.invalidate_region(region);
+                        }
+                    }
+
+                    let full = tree.damage().is_full() || !canvas.has_previous_frame();
+                    let regions = tree.damage().regions().len();
+
+                    // Re-render only damaged regions; the rest of the previous
+                    // frame is kept as-is
                     let region = Region::from_u16(0, 0, cols, rows);
-                    tree.root().render(&mut canvas, region);
-                    canvas.flush()?;
+                    tree.render(&mut canvas, region);
+                    match recorder.as_mut() {
+                        Some(recorder) => {
+                            // Tee the frame's ANSI output into the recording
+                            let mut frame = Vec::new();
+                            canvas.flush_into(&mut frame)?;
+                            let mut stdout = std::io::stdout().lock();
+                            stdout.write_all(&frame)?;
+                            stdout.flush()?;
+                            recorder.output(&frame)?;
+                        }
+                        None => canvas.flush()?,
+                    }
+
+                    devtools::emit(|| devtools::DevtoolsEvent::Frame {
+                        duration: frame_start.elapsed(),
+                        full,
+                        regions: if full { 0 } else { regions },
+                    });
 
                     needs_render = false;
                 }
@@ -855,26 +1010,72 @@ pub trait App {
                         }
                     }
 
+                    // Ctrl+Z from outside the app, e.g. `kill -TSTP`
+                    _ = suspend::next_stop(stop_signals.as_mut()) => {
+                        #[cfg(unix)]
+                        app_ctx.suspend(suspend::stop_process)?;
+                    }
+
+                    // Widget timers, measured on the app's clock
+                    _ = timer::sleep_for(app_ctx.timers().next_wait()) => {
+                        if tree.fire_timers() {
+                            needs_render = true;
+                        }
+                    }
+
                     _ = palette_tick.tick() => {
-                        if drain_command_palette(self, &mut tree, &tx) {
+                        // Only rebuild when the palette did something: a
+                        // rebuild every tick would restart widget timers
+                        if drain_command_palette(self, &mut tree, &app_ctx) {
+                            tree.invalidate_widget("CommandPalette");
                             needs_render = true;
+                            needs_recompose = self.needs_recompose();
                         }
-                        needs_recompose = self.needs_recompose();
                     }
 
                     // Terminal events from crossterm
-                    maybe_event = event_stream.next() => {
+                    maybe_event = input.next() => {
+                        if let (Some(recorder), Some(Ok(event))) = (recorder.as_mut(), &maybe_event) {
+                            recorder.input(event)?;
+                        }
                         match maybe_event {
                             Some(Ok(Event::Key(key_event))) => {
+                                devtools::emit(|| devtools::DevtoolsEvent::Key {
+                                    key: devtools::describe_key(&key_event),
+                                });
+
+                                if Self::ENABLE_INSPECTOR
+                                    && matches_binding(&key_event, Self::INSPECTOR_BINDING)
+                                {
+                                    inspector_open = tree
+                                        .query_one_as::<Inspector<Self::Message>, _, _>(
+                                            "Inspector",
+                                            |inspector| inspector.toggle(),
+                                        )
+                                        .unwrap_or(false);
+                                    tree.invalidate_all();
+                                    needs_render = true;
+                                    continue;
+                                }
+
+                                #[cfg(unix)]
+                                if Self::ENABLE_SUSPEND
+                                    && matches_binding(&key_event, Self::SUSPEND_BINDING)
+                                {
+                                    app_ctx.suspend(suspend::stop_process)?;
+                                    continue;
+                                }
+
                                 let handled_by_system = Self::ENABLE_COMMAND_PALETTE
                                     && matches_binding(&key_event, Self::COMMAND_PALETTE_BINDING);
 
                                 if handled_by_system {
-                                    let event_ctx = AppContext::new(tx.clone());
+                                    let event_ctx = app_ctx.clone();
                                     let mut ctx = EventContext::new(event_ctx, &mut tree);
                                     self.dispatch_action("app.command_palette", &mut ctx);
-                                    let _ = drain_command_palette(self, &mut tree, &tx);
+                                    let _ = drain_command_palette(self, &mut tree, &app_ctx);
                                     needs_recompose = self.needs_recompose();
+                                    tree.invalidate_all();
                                     needs_render = true;
                                     continue;
                                 }
@@ -910,15 +1111,17 @@ pub trait App {
                                     let envelope = MessageEnvelope::new(msg, sender.id.as_deref(), sender.type_name);
                                     let bubbled = tree.bubble_message(envelope);
 
-                                    // App is always the final handler (even if bubbling was stopped)
-                                    let event_ctx =
-                                        AppContext::new(tx.clone());
-                                    let mut ctx = EventContext::new(event_ctx, &mut tree);
-                                    self.handle_message(bubbled, &mut ctx);
+                                    // App is the final handler (even if bubbling was stopped),
+                                    // unless a widget prevented it
+                                    trace_message(self, &bubbled);
+                                    if !bubbled.is_default_prevented() {
+                                        let event_ctx = app_ctx.clone();
+                                        let mut ctx = EventContext::new(event_ctx, &mut tree);
+                                        self.handle_message(bubbled, &mut ctx);
+                                    }
                                 }
                                 {
-                                    let event_ctx =
-                                        AppContext::new(tx.clone());
+                                    let event_ctx = app_ctx.clone();
                                     let mut ctx = EventContext::new(event_ctx, &mut tree);
                                     if !palette_open {
                                         self.on_key(key_event.code, &mut ctx);
@@ -927,23 +1130,15 @@ pub trait App {
                                 // Collect and dispatch pending actions from key events
                                 let actions = collect_pending_actions_mut(tree.root_mut());
                                 if !actions.is_empty() {
-                                    let event_ctx =
-                                        AppContext::new(tx.clone());
+                                    let event_ctx = app_ctx.clone();
                                     let mut ctx = EventContext::new(event_ctx, &mut tree);
                                     for action in actions {
                                         self.dispatch_action(&action, &mut ctx);
                                     }
                                 }
-                                if let Some(restored) = tree
-                                    .query_one_as::<CommandPalette<Self::Message>, _, _>(
-                                        "CommandPalette",
-                                        |palette| palette.take_restore_focus(),
-                                    )
-                                    .flatten()
-                                {
-                                    let _ = tree.set_focus_index(restored);
-                                }
-                                let _ = drain_command_palette(self, &mut tree, &tx);
+                                // Return focus from overlays that closed (e.g. the palette)
+                                tree.restore_focus();
+                                let _ = drain_command_palette(self, &mut tree, &app_ctx);
                                 // Check if app wants tree rebuild (Elm-style)
                                 needs_recompose = self.needs_recompose();
                                 needs_render = needs_render || focus_changed;
@@ -955,24 +1150,36 @@ pub trait App {
                                 canvas = Canvas::new(cols, rows);
 
                                 // Propagate resize to Screen for breakpoint updates
-                                tree.root_mut().on_resize(Size::new(cols, rows));
+                                tree.resize(Size::new(cols, rows));
+                                tree.invalidate_all();
+
+                                // Restyle everything if an @media query may now
+                                // evaluate differently
+                                environment.width = cols;
+                                environment.height = rows;
+                                if stylesheet.set_environment(environment) {
+                                    tree.root_mut().mark_dirty();
+                                }
 
                                 needs_render = true;
                             }
                             Some(Ok(Event::Mouse(mouse_event))) => {
+                                devtools::emit(|| devtools::mouse_event(&mouse_event));
+
                                 // Compute the full-screen region for mouse event routing
                                 let region = Region::from_u16(0, 0, cols, rows);
 
                                 // Clear hover state on all widgets before processing move events
                                 // This ensures widgets that are no longer hovered clear their state
-                                if matches!(mouse_event.kind, crossterm::event::MouseEventKind::Moved) {
+                                let moved =
+                                    matches!(mouse_event.kind, crossterm::event::MouseEventKind::Moved);
+                                if moved {
                                     clear_all_hover(tree.root_mut());
                                 }
 
                                 // Route mouse event through widget tree hit-testing
                                 // NOTE: Mouse events use hit-testing, not focus path.
-                                // Full mouse bubbling would require on_mouse to track the hit path.
-                                // For now, messages go directly to App without parent interception.
+                                // Messages bubble through the ancestors on the hit path.
                                 let palette_open = tree
                                     .query_one_as::<CommandPalette<Self::Message>, _, _>(
                                         "CommandPalette",
@@ -980,6 +1187,18 @@ pub trait App {
                                     )
                                     .unwrap_or(false);
 
+                                // Clicks, drags and wheel events can change the state of
+                                // the widget under the cursor without marking it dirty, so
+                                // repaint it. Hover and active changes are tracked via
+                                // dirty flags.
+                                if !moved {
+                                    if palette_open {
+                                        tree.invalidate_widget("CommandPalette");
+                                    } else {
+                                        tree.invalidate_at(mouse_event.column, mouse_event.row);
+                                    }
+                                }
+
                                 let mouse_result = if palette_open {
                                     tree.query_one_as::<CommandPalette<Self::Message>, _, _>(
                                         "CommandPalette",
@@ -993,35 +1212,53 @@ pub trait App {
                                 if let Some((msg, sender)) = mouse_result {
                                     let envelope =
                                         MessageEnvelope::new(msg, sender.id.as_deref(), sender.type_name);
-                                    let event_ctx =
-                                        AppContext::new(tx.clone());
-                                    let mut ctx = EventContext::new(event_ctx, &mut tree);
-                                    self.handle_message(envelope, &mut ctx);
+                                    // Only hit-test when there is something to bubble;
+                                    // move events are far more frequent than messages
+                                    let envelope = if palette_open {
+                                        envelope
+                                    } else {
+                                        let hit_path = tree.hit_path(mouse_event.column, mouse_event.row);
+                                        tree.bubble_mouse_message(envelope, &hit_path)
+                                    };
+                                    trace_message(self, &envelope);
+                                    if !envelope.is_default_prevented() {
+                                        let event_ctx = app_ctx.clone();
+                                        let mut ctx = EventContext::new(event_ctx, &mut tree);
+                                        self.handle_message(envelope, &mut ctx);
+                                    }
+                                }
+
+                                // Enter/leave, counted clicks and drags, unless the
+                                // palette is capturing the mouse
+                                if !palette_open {
+                                    let envelopes =
+                                        tree.dispatch_pointer(&mut pointer, mouse_event, Instant::now());
+                                    for envelope in envelopes {
+                                        trace_message(self, &envelope);
+                                        if envelope.is_default_prevented() {
+                                            continue;
+                                        }
+                                        let event_ctx = app_ctx.clone();
+                                        let mut ctx = EventContext::new(event_ctx, &mut tree);
+                                        self.handle_message(envelope, &mut ctx);
+                                    }
                                 }
 
                                 // Collect and dispatch pending actions from link clicks
                                 let actions = collect_pending_actions_mut(tree.root_mut());
                                 if !actions.is_empty() {
-                                    let event_ctx =
-                                        AppContext::new(tx.clone());
+                                    let event_ctx = app_ctx.clone();
                                     let mut ctx = EventContext::new(event_ctx, &mut tree);
                                     for action in actions {
                                         self.dispatch_action(&action, &mut ctx);
                                     }
                                 }
-                                if let Some(restored) = tree
-                                    .query_one_as::<CommandPalette<Self::Message>, _, _>(
-                                        "CommandPalette",
-                                        |palette| palette.take_restore_focus(),
-                                    )
-                                    .flatten()
-                                {
-                                    let _ = tree.set_focus_index(restored);
-                                }
-                                let _ = drain_command_palette(self, &mut tree, &tx);
+                                // Return focus from overlays that closed (e.g. the palette)
+                                tree.restore_focus();
+                                let _ = drain_command_palette(self, &mut tree, &app_ctx);
 
                                 // Tooltip handling: update pending tooltip on mouse move.
-                                if matches!(mouse_event.kind, crossterm::event::MouseEventKind::Moved) {
+                                if moved {
                                     let current_pos =
                                         (mouse_event.column as i32, mouse_event.row as i32);
                                     let pos_changed = last_mouse_pos != Some(current_pos);
@@ -1094,13 +1331,22 @@ pub trait App {
                         }
                     }
 
-                    // Messages from async tasks (timers, background work)
+                    // Messages from async tasks (timers, background work) and
+                    // messages posted to widgets
                     Some(envelope) = rx.recv() => {
                         log::debug!("EVENT_LOOP: Received message from {:?}", envelope.sender_type);
-                        let event_ctx = AppContext::new(tx.clone());
-                        let mut ctx = EventContext::new(event_ctx, &mut tree);
-                        self.handle_message(envelope, &mut ctx);
-                        if drain_command_palette(self, &mut tree, &tx) {
+                        // Widgets handling a posted message may change state
+                        // without marking themselves dirty
+                        needs_render |= envelope.target.is_some();
+                        let envelope = tree.deliver_message(envelope, &theme);
+                        trace_message(self, &envelope);
+                        if !envelope.is_default_prevented() {
+                            let event_ctx = app_ctx.clone();
+                            let mut ctx = EventContext::new(event_ctx, &mut tree);
+                            self.handle_message(envelope, &mut ctx);
+                        }
+                        if drain_command_palette(self, &mut tree, &app_ctx) {
+                            tree.invalidate_widget("CommandPalette");
                             needs_render = true;
                         }
                         // Check if app wants tree rebuild (Elm-style)
@@ -1109,7 +1355,27 @@ pub trait App {
                 }
             }
 
+            tree.detach();
+            app_ctx.workers().cancel_all();
+            app_ctx.timers().cancel_all();
             Ok(())
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_key_token_function_keys() {
+        assert_eq!(parse_key_token("f1"), Some(KeyCode::F(1)));
+        assert_eq!(parse_key_token("f12"), Some(KeyCode::F(12)));
+        assert_eq!(parse_key_token("f24"), Some(KeyCode::F(24)));
+        assert_eq!(parse_key_token("f0"), None);
+        assert_eq!(parse_key_token("f25"), None);
+        // Other words starting with `f` aren't function keys
+        assert_eq!(parse_key_token("foo"), None);
+        assert_eq!(parse_key_token("f"), Some(KeyCode::Char('f')));
+    }
+}
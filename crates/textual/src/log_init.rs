@@ -20,6 +20,7 @@ impl log::Log for FileLogger {
             {
                 let _ = writeln!(file, "[{}] {}", record.level(), record.args());
             }
+            crate::devtools::forward_log(record);
         }
     }
 
@@ -67,6 +67,10 @@ macro_rules! impl_widget_delegation {
                 self.$field.get_state()
             }
 
+            fn attributes(&self) -> Vec<(&'static str, String)> {
+                self.$field.attributes()
+            }
+
             fn set_style(&mut self, style: ::tcss::ComputedStyle) {
                 self.$field.set_style(style)
             }
@@ -104,6 +108,10 @@ macro_rules! impl_widget_delegation {
                 self.$field.mark_clean()
             }
 
+            fn invalidation(&self) -> Option<$crate::damage::Invalidation> {
+                self.$field.invalidation()
+            }
+
             fn on_event(&mut self, key: $crate::KeyCode) -> Option<$m> {
                 self.$field.on_event(key)
             }
@@ -120,6 +128,10 @@ macro_rules! impl_widget_delegation {
                 self.$field.on_mouse_with_sender(event, region)
             }
 
+            fn on_pointer(&mut self, event: &$crate::mouse::PointerEvent) -> Option<$m> {
+                self.$field.on_pointer(event)
+            }
+
             fn set_hover(&mut self, is_hovered: bool) -> bool {
                 self.$field.set_hover(is_hovered)
             }
@@ -136,6 +148,18 @@ macro_rules! impl_widget_delegation {
                 self.$field.is_focusable()
             }
 
+            fn tab_index(&self) -> i32 {
+                self.$field.tab_index()
+            }
+
+            fn can_focus_children(&self) -> bool {
+                self.$field.can_focus_children()
+            }
+
+            fn is_focus_trap(&self) -> bool {
+                self.$field.is_focus_trap()
+            }
+
             fn is_visible(&self) -> bool {
                 self.$field.is_visible()
             }
@@ -188,10 +212,50 @@ macro_rules! impl_widget_delegation {
                 self.$field.get_child_mut(index)
             }
 
+            fn insert_child(
+                &mut self,
+                index: usize,
+                child: Box<dyn $crate::Widget<$m>>,
+            ) -> Result<(), Box<dyn $crate::Widget<$m>>> {
+                self.$field.insert_child(index, child)
+            }
+
+            fn remove_child(&mut self, index: usize) -> Option<Box<dyn $crate::Widget<$m>>> {
+                self.$field.remove_child(index)
+            }
+
+            fn child_regions(
+                &self,
+                region: $crate::Region,
+                viewport: $crate::layouts::Viewport,
+            ) -> Vec<(usize, $crate::Region)> {
+                self.$field.child_regions(region, viewport)
+            }
+
+            fn scroll_to_region(
+                &mut self,
+                region: $crate::Region,
+                viewport: $crate::layouts::Viewport,
+                target: $crate::Region,
+                animate: bool,
+                center: bool,
+            ) -> Option<(i32, i32)> {
+                self.$field
+                    .scroll_to_region(region, viewport, target, animate, center)
+            }
+
             fn handle_message(&mut self, envelope: &mut $crate::MessageEnvelope<$m>) -> Option<$m> {
                 self.$field.handle_message(envelope)
             }
 
+            fn on_message(
+                &mut self,
+                envelope: &mut $crate::MessageEnvelope<$m>,
+                ctx: &$crate::AppContext<$m>,
+            ) -> Option<$m> {
+                self.$field.on_message(envelope, ctx)
+            }
+
             fn id(&self) -> Option<&str> {
                 self.$field.id()
             }
@@ -206,6 +270,26 @@ macro_rules! impl_widget_delegation {
                 self.$field.on_resize(size)
             }
 
+            fn on_mount(&mut self, ctx: &$crate::AppContext<$m>) {
+                self.$field.on_mount(ctx)
+            }
+
+            fn on_unmount(&mut self, ctx: &$crate::AppContext<$m>) {
+                self.$field.on_unmount(ctx)
+            }
+
+            fn on_show(&mut self, ctx: &$crate::AppContext<$m>) {
+                self.$field.on_show(ctx)
+            }
+
+            fn on_hide(&mut self, ctx: &$crate::AppContext<$m>) {
+                self.$field.on_hide(ctx)
+            }
+
+            fn on_timer(&mut self, timer: $crate::timer::TimerId, ctx: &$crate::AppContext<$m>) {
+                self.$field.on_timer(timer, ctx)
+            }
+
             fn for_each_child(&mut self, f: &mut dyn FnMut(&mut dyn $crate::Widget<$m>)) {
                 self.$field.for_each_child(f)
             }
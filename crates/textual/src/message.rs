@@ -9,7 +9,9 @@
 /// is wrapped in an envelope that tracks:
 /// - The original message payload
 /// - Which widget sent it (by ID and type name)
+/// - Which widget it was posted to, if any (see `AppContext::post_to`)
 /// - Whether it should continue bubbling up the tree
+/// - Whether the app's default handling was prevented
 #[derive(Debug, Clone)]
 pub struct MessageEnvelope<M> {
     /// The actual message payload.
@@ -23,9 +25,17 @@ pub struct MessageEnvelope<M> {
     /// Automatically extracted from the widget's type.
     pub sender_type: String,
 
+    /// Selector of the widget this message was posted to, or `None` for
+    /// messages posted to the app.
+    pub target: Option<String>,
+
     /// Whether this message should continue bubbling up to parent widgets.
     /// Call `stop()` to prevent further propagation.
     bubbling: bool,
+
+    /// Whether `App::handle_message` should be skipped.
+    /// Call `prevent_default()` to set.
+    default_prevented: bool,
 }
 
 impl<M> MessageEnvelope<M> {
@@ -40,10 +50,18 @@ impl<M> MessageEnvelope<M> {
             message,
             sender_id: sender_id.map(String::from),
             sender_type: sender_type.to_string(),
+            target: None,
             bubbling: true,
+            default_prevented: false,
         }
     }
 
+    /// Address this message to the first widget matching `selector`.
+    pub fn with_target(mut self, selector: impl Into<String>) -> Self {
+        self.target = Some(selector.into());
+        self
+    }
+
     /// Stop this message from bubbling further up the widget tree.
     ///
     /// After calling this, parent widgets will not receive the message
@@ -58,6 +76,20 @@ impl<M> MessageEnvelope<M> {
         self.bubbling
     }
 
+    /// Skip the app's handling of this message.
+    ///
+    /// Widgets call this when they have fully handled a message, so that
+    /// `App::handle_message` doesn't act on it as well. Unlike `stop()`, the
+    /// message keeps bubbling to parent widgets.
+    pub fn prevent_default(&mut self) {
+        self.default_prevented = true;
+    }
+
+    /// Check if a widget has prevented the app's handling of this message.
+    pub fn is_default_prevented(&self) -> bool {
+        self.default_prevented
+    }
+
     /// Transform the message payload while preserving envelope metadata.
     ///
     /// Useful when a parent widget wants to transform a child's message
@@ -70,7 +102,9 @@ impl<M> MessageEnvelope<M> {
             message: f(self.message),
             sender_id: self.sender_id,
             sender_type: self.sender_type,
+            target: self.target,
             bubbling: self.bubbling,
+            default_prevented: self.default_prevented,
         }
     }
 }
@@ -107,4 +141,16 @@ mod tests {
         assert_eq!(mapped.sender_id, Some("counter".to_string()));
         assert_eq!(mapped.sender_type, "Counter");
     }
+
+    #[test]
+    fn test_prevent_default_keeps_bubbling() {
+        let mut envelope = MessageEnvelope::new(42, None, "Button").with_target("#panel");
+
+        assert_eq!(envelope.target.as_deref(), Some("#panel"));
+        assert!(!envelope.is_default_prevented());
+        envelope.prevent_default();
+        assert!(envelope.is_default_prevented());
+        assert!(envelope.is_bubbling());
+        assert!(envelope.map(|n| n + 1).is_default_prevented());
+    }
 }
@@ -0,0 +1,510 @@
+//! Higher-level mouse events synthesized from raw terminal input.
+//!
+//! Terminals only report presses, releases, drags, moves and wheel turns at
+//! a screen cell. [`PointerTracker`] turns that stream into the events
+//! widgets actually want:
+//!
+//! - `Enter` / `Leave` when the cursor moves onto or off a widget (every
+//!   widget on the path under the cursor, not just the innermost one)
+//! - `Click` with a count, so double and triple clicks can be told apart
+//! - `DragStart` / `DragMove` / `DragEnd`, with the widget the drag started
+//!   on capturing the mouse until the button is released, so dragging a
+//!   scrollbar thumb or a splitter keeps working outside its region
+//!
+//! Widgets receive these through [`Widget::on_pointer`](crate::widget::Widget::on_pointer),
+//! with coordinates relative to their own region.
+
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+use crate::canvas::Region;
+
+/// Default maximum time between clicks that count as a double click.
+pub const DEFAULT_CLICK_INTERVAL: Duration = Duration::from_millis(500);
+
+/// What happened to a widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerAction {
+    /// The cursor moved onto the widget.
+    Enter,
+    /// The cursor moved off the widget.
+    Leave,
+    /// A button was pressed over the widget.
+    Down(MouseButton),
+    /// A button was released over the widget.
+    Up(MouseButton),
+    /// A button was pressed and released over the widget.
+    ///
+    /// `count` is 2 for a double click, 3 for a triple click, and so on.
+    Click { button: MouseButton, count: u32 },
+    /// The mouse moved with a button held after being pressed over the
+    /// widget, which now captures the mouse.
+    DragStart(MouseButton),
+    /// The mouse moved during a drag that started on the widget, wherever
+    /// the cursor is.
+    DragMove(MouseButton),
+    /// The button was released, ending a drag that started on the widget.
+    DragEnd(MouseButton),
+}
+
+/// A synthesized mouse event, delivered to a single widget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PointerEvent {
+    /// What happened.
+    pub action: PointerAction,
+    /// Column relative to the widget's region (negative or past its width
+    /// when a captured drag leaves it).
+    pub x: i32,
+    /// Row relative to the widget's region.
+    pub y: i32,
+    /// Column on the screen.
+    pub screen_x: i32,
+    /// Row on the screen.
+    pub screen_y: i32,
+    /// Modifier keys held at the time.
+    pub modifiers: KeyModifiers,
+}
+
+impl PointerEvent {
+    /// Whether this is a click with at least `count` clicks
+    /// (`is_click(2)` for a double click).
+    pub fn is_click(&self, count: u32) -> bool {
+        matches!(self.action, PointerAction::Click { count: n, .. } if n >= count)
+    }
+}
+
+/// The widgets under a screen cell, from the root down, with the region
+/// each one was laid out in.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HitPath {
+    indices: Vec<usize>,
+    regions: Vec<Region>,
+}
+
+impl HitPath {
+    /// A path holding only the root, laid out in `region`.
+    pub fn root(region: Region) -> Self {
+        Self {
+            indices: Vec::new(),
+            regions: vec![region],
+        }
+    }
+
+    /// Extend the path to the child at `index`, laid out in `region`.
+    pub fn push(&mut self, index: usize, region: Region) {
+        self.indices.push(index);
+        self.regions.push(region);
+    }
+
+    /// Child indices from the root to the innermost widget.
+    pub fn indices(&self) -> &[usize] {
+        &self.indices
+    }
+
+    /// Region of the innermost widget.
+    pub fn region(&self) -> Region {
+        self.regions.last().copied().unwrap_or_default()
+    }
+
+    /// Number of widgets on the path, including the root.
+    fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// The first `depth` widgets of the path.
+    fn truncated(&self, depth: usize) -> Self {
+        Self {
+            indices: self.indices[..depth.saturating_sub(1)].to_vec(),
+            regions: self.regions[..depth].to_vec(),
+        }
+    }
+}
+
+/// A synthesized event and the widget it is for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PointerTarget {
+    /// Child indices from the root to the widget.
+    pub path: Vec<usize>,
+    pub event: PointerEvent,
+}
+
+/// A button held down over a widget.
+#[derive(Debug, Clone)]
+struct Press {
+    target: HitPath,
+    button: MouseButton,
+    dragging: bool,
+}
+
+/// The most recent click, for counting repeats.
+#[derive(Debug, Clone)]
+struct LastClick {
+    target: Vec<usize>,
+    button: MouseButton,
+    at: Instant,
+    count: u32,
+}
+
+/// Turns raw mouse events into [`PointerEvent`]s.
+///
+/// The event loop keeps one tracker for the lifetime of the app and feeds
+/// it every mouse event together with the [`HitPath`] under the cursor.
+#[derive(Debug, Clone)]
+pub struct PointerTracker {
+    click_interval: Duration,
+    hover: Option<HitPath>,
+    press: Option<Press>,
+    last_click: Option<LastClick>,
+}
+
+impl Default for PointerTracker {
+    fn default() -> Self {
+        Self::new(DEFAULT_CLICK_INTERVAL)
+    }
+}
+
+impl PointerTracker {
+    /// Create a tracker counting clicks no more than `click_interval` apart
+    /// as repeats.
+    pub fn new(click_interval: Duration) -> Self {
+        Self {
+            click_interval,
+            hover: None,
+            press: None,
+            last_click: None,
+        }
+    }
+
+    /// Maximum time between clicks that count as a repeat.
+    pub fn click_interval(&self) -> Duration {
+        self.click_interval
+    }
+
+    /// Whether a drag is in progress.
+    pub fn is_dragging(&self) -> bool {
+        self.press.as_ref().is_some_and(|press| press.dragging)
+    }
+
+    /// The path of the widget capturing the mouse, if a drag is in progress.
+    pub fn captured(&self) -> Option<&[usize]> {
+        self.press
+            .as_ref()
+            .filter(|press| press.dragging)
+            .map(|press| press.target.indices())
+    }
+
+    /// Forget everything about the widgets under the cursor.
+    ///
+    /// Call this when the widget tree is rebuilt, since the paths no longer
+    /// refer to the same widgets.
+    pub fn reset(&mut self) {
+        self.hover = None;
+        self.press = None;
+        self.last_click = None;
+    }
+
+    /// Work out which widgets get which events for a raw mouse event.
+    ///
+    /// `hit` is the path under the cursor. Events come back in the order
+    /// they should be delivered.
+    pub(crate) fn track(
+        &mut self,
+        event: &MouseEvent,
+        hit: HitPath,
+        now: Instant,
+    ) -> Vec<PointerTarget> {
+        let position = (event.column as i32, event.row as i32);
+        let target = |path: &HitPath, action| PointerTarget {
+            path: path.indices().to_vec(),
+            event: PointerEvent {
+                action,
+                x: position.0 - path.region().x,
+                y: position.1 - path.region().y,
+                screen_x: position.0,
+                screen_y: position.1,
+                modifiers: event.modifiers,
+            },
+        };
+
+        let mut targets = Vec::new();
+
+        // A captured drag doesn't change what is hovered until it ends
+        if !self.is_dragging() {
+            self.update_hover(&hit, &mut |path, action| targets.push(target(path, action)));
+        }
+
+        match event.kind {
+            MouseEventKind::Down(button) => {
+                targets.push(target(&hit, PointerAction::Down(button)));
+                self.press = Some(Press {
+                    target: hit,
+                    button,
+                    dragging: false,
+                });
+            }
+            MouseEventKind::Drag(button) => {
+                if let Some(press) = self.press.as_mut().filter(|press| press.button == button) {
+                    let action = if press.dragging {
+                        PointerAction::DragMove(button)
+                    } else {
+                        press.dragging = true;
+                        PointerAction::DragStart(button)
+                    };
+                    targets.push(target(&press.target, action));
+                }
+            }
+            MouseEventKind::Up(button) => {
+                let press = self.press.take().filter(|press| press.button == button);
+                match press {
+                    Some(press) if press.dragging => {
+                        targets.push(target(&press.target, PointerAction::DragEnd(button)));
+                        // Catch up with whatever the drag ended over
+                        self.update_hover(&hit, &mut |path, action| {
+                            targets.push(target(path, action))
+                        });
+                    }
+                    press => {
+                        targets.push(target(&hit, PointerAction::Up(button)));
+                        if press.is_some_and(|press| press.target.indices() == hit.indices()) {
+                            let count = self.count_click(hit.indices(), button, now);
+                            targets.push(target(&hit, PointerAction::Click { button, count }));
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        targets
+    }
+
+    /// Send `Leave` to widgets no longer under the cursor (innermost first)
+    /// and `Enter` to newly hovered ones (outermost first).
+    fn update_hover(&mut self, hit: &HitPath, emit: &mut impl FnMut(&HitPath, PointerAction)) {
+        let previous = self.hover.replace(hit.clone());
+        let common = match &previous {
+            Some(previous) => {
+                1 + previous
+                    .indices()
+                    .iter()
+                    .zip(hit.indices())
+                    .take_while(|(a, b)| a == b)
+                    .count()
+            }
+            None => 0,
+        };
+        if let Some(previous) = previous {
+            for depth in (common + 1..=previous.len()).rev() {
+                emit(&previous.truncated(depth), PointerAction::Leave);
+            }
+        }
+        for depth in common + 1..=hit.len() {
+            emit(&hit.truncated(depth), PointerAction::Enter);
+        }
+    }
+
+    /// Record a click on `target` and return how many it makes in a row.
+    fn count_click(&mut self, target: &[usize], button: MouseButton, now: Instant) -> u32 {
+        let count = match &self.last_click {
+            Some(last)
+                if last.target == target
+                    && last.button == button
+                    && now.duration_since(last.at) <= self.click_interval =>
+            {
+                last.count + 1
+            }
+            _ => 1,
+        };
+        self.last_click = Some(LastClick {
+            target: target.to_vec(),
+            button,
+            at: now,
+            count,
+        });
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mouse(kind: MouseEventKind, column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind,
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    /// Root (20x10) -> [left (0..10), right (10..20) -> [button (row 2)]]
+    fn hit(column: u16, row: u16) -> HitPath {
+        let mut path = HitPath::root(Region::new(0, 0, 20, 10));
+        if column < 10 {
+            path.push(0, Region::new(0, 0, 10, 10));
+        } else {
+            path.push(1, Region::new(10, 0, 10, 10));
+            if row == 2 {
+                path.push(0, Region::new(12, 2, 4, 1));
+            }
+        }
+        path
+    }
+
+    fn feed(
+        tracker: &mut PointerTracker,
+        kind: MouseEventKind,
+        column: u16,
+        row: u16,
+        now: Instant,
+    ) -> Vec<(Vec<usize>, PointerAction)> {
+        tracker
+            .track(&mouse(kind, column, row), hit(column, row), now)
+            .into_iter()
+            .map(|target| (target.path, target.event.action))
+            .collect()
+    }
+
+    #[test]
+    fn test_enter_and_leave_follow_the_path() {
+        let mut tracker = PointerTracker::default();
+        let now = Instant::now();
+
+        let events = feed(&mut tracker, MouseEventKind::Moved, 1, 1, now);
+        assert_eq!(
+            events,
+            [
+                (vec![], PointerAction::Enter),
+                (vec![0], PointerAction::Enter)
+            ]
+        );
+
+        // Staying on the same widget sends nothing
+        assert!(feed(&mut tracker, MouseEventKind::Moved, 2, 1, now).is_empty());
+
+        let events = feed(&mut tracker, MouseEventKind::Moved, 13, 2, now);
+        assert_eq!(
+            events,
+            [
+                (vec![0], PointerAction::Leave),
+                (vec![1], PointerAction::Enter),
+                (vec![1, 0], PointerAction::Enter),
+            ]
+        );
+
+        let events = feed(&mut tracker, MouseEventKind::Moved, 13, 5, now);
+        assert_eq!(events, [(vec![1, 0], PointerAction::Leave)]);
+    }
+
+    #[test]
+    fn test_clicks_are_counted_within_interval() {
+        let mut tracker = PointerTracker::new(Duration::from_millis(300));
+        let start = Instant::now();
+        let mut click = |at: Duration| {
+            let now = start + at;
+            feed(
+                &mut tracker,
+                MouseEventKind::Down(MouseButton::Left),
+                13,
+                2,
+                now,
+            );
+            feed(
+                &mut tracker,
+                MouseEventKind::Up(MouseButton::Left),
+                13,
+                2,
+                now,
+            )
+            .into_iter()
+            .find_map(|(_, action)| match action {
+                PointerAction::Click { count, .. } => Some(count),
+                _ => None,
+            })
+        };
+
+        assert_eq!(click(Duration::ZERO), Some(1));
+        assert_eq!(click(Duration::from_millis(200)), Some(2));
+        assert_eq!(click(Duration::from_millis(400)), Some(3));
+        // Too slow, starts over
+        assert_eq!(click(Duration::from_millis(1000)), Some(1));
+    }
+
+    #[test]
+    fn test_release_elsewhere_is_not_a_click() {
+        let mut tracker = PointerTracker::default();
+        let now = Instant::now();
+        feed(
+            &mut tracker,
+            MouseEventKind::Down(MouseButton::Left),
+            13,
+            2,
+            now,
+        );
+        let events = feed(
+            &mut tracker,
+            MouseEventKind::Up(MouseButton::Left),
+            1,
+            1,
+            now,
+        );
+        assert!(events.contains(&(vec![0], PointerAction::Up(MouseButton::Left))));
+        assert!(
+            !events
+                .iter()
+                .any(|(_, action)| matches!(action, PointerAction::Click { .. }))
+        );
+    }
+
+    #[test]
+    fn test_drag_captures_the_pressed_widget() {
+        let mut tracker = PointerTracker::default();
+        let now = Instant::now();
+        let left = MouseButton::Left;
+
+        feed(&mut tracker, MouseEventKind::Moved, 13, 2, now);
+        feed(&mut tracker, MouseEventKind::Down(left), 13, 2, now);
+        assert_eq!(tracker.captured(), None);
+
+        let events = feed(&mut tracker, MouseEventKind::Drag(left), 14, 2, now);
+        assert_eq!(events, [(vec![1, 0], PointerAction::DragStart(left))]);
+        assert_eq!(tracker.captured(), Some(&[1, 0][..]));
+
+        // Outside the button, but it keeps the events and hover is frozen
+        let targets = tracker.track(&mouse(MouseEventKind::Drag(left), 1, 5), hit(1, 5), now);
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].path, [1, 0]);
+        assert_eq!(targets[0].event.action, PointerAction::DragMove(left));
+        assert_eq!((targets[0].event.x, targets[0].event.y), (-11, 3));
+        assert_eq!(
+            (targets[0].event.screen_x, targets[0].event.screen_y),
+            (1, 5)
+        );
+
+        let events = feed(&mut tracker, MouseEventKind::Up(left), 1, 5, now);
+        assert_eq!(
+            events,
+            [
+                (vec![1, 0], PointerAction::DragEnd(left)),
+                (vec![1, 0], PointerAction::Leave),
+                (vec![1], PointerAction::Leave),
+                (vec![0], PointerAction::Enter),
+            ]
+        );
+        assert!(!tracker.is_dragging());
+    }
+
+    #[test]
+    fn test_local_coordinates() {
+        let mut tracker = PointerTracker::default();
+        let targets = tracker.track(
+            &mouse(MouseEventKind::Down(MouseButton::Left), 14, 2),
+            hit(14, 2),
+            Instant::now(),
+        );
+        let down = targets.last().unwrap();
+        assert_eq!(down.path, [1, 0]);
+        assert_eq!((down.event.x, down.event.y), (2, 0));
+    }
+}
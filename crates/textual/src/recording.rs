@@ -0,0 +1,881 @@
+//! Session recording and replay in the asciicast v2 format.
+//!
+//! Setting `TEXTUAL_RECORD=session.cast` makes the event loop write every
+//! flushed frame (the exact ANSI bytes [`Canvas`](crate::Canvas) sends to
+//! the terminal) and every input event, with timestamps, to an
+//! [asciicast v2](https://docs.asciinema.org/manual/asciicast/v2/) file.
+//! The file plays back in any asciicast player:
+//!
+//! ```text
+//! TEXTUAL_RECORD=demo.cast cargo run --example my_app
+//! asciinema play demo.cast
+//! ```
+//!
+//! Setting `TEXTUAL_REPLAY=session.cast` instead feeds the recorded input
+//! events back through the event loop at their original times, which
+//! reproduces the session in the running app. Live input keeps working
+//! during and after the replay.
+//!
+//! Input is stored as `"i"` events holding the terminal escape sequence for
+//! the key or mouse event (see [`encode_event`]); terminal resizes are
+//! stored as `"r"` events (`"COLSxROWS"`), and frames as `"o"` events.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::error::{Result, TextualError};
+use crate::suspend::TerminalControl;
+use crossterm::event::{
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+
+/// Environment variable naming the file to record the session to.
+pub const RECORD_ENV_VAR: &str = "TEXTUAL_RECORD";
+
+/// Environment variable naming a recording to replay.
+pub const REPLAY_ENV_VAR: &str = "TEXTUAL_REPLAY";
+
+/// How the event loop records or replays a session.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum SessionMode {
+    /// Plain interactive session.
+    #[default]
+    Live,
+    /// Record frames and input to the given asciicast file.
+    Record(PathBuf),
+    /// Replay the input events of the given asciicast file.
+    Replay(PathBuf),
+}
+
+impl SessionMode {
+    /// Read the mode from [`RECORD_ENV_VAR`] and [`REPLAY_ENV_VAR`].
+    ///
+    /// Replaying takes precedence when both are set.
+    pub fn from_env() -> Self {
+        let path = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+        if let Some(path) = path(REPLAY_ENV_VAR) {
+            SessionMode::Replay(path.into())
+        } else if let Some(path) = path(RECORD_ENV_VAR) {
+            SessionMode::Record(path.into())
+        } else {
+            SessionMode::Live
+        }
+    }
+}
+
+// ============================================================================
+// Recording
+// ============================================================================
+
+/// Writes an asciicast v2 stream.
+///
+/// The header is written on construction; timestamps are relative to it.
+pub struct Recorder<W: Write> {
+    out: W,
+    start: Instant,
+}
+
+impl Recorder<BufWriter<File>> {
+    /// Create (or truncate) `path` and write the header for a
+    /// `width`x`height` terminal.
+    pub fn create(path: impl AsRef<Path>, width: u16, height: u16) -> io::Result<Self> {
+        Recorder::new(BufWriter::new(File::create(path)?), width, height)
+    }
+}
+
+impl<W: Write> Recorder<W> {
+    /// Write the header to `out` and start the clock.
+    pub fn new(mut out: W, width: u16, height: u16) -> io::Result<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let term = std::env::var("TERM").unwrap_or_else(|_| "xterm-256color".to_string());
+        writeln!(
+            out,
+            "{{\"version\": 2, \"width\": {width}, \"height\": {height}, \
+             \"timestamp\": {timestamp}, \"env\": {{\"TERM\": {}}}}}",
+            json_string(&term)
+        )?;
+        Ok(Self {
+            out,
+            start: Instant::now(),
+        })
+    }
+
+    /// Record bytes written to the terminal.
+    pub fn output(&mut self, data: &[u8]) -> io::Result<()> {
+        self.record(self.start.elapsed(), "o", &String::from_utf8_lossy(data))?;
+        // Frames are the expensive part; flush so a crash keeps the session.
+        self.out.flush()
+    }
+
+    /// Record an input event.
+    ///
+    /// Resizes become `"r"` events; events with no terminal encoding
+    /// (focus changes, key releases) are skipped.
+    pub fn input(&mut self, event: &Event) -> io::Result<()> {
+        let elapsed = self.start.elapsed();
+        match event {
+            Event::Resize(cols, rows) => self.record(elapsed, "r", &format!("{cols}x{rows}")),
+            event => match encode_event(event) {
+                Some(data) => self.record(elapsed, "i", &data),
+                None => Ok(()),
+            },
+        }
+    }
+
+    fn record(&mut self, elapsed: Duration, code: &str, data: &str) -> io::Result<()> {
+        writeln!(
+            self.out,
+            "[{:.6}, \"{code}\", {}]",
+            elapsed.as_secs_f64(),
+            json_string(data)
+        )
+    }
+
+    /// Flush and return the underlying writer.
+    pub fn into_inner(mut self) -> io::Result<W> {
+        self.out.flush()?;
+        Ok(self.out)
+    }
+}
+
+// ============================================================================
+// Replay
+// ============================================================================
+
+/// The input events of a recording, in order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Replay {
+    /// Terminal size from the header.
+    pub width: u16,
+    pub height: u16,
+    /// Input events with their offset from the start of the recording.
+    pub events: Vec<(Duration, Event)>,
+}
+
+impl Replay {
+    /// Read a recording from disk.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parse an asciicast v2 document.
+    ///
+    /// Output events are ignored; `"i"` events are decoded with
+    /// [`decode_input`] and `"r"` events become [`Event::Resize`].
+    pub fn parse(source: &str) -> Result<Self> {
+        let invalid = |line: usize, reason: &str| {
+            TextualError::InvalidRecording(format!("line {}: {reason}", line + 1))
+        };
+        let mut lines = source
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty());
+
+        let (_, header) = lines.next().ok_or_else(|| invalid(0, "missing header"))?;
+        let header = Json::parse(header).ok_or_else(|| invalid(0, "malformed header"))?;
+        if header.get("version").and_then(Json::as_f64) != Some(2.0) {
+            return Err(invalid(0, "only asciicast version 2 is supported"));
+        }
+        let dimension = |key| {
+            header
+                .get(key)
+                .and_then(Json::as_f64)
+                .map(|v| v as u16)
+                .ok_or_else(|| invalid(0, "missing terminal size"))
+        };
+        let (width, height) = (dimension("width")?, dimension("height")?);
+
+        let mut events = Vec::new();
+        for (number, line) in lines {
+            let entry = Json::parse(line).ok_or_else(|| invalid(number, "malformed event"))?;
+            let (Some(time), Some(code), Some(data)) = (
+                entry.index(0).and_then(Json::as_f64),
+                entry.index(1).and_then(Json::as_str),
+                entry.index(2).and_then(Json::as_str),
+            ) else {
+                return Err(invalid(number, "expected [time, code, data]"));
+            };
+            let at = Duration::from_secs_f64(time.max(0.0));
+            match code {
+                "i" => events.extend(decode_input(data).into_iter().map(|e| (at, e))),
+                "r" => {
+                    let size = data
+                        .split_once('x')
+                        .and_then(|(c, r)| Some((c.parse().ok()?, r.parse().ok()?)));
+                    let (cols, rows) = size.ok_or_else(|| invalid(number, "malformed resize"))?;
+                    events.push((at, Event::Resize(cols, rows)));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Self {
+            width,
+            height,
+            events,
+        })
+    }
+}
+
+/// Terminal input for the event loop, optionally preceded by a replay.
+pub(crate) struct InputSource {
+    live: TerminalControl,
+    replay: VecDeque<(tokio::time::Instant, Event)>,
+}
+
+impl InputSource {
+    /// Events from the terminal `live` controls, which stop while the app
+    /// is suspended.
+    pub(crate) fn live(live: TerminalControl) -> Self {
+        Self {
+            live,
+            replay: VecDeque::new(),
+        }
+    }
+
+    /// Schedule `replay`'s events relative to now.
+    ///
+    /// A resize to the recorded terminal size is sent first so layout
+    /// matches the recording.
+    pub(crate) fn with_replay(mut self, replay: Replay) -> Self {
+        let start = tokio::time::Instant::now();
+        self.replay
+            .push_back((start, Event::Resize(replay.width, replay.height)));
+        self.replay.extend(
+            replay
+                .events
+                .into_iter()
+                .map(|(at, event)| (start + at, event)),
+        );
+        self
+    }
+
+    /// The next replayed or live event, whichever comes first.
+    ///
+    /// Cancel-safe: a replayed event is only dequeued once it is due.
+    pub(crate) async fn next(&mut self) -> Option<io::Result<Event>> {
+        let Some(&(due, _)) = self.replay.front() else {
+            return self.live.next_event().await;
+        };
+        tokio::select! {
+            _ = tokio::time::sleep_until(due) => {
+                self.replay.pop_front().map(|(_, event)| Ok(event))
+            }
+            live = self.live.next_event() => live,
+        }
+    }
+}
+
+// ============================================================================
+// Input encoding
+// ============================================================================
+
+/// xterm modifier parameter (`1 + shift + 2*alt + 4*ctrl`).
+fn modifier_param(modifiers: KeyModifiers) -> u8 {
+    1 + u8::from(modifiers.contains(KeyModifiers::SHIFT))
+        + 2 * u8::from(modifiers.contains(KeyModifiers::ALT))
+        + 4 * u8::from(modifiers.contains(KeyModifiers::CONTROL))
+}
+
+fn modifiers_from_param(param: u8) -> KeyModifiers {
+    let bits = param.saturating_sub(1);
+    let mut modifiers = KeyModifiers::NONE;
+    modifiers.set(KeyModifiers::SHIFT, bits & 1 != 0);
+    modifiers.set(KeyModifiers::ALT, bits & 2 != 0);
+    modifiers.set(KeyModifiers::CONTROL, bits & 4 != 0);
+    modifiers
+}
+
+/// Encode a key or mouse event as the bytes an xterm-compatible terminal
+/// sends for it. Mouse events use SGR (1006) encoding.
+///
+/// Returns `None` for events with no encoding (key releases, media keys,
+/// focus and paste events).
+pub fn encode_event(event: &Event) -> Option<String> {
+    match event {
+        Event::Key(key) => encode_key(key),
+        Event::Mouse(mouse) => encode_mouse(mouse),
+        _ => None,
+    }
+}
+
+fn encode_key(key: &KeyEvent) -> Option<String> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+    let modifiers = key.modifiers;
+    let param = modifier_param(modifiers);
+
+    // Keys with a CSI form carry all modifiers in the parameter.
+    let csi = |final_byte: char| {
+        Some(if param > 1 {
+            format!("\x1b[1;{param}{final_byte}")
+        } else {
+            format!("\x1b[{final_byte}")
+        })
+    };
+    let tilde = |number: u8| {
+        Some(if param > 1 {
+            format!("\x1b[{number};{param}~")
+        } else {
+            format!("\x1b[{number}~")
+        })
+    };
+
+    let plain = match key.code {
+        KeyCode::Up => return csi('A'),
+        KeyCode::Down => return csi('B'),
+        KeyCode::Right => return csi('C'),
+        KeyCode::Left => return csi('D'),
+        KeyCode::Home => return csi('H'),
+        KeyCode::End => return csi('F'),
+        KeyCode::BackTab => return Some("\x1b[Z".to_string()),
+        KeyCode::Insert => return tilde(2),
+        KeyCode::Delete => return tilde(3),
+        KeyCode::PageUp => return tilde(5),
+        KeyCode::PageDown => return tilde(6),
+        KeyCode::F(n @ 1..=4) => {
+            let final_byte = (b'P' + n - 1) as char;
+            return if param > 1 {
+                csi(final_byte)
+            } else {
+                Some(format!("\x1bO{final_byte}"))
+            };
+        }
+        KeyCode::F(n @ 5..=12) => return tilde([15, 17, 18, 19, 20, 21, 23, 24][n as usize - 5]),
+        KeyCode::Enter => "\r".to_string(),
+        KeyCode::Tab => "\t".to_string(),
+        KeyCode::Backspace => "\x7f".to_string(),
+        KeyCode::Esc => "\x1b".to_string(),
+        KeyCode::Char(' ') if modifiers.contains(KeyModifiers::CONTROL) => "\0".to_string(),
+        KeyCode::Char(c)
+            if modifiers.contains(KeyModifiers::CONTROL) && c.is_ascii_alphabetic() =>
+        {
+            ((c.to_ascii_lowercase() as u8 - b'a' + 1) as char).to_string()
+        }
+        KeyCode::Char(c) => c.to_string(),
+        _ => return None,
+    };
+
+    // Everything else is prefixed with ESC when Alt is held.
+    Some(if modifiers.contains(KeyModifiers::ALT) {
+        format!("\x1b{plain}")
+    } else {
+        plain
+    })
+}
+
+fn encode_mouse(mouse: &MouseEvent) -> Option<String> {
+    let button = |b: MouseButton| match b {
+        MouseButton::Left => 0,
+        MouseButton::Middle => 1,
+        MouseButton::Right => 2,
+    };
+    let (code, release) = match mouse.kind {
+        MouseEventKind::Down(b) => (button(b), false),
+        MouseEventKind::Up(b) => (button(b), true),
+        MouseEventKind::Drag(b) => (button(b) + 32, false),
+        MouseEventKind::Moved => (35, false),
+        MouseEventKind::ScrollUp => (64, false),
+        MouseEventKind::ScrollDown => (65, false),
+        MouseEventKind::ScrollLeft => (66, false),
+        MouseEventKind::ScrollRight => (67, false),
+    };
+    let code = code
+        + 4 * u8::from(mouse.modifiers.contains(KeyModifiers::SHIFT))
+        + 8 * u8::from(mouse.modifiers.contains(KeyModifiers::ALT))
+        + 16 * u8::from(mouse.modifiers.contains(KeyModifiers::CONTROL));
+    Some(format!(
+        "\x1b[<{code};{};{}{}",
+        mouse.column + 1,
+        mouse.row + 1,
+        if release { 'm' } else { 'M' }
+    ))
+}
+
+/// Decode terminal input bytes into key and mouse events.
+///
+/// Understands everything [`encode_event`] produces. Unrecognized escape
+/// sequences are dropped; a lone ESC is the Escape key and ESC followed by
+/// another key is that key with Alt.
+pub fn decode_input(data: &str) -> Vec<Event> {
+    let chars: Vec<char> = data.chars().collect();
+    let mut events = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let (event, consumed) = decode_one(&chars[i..]);
+        events.extend(event);
+        i += consumed.max(1);
+    }
+    events
+}
+
+fn key(code: KeyCode, modifiers: KeyModifiers) -> Option<Event> {
+    Some(Event::Key(KeyEvent::new(code, modifiers)))
+}
+
+fn decode_one(chars: &[char]) -> (Option<Event>, usize) {
+    match chars {
+        ['\x1b', '[', rest @ ..] => {
+            let (event, consumed) = decode_csi(rest);
+            (event, consumed + 2)
+        }
+        ['\x1b', 'O', c @ 'P'..='S', ..] => {
+            (key(KeyCode::F(*c as u8 - b'P' + 1), KeyModifiers::NONE), 3)
+        }
+        ['\x1b', 'O', c @ ('A' | 'B' | 'C' | 'D' | 'H' | 'F'), ..] => (decode_csi(&[*c]).0, 3),
+        ['\x1b'] => (key(KeyCode::Esc, KeyModifiers::NONE), 1),
+        ['\x1b', rest @ ..] => {
+            let (event, consumed) = decode_one(rest);
+            let event = event.map(|event| match event {
+                Event::Key(mut k) => {
+                    k.modifiers |= KeyModifiers::ALT;
+                    Event::Key(k)
+                }
+                other => other,
+            });
+            (event, consumed + 1)
+        }
+        ['\r' | '\n', ..] => (key(KeyCode::Enter, KeyModifiers::NONE), 1),
+        ['\t', ..] => (key(KeyCode::Tab, KeyModifiers::NONE), 1),
+        ['\x7f' | '\x08', ..] => (key(KeyCode::Backspace, KeyModifiers::NONE), 1),
+        ['\0', ..] => (key(KeyCode::Char(' '), KeyModifiers::CONTROL), 1),
+        [c @ '\x01'..='\x1a', ..] => (
+            key(
+                KeyCode::Char((*c as u8 - 1 + b'a') as char),
+                KeyModifiers::CONTROL,
+            ),
+            1,
+        ),
+        [c, ..] if c.is_uppercase() => (key(KeyCode::Char(*c), KeyModifiers::SHIFT), 1),
+        [c, ..] => (key(KeyCode::Char(*c), KeyModifiers::NONE), 1),
+        [] => (None, 0),
+    }
+}
+
+/// Decode the part of a CSI sequence after `ESC [`.
+fn decode_csi(chars: &[char]) -> (Option<Event>, usize) {
+    let sgr = chars.first() == Some(&'<');
+    let start = usize::from(sgr);
+    let Some(end) = chars[start..]
+        .iter()
+        .position(|c| ('@'..='~').contains(c))
+        .map(|p| p + start)
+    else {
+        return (None, chars.len());
+    };
+    let params: Vec<u16> = chars[start..end]
+        .iter()
+        .collect::<String>()
+        .split(';')
+        .map(|p| p.parse().unwrap_or(0))
+        .collect();
+    let final_byte = chars[end];
+    let consumed = end + 1;
+
+    if sgr {
+        return (decode_sgr_mouse(&params, final_byte == 'm'), consumed);
+    }
+
+    let param = |i: usize| params.get(i).copied().unwrap_or(0);
+    let modifiers = modifiers_from_param(param(1) as u8);
+    let code = match final_byte {
+        'A' => KeyCode::Up,
+        'B' => KeyCode::Down,
+        'C' => KeyCode::Right,
+        'D' => KeyCode::Left,
+        'H' => KeyCode::Home,
+        'F' => KeyCode::End,
+        'Z' => return (key(KeyCode::BackTab, KeyModifiers::SHIFT), consumed),
+        'P'..='S' => KeyCode::F(final_byte as u8 - b'P' + 1),
+        '~' => match param(0) {
+            1 | 7 => KeyCode::Home,
+            2 => KeyCode::Insert,
+            3 => KeyCode::Delete,
+            4 | 8 => KeyCode::End,
+            5 => KeyCode::PageUp,
+            6 => KeyCode::PageDown,
+            n @ 15..=24 => match [15, 17, 18, 19, 20, 21, 23, 24]
+                .iter()
+                .position(|&f| f == n)
+            {
+                Some(index) => KeyCode::F(index as u8 + 5),
+                None => return (None, consumed),
+            },
+            _ => return (None, consumed),
+        },
+        _ => return (None, consumed),
+    };
+    (key(code, modifiers), consumed)
+}
+
+fn decode_sgr_mouse(params: &[u16], release: bool) -> Option<Event> {
+    let [code, column, row] = params else {
+        return None;
+    };
+    let button = match code & 0b11 {
+        0 => MouseButton::Left,
+        1 => MouseButton::Middle,
+        _ => MouseButton::Right,
+    };
+    let kind = match (code & !0b11100, release) {
+        (0..=2, true) => MouseEventKind::Up(button),
+        (0..=2, false) => MouseEventKind::Down(button),
+        (35, _) => MouseEventKind::Moved,
+        (32..=34, _) => MouseEventKind::Drag(button),
+        (64, _) => MouseEventKind::ScrollUp,
+        (65, _) => MouseEventKind::ScrollDown,
+        (66, _) => MouseEventKind::ScrollLeft,
+        (67, _) => MouseEventKind::ScrollRight,
+        _ => return None,
+    };
+    let mut modifiers = KeyModifiers::NONE;
+    modifiers.set(KeyModifiers::SHIFT, code & 4 != 0);
+    modifiers.set(KeyModifiers::ALT, code & 8 != 0);
+    modifiers.set(KeyModifiers::CONTROL, code & 16 != 0);
+    Some(Event::Mouse(MouseEvent {
+        kind,
+        column: column.saturating_sub(1),
+        row: row.saturating_sub(1),
+        modifiers,
+    }))
+}
+
+// ============================================================================
+// Minimal JSON
+// ============================================================================
+
+fn json_string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 || c == '\x7f' => {
+                out.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Just enough JSON to read asciicast headers and event lines.
+#[derive(Debug, Clone, PartialEq)]
+enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    fn parse(source: &str) -> Option<Json> {
+        let mut parser = JsonParser {
+            chars: source.chars().collect(),
+            pos: 0,
+        };
+        let value = parser.value()?;
+        parser.skip_whitespace();
+        (parser.pos == parser.chars.len()).then_some(value)
+    }
+
+    fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    fn index(&self, index: usize) -> Option<&Json> {
+        match self {
+            Json::Array(items) => items.get(index),
+            _ => None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match self {
+            Json::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+struct JsonParser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl JsonParser {
+    fn skip_whitespace(&mut self) {
+        while self.chars.get(self.pos).is_some_and(|c| c.is_whitespace()) {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, expected: char) -> Option<()> {
+        self.skip_whitespace();
+        (self.chars.get(self.pos) == Some(&expected)).then(|| self.pos += 1)
+    }
+
+    fn literal(&mut self, word: &str, value: Json) -> Option<Json> {
+        let end = self.pos + word.len();
+        (self
+            .chars
+            .get(self.pos..end)?
+            .iter()
+            .copied()
+            .eq(word.chars()))
+        .then(|| {
+            self.pos = end;
+            value
+        })
+    }
+
+    fn value(&mut self) -> Option<Json> {
+        self.skip_whitespace();
+        match self.chars.get(self.pos)? {
+            'n' => self.literal("null", Json::Null),
+            't' => self.literal("true", Json::Bool(true)),
+            'f' => self.literal("false", Json::Bool(false)),
+            '"' => self.string().map(Json::String),
+            '[' => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if self.eat(']').is_some() {
+                    return Some(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    if self.eat(']').is_some() {
+                        return Some(Json::Array(items));
+                    }
+                    self.eat(',')?;
+                }
+            }
+            '{' => {
+                self.pos += 1;
+                let mut entries = Vec::new();
+                if self.eat('}').is_some() {
+                    return Some(Json::Object(entries));
+                }
+                loop {
+                    self.skip_whitespace();
+                    let key = self.string()?;
+                    self.eat(':')?;
+                    entries.push((key, self.value()?));
+                    if self.eat('}').is_some() {
+                        return Some(Json::Object(entries));
+                    }
+                    self.eat(',')?;
+                }
+            }
+            _ => {
+                let start = self.pos;
+                while self
+                    .chars
+                    .get(self.pos)
+                    .is_some_and(|c| c.is_ascii_digit() || matches!(c, '-' | '+' | '.' | 'e' | 'E'))
+                {
+                    self.pos += 1;
+                }
+                let number: String = self.chars[start..self.pos].iter().collect();
+                number.parse().ok().map(Json::Number)
+            }
+        }
+    }
+
+    fn string(&mut self) -> Option<String> {
+        if self.chars.get(self.pos) != Some(&'"') {
+            return None;
+        }
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = *self.chars.get(self.pos)?;
+            self.pos += 1;
+            match c {
+                '"' => return Some(out),
+                '\\' => {
+                    let escape = *self.chars.get(self.pos)?;
+                    self.pos += 1;
+                    match escape {
+                        'n' => out.push('\n'),
+                        'r' => out.push('\r'),
+                        't' => out.push('\t'),
+                        'b' => out.push('\x08'),
+                        'f' => out.push('\x0c'),
+                        'u' => {
+                            let mut code = self.hex4()?;
+                            // Surrogate pair
+                            if (0xd800..0xdc00).contains(&code)
+                                && self.chars.get(self.pos..self.pos + 2) == Some(&['\\', 'u'])
+                            {
+                                self.pos += 2;
+                                let low = self.hex4()?;
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            out.push(char::from_u32(code).unwrap_or('\u{fffd}'));
+                        }
+                        other => out.push(other),
+                    }
+                }
+                c => out.push(c),
+            }
+        }
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits: String = self.chars.get(self.pos..self.pos + 4)?.iter().collect();
+        self.pos += 4;
+        u32::from_str_radix(&digits, 16).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key_event(code: KeyCode, modifiers: KeyModifiers) -> Event {
+        Event::Key(KeyEvent::new(code, modifiers))
+    }
+
+    #[test]
+    fn keys_round_trip_through_escape_sequences() {
+        let events = [
+            key_event(KeyCode::Char('a'), KeyModifiers::NONE),
+            key_event(KeyCode::Char('Q'), KeyModifiers::SHIFT),
+            key_event(KeyCode::Char('c'), KeyModifiers::CONTROL),
+            key_event(KeyCode::Char('x'), KeyModifiers::ALT),
+            key_event(KeyCode::Char('é'), KeyModifiers::NONE),
+            key_event(KeyCode::Enter, KeyModifiers::NONE),
+            key_event(KeyCode::Tab, KeyModifiers::NONE),
+            key_event(KeyCode::BackTab, KeyModifiers::SHIFT),
+            key_event(KeyCode::Esc, KeyModifiers::NONE),
+            key_event(KeyCode::Backspace, KeyModifiers::NONE),
+            key_event(KeyCode::Up, KeyModifiers::NONE),
+            key_event(KeyCode::Left, KeyModifiers::CONTROL | KeyModifiers::SHIFT),
+            key_event(KeyCode::PageDown, KeyModifiers::NONE),
+            key_event(KeyCode::Delete, KeyModifiers::ALT),
+            key_event(KeyCode::F(1), KeyModifiers::NONE),
+            key_event(KeyCode::F(3), KeyModifiers::CONTROL),
+            key_event(KeyCode::F(12), KeyModifiers::NONE),
+        ];
+        for event in events {
+            let encoded = encode_event(&event).expect("encodable");
+            assert_eq!(decode_input(&encoded), vec![event], "{encoded:?}");
+        }
+    }
+
+    #[test]
+    fn mouse_events_round_trip_through_sgr() {
+        let mouse = |kind, modifiers| {
+            Event::Mouse(MouseEvent {
+                kind,
+                column: 12,
+                row: 3,
+                modifiers,
+            })
+        };
+        let events = [
+            mouse(MouseEventKind::Down(MouseButton::Left), KeyModifiers::NONE),
+            mouse(MouseEventKind::Up(MouseButton::Right), KeyModifiers::NONE),
+            mouse(MouseEventKind::Drag(MouseButton::Left), KeyModifiers::SHIFT),
+            mouse(MouseEventKind::Moved, KeyModifiers::NONE),
+            mouse(MouseEventKind::ScrollDown, KeyModifiers::CONTROL),
+        ];
+        for event in events.clone() {
+            let encoded = encode_event(&event).expect("encodable");
+            assert_eq!(decode_input(&encoded), vec![event], "{encoded:?}");
+        }
+        assert_eq!(encode_event(&events[0]).as_deref(), Some("\x1b[<0;13;4M"));
+    }
+
+    #[test]
+    fn decodes_typed_text_as_separate_keys() {
+        let events = decode_input("hi\r");
+        assert_eq!(
+            events,
+            vec![
+                key_event(KeyCode::Char('h'), KeyModifiers::NONE),
+                key_event(KeyCode::Char('i'), KeyModifiers::NONE),
+                key_event(KeyCode::Enter, KeyModifiers::NONE),
+            ]
+        );
+    }
+
+    #[test]
+    fn recording_replays_input_and_resizes() {
+        let mut recorder = Recorder::new(Vec::new(), 80, 24).unwrap();
+        recorder.output(b"\x1b[1;1Hhello \"world\"").unwrap();
+        recorder
+            .input(&key_event(KeyCode::Char('q'), KeyModifiers::NONE))
+            .unwrap();
+        recorder.input(&Event::Resize(100, 30)).unwrap();
+        recorder.input(&Event::FocusGained).unwrap();
+        let cast = String::from_utf8(recorder.into_inner().unwrap()).unwrap();
+
+        let lines: Vec<&str> = cast.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("{\"version\": 2, \"width\": 80, \"height\": 24"));
+        assert!(lines[1].ends_with(", \"o\", \"\\u001b[1;1Hhello \\\"world\\\"\"]"));
+
+        let replay = Replay::parse(&cast).unwrap();
+        assert_eq!((replay.width, replay.height), (80, 24));
+        let events: Vec<Event> = replay.events.into_iter().map(|(_, e)| e).collect();
+        assert_eq!(
+            events,
+            vec![
+                key_event(KeyCode::Char('q'), KeyModifiers::NONE),
+                Event::Resize(100, 30),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_timestamps_from_external_recordings() {
+        let cast = "{\"version\": 2, \"width\": 40, \"height\": 10}\n\
+                    [0.5, \"o\", \"ignored\"]\n\
+                    [1.25, \"i\", \"\\u001b[A\"]\n";
+        let replay = Replay::parse(cast).unwrap();
+        assert_eq!(
+            replay.events,
+            vec![(
+                Duration::from_millis(1250),
+                key_event(KeyCode::Up, KeyModifiers::NONE)
+            )]
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_versions() {
+        let err = Replay::parse("{\"version\": 1, \"width\": 80, \"height\": 24}").unwrap_err();
+        assert!(err.to_string().contains("version 2"));
+    }
+
+    #[test]
+    fn session_mode_defaults_to_live() {
+        assert_eq!(SessionMode::default(), SessionMode::Live);
+    }
+}
@@ -1,5 +1,7 @@
 //! Scroll-related messages and types.
 
+use crate::canvas::Region;
+
 /// Messages emitted by scrollbars for container handling.
 #[derive(Debug, Clone)]
 pub enum ScrollMessage {
@@ -124,6 +126,50 @@ impl ScrollState {
         }
     }
 
+    /// The offsets that bring `target` into view when the content is shown
+    /// in `viewport`, both in screen coordinates at the current offset.
+    ///
+    /// An axis where the target is already visible keeps its offset
+    /// (`None`), unless `center` asks for the target to be centered. Pass
+    /// the result to [`scroll_to`](Self::scroll_to).
+    pub fn offset_to_show(
+        &self,
+        viewport: Region,
+        target: Region,
+        center: bool,
+    ) -> (Option<f32>, Option<f32>) {
+        let axis = |offset: i32, view_start: i32, view_size: i32, start: i32, size: i32| {
+            // Target position within the virtual content
+            let start = start - view_start + offset;
+            let end = start + size;
+            if center {
+                Some((start + size / 2 - view_size / 2) as f32)
+            } else if start < offset || size > view_size {
+                Some(start as f32)
+            } else if end > offset + view_size {
+                Some((end - view_size) as f32)
+            } else {
+                None
+            }
+        };
+        (
+            axis(
+                self.offset_x,
+                viewport.x,
+                viewport.width,
+                target.x,
+                target.width,
+            ),
+            axis(
+                self.offset_y,
+                viewport.y,
+                viewport.height,
+                target.y,
+                target.height,
+            ),
+        )
+    }
+
     /// Update viewport dimensions.
     pub fn set_viewport(&mut self, width: i32, height: i32) {
         self.viewport_width = width;
@@ -158,6 +204,38 @@ mod tests {
         assert_eq!(state.offset_y, 0);
     }
 
+    #[test]
+    fn test_offset_to_show() {
+        let mut state = ScrollState::new(10, 100, 10, 20);
+        state.offset_y = 30;
+        let viewport = Region::new(0, 5, 10, 20);
+        // Rows 30..50 of the content are on screen at y 5..25
+        let at = |y, height| Region::new(0, y, 10, height);
+
+        assert_eq!(
+            state.offset_to_show(viewport, at(10, 3), false),
+            (None, None)
+        );
+        assert_eq!(
+            state.offset_to_show(viewport, at(2, 2), false),
+            (None, Some(27.0))
+        );
+        assert_eq!(
+            state.offset_to_show(viewport, at(24, 3), false),
+            (None, Some(32.0))
+        );
+        // Taller than the viewport: show its top
+        assert_eq!(
+            state.offset_to_show(viewport, at(40, 30), false),
+            (None, Some(65.0))
+        );
+        // Centering moves even a visible target
+        assert_eq!(
+            state.offset_to_show(viewport, at(10, 2), true),
+            (Some(0.0), Some(26.0))
+        );
+    }
+
     #[test]
     fn test_max_scroll() {
         let state = ScrollState::new(200, 300, 100, 150);
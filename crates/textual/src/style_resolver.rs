@@ -1,8 +1,15 @@
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
 
+use crate::canvas::Size;
+use crate::damage::{DamageCollector, style_invalidation};
+use crate::error::{Result, TextualError};
 use crate::widget::Widget;
-use tcss::parser::StyleSheet;
-use tcss::parser::cascade::{WidgetMeta, compute_style};
+use tcss::parser::cascade::{WidgetMeta, WidgetStates, matches_selectors};
+use tcss::parser::{
+    CascadeTier, ComplexSelector, MediaEnvironment, SelectorList, StyleSheet, load_stylesheet,
+    parse_stylesheet,
+};
 use tcss::types::{ComputedStyle, RgbaColor, Theme};
 
 /// Context inherited from parent for CSS property inheritance.
@@ -34,6 +41,135 @@ impl InheritedContext {
     }
 }
 
+/// Builds the stylesheet for a widget tree from widget defaults and app CSS.
+///
+/// The base widget CSS and each widget type's `default_css()` (in tree
+/// order, without duplicates) form the default tier, which app CSS always
+/// overrides. Widgets that set [`Widget::scoped_css`] get their defaults
+/// scoped to their own subtree.
+pub fn build_stylesheet<M: 'static>(root: &mut dyn Widget<M>, app_css: &str) -> Result<StyleSheet> {
+    build_app_stylesheet(root, &[], app_css)
+}
+
+/// Like [`build_stylesheet`], with the app's stylesheet files (and their
+/// `@import`s) loaded between the widget defaults and `app_css`.
+///
+/// Rule origins name the file, widget (`Button.DEFAULT_CSS`) or `App.CSS`
+/// each rule was written in.
+pub fn build_app_stylesheet<M: 'static>(
+    root: &mut dyn Widget<M>,
+    css_paths: &[&str],
+    app_css: &str,
+) -> Result<StyleSheet> {
+    let mut stylesheet = parse_default_css((
+        crate::widget::screen::Screen::<M>::base_widget_css(),
+        None,
+        "Screen",
+    ))?;
+    let mut defaults = Vec::new();
+    collect_default_css(root, &mut defaults);
+    for default_css in defaults {
+        stylesheet.merge(parse_default_css(default_css)?);
+    }
+    for path in css_paths {
+        stylesheet.merge(load_stylesheet(path).map_err(css_error)?);
+    }
+    stylesheet.merge(
+        parse_stylesheet(app_css)
+            .map_err(css_error)?
+            .with_source("App.CSS"),
+    );
+    Ok(stylesheet)
+}
+
+/// Adds widget defaults collected from newly mounted widgets to
+/// `stylesheet`, skipping any it already has.
+///
+/// Returns whether any rules were added.
+pub(crate) fn merge_default_css(
+    stylesheet: &mut StyleSheet,
+    defaults: &[DefaultCss],
+) -> Result<bool> {
+    let mut merged = false;
+    for &(css, scope, type_name) in defaults {
+        let source = default_css_source(type_name);
+        let present = stylesheet.rules().iter().any(|rule| {
+            rule.tier == CascadeTier::Default
+                && rule.scope.as_deref() == scope
+                && rule
+                    .origin
+                    .as_ref()
+                    .is_some_and(|origin| origin.source.as_deref() == Some(&*source))
+        });
+        if !present {
+            let parsed = parse_default_css((css, scope, type_name))?;
+            merged |= !parsed.rules().is_empty();
+            stylesheet.merge(parsed);
+        }
+    }
+    Ok(merged)
+}
+
+fn css_error(error: tcss::TcssError) -> TextualError {
+    TextualError::InvalidCss(error.to_string())
+}
+
+/// The origin rules from a widget type's `default_css` are labeled with.
+fn default_css_source(type_name: &str) -> String {
+    format!("{type_name}.DEFAULT_CSS")
+}
+
+/// Parses a widget type's `default_css` into the default cascade tier.
+fn parse_default_css((css, scope, type_name): DefaultCss) -> Result<StyleSheet> {
+    Ok(parse_stylesheet(css)
+        .map_err(css_error)?
+        .with_source(&default_css_source(type_name))
+        .into_default_tier(scope))
+}
+
+/// The environment `@media` queries see for a terminal of `size`, with its
+/// color depth taken from `COLORTERM` and `TERM`.
+pub fn media_environment(size: Size) -> MediaEnvironment {
+    let var = |name| std::env::var(name).ok();
+    let colors = terminal_colors(var("COLORTERM").as_deref(), var("TERM").as_deref());
+    MediaEnvironment::new(size.width, size.height).with_colors(colors)
+}
+
+/// Number of colors a terminal supports, judging by `COLORTERM` and `TERM`.
+fn terminal_colors(colorterm: Option<&str>, term: Option<&str>) -> u32 {
+    let term = term.unwrap_or_default();
+    if matches!(colorterm, Some("truecolor" | "24bit")) || term.ends_with("-direct") {
+        MediaEnvironment::TRUECOLOR
+    } else if term.contains("256color") {
+        256
+    } else {
+        16
+    }
+}
+
+/// A widget type's `default_css`, the type it's scoped to (if any) and the
+/// type's name.
+pub(crate) type DefaultCss = (&'static str, Option<&'static str>, &'static str);
+
+/// Collects the [`DefaultCss`] of a widget and its descendants, in tree
+/// order, skipping repeated CSS and scope.
+pub(crate) fn collect_default_css<M>(widget: &mut dyn Widget<M>, collected: &mut Vec<DefaultCss>) {
+    let default_css = widget.default_css();
+    if !default_css.is_empty() {
+        let type_name = widget.get_meta().type_name;
+        let scope = widget.scoped_css().then_some(type_name);
+        if !collected
+            .iter()
+            .any(|(css, s, _)| (*css, *s) == (default_css, scope))
+        {
+            collected.push((default_css, scope, type_name));
+        }
+    }
+    widget.for_each_child(&mut |child| {
+        collect_default_css(child, collected);
+    });
+}
+
 /// Resolves styles for all widgets in the tree.
 /// Used for initial style resolution at startup.
 pub fn resolve_styles<M>(
@@ -58,6 +194,10 @@ pub fn resolve_styles<M>(
 /// A widget is restyled if:
 /// - It is marked dirty (state changed like focus/hover/active)
 /// - A parent was dirty (selectors like `Parent:focus > Child` may apply)
+/// - An earlier sibling was dirty and the stylesheet uses sibling
+///   combinators (`Label:hover + Input` may apply)
+/// - It gained or lost `:focus-within` (tracked by
+///   [`WidgetTree::resolve_dirty_styles`](crate::tree::WidgetTree::resolve_dirty_styles))
 ///
 /// Invisible widgets are skipped entirely (optimization).
 ///
@@ -72,29 +212,388 @@ pub fn resolve_dirty_styles<M>(
     ancestors: &mut VecDeque<WidgetMeta>,
     parent_dirty: bool,
     inherited: &InheritedContext,
+) {
+    let focus = focus_path_for(widget, stylesheet);
+    resolve_dirty(
+        widget,
+        &Cascade::new(stylesheet, theme),
+        ancestors,
+        Position::root(focus.as_deref(), focus.as_deref()),
+        parent_dirty,
+        inherited,
+        None,
+    );
+}
+
+/// Like [`resolve_dirty_styles`], but also records which screen regions
+/// need re-rendering.
+///
+/// Each dirty widget's [`Widget::invalidation`] is collected before it is
+/// marked clean, and restyled widgets whose computed style actually changed
+/// are damaged according to [`style_invalidation`].
+///
+/// `focus_path` holds the path to the focused widget as of the previous call
+/// and is updated; widgets whose `:focus-within` state changed since then
+/// are restyled.
+pub(crate) fn resolve_dirty_styles_with_damage<M>(
+    widget: &mut dyn Widget<M>,
+    stylesheet: &StyleSheet,
+    theme: &Theme,
+    ancestors: &mut VecDeque<WidgetMeta>,
+    inherited: &InheritedContext,
+    focus_path: &mut Option<Vec<usize>>,
+    damage: &mut DamageCollector<'_>,
+) {
+    let focus = focus_path_for(widget, stylesheet);
+    let previous = std::mem::replace(focus_path, focus.clone());
+    resolve_dirty(
+        widget,
+        &Cascade::new(stylesheet, theme),
+        ancestors,
+        Position::root(focus.as_deref(), previous.as_deref()),
+        false,
+        inherited,
+        Some(damage),
+    );
+}
+
+/// Marks dirty every widget matched by one of `rules` (indices into
+/// [`StyleSheet::rules`]), returning whether any was.
+///
+/// Only the topmost matching widget of a subtree is marked, since
+/// restyling a widget restyles its descendants. Invisible widgets aren't
+/// matched; they are marked dirty so they restyle once shown.
+pub(crate) fn mark_matched_dirty<M>(
+    widget: &mut dyn Widget<M>,
+    stylesheet: &StyleSheet,
+    theme: &Theme,
+    rules: &HashSet<usize>,
+) -> bool {
+    let focus = focus_path_for(widget, stylesheet);
+    mark_matched(
+        widget,
+        &Cascade::new(stylesheet, theme),
+        rules,
+        &mut VecDeque::new(),
+        Position::root(focus.as_deref(), focus.as_deref()),
+    )
+}
+
+fn mark_matched<M>(
+    widget: &mut dyn Widget<M>,
+    cascade: &Cascade<'_>,
+    rules: &HashSet<usize>,
+    ancestors: &mut VecDeque<WidgetMeta>,
+    position: Position,
+) -> bool {
+    if !widget.is_visible() {
+        widget.mark_dirty();
+        return true;
+    }
+
+    let meta = position.meta(&*widget);
+    let matched = tcss::parser::cascade::matching_rules(
+        &meta,
+        ancestors.make_contiguous(),
+        cascade.stylesheet,
+        cascade.theme,
+    )
+    .iter()
+    .any(|rule| rules.contains(&rule.source_order));
+    if matched {
+        widget.mark_dirty();
+        return true;
+    }
+
+    ancestors.push_front(meta);
+    let siblings = if cascade.uses_siblings {
+        position.child_metas(widget)
+    } else {
+        Arc::default()
+    };
+    let mut marked = false;
+    let mut index = 0;
+    widget.for_each_child(&mut |child| {
+        marked |= mark_matched(
+            child,
+            cascade,
+            rules,
+            ancestors,
+            position.child(&siblings, index),
+        );
+        index += 1;
+    });
+    ancestors.pop_front();
+    marked
+}
+
+/// The path of child indices from `root` down to the first widget, in tree
+/// order, that matches any of `selectors`.
+pub(crate) fn first_match_path<M>(
+    root: &mut dyn Widget<M>,
+    selectors: &SelectorList,
+    theme: &Theme,
+) -> Option<Vec<usize>> {
+    let uses_siblings = selectors
+        .selectors
+        .iter()
+        .any(ComplexSelector::uses_siblings);
+    let focus = focus_path(root);
+    let mut path = Vec::new();
+    find_match(
+        root,
+        selectors,
+        theme,
+        uses_siblings,
+        &mut VecDeque::new(),
+        Position::root(focus.as_deref(), focus.as_deref()),
+        &mut path,
+    )
+    .then_some(path)
+}
+
+fn find_match<M>(
+    widget: &mut dyn Widget<M>,
+    selectors: &SelectorList,
+    theme: &Theme,
+    uses_siblings: bool,
+    ancestors: &mut VecDeque<WidgetMeta>,
+    position: Position,
+    path: &mut Vec<usize>,
+) -> bool {
+    let mut meta = position.meta(&*widget);
+    with_type_name(&mut meta, widget.type_name());
+    if matches_selectors(&meta, ancestors.make_contiguous(), selectors, theme) {
+        return true;
+    }
+
+    ancestors.push_front(meta);
+    // Walk children by index, as tree paths do
+    let child_count = widget.child_count();
+    let siblings: Arc<[WidgetMeta]> = if uses_siblings {
+        let focused_child = position.focus.and_then(|path| path.first().copied());
+        (0..child_count)
+            .map_while(|index| {
+                let child = widget.get_child_mut(index)?;
+                let mut meta = child.get_meta();
+                with_type_name(&mut meta, child.type_name());
+                meta.states |= tree_states(&*child, focused_child == Some(index));
+                meta.attributes = child.attributes();
+                Some(meta)
+            })
+            .collect()
+    } else {
+        Arc::default()
+    };
+    let mut found = false;
+    for index in 0..child_count {
+        let Some(child) = widget.get_child_mut(index) else {
+            break;
+        };
+        path.push(index);
+        found = find_match(
+            child,
+            selectors,
+            theme,
+            uses_siblings,
+            ancestors,
+            position.child(&siblings, index),
+            path,
+        );
+        if found {
+            break;
+        }
+        path.pop();
+    }
+    ancestors.pop_front();
+    found
+}
+
+/// Makes `meta` match by the [`type_name`](Widget::type_name) queries use,
+/// which may differ from the Rust type name stylesheets match.
+fn with_type_name(meta: &mut WidgetMeta, type_name: &'static str) {
+    if meta.type_name != type_name {
+        meta.type_name = type_name;
+        meta.type_names.insert(0, type_name);
+    }
+}
+
+/// The path of child indices from `widget` down to the focused widget.
+///
+/// Returns an empty path if `widget` itself is focused, and `None` if no
+/// widget in the subtree is.
+pub(crate) fn focus_path<M>(widget: &mut dyn Widget<M>) -> Option<Vec<usize>> {
+    if widget.get_state().contains(WidgetStates::FOCUS) {
+        return Some(Vec::new());
+    }
+    let mut path = None;
+    let mut index = 0;
+    widget.for_each_child(&mut |child| {
+        if path.is_none()
+            && let Some(mut rest) = focus_path(child)
+        {
+            rest.insert(0, index);
+            path = Some(rest);
+        }
+        index += 1;
+    });
+    path
+}
+
+/// [`focus_path`], skipping the walk when nothing uses `:focus-within`.
+fn focus_path_for<M>(widget: &mut dyn Widget<M>, stylesheet: &StyleSheet) -> Option<Vec<usize>> {
+    if stylesheet.uses_pseudo_class("focus-within") {
+        focus_path(widget)
+    } else {
+        None
+    }
+}
+
+/// Stylesheet and theme for one resolution pass.
+struct Cascade<'a> {
+    stylesheet: &'a StyleSheet,
+    theme: &'a Theme,
+    /// Whether selectors look at siblings, so sibling metadata is worth
+    /// collecting.
+    uses_siblings: bool,
+}
+
+impl<'a> Cascade<'a> {
+    fn new(stylesheet: &'a StyleSheet, theme: &'a Theme) -> Self {
+        Self {
+            stylesheet,
+            theme,
+            uses_siblings: stylesheet.needs_sibling_context(),
+        }
+    }
+}
+
+/// Where a widget sits in the tree, as far as selector matching cares.
+pub(crate) struct Position<'p> {
+    /// All children of the parent (empty when not collected).
+    siblings: Arc<[WidgetMeta]>,
+    index: usize,
+    /// Path from this widget to the focused widget, if it contains focus.
+    focus: Option<&'p [usize]>,
+    /// The same, as of the previous resolution.
+    previous_focus: Option<&'p [usize]>,
+}
+
+impl<'p> Position<'p> {
+    /// The position of the root, given the paths from it to the focused
+    /// widget now and at the previous resolution.
+    pub(crate) fn root(focus: Option<&'p [usize]>, previous_focus: Option<&'p [usize]>) -> Self {
+        Self {
+            siblings: Arc::default(),
+            index: 0,
+            focus,
+            previous_focus,
+        }
+    }
+
+    /// `widget`'s metadata, completed with what only the resolver knows:
+    /// siblings, `:focus-within`, `:empty`, plus the widget's attributes.
+    pub(crate) fn meta<M>(&self, widget: &dyn Widget<M>) -> WidgetMeta {
+        let mut meta = widget.get_meta();
+        meta.siblings = self.siblings.clone();
+        meta.sibling_index = self.index;
+        meta.states |= tree_states(widget, self.focus.is_some());
+        meta.attributes = widget.attributes();
+        meta
+    }
+
+    /// Metadata for each of `widget`'s children, in order, shared by the
+    /// children as [`WidgetMeta::siblings`].
+    pub(crate) fn child_metas<M>(&self, widget: &mut dyn Widget<M>) -> Arc<[WidgetMeta]> {
+        let focused_child = self.focus.and_then(|path| path.first().copied());
+        let mut metas = Vec::new();
+        widget.for_each_child(&mut |child| {
+            let mut meta = child.get_meta();
+            meta.states |= tree_states(&*child, focused_child == Some(metas.len()));
+            meta.attributes = child.attributes();
+            metas.push(meta);
+        });
+        metas.into()
+    }
+
+    /// The position of the child at `index`.
+    pub(crate) fn child(&self, siblings: &Arc<[WidgetMeta]>, index: usize) -> Position<'p> {
+        let descend = |path: Option<&'p [usize]>| match path {
+            Some([first, rest @ ..]) if *first == index => Some(rest),
+            _ => None,
+        };
+        Position {
+            siblings: siblings.clone(),
+            index,
+            focus: descend(self.focus),
+            previous_focus: descend(self.previous_focus),
+        }
+    }
+
+    fn focus_within_changed(&self) -> bool {
+        self.focus.is_some() != self.previous_focus.is_some()
+    }
+}
+
+/// States that depend on the widget's subtree rather than the widget.
+fn tree_states<M>(widget: &dyn Widget<M>, focus_within: bool) -> WidgetStates {
+    let mut states = WidgetStates::empty();
+    states.set(WidgetStates::FOCUS_WITHIN, focus_within);
+    states.set(WidgetStates::EMPTY, widget.child_count() == 0);
+    states
+}
+
+fn resolve_dirty<M>(
+    widget: &mut dyn Widget<M>,
+    cascade: &Cascade<'_>,
+    ancestors: &mut VecDeque<WidgetMeta>,
+    position: Position,
+    parent_dirty: bool,
+    inherited: &InheritedContext,
+    mut damage: Option<&mut DamageCollector<'_>>,
 ) {
     // Skip invisible widgets and their subtrees
     if !widget.is_visible() {
+        if let Some(damage) = damage
+            && widget.is_dirty()
+        {
+            damage.hidden(widget);
+        }
         return;
     }
 
     let is_dirty = widget.is_dirty();
-    let should_restyle = is_dirty || parent_dirty;
+    let should_restyle = is_dirty || parent_dirty || position.focus_within_changed();
+
+    if let Some(damage) = damage.as_deref_mut()
+        && let Some(kind) = widget.invalidation()
+    {
+        damage.invalidate(widget, kind);
+    }
+
+    let meta = position.meta(&*widget);
 
     let mut style = if should_restyle {
         // Compute style for the current widget
         // make_contiguous() ensures the VecDeque can be passed as a slice
-        let meta = widget.get_meta();
         let ancestors_slice = ancestors.make_contiguous();
-        let mut style = compute_style(&meta, ancestors_slice, stylesheet, theme);
+        let mut cascaded = tcss::parser::cascade::cascade(
+            &meta,
+            ancestors_slice,
+            cascade.stylesheet,
+            cascade.theme,
+        );
+        let mut style = std::mem::take(&mut cascaded.style);
 
         // Apply CSS inheritance for properties that weren't explicitly set
         apply_inheritance(&mut style, inherited);
 
-        // Apply inline style overrides (highest priority)
+        // Apply inline style overrides, then `!important` declarations,
+        // which outrank them
         if let Some(inline) = widget.inline_style() {
             inline.apply_to(&mut style);
         }
+        cascaded.apply_important(&mut style, cascade.theme);
 
         log::trace!(
             "CASCADE: Widget='{}' States={:?} -> Color={:?} (dirty={})",
@@ -104,8 +603,13 @@ pub fn resolve_dirty_styles<M>(
             is_dirty
         );
 
+        if let Some(damage) = damage.as_deref_mut()
+            && let Some(kind) = style_invalidation(&widget.get_style(), &style)
+        {
+            damage.invalidate(widget, kind);
+        }
+
         widget.set_style(style.clone());
-        widget.mark_clean();
         style
     } else {
         widget.get_style()
@@ -115,21 +619,39 @@ pub fn resolve_dirty_styles<M>(
     let child_inherited = build_inherited_context(&mut style, inherited);
 
     // Prepare for children: push current widget onto ancestor stack (at front for leaf-to-root order)
-    let meta = widget.get_meta();
     ancestors.push_front(meta);
 
-    // Recurse into children, propagating dirty state
+    let siblings = if cascade.uses_siblings {
+        position.child_metas(widget)
+    } else {
+        Arc::default()
+    };
+
+    // Recurse into children, propagating dirty state to descendants and,
+    // for sibling combinators, to later siblings
+    let mut index = 0;
+    let mut earlier_sibling_dirty = false;
     widget.for_each_child(&mut |child| {
-        resolve_dirty_styles(
+        let child_dirty = cascade.uses_siblings && child.is_dirty();
+        resolve_dirty(
             child,
-            stylesheet,
-            theme,
+            cascade,
             ancestors,
-            should_restyle,
+            position.child(&siblings, index),
+            should_restyle || earlier_sibling_dirty,
             &child_inherited,
+            damage.as_deref_mut(),
         );
+        earlier_sibling_dirty |= child_dirty;
+        index += 1;
     });
 
+    // Mark clean only after the subtree has been visited: containers clean
+    // their children too, which would hide the children's own invalidations.
+    if should_restyle {
+        widget.mark_clean();
+    }
+
     // Clean up stack after visiting subtree
     ancestors.pop_front();
 }
@@ -194,3 +716,19 @@ fn compute_effective_background(
         (None, _) => parent_inherited.effective_background.clone(),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terminal_colors() {
+        let truecolor = MediaEnvironment::TRUECOLOR;
+        assert_eq!(terminal_colors(Some("truecolor"), Some("xterm")), truecolor);
+        assert_eq!(terminal_colors(Some("24bit"), None), truecolor);
+        assert_eq!(terminal_colors(None, Some("xterm-direct")), truecolor);
+        assert_eq!(terminal_colors(None, Some("xterm-256color")), 256);
+        assert_eq!(terminal_colors(None, Some("xterm")), 16);
+        assert_eq!(terminal_colors(None, None), 16);
+    }
+}
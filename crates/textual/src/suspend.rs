@@ -0,0 +1,263 @@
+//! Suspending the app to hand the terminal to another program, such as
+//! `$EDITOR` or a pager.
+//!
+//! [`AppContext::suspend`] leaves raw mode and the alternate screen, runs a
+//! closure, then takes the terminal back and redraws the whole screen:
+//!
+//! ```ignore
+//! let status = ctx.suspend(|| Command::new(&editor).arg(&path).status())??;
+//! ```
+//!
+//! While the app is suspended it stops reading input, so the program gets
+//! every keystroke, and its [`Clock`] is paused, so widget timers don't
+//! fire.
+//!
+//! On Unix, [`App::SUSPEND_BINDING`](crate::App::SUSPEND_BINDING) (Ctrl+Z
+//! by default) and `SIGTSTP` suspend the app to the shell until it is
+//! continued, e.g. with `fg`.
+//!
+//! [`AppContext::suspend`]: crate::AppContext::suspend
+
+use std::future::poll_fn;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Poll, Waker};
+
+use crossterm::event::{DisableMouseCapture, EnableMouseCapture, Event, EventStream};
+use crossterm::{cursor, execute, terminal};
+use futures::StreamExt;
+
+use crate::timer::Clock;
+
+/// Enter raw mode and the alternate screen, hide the cursor and capture the
+/// mouse.
+pub(crate) fn enter_app_mode() -> io::Result<()> {
+    terminal::enable_raw_mode()?;
+    execute!(
+        io::stdout(),
+        terminal::EnterAlternateScreen,
+        cursor::Hide,
+        EnableMouseCapture
+    )
+}
+
+/// Undo [`enter_app_mode`], leaving the terminal as the shell expects it.
+pub(crate) fn leave_app_mode() -> io::Result<()> {
+    execute!(
+        io::stdout(),
+        DisableMouseCapture,
+        cursor::Show,
+        terminal::LeaveAlternateScreen
+    )?;
+    terminal::disable_raw_mode()
+}
+
+#[derive(Default)]
+struct Input {
+    /// Terminal events, while the app holds the terminal.
+    stream: Option<EventStream>,
+    /// Wakes the event loop once input resumes.
+    waker: Option<Waker>,
+}
+
+#[derive(Default)]
+struct Shared {
+    /// Whether the app holds the terminal.
+    attached: AtomicBool,
+    /// Set on resuming, until the event loop has redrawn.
+    resumed: AtomicBool,
+    input: Mutex<Input>,
+}
+
+/// The app's hold on the terminal: its input and whether it is suspended.
+///
+/// Shared by every [`AppContext`](crate::AppContext) the app hands out.
+#[derive(Clone, Default)]
+pub(crate) struct TerminalControl {
+    shared: Arc<Shared>,
+}
+
+impl TerminalControl {
+    /// Control of a terminal the app has put in [app mode](enter_app_mode),
+    /// reading its events.
+    pub(crate) fn attached() -> Self {
+        let control = Self::default();
+        control.shared.attached.store(true, Ordering::Release);
+        control.lock_input().stream = Some(EventStream::new());
+        control
+    }
+
+    /// The next terminal event. Pending while suspended.
+    pub(crate) async fn next_event(&self) -> Option<io::Result<Event>> {
+        poll_fn(|cx| {
+            let mut input = self.lock_input();
+            match input.stream.as_mut() {
+                Some(stream) => stream.poll_next_unpin(cx),
+                None => {
+                    input.waker = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        })
+        .await
+    }
+
+    /// Whether the app resumed since the last call, so the screen must be
+    /// redrawn.
+    pub(crate) fn take_resumed(&self) -> bool {
+        self.shared.resumed.swap(false, Ordering::AcqRel)
+    }
+
+    /// Give up the terminal and pause `clock`, run `f`, then take the
+    /// terminal back and resume the clock, even if `f` panics.
+    ///
+    /// Without a terminal (e.g. in tests) only the clock is paused.
+    pub(crate) fn suspend<R>(&self, clock: &Clock, f: impl FnOnce() -> R) -> io::Result<R> {
+        let attached = self.shared.attached.load(Ordering::Acquire);
+        clock.pause();
+        // Guard first, so input and the clock come back even if leaving
+        // the terminal fails
+        let mut suspended = Suspended {
+            control: self,
+            clock,
+            attached,
+            resumed: false,
+        };
+        if attached {
+            // Dropping the stream stops crossterm's reader thread, which
+            // would otherwise steal the program's input
+            self.lock_input().stream = None;
+            leave_app_mode()?;
+        }
+
+        let result = f();
+        suspended.resume()?;
+        Ok(result)
+    }
+
+    fn lock_input(&self) -> std::sync::MutexGuard<'_, Input> {
+        self.shared
+            .input
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// The app while suspended; resumes it when dropped.
+struct Suspended<'a> {
+    control: &'a TerminalControl,
+    clock: &'a Clock,
+    attached: bool,
+    resumed: bool,
+}
+
+impl Suspended<'_> {
+    fn resume(&mut self) -> io::Result<()> {
+        self.resumed = true;
+        self.clock.resume();
+        if !self.attached {
+            return Ok(());
+        }
+        self.control.shared.resumed.store(true, Ordering::Release);
+        // Restore input even if the terminal couldn't be set up again, so
+        // the event loop doesn't wait on a stream that never comes back
+        let entered = enter_app_mode();
+        let mut input = self.control.lock_input();
+        input.stream = Some(EventStream::new());
+        if let Some(waker) = input.waker.take() {
+            waker.wake();
+        }
+        entered
+    }
+}
+
+impl Drop for Suspended<'_> {
+    fn drop(&mut self) {
+        if !self.resumed {
+            let _ = self.resume();
+        }
+    }
+}
+
+/// Stop the process until it is continued, as the shell's job control
+/// expects for Ctrl+Z.
+///
+/// `SIGSTOP` is raised rather than `SIGTSTP`, which the app handles itself.
+#[cfg(unix)]
+pub(crate) fn stop_process() {
+    // SAFETY: raising a signal in the current process has no memory-safety
+    // requirements
+    unsafe {
+        libc::raise(libc::SIGSTOP);
+    }
+}
+
+/// `SIGTSTP` deliveries, asking the app to suspend itself.
+pub(crate) struct StopSignals {
+    #[cfg(unix)]
+    signal: Option<tokio::signal::unix::Signal>,
+}
+
+impl StopSignals {
+    /// Handle `SIGTSTP` from now on. Must be called within the runtime.
+    pub(crate) fn new() -> Self {
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+            let signal = signal(SignalKind::from_raw(libc::SIGTSTP))
+                .inspect_err(|e| log::warn!("SUSPEND: can't handle SIGTSTP: {e}"))
+                .ok();
+            Self { signal }
+        }
+        #[cfg(not(unix))]
+        Self {}
+    }
+
+    /// Resolves on the next `SIGTSTP`; never on other platforms.
+    pub(crate) async fn recv(&mut self) {
+        #[cfg(unix)]
+        if let Some(signal) = self.signal.as_mut() {
+            signal.recv().await;
+            return;
+        }
+        std::future::pending::<()>().await
+    }
+}
+
+/// Resolves on the next `SIGTSTP` if `signals` is set; never otherwise.
+pub(crate) async fn next_stop(signals: Option<&mut StopSignals>) {
+    match signals {
+        Some(signals) => signals.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+
+    use super::*;
+
+    #[test]
+    fn test_suspend_pauses_the_clock_while_running_the_closure() {
+        let control = TerminalControl::default();
+        let clock = Clock::new();
+        let paused = control.suspend(&clock, || clock.is_paused()).unwrap();
+        assert!(paused);
+        assert!(!clock.is_paused());
+        // Nothing to redraw without a terminal
+        assert!(!control.take_resumed());
+    }
+
+    #[test]
+    fn test_suspend_resumes_after_a_panic() {
+        let control = TerminalControl::default();
+        let clock = Clock::new();
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            control.suspend(&clock, || panic!("editor crashed"))
+        }));
+        assert!(result.is_err());
+        assert!(!clock.is_paused());
+    }
+}
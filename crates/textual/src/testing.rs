@@ -3,36 +3,35 @@
 //! This module provides headless rendering without the event loop,
 //! useful for snapshot testing UI layouts.
 
-use std::collections::{HashSet, VecDeque};
+use std::collections::VecDeque;
 
 use crate::{
     Size,
     canvas::Canvas,
-    style_resolver::resolve_styles,
+    style_resolver::{collect_default_css, resolve_styles},
     tree::WidgetTree,
     widget::{Compose, Widget, screen::Screen},
 };
 
-/// Collect default CSS from a widget and all its descendants.
-fn collect_default_css<M>(widget: &mut dyn Widget<M>, collected: &mut HashSet<&'static str>) {
-    let default_css = widget.default_css();
-    if !default_css.is_empty() {
-        collected.insert(default_css);
-    }
-    widget.for_each_child(&mut |child| {
-        collect_default_css(child, collected);
-    });
-}
+pub use crate::style_resolver::build_stylesheet;
 
-/// Build a combined stylesheet from widget defaults and app CSS.
+/// Build a combined stylesheet source from widget defaults and app CSS.
+///
+/// Widget defaults come first, in tree order. The result is a single
+/// stylesheet in which defaults only lose to app rules by source order;
+/// prefer [`build_stylesheet`], which keeps them in a lower cascade tier.
 pub fn build_combined_css<M>(root: &mut dyn Widget<M>, app_css: &str) -> String {
-    let mut defaults: HashSet<&'static str> = HashSet::new();
+    let mut defaults = Vec::new();
     collect_default_css(root, &mut defaults);
 
     let mut combined = String::new();
-    for css in defaults {
-        combined.push_str(css);
-        combined.push('\n');
+    let mut seen: Vec<&'static str> = Vec::new();
+    for (css, _, _) in defaults {
+        if !seen.contains(&css) {
+            seen.push(css);
+            combined.push_str(css);
+            combined.push('\n');
+        }
     }
     combined.push_str(app_css);
     combined
@@ -68,12 +67,13 @@ where
     let mut tree = WidgetTree::new(root);
 
     // Initialize Screen with size for breakpoints
-    tree.root_mut().on_resize(Size::new(width, height));
+    tree.resize(Size::new(width, height));
 
     // Collect widget default CSS and combine with app CSS
-    // Widget defaults are prepended (lower specificity), app CSS overrides
-    let combined_css = build_combined_css(tree.root_mut(), css);
-    let stylesheet = tcss::parser::parse_stylesheet(&combined_css).expect("CSS parsing failed");
+    // Widget defaults form a lower cascade tier, app CSS overrides
+    let mut stylesheet = build_stylesheet(tree.root_mut(), css).expect("CSS parsing failed");
+    // A fixed truecolor environment keeps snapshots independent of $TERM
+    stylesheet.set_environment(tcss::parser::MediaEnvironment::new(width, height));
 
     // Resolve styles
     let mut ancestors = VecDeque::new();
@@ -0,0 +1,563 @@
+//! Widget timers: one-shot and repeating timers that call back into the
+//! widget that started them.
+//!
+//! A timer is started with [`AppContext::start_timer`] or
+//! [`AppContext::start_interval`] from a context handed to one of a
+//! widget's hooks. When it fires, the event loop calls the widget's
+//! [`on_timer`](crate::Widget::on_timer) with the timer's id:
+//!
+//! ```ignore
+//! fn on_mount(&mut self, ctx: &AppContext<M>) {
+//!     self.spinner = ctx.start_interval(Duration::from_millis(100));
+//! }
+//!
+//! fn on_timer(&mut self, _timer: TimerId, _ctx: &AppContext<M>) {
+//!     self.tick();
+//! }
+//! ```
+//!
+//! Timers are paused while:
+//! - [`TimerHandle::pause`] is in effect
+//! - the widget that owns them is hidden
+//! - the app's [`Clock`] is paused, e.g. while the app is suspended
+//!
+//! and are cancelled when the owning widget is removed or the app exits.
+//!
+//! All timers measure time on the app's [`Clock`], which is built on Tokio's
+//! clock, so tests can drive them with `#[tokio::test(start_paused = true)]`
+//! and `tokio::time::advance`.
+//!
+//! [`AppContext::start_timer`]: crate::AppContext::start_timer
+//! [`AppContext::start_interval`]: crate::AppContext::start_interval
+
+use std::fmt;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// Identifies a timer for the lifetime of the app.
+pub type TimerId = u64;
+
+struct ClockState {
+    start: Instant,
+    /// When the clock was paused, if it is.
+    paused_at: Option<Instant>,
+    /// Total time spent paused before `paused_at`.
+    paused_for: Duration,
+}
+
+/// The app's runtime clock: time elapsed since the app started, not
+/// counting the time it was paused.
+///
+/// Clones share the same clock.
+#[derive(Clone)]
+pub struct Clock {
+    state: Arc<Mutex<ClockState>>,
+}
+
+impl Clock {
+    /// A running clock starting at zero.
+    pub fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(ClockState {
+                start: Instant::now(),
+                paused_at: None,
+                paused_for: Duration::ZERO,
+            })),
+        }
+    }
+
+    /// Time elapsed on the clock.
+    pub fn now(&self) -> Duration {
+        let state = self.lock();
+        let until = state.paused_at.unwrap_or_else(Instant::now);
+        until.duration_since(state.start) - state.paused_for
+    }
+
+    /// Stop the clock, until [`resume`](Self::resume) is called.
+    pub fn pause(&self) {
+        let mut state = self.lock();
+        if state.paused_at.is_none() {
+            state.paused_at = Some(Instant::now());
+        }
+    }
+
+    /// Restart a paused clock where it stopped.
+    pub fn resume(&self) {
+        let mut state = self.lock();
+        if let Some(paused_at) = state.paused_at.take() {
+            state.paused_for += paused_at.elapsed();
+        }
+    }
+
+    /// Whether the clock is paused.
+    pub fn is_paused(&self) -> bool {
+        self.lock().paused_at.is_some()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, ClockState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Clock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Clock")
+            .field("now", &self.now())
+            .field("paused", &self.is_paused())
+            .finish()
+    }
+}
+
+struct TimerEntry {
+    id: TimerId,
+    /// Identity of the widget the timer calls back into.
+    owner: usize,
+    /// The timer's delay, or period if it repeats.
+    delay: Duration,
+    repeat: bool,
+    /// Clock time the timer fires at, while it runs.
+    due: Duration,
+    /// Time left when the timer was paused, while it is.
+    remaining: Option<Duration>,
+    /// Paused through its handle.
+    paused: bool,
+    /// Paused because its owner is hidden.
+    hidden: bool,
+}
+
+impl TimerEntry {
+    /// Pause or resume the timer after its `paused` or `hidden` flag
+    /// changed.
+    fn update(&mut self, now: Duration) {
+        match (self.paused || self.hidden, self.remaining) {
+            (true, None) => self.remaining = Some(self.due.saturating_sub(now)),
+            (false, Some(remaining)) => {
+                self.due = now + remaining;
+                self.remaining = None;
+            }
+            _ => {}
+        }
+    }
+}
+
+#[derive(Default)]
+struct TimerList {
+    next_id: TimerId,
+    timers: Vec<TimerEntry>,
+}
+
+/// The widget timers started in an app, measured on its [`Clock`].
+///
+/// Shared by every [`AppContext`](crate::AppContext) the app hands out.
+#[derive(Clone, Default)]
+pub struct TimerManager {
+    clock: Clock,
+    list: Arc<Mutex<TimerList>>,
+}
+
+impl TimerManager {
+    /// The clock timers are measured on.
+    pub fn clock(&self) -> &Clock {
+        &self.clock
+    }
+
+    /// How many timers haven't finished or been cancelled.
+    pub fn len(&self) -> usize {
+        self.lock().timers.len()
+    }
+
+    /// Whether there are no live timers.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Cancel every timer, returning how many there were.
+    pub fn cancel_all(&self) -> usize {
+        let mut list = self.lock();
+        let count = list.timers.len();
+        list.timers.clear();
+        count
+    }
+
+    /// Time until the next running timer is due, or `None` if none is
+    /// running or the clock is paused.
+    pub fn next_wait(&self) -> Option<Duration> {
+        if self.clock.is_paused() {
+            return None;
+        }
+        let now = self.clock.now();
+        self.lock()
+            .timers
+            .iter()
+            .filter(|timer| timer.remaining.is_none())
+            .map(|timer| timer.due.saturating_sub(now))
+            .min()
+    }
+
+    /// Start a timer calling back into the widget with identity `owner`.
+    pub(crate) fn start(&self, owner: usize, delay: Duration, repeat: bool) -> TimerHandle {
+        let due = self.clock.now() + delay;
+        let mut list = self.lock();
+        list.next_id += 1;
+        let id = list.next_id;
+        list.timers.push(TimerEntry {
+            id,
+            owner,
+            // A zero period would fire on every pass of the event loop
+            delay: if repeat {
+                delay.max(Duration::from_millis(1))
+            } else {
+                delay
+            },
+            repeat,
+            due,
+            remaining: None,
+            paused: false,
+            hidden: false,
+        });
+        TimerHandle {
+            id,
+            manager: self.clone(),
+        }
+    }
+
+    /// Take the timers that are due, as `(timer, owner)` pairs in the order
+    /// they fell due. Repeating timers are rescheduled; if they fell more
+    /// than a period behind, the missed ticks are skipped.
+    pub(crate) fn take_due(&self) -> Vec<(TimerId, usize)> {
+        if self.clock.is_paused() {
+            return Vec::new();
+        }
+        let now = self.clock.now();
+        let mut list = self.lock();
+        let mut due: Vec<_> = list
+            .timers
+            .iter()
+            .filter(|timer| timer.remaining.is_none() && timer.due <= now)
+            .map(|timer| (timer.due, timer.id, timer.owner))
+            .collect();
+        due.sort_unstable();
+
+        list.timers.retain_mut(|timer| {
+            if timer.remaining.is_some() || timer.due > now {
+                return true;
+            }
+            if timer.repeat {
+                timer.due += timer.delay;
+                if timer.due <= now {
+                    timer.due = now + timer.delay;
+                }
+            }
+            timer.repeat
+        });
+        due.into_iter().map(|(_, id, owner)| (id, owner)).collect()
+    }
+
+    /// Pause the timers of the widgets `is_hidden` picks out, and resume
+    /// those of the others.
+    pub(crate) fn set_hidden_owners(&self, is_hidden: impl Fn(usize) -> bool) {
+        let now = self.clock.now();
+        for timer in &mut self.lock().timers {
+            timer.hidden = is_hidden(timer.owner);
+            timer.update(now);
+        }
+    }
+
+    /// Cancel the timers of the widget with identity `owner`.
+    pub(crate) fn cancel_owned_by(&self, owner: usize) -> usize {
+        let mut list = self.lock();
+        let before = list.timers.len();
+        list.timers.retain(|timer| timer.owner != owner);
+        before - list.timers.len()
+    }
+
+    fn with_timer<R>(
+        &self,
+        id: TimerId,
+        f: impl FnOnce(&mut TimerEntry, Duration) -> R,
+    ) -> Option<R> {
+        let now = self.clock.now();
+        let mut list = self.lock();
+        list.timers
+            .iter_mut()
+            .find(|timer| timer.id == id)
+            .map(|timer| f(timer, now))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TimerList> {
+        self.list.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A handle to a widget timer.
+///
+/// Dropping the handle does not cancel the timer; call
+/// [`cancel`](Self::cancel) for that.
+#[derive(Clone)]
+pub struct TimerHandle {
+    id: TimerId,
+    manager: TimerManager,
+}
+
+impl TimerHandle {
+    /// The timer's id, as passed to [`on_timer`](crate::Widget::on_timer).
+    pub fn id(&self) -> TimerId {
+        self.id
+    }
+
+    /// Whether the timer will still fire: it hasn't fired (if one-shot) or
+    /// been cancelled.
+    pub fn is_active(&self) -> bool {
+        self.manager.with_timer(self.id, |_, _| ()).is_some()
+    }
+
+    /// Whether the timer is paused, through its handle or because its
+    /// widget is hidden.
+    pub fn is_paused(&self) -> bool {
+        self.manager
+            .with_timer(self.id, |timer, _| timer.remaining.is_some())
+            .unwrap_or(false)
+    }
+
+    /// Stop the timer from counting down until [`resume`](Self::resume) is
+    /// called.
+    pub fn pause(&self) {
+        self.manager.with_timer(self.id, |timer, now| {
+            timer.paused = true;
+            timer.update(now);
+        });
+    }
+
+    /// Resume a paused timer with the time it had left. It stays paused
+    /// while its widget is hidden.
+    pub fn resume(&self) {
+        self.manager.with_timer(self.id, |timer, now| {
+            timer.paused = false;
+            timer.update(now);
+        });
+    }
+
+    /// Restart the timer's countdown from its full delay.
+    pub fn reset(&self) {
+        self.manager
+            .with_timer(self.id, |timer, now| match &mut timer.remaining {
+                Some(remaining) => *remaining = timer.delay,
+                None => timer.due = now + timer.delay,
+            });
+    }
+
+    /// Stop the timer for good. Does nothing if it already finished.
+    pub fn cancel(&self) {
+        self.manager
+            .lock()
+            .timers
+            .retain(|timer| timer.id != self.id);
+    }
+}
+
+impl fmt::Debug for TimerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TimerHandle")
+            .field("id", &self.id)
+            .field("active", &self.is_active())
+            .field("paused", &self.is_paused())
+            .finish()
+    }
+}
+
+/// Sleep for `wait`, or forever if it is `None`.
+pub(crate) async fn sleep_for(wait: Option<Duration>) {
+    match wait {
+        Some(wait) => tokio::time::sleep(wait).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    use tcss::types::Theme;
+    use tokio::sync::mpsc;
+    use tokio::time::advance;
+
+    use super::*;
+    use crate::{AppContext, Canvas, Container, MountPosition, Region, Size, Widget, WidgetTree};
+
+    /// Counts the ticks of an interval it starts when mounted.
+    struct Ticker {
+        ticks: Rc<Cell<u32>>,
+        visible: bool,
+        timer: Option<TimerHandle>,
+    }
+
+    impl Widget<()> for Ticker {
+        fn render(&self, _canvas: &mut Canvas, _region: Region) {}
+
+        fn desired_size(&self) -> Size {
+            Size::new(1, 1)
+        }
+
+        fn id(&self) -> Option<&str> {
+            Some("ticker")
+        }
+
+        fn is_visible(&self) -> bool {
+            self.visible
+        }
+
+        fn set_visible(&mut self, visible: bool) {
+            self.visible = visible;
+        }
+
+        fn on_mount(&mut self, ctx: &AppContext<()>) {
+            self.timer = ctx.start_interval(Duration::from_secs(1));
+        }
+
+        fn on_timer(&mut self, timer: TimerId, _ctx: &AppContext<()>) {
+            assert_eq!(self.timer.as_ref().map(TimerHandle::id), Some(timer));
+            self.ticks.set(self.ticks.get() + 1);
+        }
+    }
+
+    /// An attached tree holding a mounted [`Ticker`], and its tick count.
+    fn ticker_tree() -> (WidgetTree<()>, AppContext<()>, Rc<Cell<u32>>) {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let ctx = AppContext::new(tx);
+        let ticks = Rc::new(Cell::new(0));
+        let mut tree = WidgetTree::new(Box::new(Container::new(vec![]).with_id("root")));
+        tree.attach(ctx.clone());
+        let ticker = Ticker {
+            ticks: ticks.clone(),
+            visible: true,
+            timer: None,
+        };
+        tree.mount("#root", Box::new(ticker), MountPosition::Last)
+            .unwrap();
+        (tree, ctx, ticks)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_clock_does_not_count_paused_time() {
+        let clock = Clock::new();
+        advance(Duration::from_secs(2)).await;
+        clock.pause();
+        advance(Duration::from_secs(5)).await;
+        assert_eq!(clock.now(), Duration::from_secs(2));
+
+        clock.resume();
+        advance(Duration::from_secs(1)).await;
+        assert_eq!(clock.now(), Duration::from_secs(3));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_interval_calls_back_into_its_widget() {
+        let (mut tree, ctx, ticks) = ticker_tree();
+        assert_eq!(ctx.timers().next_wait(), Some(Duration::from_secs(1)));
+        assert!(!tree.fire_timers());
+
+        advance(Duration::from_secs(1)).await;
+        assert!(tree.fire_timers());
+        assert_eq!(ticks.get(), 1);
+
+        // Missed ticks are skipped rather than fired in a burst
+        advance(Duration::from_millis(3500)).await;
+        assert!(tree.fire_timers());
+        assert_eq!(ticks.get(), 2);
+        assert_eq!(ctx.timers().next_wait(), Some(Duration::from_secs(1)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_one_shot_timer_fires_once() {
+        let (tx, _rx) = mpsc::unbounded_channel::<crate::MessageEnvelope<()>>();
+        let ctx = AppContext::new(tx).with_owner(1);
+        let timer = ctx.start_timer(Duration::from_secs(1)).unwrap();
+        advance(Duration::from_secs(1)).await;
+        assert_eq!(ctx.timers().take_due(), [(timer.id(), 1)]);
+        assert!(!timer.is_active());
+
+        advance(Duration::from_secs(1)).await;
+        assert!(ctx.timers().take_due().is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_paused_timer_keeps_the_time_it_had_left() {
+        let (tx, _rx) = mpsc::unbounded_channel::<crate::MessageEnvelope<()>>();
+        let ctx = AppContext::new(tx).with_owner(1);
+        let timer = ctx.start_interval(Duration::from_secs(2)).unwrap();
+        advance(Duration::from_millis(500)).await;
+        timer.pause();
+        assert!(timer.is_paused());
+        assert_eq!(ctx.timers().next_wait(), None);
+
+        advance(Duration::from_secs(10)).await;
+        assert!(ctx.timers().take_due().is_empty());
+
+        timer.resume();
+        assert_eq!(ctx.timers().next_wait(), Some(Duration::from_millis(1500)));
+
+        timer.reset();
+        assert_eq!(ctx.timers().next_wait(), Some(Duration::from_secs(2)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_timers_pause_while_their_widget_is_hidden() {
+        let (mut tree, ctx, ticks) = ticker_tree();
+        let stylesheet = tcss::parser::parse_stylesheet("").expect("valid CSS");
+        let theme = Theme::new("test", true);
+        let canvas = Canvas::new(10, 5);
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+
+        tree.query_one("#ticker", |w| w.set_visible(false));
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        advance(Duration::from_secs(5)).await;
+        assert!(!tree.fire_timers());
+        assert_eq!(ticks.get(), 0);
+
+        tree.query_one("#ticker", |w| w.set_visible(true));
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert_eq!(ctx.timers().next_wait(), Some(Duration::from_secs(1)));
+        advance(Duration::from_secs(1)).await;
+        assert!(tree.fire_timers());
+        assert_eq!(ticks.get(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_paused_clock_holds_every_timer() {
+        let (mut tree, ctx, ticks) = ticker_tree();
+        ctx.timers().clock().pause();
+        assert_eq!(ctx.timers().next_wait(), None);
+        advance(Duration::from_secs(5)).await;
+        assert!(!tree.fire_timers());
+
+        ctx.timers().clock().resume();
+        advance(Duration::from_secs(1)).await;
+        assert!(tree.fire_timers());
+        assert_eq!(ticks.get(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_removing_a_widget_cancels_its_timers() {
+        let (mut tree, ctx, _ticks) = ticker_tree();
+        assert_eq!(ctx.timers().len(), 1);
+        assert_eq!(tree.remove("#ticker"), 1);
+        assert!(ctx.timers().is_empty());
+    }
+
+    #[test]
+    fn test_timers_need_a_widget_to_call_back() {
+        let (tx, _rx) = mpsc::unbounded_channel::<crate::MessageEnvelope<()>>();
+        let ctx = AppContext::new(tx);
+        assert!(ctx.start_interval(Duration::from_secs(1)).is_none());
+        assert!(ctx.timers().is_empty());
+    }
+}
@@ -4,10 +4,25 @@
 //! root to the focused widget. Instead of searching through all containers,
 //! events go directly to the focused widget and bubble up through the cached path.
 
-use crate::KeyCode;
+use std::collections::{HashSet, VecDeque};
+use std::time::Instant;
+
+use crate::canvas::{Canvas, Region, Size};
+use crate::context::AppContext;
+use crate::damage::{DamageCollector, DirtyRegions, widget_key};
+use crate::error::{Result, TextualError};
+use crate::layouts::Viewport;
 use crate::message::MessageEnvelope;
+use crate::mouse::{HitPath, PointerTracker};
+use crate::style_resolver::{
+    DefaultCss, InheritedContext, collect_default_css, first_match_path, mark_matched_dirty,
+    merge_default_css, resolve_dirty_styles_with_damage,
+};
 use crate::widget::{SenderInfo, Widget};
+use crate::{KeyCode, MouseEvent};
 use tcss::WidgetStates;
+use tcss::parser::{AttributeSelector, StyleSheet};
+use tcss::types::{Theme, Visibility};
 
 /// A path from the root to a specific widget in the tree.
 ///
@@ -57,6 +72,54 @@ impl FocusPath {
     }
 }
 
+/// Where [`WidgetTree::mount`] places a widget among its parent's children.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MountPosition {
+    /// Before the existing children.
+    First,
+    /// After the existing children.
+    #[default]
+    Last,
+    /// At this child index, shifting later children back. Indices past the
+    /// last child mount at the end.
+    Index(usize),
+}
+
+/// Focus moving between widgets, reported to
+/// [`App::on_focus_event`](crate::App::on_focus_event).
+#[derive(Debug, Clone)]
+pub enum FocusEvent {
+    /// The widget received focus.
+    FocusGained(SenderInfo),
+    /// The widget lost focus.
+    FocusLost(SenderInfo),
+}
+
+/// Where focus has been, kept across tree rebuilds.
+///
+/// Take it from the old tree with [`WidgetTree::take_focus_memory`] and
+/// hand it to the new one with [`WidgetTree::set_focus_memory`] so focus
+/// can still be restored, and so an unchanged focus isn't reported again.
+#[derive(Debug, Clone, Default)]
+pub struct FocusMemory {
+    /// Paths focus was at before it moved into a focus trap, most recent
+    /// last.
+    saved: Vec<Vec<usize>>,
+    /// Path and sender info of the focused widget when focus events were
+    /// last taken.
+    reported: Option<(Vec<usize>, SenderInfo)>,
+}
+
+/// A focusable widget, in tree order.
+struct Focusable {
+    /// Focus index (position among focusable widgets in tree order).
+    index: usize,
+    path: Vec<usize>,
+    tab_index: i32,
+    /// False if an ancestor doesn't let Tab visit its descendants.
+    tabbable: bool,
+}
+
 /// Manages a widget tree with cached focus path for O(d) dispatch.
 pub struct WidgetTree<M> {
     /// The root widget of the tree.
@@ -65,15 +128,50 @@ pub struct WidgetTree<M> {
     focus_path: FocusPath,
     /// The focus index that was used to compute the current path.
     current_focus_index: usize,
+    /// Screen regions to re-render on the next frame.
+    damage: DirtyRegions,
+    /// Widgets (by identity) that handled input and must be repainted even
+    /// if they did not mark themselves dirty.
+    pending_repaints: Vec<usize>,
+    /// Path to the focused widget when styles were last resolved, used to
+    /// restyle widgets whose `:focus-within` state changed.
+    styled_focus_path: Option<Vec<usize>>,
+    /// CSS variable overrides set (`Some`) or removed (`None`) since the
+    /// stylesheet was last updated.
+    css_variables: Vec<(String, Option<String>)>,
+    /// Default CSS of mounted widgets, not yet added to the stylesheet.
+    mounted_css: Vec<DefaultCss>,
+    /// Terminal size from the last [`resize`](Self::resize), passed on to
+    /// mounted widgets.
+    size: Option<Size>,
+    /// The app's context, handed to lifecycle hooks while the tree is
+    /// [attached](Self::attach) to an app.
+    app: Option<AppContext<M>>,
+    /// Widgets (by identity) that were shown when styles were last
+    /// resolved, to detect `on_show`/`on_hide`.
+    shown: HashSet<usize>,
+    /// Focus to restore when traps close, and the last reported focus.
+    focus_memory: FocusMemory,
 }
 
 impl<M> WidgetTree<M> {
     /// Create a new widget tree from a root widget.
+    ///
+    /// A new tree has never been drawn, so its first frame is a full render.
     pub fn new(root: Box<dyn Widget<M>>) -> Self {
         Self {
             root,
             focus_path: FocusPath::new(),
             current_focus_index: 0,
+            damage: DirtyRegions::full(),
+            pending_repaints: Vec::new(),
+            styled_focus_path: None,
+            css_variables: Vec::new(),
+            mounted_css: Vec::new(),
+            size: None,
+            app: None,
+            shown: HashSet::new(),
+            focus_memory: FocusMemory::default(),
         }
     }
 
@@ -87,6 +185,12 @@ impl<M> WidgetTree<M> {
         self.root.as_mut()
     }
 
+    /// Pass a new terminal size to every widget in the tree.
+    pub fn resize(&mut self, size: Size) {
+        self.size = Some(size);
+        self.root.on_resize(size);
+    }
+
     /// Get the current focus path.
     pub fn focus_path(&self) -> &FocusPath {
         &self.focus_path
@@ -112,6 +216,9 @@ impl<M> WidgetTree<M> {
     }
 
     /// Set focus to the nth focusable widget and update the cached path.
+    ///
+    /// Moving focus into a [focus trap](Widget::is_focus_trap) from outside
+    /// remembers where it was, for [`restore_focus`](Self::restore_focus).
     pub fn set_focus_index(&mut self, focus_index: usize) -> bool {
         let count = self.focusable_count();
         if count == 0 {
@@ -124,46 +231,274 @@ impl<M> WidgetTree<M> {
             return false;
         }
 
+        let previous = self
+            .focus_is_valid()
+            .then(|| self.focus_path.indices.clone());
         self.root.clear_focus();
         if !self.root.focus_nth(focus_index) {
             return false;
         }
         self.update_focus(focus_index);
+
+        if let Some(previous) = previous {
+            let path = self.focus_path.indices.clone();
+            if let Some(depth) = innermost_trap(self.root.as_mut(), &path)
+                && !previous.starts_with(&path[..depth])
+            {
+                self.focus_memory.saved.push(previous);
+            }
+        }
         true
     }
 
     /// Move focus forward (Tab behavior), wrapping around.
+    ///
+    /// Follows [`Widget::tab_index`], skips the descendants of widgets that
+    /// can't [focus children](Widget::can_focus_children), and stays inside
+    /// the active [focus trap](Widget::is_focus_trap).
     pub fn focus_next(&mut self) -> Option<usize> {
-        let count = self.focusable_count();
-        if count == 0 {
-            return None;
-        }
-        let next = (self.current_focus_index + 1) % count;
-        if self.set_focus_index(next) {
-            Some(next)
-        } else {
-            None
-        }
+        self.cycle_focus(true)
     }
 
     /// Move focus backward (Shift+Tab behavior), wrapping around.
+    ///
+    /// The reverse of [`focus_next`](Self::focus_next).
     pub fn focus_previous(&mut self) -> Option<usize> {
-        let count = self.focusable_count();
-        if count == 0 {
+        self.cycle_focus(false)
+    }
+
+    /// Move focus one step along the Tab order.
+    fn cycle_focus(&mut self, forward: bool) -> Option<usize> {
+        let chain = self.tab_chain();
+        if chain.is_empty() {
             return None;
         }
-        let prev = if self.current_focus_index == 0 {
-            count - 1
-        } else {
-            self.current_focus_index - 1
+        let len = chain.len();
+        let position = chain
+            .iter()
+            .position(|&index| index == self.current_focus_index);
+        let next = match (position, forward) {
+            (Some(position), true) => (position + 1) % len,
+            (Some(position), false) => (position + len - 1) % len,
+            // Focus is outside the chain (e.g. a trap just opened)
+            (None, true) => 0,
+            (None, false) => len - 1,
         };
-        if self.set_focus_index(prev) {
-            Some(prev)
+        let index = chain[next];
+        if self.set_focus_index(index) {
+            self.scroll_focused_visible();
+            Some(index)
         } else {
             None
         }
     }
 
+    /// Focus indices in the order Tab visits them.
+    ///
+    /// If focus is inside a focus trap, only widgets in the innermost one
+    /// are included; otherwise, if a trap is showing, only widgets in the
+    /// last one (in tree order) are.
+    fn tab_chain(&mut self) -> Vec<usize> {
+        let mut focusables = Vec::new();
+        let mut traps = Vec::new();
+        collect_focusables(
+            self.root.as_mut(),
+            &mut Vec::new(),
+            true,
+            &mut 0,
+            &mut focusables,
+            &mut traps,
+        );
+        focusables.retain(|focusable| focusable.tabbable);
+
+        let inside = |trap: &Vec<usize>| {
+            focusables
+                .iter()
+                .any(|focusable| focusable.path.starts_with(trap))
+        };
+        let focus_path = &self.focus_path.indices;
+        let trap = traps
+            .iter()
+            .filter(|trap| focus_path.starts_with(trap) && inside(trap))
+            .max_by_key(|trap| trap.len())
+            .or_else(|| traps.iter().rev().find(|trap| inside(trap)))
+            .cloned();
+        if let Some(trap) = trap {
+            focusables.retain(|focusable| focusable.path.starts_with(&trap));
+        }
+
+        // Stable, so equal tab indices keep tree order
+        focusables.sort_by_key(|focusable| focusable.tab_index);
+        focusables
+            .into_iter()
+            .map(|focusable| focusable.index)
+            .collect()
+    }
+
+    /// Whether the focused widget can still take focus: it is focusable
+    /// and it and all its ancestors are laid out.
+    fn focus_is_valid(&mut self) -> bool {
+        can_take_focus(self.root.as_mut(), &self.focus_path.indices)
+    }
+
+    /// Move focus somewhere sensible if the focused widget can no longer
+    /// take focus, e.g. because the overlay holding it was hidden.
+    ///
+    /// Focus goes back to where it was before it moved into a focus trap
+    /// (most recent first), or else to the first focusable widget. Returns
+    /// whether focus moved.
+    pub fn restore_focus(&mut self) -> bool {
+        if self.focus_is_valid() {
+            return false;
+        }
+        while let Some(path) = self.focus_memory.saved.pop() {
+            if let Some(index) = self.focus_index_of(&path)
+                && self.set_focus_index(index)
+            {
+                return true;
+            }
+        }
+        self.set_focus_index(0)
+    }
+
+    /// Focus index of the focusable widget at `path`.
+    fn focus_index_of(&mut self, path: &[usize]) -> Option<usize> {
+        let mut focusables = Vec::new();
+        collect_focusables(
+            self.root.as_mut(),
+            &mut Vec::new(),
+            true,
+            &mut 0,
+            &mut focusables,
+            &mut Vec::new(),
+        );
+        focusables
+            .into_iter()
+            .find(|focusable| focusable.path == path)
+            .map(|focusable| focusable.index)
+    }
+
+    /// Report focus changes since the last call: [`FocusEvent::FocusLost`]
+    /// for the widget that had focus then, followed by
+    /// [`FocusEvent::FocusGained`] for the one that has it now.
+    ///
+    /// Widgets are told apart by their path, ID and type, so a rebuilt
+    /// widget in the same place doesn't count as a change.
+    pub fn take_focus_events(&mut self) -> Vec<FocusEvent> {
+        let path = self.focus_path.indices.clone();
+        let current = self
+            .with_focused(|widget| widget.is_focused().then(|| widget.sender_info()))
+            .flatten()
+            .map(|info| (path, info));
+
+        let same = match (&self.focus_memory.reported, &current) {
+            (Some((old_path, old)), Some((new_path, new))) => {
+                old_path == new_path && old.id == new.id && old.type_name == new.type_name
+            }
+            (None, None) => true,
+            _ => false,
+        };
+        if same {
+            return Vec::new();
+        }
+
+        let mut events = Vec::new();
+        if let Some((_, old)) = self.focus_memory.reported.take() {
+            events.push(FocusEvent::FocusLost(old));
+        }
+        if let Some((_, new)) = &current {
+            events.push(FocusEvent::FocusGained(new.clone()));
+        }
+        self.focus_memory.reported = current;
+        events
+    }
+
+    /// Take the focus memory, to carry it over to a rebuilt tree.
+    pub fn take_focus_memory(&mut self) -> FocusMemory {
+        std::mem::take(&mut self.focus_memory)
+    }
+
+    /// Replace the focus memory with one taken from a previous tree.
+    pub fn set_focus_memory(&mut self, memory: FocusMemory) {
+        self.focus_memory = memory;
+    }
+
+    /// Scroll the focused widget's scrollable ancestors so it is visible.
+    fn scroll_focused_visible(&mut self) {
+        let path = self.focus_path.indices.clone();
+        self.scroll_path_visible(&path, false, false);
+    }
+
+    /// Scroll every scrollable ancestor of the first widget matching
+    /// `selector` so that the widget is visible, returning whether a match
+    /// was found.
+    ///
+    /// With `center`, the widget is centered in each ancestor even if it is
+    /// already visible. Positions come from laying the tree out at the size
+    /// of the last [`resize`](Self::resize); before that nothing scrolls.
+    pub fn scroll_to_widget(&mut self, selector: &str, animate: bool, center: bool) -> bool {
+        let selector = parse_simple_selector(selector);
+        let mut paths = Vec::new();
+        collect_matching_paths(self.root.as_mut(), &selector, &mut Vec::new(), &mut paths);
+        match paths.first() {
+            Some(path) => {
+                self.scroll_path_visible(path, animate, center);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Scroll the ancestors of the widget at `path`, innermost first, so the
+    /// widget is visible. Returns whether anything scrolled.
+    fn scroll_path_visible(&mut self, path: &[usize], animate: bool, center: bool) -> bool {
+        let Some(size) = self.size else {
+            return false;
+        };
+        let viewport = Viewport {
+            width: size.width as i32,
+            height: size.height as i32,
+        };
+
+        // Where each widget along the path is drawn, from the root down
+        let mut regions = vec![Region::new(0, 0, viewport.width, viewport.height)];
+        for depth in 0..path.len() {
+            let Some(widget) = widget_at_path(self.root.as_mut(), &path[..depth]) else {
+                return false;
+            };
+            let placed = widget
+                .child_regions(regions[depth], viewport)
+                .into_iter()
+                .find(|&(index, _)| index == path[depth]);
+            match placed {
+                Some((_, region)) => regions.push(region),
+                // Not laid out (e.g. hidden), so there is nothing to show
+                None => return false,
+            }
+        }
+
+        // Scrolling an ancestor moves the target but not the ancestors
+        // above it, so their regions stay valid
+        let mut target = regions[path.len()];
+        let mut scrolled = false;
+        for depth in (0..path.len()).rev() {
+            let Some(widget) = widget_at_path(self.root.as_mut(), &path[..depth]) else {
+                continue;
+            };
+            if let Some((dx, dy)) =
+                widget.scroll_to_region(regions[depth], viewport, target, animate, center)
+            {
+                target.x -= dx;
+                target.y -= dy;
+                scrolled |= (dx, dy) != (0, 0);
+            }
+        }
+        if scrolled {
+            self.invalidate_all();
+        }
+        scrolled
+    }
+
     /// Update the focus path for a given focus index.
     ///
     /// This walks the tree to find the nth focusable widget and caches
@@ -200,9 +535,17 @@ impl<M> WidgetTree<M> {
 
     /// Dispatch a key event to the focused widget.
     ///
+    /// The focused widget is scheduled for a repaint, since key handlers
+    /// may change what it draws without marking it dirty.
+    ///
     /// Returns the message produced, if any.
     pub fn dispatch_key(&mut self, key: KeyCode) -> Option<M> {
-        self.with_focused(|widget| widget.on_event(key)).flatten()
+        let (key_id, msg) = self.with_focused(|widget| {
+            let key_id = widget_key(&*widget);
+            (key_id, widget.on_event(key))
+        })?;
+        self.pending_repaints.push(key_id);
+        msg
     }
 
     /// Get sender info for the focused widget.
@@ -219,6 +562,362 @@ impl<M> WidgetTree<M> {
         })
     }
 
+    // =========================================================================
+    // Damage Tracking
+    // =========================================================================
+
+    /// Returns the damage accumulated since the last render.
+    pub fn damage(&self) -> &DirtyRegions {
+        &self.damage
+    }
+
+    /// Schedule a full re-render on the next frame.
+    ///
+    /// Use after changes the tree cannot attribute to individual widgets,
+    /// such as a resize or theme switch.
+    pub fn invalidate_all(&mut self) {
+        self.damage.mark_full();
+    }
+
+    /// Schedule a screen region for re-rendering on the next frame.
+    pub fn invalidate_region(&mut self, region: Region) {
+        self.damage.add(region);
+    }
+
+    /// Schedule the innermost widget under a screen cell for a repaint on
+    /// the next frame.
+    ///
+    /// Use after mouse handlers that may change what the widget draws
+    /// without marking it dirty.
+    pub fn invalidate_at(&mut self, column: u16, row: u16) {
+        let path = self.hit_path(column, row);
+        if let Some(widget) = widget_at_path(self.root.as_mut(), &path) {
+            self.pending_repaints.push(widget_key(&*widget));
+        }
+    }
+
+    /// Schedule the first widget matching `selector` for a repaint on the
+    /// next frame.
+    ///
+    /// Returns false if no widget matches.
+    pub fn invalidate_widget(&mut self, selector: &str) -> bool {
+        let Some(key) = self.query_one(selector, |widget| widget_key(&*widget)) else {
+            return false;
+        };
+        self.pending_repaints.push(key);
+        true
+    }
+
+    // =========================================================================
+    // Lifecycle
+    // =========================================================================
+
+    /// Attach the tree to a running app, calling [`Widget::on_mount`] on
+    /// every widget.
+    ///
+    /// While attached, widgets are mounted and unmounted as they are added
+    /// and removed, and get [`Widget::on_show`] and [`Widget::on_hide`] when
+    /// styles are resolved. Hooks get a clone of `app` bound to their
+    /// widget; workers and timers they start are cancelled when the widget
+    /// is removed.
+    pub fn attach(&mut self, app: AppContext<M>) {
+        mount_subtree(self.root.as_mut(), &app);
+        self.app = Some(app);
+    }
+
+    /// Call [`Widget::on_unmount`] on every widget and detach the tree from
+    /// the app, before it is replaced by recomposition or the app exits.
+    pub fn detach(&mut self) {
+        if let Some(app) = self.app.take() {
+            unmount_subtree(self.root.as_mut(), &app, &mut self.shown);
+        }
+    }
+
+    /// Call [`Widget::on_timer`] on the widgets whose timers are due,
+    /// returning whether any fired.
+    ///
+    /// Does nothing unless the tree is [attached](Self::attach).
+    pub fn fire_timers(&mut self) -> bool {
+        let Some(app) = &self.app else {
+            return false;
+        };
+        let due = app.timers().take_due();
+        if due.is_empty() {
+            return false;
+        }
+        let mut fired = 0;
+        for (timer, owner) in due {
+            let mut path = Vec::new();
+            if path_to_key(self.root.as_mut(), owner, &mut path)
+                && let Some(widget) = widget_at_path(self.root.as_mut(), &path)
+            {
+                let ctx = lifecycle_context(&*widget, app);
+                widget.on_timer(timer, &ctx);
+                fired += 1;
+            }
+        }
+        fired > 0
+    }
+
+    // =========================================================================
+    // Mounting
+    // =========================================================================
+
+    /// Insert `widget` as a child of the first widget matching `parent`
+    /// (a selector as for [`query_one`](Self::query_one)).
+    ///
+    /// The rest of the tree, including widget state, is left as it is. If the
+    /// tree is [attached](Self::attach), the new widgets are mounted. The
+    /// parent and the new subtree are restyled on the next frame, once the
+    /// event loop has added any new widget types' default CSS with
+    /// [`apply_mounted_css`](Self::apply_mounted_css). The focused widget
+    /// keeps focus.
+    ///
+    /// Fails if no widget matches `parent` or it can't hold children.
+    pub fn mount(
+        &mut self,
+        parent: &str,
+        mut widget: Box<dyn Widget<M>>,
+        position: MountPosition,
+    ) -> Result<()> {
+        let selector = parse_simple_selector(parent);
+        let mut paths = Vec::new();
+        collect_matching_paths(self.root.as_mut(), &selector, &mut Vec::new(), &mut paths);
+        let parent_widget = paths
+            .first()
+            .and_then(|path| widget_at_path(self.root.as_mut(), path))
+            .ok_or_else(|| TextualError::Mount(format!("no widget matches {parent:?}")))?;
+
+        if let Some(size) = self.size {
+            widget.on_resize(size);
+        }
+        let mut defaults = Vec::new();
+        collect_default_css(widget.as_mut(), &mut defaults);
+
+        let index = match position {
+            MountPosition::First => 0,
+            MountPosition::Last => parent_widget.child_count(),
+            MountPosition::Index(index) => index.min(parent_widget.child_count()),
+        };
+        let parent_type = parent_widget.type_name();
+        parent_widget
+            .insert_child(index, widget)
+            .map_err(|widget| {
+                TextualError::Mount(format!(
+                    "{parent_type} can't contain mounted widgets (mounting {})",
+                    widget.type_name()
+                ))
+            })?;
+        // Restyle the parent's subtree: selectors like `:last-child` may
+        // now match differently
+        parent_widget.mark_dirty();
+        if let Some(app) = &self.app
+            && let Some(mounted) = parent_widget.get_child_mut(index)
+        {
+            mount_subtree(mounted, app);
+        }
+
+        self.mounted_css.extend(defaults);
+        self.refocus(self.current_focus_index);
+        self.invalidate_all();
+        Ok(())
+    }
+
+    /// Remove every widget matching `selector` (with its descendants) from
+    /// the tree, returning how many were removed.
+    ///
+    /// Removed widgets are unmounted, descendants first. If the focused
+    /// widget is removed, focus moves to the widget that takes its place in
+    /// focus order, or the last focusable widget.
+    pub fn remove(&mut self, selector: &str) -> usize {
+        let selector = parse_simple_selector(selector);
+        let mut paths = Vec::new();
+        collect_matching_paths(self.root.as_mut(), &selector, &mut Vec::new(), &mut paths);
+
+        // Matches inside a removed widget go with it; the root stays
+        let mut removals: Vec<Vec<usize>> = Vec::new();
+        for path in paths {
+            if !path.is_empty() && !removals.iter().any(|removed| path.starts_with(removed)) {
+                removals.push(path);
+            }
+        }
+
+        // Paths are in tree order, so removing from the back leaves the
+        // remaining paths valid
+        let mut removed = 0;
+        for path in removals.iter().rev() {
+            let (&index, parent_path) = path.split_last().expect("root is never removed");
+            if let Some(parent) = widget_at_path(self.root.as_mut(), parent_path)
+                && let Some(mut widget) = parent.remove_child(index)
+            {
+                parent.mark_dirty();
+                if let Some(app) = &self.app {
+                    unmount_subtree(widget.as_mut(), app, &mut self.shown);
+                }
+                removed += 1;
+            }
+        }
+
+        if removed > 0 {
+            self.refocus(self.current_focus_index);
+            self.invalidate_all();
+        }
+        removed
+    }
+
+    /// Add the default CSS of widgets mounted since the last call to
+    /// `stylesheet`, for widget types it doesn't style yet.
+    ///
+    /// The whole tree is restyled if rules were added, since a widget's
+    /// defaults can target other widgets. Returns whether that happened.
+    pub fn apply_mounted_css(&mut self, stylesheet: &mut StyleSheet) -> Result<bool> {
+        if self.mounted_css.is_empty() {
+            return Ok(false);
+        }
+        let defaults = std::mem::take(&mut self.mounted_css);
+        let merged = merge_default_css(stylesheet, &defaults)?;
+        if merged {
+            self.root.mark_dirty();
+        }
+        Ok(merged)
+    }
+
+    /// Recompute the focus index and path after widgets were mounted or
+    /// removed.
+    ///
+    /// The focused widget keeps focus at its new index; if it's gone, the
+    /// widget now at `previous_index` (or the last one) gets focus.
+    fn refocus(&mut self, previous_index: usize) {
+        self.focus_path.clear();
+        let mut index = 0;
+        match find_focused_index(self.root.as_mut(), &mut index) {
+            Some(focused) => {
+                self.current_focus_index = focused;
+                let mut remaining = focused;
+                find_focus_path_recursive(self.root.as_mut(), &mut self.focus_path, &mut remaining);
+            }
+            None => {
+                let count = self.focusable_count();
+                self.set_focus_index(previous_index.min(count.saturating_sub(1)));
+            }
+        }
+    }
+
+    // =========================================================================
+    // CSS Variables
+    // =========================================================================
+
+    /// Set CSS variable `name` (`$name`) for the app, overriding stylesheet
+    /// definitions and theme variables.
+    ///
+    /// Takes effect when the event loop next calls
+    /// [`apply_css_variables`](Self::apply_css_variables).
+    pub fn set_css_variable(&mut self, name: &str, value: &str) {
+        self.css_variables
+            .push((name.to_string(), Some(value.to_string())));
+    }
+
+    /// Remove an app-set CSS variable, restoring the stylesheet or theme
+    /// value.
+    pub fn remove_css_variable(&mut self, name: &str) {
+        self.css_variables.push((name.to_string(), None));
+    }
+
+    /// Apply CSS variable changes made since the last call to `stylesheet`.
+    ///
+    /// Widgets matched by a rule using a changed variable are marked dirty
+    /// so the next style pass restyles them; only those whose style actually
+    /// changed are repainted. Returns whether any widget was marked.
+    pub fn apply_css_variables(&mut self, stylesheet: &mut StyleSheet, theme: &Theme) -> bool {
+        let mut rules = HashSet::new();
+        for (name, value) in self.css_variables.drain(..) {
+            let used = match value {
+                Some(value) => stylesheet.set_variable(&name, &value),
+                None => stylesheet.remove_variable(&name),
+            };
+            if used {
+                rules.extend(stylesheet.rules_using_variable(&name));
+            }
+        }
+        !rules.is_empty() && mark_matched_dirty(&mut *self.root, stylesheet, theme, &rules)
+    }
+
+    /// Resolve styles for dirty widgets and collect the damage they cause.
+    ///
+    /// Must run before [`render`](Self::render), against the canvas that
+    /// holds the previous frame, so widget repaints can be mapped to the
+    /// regions they occupied. If the tree is attached, widgets shown or
+    /// hidden by the new styles get [`Widget::on_show`] or
+    /// [`Widget::on_hide`].
+    pub fn resolve_dirty_styles(
+        &mut self,
+        stylesheet: &StyleSheet,
+        theme: &Theme,
+        canvas: &Canvas,
+    ) {
+        let mut collector = DamageCollector::new(canvas, &mut self.damage);
+        for key in self.pending_repaints.drain(..) {
+            collector.repaint_key(key);
+        }
+
+        let mut ancestors = VecDeque::new();
+        resolve_dirty_styles_with_damage(
+            self.root.as_mut(),
+            stylesheet,
+            theme,
+            &mut ancestors,
+            &InheritedContext::from_theme(theme),
+            &mut self.styled_focus_path,
+            &mut collector,
+        );
+
+        if let Some(app) = &self.app {
+            let mut shown = HashSet::with_capacity(self.shown.len());
+            update_shown(self.root.as_mut(), true, app, &self.shown, &mut shown);
+            app.timers()
+                .set_hidden_owners(|owner| !shown.contains(&owner));
+            self.shown = shown;
+        }
+    }
+
+    /// Render the tree onto `canvas`, re-running widgets only where damaged.
+    ///
+    /// Full damage (or a canvas without a previous frame) clears and redraws
+    /// everything. Otherwise the previous frame is kept and each damaged
+    /// region is cleared and re-rendered under a clip, skipping widgets that
+    /// lie entirely outside it. Damaged regions are widened to full rows so
+    /// wide glyphs are never split at a region edge.
+    pub fn render(&mut self, canvas: &mut Canvas, region: Region) {
+        let damage = std::mem::take(&mut self.damage);
+
+        if damage.is_full() || !canvas.has_previous_frame() {
+            canvas.begin_full_frame();
+            self.root.render(canvas, region);
+            return;
+        }
+
+        canvas.begin_partial_frame();
+
+        let mut rows = DirtyRegions::new();
+        for dirty in damage.regions() {
+            rows.add(
+                Region::new(region.x, dirty.y, region.width, dirty.height).intersection(&region),
+            );
+        }
+        for &band in rows.regions() {
+            canvas.clear_region(band);
+            canvas.set_damage_clip(Some(band));
+            self.root.render(canvas, region);
+        }
+        canvas.set_damage_clip(None);
+
+        // Widgets outside the damage keep their regions from earlier frames;
+        // drop those of removed widgets, whose keys may be reused
+        let mut live = HashSet::new();
+        collect_widget_keys(self.root.as_mut(), &mut live);
+        canvas.retain_widget_regions(|key| live.contains(&key));
+    }
+
     // =========================================================================
     // Widget Query API
     // =========================================================================
@@ -407,11 +1106,161 @@ impl<M> WidgetTree<M> {
     /// If an ancestor calls `envelope.stop()`, bubbling stops.
     ///
     /// Returns the final envelope (possibly transformed by ancestors).
-    pub fn bubble_message(&mut self, mut envelope: MessageEnvelope<M>) -> MessageEnvelope<M> {
+    pub fn bubble_message(&mut self, envelope: MessageEnvelope<M>) -> MessageEnvelope<M> {
         let path = self.focus_path.indices.clone();
+        self.bubble_along(&path, envelope)
+    }
+
+    /// Find the path of child indices to the innermost widget under a
+    /// screen cell.
+    ///
+    /// Where children overlap, the last one laid out (drawn on top) wins.
+    /// The path ends at the first widget that doesn't report where its
+    /// children are placed (see [`Widget::child_regions`]). Positions come
+    /// from laying the tree out at the size of the last
+    /// [`resize`](Self::resize); before that the path is empty.
+    pub fn hit_path(&mut self, column: u16, row: u16) -> Vec<usize> {
+        self.hit_test(column, row).indices().to_vec()
+    }
+
+    /// Like [`hit_path`](Self::hit_path), also recording the region each
+    /// widget on the path was laid out in.
+    pub fn hit_test(&mut self, column: u16, row: u16) -> HitPath {
+        let size = self.size.unwrap_or(Size::new(0, 0));
+        let viewport = Viewport {
+            width: size.width as i32,
+            height: size.height as i32,
+        };
+        let (x, y) = (column as i32, row as i32);
+
+        let mut region = Region::new(0, 0, viewport.width, viewport.height);
+        let mut path = HitPath::root(region);
+        while let Some(widget) = widget_at_path(self.root.as_mut(), path.indices()) {
+            let hit = widget
+                .child_regions(region, viewport)
+                .into_iter()
+                .rev()
+                .find(|(_, child)| child.contains_point(x, y));
+            let Some((index, child_region)) = hit else {
+                break;
+            };
+            path.push(index, child_region);
+            region = child_region;
+        }
+        path
+    }
+
+    /// Deliver the [`PointerEvent`](crate::mouse::PointerEvent)s a raw
+    /// mouse event gives rise to.
+    ///
+    /// `tracker` remembers hover, presses and recent clicks between calls.
+    /// Each widget's [`on_pointer`](Widget::on_pointer) message is bubbled
+    /// through its ancestors; the envelopes come back for the app to
+    /// handle, in order. Widgets that get an event are scheduled for a
+    /// repaint.
+    pub fn dispatch_pointer(
+        &mut self,
+        tracker: &mut PointerTracker,
+        event: MouseEvent,
+        now: Instant,
+    ) -> Vec<MessageEnvelope<M>> {
+        let hit = self.hit_test(event.column, event.row);
+        let mut envelopes = Vec::new();
+        for target in tracker.track(&event, hit, now) {
+            let Some(widget) = widget_at_path(self.root.as_mut(), &target.path) else {
+                continue;
+            };
+            // Pointer handlers may change what the widget draws (e.g.
+            // hover or drag state) without marking it dirty
+            self.pending_repaints.push(widget_key(&*widget));
+            if let Some(message) = widget.on_pointer(&target.event) {
+                let sender = widget.sender_info();
+                let envelope =
+                    MessageEnvelope::new(message, sender.id.as_deref(), sender.type_name);
+                envelopes.push(self.bubble_along(&target.path, envelope));
+            }
+        }
+        envelopes
+    }
+
+    /// Bubble a mouse-originated message up from its sender to ancestors.
+    ///
+    /// `hit_path` is the [`hit_path`](Self::hit_path) under the cursor when
+    /// the event was dispatched. The sender is looked up on it by ID and
+    /// type, innermost first, and each of its ancestors gets a chance to
+    /// intercept the message via `handle_message`, just like
+    /// [`bubble_message`](Self::bubble_message) does for key messages. A
+    /// sender that isn't on the path (e.g. a modal overlay) bubbles nowhere.
+    pub fn bubble_mouse_message(
+        &mut self,
+        envelope: MessageEnvelope<M>,
+        hit_path: &[usize],
+    ) -> MessageEnvelope<M> {
+        let sender_depth = (0..=hit_path.len()).rev().find(|&depth| {
+            widget_at_path(self.root.as_mut(), &hit_path[..depth]).is_some_and(|widget| {
+                widget.type_name() == envelope.sender_type
+                    && widget.id() == envelope.sender_id.as_deref()
+            })
+        });
+        match sender_depth {
+            Some(depth) => self.bubble_along(&hit_path[..depth], envelope),
+            None => envelope,
+        }
+    }
+
+    /// Deliver a message posted with
+    /// [`AppContext::post_to`](crate::AppContext::post_to) to the first
+    /// widget matching its `target`, then bubble it through that widget's
+    /// ancestors.
+    ///
+    /// Returns the final envelope (possibly transformed by handlers) for the
+    /// app. Envelopes without a target, or whose target matches nothing,
+    /// come back untouched.
+    ///
+    /// Targets are TCSS selector lists, matched as stylesheet selectors are
+    /// (classes, pseudo-classes and combinators included), with `theme`
+    /// deciding theme pseudo-classes like `:dark`. Type selectors match
+    /// [`Widget::type_name`], as queries do.
+    pub fn deliver_message(
+        &mut self,
+        mut envelope: MessageEnvelope<M>,
+        theme: &Theme,
+    ) -> MessageEnvelope<M> {
+        let Some(target) = envelope.target.as_deref() else {
+            return envelope;
+        };
+        let selectors = match tcss::parser::parse_selector_list(target) {
+            Ok((rest, selectors)) if rest.trim().is_empty() => selectors,
+            _ => {
+                log::warn!("Invalid message target {target:?}");
+                return envelope;
+            }
+        };
+        let Some(path) = first_match_path(self.root.as_mut(), &selectors, theme) else {
+            log::warn!("No widget matches message target {target:?}");
+            return envelope;
+        };
 
-        // Walk the path in reverse (from focused widget's parent up to root)
-        // Skip the focused widget itself (it produced the message)
+        if let Some(widget) = widget_at_path(self.root.as_mut(), &path) {
+            self.pending_repaints.push(widget_key(&*widget));
+        }
+        if let Some(new_msg) =
+            navigate_and_handle(self.root.as_mut(), &path, &mut envelope, self.app.as_ref())
+        {
+            envelope.message = new_msg;
+        }
+        self.bubble_along(&path, envelope)
+    }
+
+    /// Offer `envelope` to the ancestors of the widget at `path`, innermost
+    /// first, until one stops it.
+    fn bubble_along(
+        &mut self,
+        path: &[usize],
+        mut envelope: MessageEnvelope<M>,
+    ) -> MessageEnvelope<M> {
+        // Walk the path in reverse (from the sender's parent up to root)
+        // Skip the sender itself (it produced the message)
         // When depth=0, ancestor_path is empty [], so navigate_and_handle calls root directly
         for depth in (0..path.len()).rev() {
             if !envelope.is_bubbling() {
@@ -420,9 +1269,12 @@ impl<M> WidgetTree<M> {
 
             // Navigate to the ancestor at this depth and call handle_message
             let ancestor_path = &path[..depth];
-            if let Some(new_msg) =
-                navigate_and_handle(self.root.as_mut(), ancestor_path, &mut envelope)
-            {
+            if let Some(new_msg) = navigate_and_handle(
+                self.root.as_mut(),
+                ancestor_path,
+                &mut envelope,
+                self.app.as_ref(),
+            ) {
                 envelope.message = new_msg;
             }
         }
@@ -504,13 +1356,16 @@ pub fn find_hovered_tooltip_mut<M>(widget: &mut dyn Widget<M>) -> Option<String>
     found
 }
 
-/// Navigate to a widget at the given path and call handle_message on it.
+/// Navigate to a widget at the given path and offer it the message.
 ///
-/// This is a free function to avoid borrow conflicts in bubble_message.
+/// Calls `on_message` with a context bound to the widget when the tree is
+/// attached to an app, and `handle_message` otherwise. This is a free
+/// function to avoid borrow conflicts in bubble_message.
 fn navigate_and_handle<M>(
     mut widget: &mut dyn Widget<M>,
     path: &[usize],
     envelope: &mut MessageEnvelope<M>,
+    app: Option<&AppContext<M>>,
 ) -> Option<M> {
     for &index in path {
         match widget.get_child_mut(index) {
@@ -518,7 +1373,13 @@ fn navigate_and_handle<M>(
             None => return None,
         }
     }
-    widget.handle_message(envelope)
+    match app {
+        Some(app) => {
+            let ctx = lifecycle_context(&*widget, app);
+            widget.on_message(envelope, &ctx)
+        }
+        None => widget.handle_message(envelope),
+    }
 }
 
 /// Recursively find the path to the nth focusable widget.
@@ -553,36 +1414,206 @@ fn find_focus_path_recursive<M>(
     false
 }
 
-/// Recursively compute the focus index for a widget with the given ID.
-fn find_focus_index_by_id<M>(
+/// Collect the focusable widgets and focus traps under `widget`, in tree
+/// order, numbering focusables the way [`Widget::focus_nth`] does.
+fn collect_focusables<M>(
     widget: &mut dyn Widget<M>,
-    id: &str,
-    index: &mut usize,
-) -> Option<usize> {
-    if !widget.participates_in_layout() {
-        return None;
+    path: &mut Vec<usize>,
+    tabbable: bool,
+    next_index: &mut usize,
+    focusables: &mut Vec<Focusable>,
+    traps: &mut Vec<Vec<usize>>,
+) {
+    if widget.is_focus_trap() {
+        traps.push(path.clone());
     }
     if widget.is_focusable() {
-        if widget.id() == Some(id) {
-            return Some(*index);
+        focusables.push(Focusable {
+            index: *next_index,
+            path: path.clone(),
+            tab_index: widget.tab_index(),
+            tabbable,
+        });
+        *next_index += 1;
+    }
+
+    let tabbable = tabbable && widget.can_focus_children();
+    for i in 0..widget.child_count() {
+        if let Some(child) = widget.get_child_mut(i)
+            && child.participates_in_layout()
+        {
+            path.push(i);
+            collect_focusables(child, path, tabbable, next_index, focusables, traps);
+            path.pop();
         }
-        *index += 1;
     }
+}
 
-    let child_count = widget.child_count();
-    for i in 0..child_count {
-        if let Some(child) = widget.get_child_mut(i) {
-            if let Some(found) = find_focus_index_by_id(child, id, index) {
-                return Some(found);
-            }
+/// Whether the widget at `path` is focusable and it and its ancestors below
+/// `widget` are laid out.
+fn can_take_focus<M>(mut widget: &mut dyn Widget<M>, path: &[usize]) -> bool {
+    for &index in path {
+        match widget.get_child_mut(index) {
+            Some(child) if child.participates_in_layout() => widget = child,
+            _ => return false,
         }
     }
-    None
+    widget.is_focusable()
 }
 
-/// Recursively find a widget by ID and apply a closure.
-///
-/// Performs depth-first search to find the first widget with the matching ID.
+/// Length of the prefix of `path` that leads to the innermost focus trap on
+/// it (the widget at `path` included), if any.
+fn innermost_trap<M>(root: &mut dyn Widget<M>, path: &[usize]) -> Option<usize> {
+    (0..=path.len())
+        .rev()
+        .find(|&depth| widget_at_path(root, &path[..depth]).is_some_and(|w| w.is_focus_trap()))
+}
+
+/// A context for `widget`'s lifecycle hooks, bound to its sender info and
+/// owning the workers it starts.
+fn lifecycle_context<M>(widget: &dyn Widget<M>, app: &AppContext<M>) -> AppContext<M> {
+    app.clone()
+        .with_sender_info(widget.id(), widget.type_name())
+        .with_owner(widget_key(widget))
+}
+
+/// Call `on_mount` on `widget` and its descendants, children first.
+fn mount_subtree<M>(widget: &mut dyn Widget<M>, app: &AppContext<M>) {
+    widget.for_each_child(&mut |child| mount_subtree(child, app));
+    let ctx = lifecycle_context(&*widget, app);
+    widget.on_mount(&ctx);
+}
+
+/// Call `on_unmount` on `widget` and its descendants, children first,
+/// cancel their workers and timers and forget that they were shown.
+fn unmount_subtree<M>(widget: &mut dyn Widget<M>, app: &AppContext<M>, shown: &mut HashSet<usize>) {
+    widget.for_each_child(&mut |child| unmount_subtree(child, app, shown));
+    let key = widget_key(&*widget);
+    shown.remove(&key);
+    let ctx = lifecycle_context(&*widget, app);
+    widget.on_unmount(&ctx);
+    app.workers().cancel_owned_by(key);
+    app.timers().cancel_owned_by(key);
+}
+
+/// Collect the widgets now shown into `now_shown`, calling `on_show` on
+/// those not in `was_shown` and `on_hide` on those that no longer are.
+///
+/// A widget is shown if it and its ancestors are displayed and its own
+/// (inherited) `visibility` is visible.
+fn update_shown<M>(
+    widget: &mut dyn Widget<M>,
+    parent_displayed: bool,
+    app: &AppContext<M>,
+    was_shown: &HashSet<usize>,
+    now_shown: &mut HashSet<usize>,
+) {
+    let displayed = parent_displayed && widget.participates_in_layout();
+    let shown = displayed && widget.get_style().visibility == Visibility::Visible;
+    let key = widget_key(&*widget);
+    if shown {
+        now_shown.insert(key);
+    }
+    if shown != was_shown.contains(&key) {
+        let ctx = lifecycle_context(&*widget, app);
+        if shown {
+            widget.on_show(&ctx);
+        } else {
+            widget.on_hide(&ctx);
+        }
+    }
+    widget.for_each_child(&mut |child| {
+        update_shown(child, displayed, app, was_shown, now_shown);
+    });
+}
+
+/// Collect the identity of `widget` and all its descendants.
+fn collect_widget_keys<M>(widget: &mut dyn Widget<M>, keys: &mut HashSet<usize>) {
+    keys.insert(widget_key(&*widget));
+    widget.for_each_child(&mut |child| collect_widget_keys(child, keys));
+}
+
+/// Find the path from `widget` to the widget with identity `key`.
+fn path_to_key<M>(widget: &mut dyn Widget<M>, key: usize, path: &mut Vec<usize>) -> bool {
+    if widget_key(&*widget) == key {
+        return true;
+    }
+    for i in 0..widget.child_count() {
+        if let Some(child) = widget.get_child_mut(i) {
+            path.push(i);
+            if path_to_key(child, key, path) {
+                return true;
+            }
+            path.pop();
+        }
+    }
+    false
+}
+
+/// Navigate from `widget` along `path`.
+fn widget_at_path<'w, M>(
+    mut widget: &'w mut dyn Widget<M>,
+    path: &[usize],
+) -> Option<&'w mut dyn Widget<M>> {
+    for &index in path {
+        widget = widget.get_child_mut(index)?;
+    }
+    Some(widget)
+}
+
+/// Recursively compute the focus index of the focused widget.
+fn find_focused_index<M>(widget: &mut dyn Widget<M>, index: &mut usize) -> Option<usize> {
+    if !widget.participates_in_layout() {
+        return None;
+    }
+    if widget.is_focusable() {
+        if widget.is_focused() {
+            return Some(*index);
+        }
+        *index += 1;
+    }
+
+    let child_count = widget.child_count();
+    for i in 0..child_count {
+        if let Some(child) = widget.get_child_mut(i)
+            && let Some(found) = find_focused_index(child, index)
+        {
+            return Some(found);
+        }
+    }
+    None
+}
+
+/// Recursively compute the focus index for a widget with the given ID.
+fn find_focus_index_by_id<M>(
+    widget: &mut dyn Widget<M>,
+    id: &str,
+    index: &mut usize,
+) -> Option<usize> {
+    if !widget.participates_in_layout() {
+        return None;
+    }
+    if widget.is_focusable() {
+        if widget.id() == Some(id) {
+            return Some(*index);
+        }
+        *index += 1;
+    }
+
+    let child_count = widget.child_count();
+    for i in 0..child_count {
+        if let Some(child) = widget.get_child_mut(i) {
+            if let Some(found) = find_focus_index_by_id(child, id, index) {
+                return Some(found);
+            }
+        }
+    }
+    None
+}
+
+/// Recursively find a widget by ID and apply a closure.
+///
+/// Performs depth-first search to find the first widget with the matching ID.
 /// Returns true if the widget was found and the closure was applied.
 fn find_and_apply_by_id<M, F>(widget: &mut dyn Widget<M>, id: &str, f: &mut F) -> bool
 where
@@ -638,12 +1669,16 @@ where
 /// - `#id` - ID only
 /// - `Type` - Type name only
 /// - `Type#id` - Both type and ID
+/// - `[attr]`, `[attr=value]`, `[attr^=value]`, `[attr$=value]`,
+///   `[attr*=value]` - attribute constraints, alone or after any of the above
 #[derive(Debug, Clone, PartialEq)]
 pub struct SimpleSelector {
     /// Type name constraint (e.g., "Label", "Button")
     pub type_name: Option<String>,
     /// ID constraint (e.g., "my-label")
     pub id: Option<String>,
+    /// Attribute constraints (e.g., `[type=password]`)
+    pub attributes: Vec<AttributeSelector>,
 }
 
 impl SimpleSelector {
@@ -663,8 +1698,22 @@ impl SimpleSelector {
             }
         }
 
+        // Check attribute constraints
+        if !self.attributes.is_empty() {
+            let attributes = widget.attributes();
+            let value = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|(attribute, _)| *attribute == name)
+                    .map(|(_, value)| value.as_str())
+            };
+            if !self.attributes.iter().all(|a| a.matches(value(&a.name))) {
+                return false;
+            }
+        }
+
         // If no constraints, match nothing (empty selector)
-        self.type_name.is_some() || self.id.is_some()
+        self.type_name.is_some() || self.id.is_some() || !self.attributes.is_empty()
     }
 }
 
@@ -674,47 +1723,60 @@ impl SimpleSelector {
 /// - `"#my-id"` → ID selector
 /// - `"Label"` → Type selector
 /// - `"Button#submit"` → Combined Type#ID
+/// - `"Input[type=password]"` → Type with an attribute constraint
 pub fn parse_simple_selector(selector: &str) -> SimpleSelector {
     let selector = selector.trim();
 
-    if selector.is_empty() {
-        return SimpleSelector {
-            type_name: None,
-            id: None,
-        };
+    // Split off attribute constraints: "Input#pw[type=password]"
+    let (selector, attributes) = match selector.find('[') {
+        Some(start) => (
+            selector[..start].trim(),
+            parse_attributes(&selector[start..]),
+        ),
+        None => (selector, Vec::new()),
+    };
+    let (type_name, id) = parse_type_and_id(selector);
+
+    SimpleSelector {
+        type_name,
+        id,
+        attributes,
     }
+}
+
+/// Parse the `Type`, `#id` or `Type#id` part of a simple selector.
+fn parse_type_and_id(selector: &str) -> (Option<String>, Option<String>) {
+    let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
 
     // Check for ID-only selector: "#my-id"
-    if selector.starts_with('#') {
-        return SimpleSelector {
-            type_name: None,
-            id: Some(selector[1..].to_string()),
-        };
+    if let Some(id) = selector.strip_prefix('#') {
+        return (None, non_empty(id));
     }
 
     // Check for combined Type#ID selector: "Button#submit"
-    if let Some(hash_pos) = selector.find('#') {
-        let type_name = &selector[..hash_pos];
-        let id = &selector[hash_pos + 1..];
-        return SimpleSelector {
-            type_name: if type_name.is_empty() {
-                None
-            } else {
-                Some(type_name.to_string())
-            },
-            id: if id.is_empty() {
-                None
-            } else {
-                Some(id.to_string())
-            },
-        };
+    if let Some((type_name, id)) = selector.split_once('#') {
+        return (non_empty(type_name), non_empty(id));
     }
 
     // Type-only selector: "Label"
-    SimpleSelector {
-        type_name: Some(selector.to_string()),
-        id: None,
+    (non_empty(selector), None)
+}
+
+/// Parse a run of `[...]` attribute selectors.
+fn parse_attributes(mut input: &str) -> Vec<AttributeSelector> {
+    let mut attributes = Vec::new();
+    while let Some(rest) = input.trim_start().strip_prefix('[') {
+        let (parsed, after) = match rest.split_once(']') {
+            Some((content, after)) => (AttributeSelector::parse(content), after),
+            None => (None, ""),
+        };
+        // An unparseable constraint keeps its raw text (brackets included)
+        // as a name no widget has, so the selector matches nothing rather
+        // than everything
+        attributes.push(parsed.unwrap_or_else(|| AttributeSelector::exists(input.trim())));
+        input = after;
     }
+    attributes
 }
 
 /// Recursively find a widget matching a selector and apply a closure.
@@ -1127,6 +2189,7 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::sync::mpsc;
 
     // A simple test widget for focus tests
     struct TestWidget {
@@ -2289,4 +3352,1097 @@ mod tests {
 
         assert!(first.is_none());
     }
+
+    // =========================================================================
+    // Damage tracking tests
+    // =========================================================================
+
+    type TestSwitch = crate::widget::switch::Switch<(), fn(bool)>;
+
+    fn damage_test_tree(second_on: bool) -> (WidgetTree<()>, StyleSheet, Theme) {
+        let children: Vec<Box<dyn Widget<()>>> = vec![
+            Box::new(TestSwitch::new(false, |_| ()).with_id("first")),
+            Box::new(TestSwitch::new(second_on, |_| ()).with_id("second")),
+            Box::new(crate::widget::label::Label::new("Footer")),
+        ];
+        let root = Box::new(crate::widget::screen::Screen::new(children));
+        let mut tree = WidgetTree::new(root);
+        tree.root_mut().on_resize(crate::Size::new(30, 12));
+
+        let stylesheet =
+            crate::style_resolver::build_stylesheet(tree.root_mut(), "").expect("valid CSS");
+        let theme = Theme::standard_themes()
+            .get("textual-dark")
+            .cloned()
+            .expect("textual-dark theme");
+        (tree, stylesheet, theme)
+    }
+
+    fn render_frame(
+        tree: &mut WidgetTree<()>,
+        stylesheet: &StyleSheet,
+        theme: &Theme,
+        canvas: &mut Canvas,
+    ) {
+        tree.resolve_dirty_styles(stylesheet, theme, canvas);
+        tree.render(canvas, Region::from_u16(0, 0, 30, 12));
+    }
+
+    #[test]
+    fn test_new_tree_starts_with_full_damage() {
+        let (tree, _, _) = damage_test_tree(false);
+        assert!(tree.damage().is_full());
+    }
+
+    #[test]
+    fn test_repaint_damages_only_widget_region() {
+        let (mut tree, stylesheet, theme) = damage_test_tree(false);
+        let mut canvas = Canvas::new(30, 12);
+        render_frame(&mut tree, &stylesheet, &theme, &mut canvas);
+        canvas.end_frame();
+
+        tree.query_one_as::<TestSwitch, _, _>("#second", |switch| switch.set_value(true))
+            .expect("switch should exist");
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+
+        let damage = tree.damage();
+        assert!(!damage.is_full());
+        assert_eq!(damage.regions().len(), 1);
+        let damaged = damage.regions()[0];
+        assert!(damaged.height < 12, "only the switch should be damaged");
+    }
+
+    #[test]
+    fn test_partial_render_matches_full_render() {
+        let (mut tree, stylesheet, theme) = damage_test_tree(false);
+        let mut canvas = Canvas::new(30, 12);
+        render_frame(&mut tree, &stylesheet, &theme, &mut canvas);
+        canvas.end_frame();
+
+        tree.query_one_as::<TestSwitch, _, _>("#second", |switch| switch.set_value(true))
+            .expect("switch should exist");
+        render_frame(&mut tree, &stylesheet, &theme, &mut canvas);
+
+        let (mut expected_tree, stylesheet, theme) = damage_test_tree(true);
+        let mut expected = Canvas::new(30, 12);
+        render_frame(&mut expected_tree, &stylesheet, &theme, &mut expected);
+
+        assert_eq!(canvas.to_ansi_snapshot(), expected.to_ansi_snapshot());
+    }
+
+    #[test]
+    fn test_invalidate_at_damages_only_the_hit_widget() {
+        let (mut tree, stylesheet, theme) = damage_test_tree(false);
+        tree.resize(crate::Size::new(30, 12));
+        let mut canvas = Canvas::new(30, 12);
+        render_frame(&mut tree, &stylesheet, &theme, &mut canvas);
+        canvas.end_frame();
+
+        let key = tree
+            .query_one("#second", |switch| widget_key(&*switch))
+            .expect("switch should exist");
+        let region = canvas.widget_region(key).expect("switch was painted");
+        tree.invalidate_at(region.x as u16, region.y as u16);
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+
+        assert_eq!(tree.damage().regions(), &[region]);
+    }
+
+    #[test]
+    fn test_partial_render_forgets_regions_of_removed_widgets() {
+        let (mut tree, stylesheet, theme) = damage_test_tree(false);
+        let mut canvas = Canvas::new(30, 12);
+        render_frame(&mut tree, &stylesheet, &theme, &mut canvas);
+        canvas.end_frame();
+        // A widget that has since been dropped
+        let stale = usize::MAX;
+        canvas.record_widget_region(stale, Region::from_u16(0, 0, 5, 1), crate::Size::new(5, 1));
+
+        tree.query_one_as::<TestSwitch, _, _>("#second", |switch| switch.set_value(true))
+            .expect("switch should exist");
+        render_frame(&mut tree, &stylesheet, &theme, &mut canvas);
+
+        assert_eq!(canvas.widget_region(stale), None);
+        let key = tree
+            .query_one("#first", |switch| widget_key(&*switch))
+            .expect("switch should exist");
+        assert!(canvas.widget_region(key).is_some());
+    }
+
+    /// A widget relying on the default [`Widget::invalidation`].
+    #[derive(Default)]
+    struct HoverBox {
+        hovered: bool,
+        dirty: bool,
+        style: tcss::ComputedStyle,
+    }
+
+    impl Widget<()> for HoverBox {
+        fn render(&self, _canvas: &mut crate::Canvas, _region: crate::Region) {}
+
+        fn desired_size(&self) -> crate::Size {
+            crate::Size::new(10, 1)
+        }
+
+        fn get_state(&self) -> WidgetStates {
+            if self.hovered {
+                WidgetStates::HOVER
+            } else {
+                WidgetStates::empty()
+            }
+        }
+
+        fn set_hover(&mut self, is_hovered: bool) -> bool {
+            let changed = self.hovered != is_hovered;
+            self.hovered = is_hovered;
+            self.dirty |= changed;
+            changed
+        }
+
+        fn set_style(&mut self, style: tcss::ComputedStyle) {
+            self.style = style;
+        }
+
+        fn get_style(&self) -> tcss::ComputedStyle {
+            self.style.clone()
+        }
+
+        fn is_dirty(&self) -> bool {
+            self.dirty
+        }
+
+        fn mark_dirty(&mut self) {
+            self.dirty = true;
+        }
+
+        fn mark_clean(&mut self) {
+            self.dirty = false;
+        }
+    }
+
+    #[test]
+    fn test_hover_change_on_plain_widget_damages_only_its_region() {
+        let mut tree = WidgetTree::new(Box::new(crate::widget::screen::Screen::new(vec![
+            Box::new(HoverBox::default()) as Box<dyn Widget<()>>,
+            Box::new(crate::widget::label::Label::new("Footer")),
+        ])));
+        tree.resize(crate::Size::new(30, 12));
+        let stylesheet = crate::style_resolver::build_stylesheet(
+            tree.root_mut(),
+            "HoverBox:hover { background: red; }",
+        )
+        .expect("valid CSS");
+        let theme = Theme::new("test", true);
+        let mut canvas = Canvas::new(30, 12);
+        render_frame(&mut tree, &stylesheet, &theme, &mut canvas);
+        canvas.end_frame();
+
+        let key = tree
+            .query_one("HoverBox", |widget| {
+                widget.set_hover(true);
+                widget_key(&*widget)
+            })
+            .expect("widget should exist");
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+
+        let region = canvas.widget_region(key).expect("widget was painted");
+        assert_eq!(tree.damage().regions(), &[region]);
+    }
+
+    #[test]
+    fn test_clean_tree_produces_no_damage() {
+        let (mut tree, stylesheet, theme) = damage_test_tree(false);
+        let mut canvas = Canvas::new(30, 12);
+        render_frame(&mut tree, &stylesheet, &theme, &mut canvas);
+        canvas.end_frame();
+
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert!(tree.damage().is_empty());
+    }
+
+    #[test]
+    fn test_relayout_escalates_to_full_damage() {
+        let (mut tree, stylesheet, theme) = damage_test_tree(false);
+        let mut canvas = Canvas::new(30, 12);
+        render_frame(&mut tree, &stylesheet, &theme, &mut canvas);
+        canvas.end_frame();
+
+        // New text changes the label's desired size
+        tree.query_one_as::<crate::widget::label::Label<()>, _, _>("Label", |label| {
+            label.update("A longer footer")
+        })
+        .expect("label should exist");
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+
+        assert!(tree.damage().is_full());
+    }
+
+    #[test]
+    fn test_sibling_selector_restyles_later_siblings() {
+        let children: Vec<Box<dyn Widget<()>>> = ["a", "b", "c"]
+            .into_iter()
+            .map(|id| {
+                Box::new(crate::widget::label::Label::new(id).with_id(id)) as Box<dyn Widget<()>>
+            })
+            .collect();
+        // A container restyles only its dirty children, unlike Screen
+        let container: Box<dyn Widget<()>> =
+            Box::new(crate::containers::container::Container::new(children));
+        let mut tree = WidgetTree::new(Box::new(crate::widget::screen::Screen::new(vec![
+            container,
+        ])));
+        tree.root_mut().on_resize(crate::Size::new(30, 12));
+        let stylesheet =
+            tcss::parser::parse_stylesheet(".marked + Label { color: red; }").expect("valid CSS");
+        let theme = Theme::new("test", true);
+        let canvas = Canvas::new(30, 12);
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+
+        let is_red = |tree: &mut WidgetTree<()>, id: &str| {
+            tree.query_one(id, |w| w.get_style().color.is_some_and(|c| c.r == 255))
+                .expect("label should exist")
+        };
+        assert!(!is_red(&mut tree, "#b"));
+
+        tree.query_one("#a", |w| w.add_class("marked"))
+            .expect("label should exist");
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+
+        assert!(is_red(&mut tree, "#b"));
+        assert!(!is_red(&mut tree, "#c"));
+    }
+
+    #[test]
+    fn test_focus_within_follows_focus() {
+        use crate::containers::container::Container;
+        use crate::widget::button::Button;
+
+        let group = |id: &str| -> Box<dyn Widget<()>> {
+            Box::new(Container::new(vec![Box::new(Button::new("OK"))]).with_id(id))
+        };
+        // Nest the groups so the Screen's restyle-everything pass doesn't hide
+        // a missing invalidation
+        let body: Box<dyn Widget<()>> = Box::new(Container::new(vec![group("a"), group("b")]));
+        let mut tree = WidgetTree::new(Box::new(crate::widget::screen::Screen::new(vec![body])));
+        tree.root_mut().on_resize(crate::Size::new(30, 12));
+        let stylesheet =
+            tcss::parser::parse_stylesheet("Container:focus-within { background: red; }")
+                .expect("valid CSS");
+        let theme = Theme::new("test", true);
+        let canvas = Canvas::new(30, 12);
+
+        let is_red = |tree: &mut WidgetTree<()>, id: &str| {
+            tree.query_one(id, |w| w.get_style().background.is_some_and(|c| c.r == 255))
+                .expect("container should exist")
+        };
+
+        assert!(tree.set_focus_index(0));
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert!(is_red(&mut tree, "#a"));
+        assert!(!is_red(&mut tree, "#b"));
+
+        tree.focus_next();
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert!(!is_red(&mut tree, "#a"));
+        assert!(is_red(&mut tree, "#b"));
+    }
+
+    #[test]
+    fn test_parse_attribute_selector() {
+        use tcss::parser::AttributeOperator;
+
+        let selector = parse_simple_selector("Input#pw[type=password][placeholder^=Pass]");
+        assert_eq!(selector.type_name, Some("Input".to_string()));
+        assert_eq!(selector.id, Some("pw".to_string()));
+        assert_eq!(
+            selector.attributes,
+            vec![
+                AttributeSelector::new("type", AttributeOperator::Equals, "password"),
+                AttributeSelector::new("placeholder", AttributeOperator::Prefix, "Pass"),
+            ]
+        );
+
+        let selector = parse_simple_selector("[value]");
+        assert_eq!(selector.type_name, None);
+        assert_eq!(
+            selector.attributes,
+            vec![AttributeSelector::exists("value")]
+        );
+    }
+
+    #[test]
+    fn test_query_by_attribute() {
+        use crate::widget::button::Button;
+        use crate::widget::input::Input;
+
+        let mut tree = WidgetTree::new(Box::new(crate::widget::screen::Screen::new(vec![
+            Box::new(Input::new().with_id("user")),
+            Box::new(Input::new().with_id("secret").with_password(true)),
+            Box::new(
+                Button::new("Delete")
+                    .with_id("delete")
+                    .with_variant("error"),
+            ),
+        ])));
+
+        let id = |tree: &mut WidgetTree<()>, selector: &str| {
+            tree.query_one(selector, |w| w.id().map(str::to_string))
+                .flatten()
+        };
+        assert_eq!(id(&mut tree, "Input[type=password]"), Some("secret".into()));
+        assert_eq!(id(&mut tree, "Input[type=text]"), Some("user".into()));
+        assert_eq!(id(&mut tree, "[variant$=ror]"), Some("delete".into()));
+        assert_eq!(id(&mut tree, "[variant*=warn]"), None);
+        assert_eq!(id(&mut tree, "Input[type"), None);
+    }
+
+    #[test]
+    fn test_attribute_selector_restyles_on_change() {
+        use crate::widget::switch::Switch;
+
+        let mut tree = WidgetTree::new(Box::new(crate::widget::screen::Screen::new(vec![
+            Box::new(Switch::new(false, (|_| ()) as fn(bool)).with_id("toggle")),
+        ])));
+        tree.root_mut().on_resize(crate::Size::new(30, 12));
+        let stylesheet = tcss::parser::parse_stylesheet("Switch[value=true] { background: red; }")
+            .expect("valid CSS");
+        let theme = Theme::new("test", true);
+        let canvas = Canvas::new(30, 12);
+        let is_red = |tree: &mut WidgetTree<()>| {
+            tree.query_one("#toggle", |w| {
+                w.get_style().background.is_some_and(|c| c.r == 255)
+            })
+            .expect("switch should exist")
+        };
+
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert!(!is_red(&mut tree));
+
+        tree.query_one_as::<Switch<(), fn(bool)>, _, _>("#toggle", |s| s.set_value(true));
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert!(is_red(&mut tree));
+    }
+
+    #[test]
+    fn test_important_outranks_inline_style() {
+        use tcss::StyleOverride;
+        use tcss::types::RgbaColor;
+
+        let mut label = crate::widget::label::Label::<()>::new("hi").with_id("label");
+        let blue = RgbaColor::rgb(0, 0, 255);
+        label.set_inline_style(StyleOverride::new().color(blue.clone()).background(blue));
+        let mut tree = WidgetTree::new(Box::new(crate::widget::screen::Screen::new(vec![
+            Box::new(label),
+        ])));
+        let stylesheet =
+            tcss::parser::parse_stylesheet("Label { color: red !important; background: red; }")
+                .expect("valid CSS");
+        let theme = Theme::new("test", true);
+        tree.resolve_dirty_styles(&stylesheet, &theme, &Canvas::new(30, 12));
+
+        let (color, background) = tree
+            .query_one("#label", |w| {
+                let style = w.get_style();
+                (style.color.map(|c| c.r), style.background.map(|c| c.r))
+            })
+            .expect("label should exist");
+        assert_eq!(color, Some(255));
+        assert_eq!(background, Some(0));
+    }
+
+    /// A widget whose scoped default CSS styles the labels inside it.
+    struct Card {
+        children: Vec<Box<dyn Widget<()>>>,
+    }
+
+    impl Widget<()> for Card {
+        fn render(&self, _canvas: &mut crate::Canvas, _region: crate::Region) {}
+
+        fn desired_size(&self) -> crate::Size {
+            crate::Size::new(10, 3)
+        }
+
+        fn default_css(&self) -> &'static str {
+            "Label { background: red; }"
+        }
+
+        fn scoped_css(&self) -> bool {
+            true
+        }
+
+        fn child_count(&self) -> usize {
+            self.children.len()
+        }
+
+        fn get_child_mut(&mut self, index: usize) -> Option<&mut (dyn Widget<()> + '_)> {
+            self.children
+                .get_mut(index)
+                .map(|child| child.as_mut() as _)
+        }
+
+        fn for_each_child(&mut self, f: &mut dyn FnMut(&mut dyn Widget<()>)) {
+            for child in &mut self.children {
+                f(child.as_mut());
+            }
+        }
+    }
+
+    #[test]
+    fn test_build_stylesheet_keeps_default_css_below_app_css() {
+        use crate::widget::label::Label;
+
+        let card = Card {
+            children: vec![Box::new(Label::new("in").with_id("inside"))],
+        };
+        let mut tree = WidgetTree::new(Box::new(crate::widget::screen::Screen::new(vec![
+            Box::new(card),
+            Box::new(Label::new("out").with_id("outside")),
+        ])));
+        let stylesheet =
+            crate::style_resolver::build_stylesheet(tree.root_mut(), "* { width: 10; }")
+                .expect("valid CSS");
+        tree.resolve_dirty_styles(&stylesheet, &Theme::new("test", true), &Canvas::new(30, 12));
+
+        let style_of = |tree: &mut WidgetTree<()>, selector: &str| {
+            tree.query_one(selector, |w| {
+                let style = w.get_style();
+                (
+                    style.width.map(|width| width.value),
+                    style.background.map(|color| color.r),
+                )
+            })
+            .expect("label should exist")
+        };
+        // `*` is less specific than Label's default `Label { width: auto; }`
+        // but still wins, and Card's scoped rule only reaches its own labels
+        assert_eq!(style_of(&mut tree, "#inside"), (Some(10.0), Some(255)));
+        assert_eq!(style_of(&mut tree, "#outside"), (Some(10.0), None));
+    }
+
+    #[test]
+    fn test_media_queries_restyle_on_environment_change() {
+        use tcss::parser::MediaEnvironment;
+
+        let mut tree = WidgetTree::new(Box::new(crate::widget::screen::Screen::new(vec![
+            Box::new(crate::widget::label::Label::<()>::new("hi").with_id("label")),
+        ])));
+        let mut stylesheet = tcss::parser::parse_stylesheet(
+            "Label { background: red; } @media (max-width: 40) { Label { background: blue; } }",
+        )
+        .expect("valid CSS");
+        let theme = Theme::new("test", true);
+        let background = |tree: &mut WidgetTree<()>| {
+            tree.query_one("#label", |w| w.get_style().background.map(|c| c.b))
+                .expect("label should exist")
+        };
+
+        stylesheet.set_environment(MediaEnvironment::new(80, 24));
+        tree.resolve_dirty_styles(&stylesheet, &theme, &Canvas::new(80, 24));
+        assert_eq!(background(&mut tree), Some(0));
+
+        assert!(stylesheet.set_environment(MediaEnvironment::new(40, 24)));
+        tree.root_mut().mark_dirty();
+        tree.resolve_dirty_styles(&stylesheet, &theme, &Canvas::new(40, 24));
+        assert_eq!(background(&mut tree), Some(255));
+    }
+
+    #[test]
+    fn test_css_variables_restyle_widgets_that_use_them() {
+        let mut tree = WidgetTree::new(Box::new(crate::widget::screen::Screen::new(vec![
+            Box::new(crate::widget::label::Label::<()>::new("hi").with_id("label")),
+            Box::new(crate::widget::label::Label::<()>::new("bye").with_id("other")),
+        ])));
+        let mut stylesheet =
+            tcss::parser::parse_stylesheet("$tint: red;\n#label { background: $tint; }")
+                .expect("valid CSS");
+        let theme = Theme::new("test", true);
+        let canvas = Canvas::new(80, 24);
+        let background = |tree: &mut WidgetTree<()>| {
+            tree.query_one("#label", |w| w.get_style().background.map(|c| (c.r, c.b)))
+                .expect("label should exist")
+        };
+
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert_eq!(background(&mut tree), Some((255, 0)));
+
+        tree.set_css_variable("tint", "blue");
+        assert!(tree.apply_css_variables(&mut stylesheet, &theme));
+        // Only the widget using the variable is restyled
+        let dirty = |tree: &mut WidgetTree<()>, selector| {
+            tree.query_one(selector, |w| w.is_dirty())
+                .expect("widget should exist")
+        };
+        assert!(dirty(&mut tree, "#label"));
+        assert!(!dirty(&mut tree, "#other"));
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert_eq!(background(&mut tree), Some((0, 255)));
+
+        // Setting the same value again changes nothing
+        tree.set_css_variable("tint", "blue");
+        assert!(!tree.apply_css_variables(&mut stylesheet, &theme));
+
+        tree.remove_css_variable("tint");
+        assert!(tree.apply_css_variables(&mut stylesheet, &theme));
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert_eq!(background(&mut tree), Some((255, 0)));
+    }
+
+    // =========================================================================
+    // Mount / remove tests
+    // =========================================================================
+
+    fn mount_test_tree() -> (WidgetTree<()>, StyleSheet) {
+        let list = crate::Vertical::new(vec![
+            Box::new(TestSwitch::new(false, |_| ()).with_id("a")),
+            Box::new(TestSwitch::new(true, |_| ()).with_id("b")),
+        ])
+        .with_id("list");
+        let root = Box::new(crate::widget::screen::Screen::new(vec![
+            Box::new(list) as Box<dyn Widget<()>>,
+            Box::new(TestSwitch::new(false, |_| ()).with_id("c")),
+        ]));
+        let mut tree = WidgetTree::new(root);
+        tree.resize(crate::Size::new(30, 12));
+        let stylesheet =
+            crate::style_resolver::build_stylesheet(tree.root_mut(), "").expect("valid CSS");
+        (tree, stylesheet)
+    }
+
+    fn child_ids(tree: &mut WidgetTree<()>, parent: &str) -> Vec<String> {
+        tree.query_one(parent, |parent| {
+            (0..parent.child_count())
+                .map(|i| {
+                    let child = parent.get_child_mut(i).unwrap();
+                    child.id().unwrap_or(child.type_name()).to_string()
+                })
+                .collect()
+        })
+        .expect("parent should exist")
+    }
+
+    fn focused_id(tree: &mut WidgetTree<()>) -> Option<String> {
+        tree.with_focused(|w| w.id().map(str::to_string)).flatten()
+    }
+
+    #[test]
+    fn test_mount_inserts_subtree_in_place() {
+        let (mut tree, mut stylesheet) = mount_test_tree();
+        let theme = Theme::new("test", true);
+        let canvas = Canvas::new(30, 12);
+        assert!(tree.focus_by_id("b"));
+        tree.query_one("#a", |w| w.set_hover(true));
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+
+        let button = crate::widget::button::Button::<()>::new("New").with_id("new");
+        tree.mount("#list", Box::new(button), MountPosition::First)
+            .expect("Vertical holds children");
+        assert_eq!(child_ids(&mut tree, "#list"), ["new", "a", "b"]);
+        assert!(tree.damage().is_full());
+
+        // Untouched widgets keep their state, and focus follows its widget
+        assert_eq!(
+            tree.query_one("#a", |w| w.get_state().contains(WidgetStates::HOVER)),
+            Some(true)
+        );
+        assert_eq!(focused_id(&mut tree).as_deref(), Some("b"));
+        assert_eq!(tree.current_focus_index(), 2);
+
+        // The new widget type's defaults are added once, then styled
+        assert!(!stylesheet.rules().iter().any(|rule| {
+            rule.origin.as_ref().and_then(|o| o.source.as_deref()) == Some("Button.DEFAULT_CSS")
+        }));
+        assert!(tree.apply_mounted_css(&mut stylesheet).unwrap());
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        let min_width = tree.query_one("#new", |w| w.get_style().min_width);
+        assert_eq!(min_width, Some(Some(tcss::types::Scalar::cells(16.0))));
+
+        let another = crate::widget::button::Button::<()>::new("Another").with_id("another");
+        tree.mount("#list", Box::new(another), MountPosition::Index(2))
+            .unwrap();
+        assert!(!tree.apply_mounted_css(&mut stylesheet).unwrap());
+        assert_eq!(child_ids(&mut tree, "#list"), ["new", "a", "another", "b"]);
+        assert_eq!(tree.current_focus_index(), 3);
+
+        tree.mount(
+            "Screen",
+            Box::new(crate::widget::label::Label::<()>::new("end")),
+            MountPosition::Last,
+        )
+        .unwrap();
+        assert_eq!(child_ids(&mut tree, "Screen"), ["list", "c", "Label"]);
+    }
+
+    #[test]
+    fn test_mount_errors() {
+        let (mut tree, _) = mount_test_tree();
+        let label = || Box::new(crate::widget::label::Label::<()>::new("x"));
+        assert!(matches!(
+            tree.mount("#missing", label(), MountPosition::Last),
+            Err(TextualError::Mount(_))
+        ));
+        // A switch has no children to add to
+        let error = tree.mount("#a", label(), MountPosition::Last).unwrap_err();
+        assert!(error.to_string().contains("Switch"), "{error}");
+        assert_eq!(child_ids(&mut tree, "#list"), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_remove_detaches_matches_and_moves_focus() {
+        let (mut tree, _) = mount_test_tree();
+        assert!(tree.focus_by_id("c"));
+
+        // Removing a widget before the focused one keeps focus on it
+        assert_eq!(tree.remove("#a"), 1);
+        assert_eq!(focused_id(&mut tree).as_deref(), Some("c"));
+        assert_eq!(tree.current_focus_index(), 1);
+
+        // Removing the focused widget focuses the one now in its place, or
+        // the last one
+        assert_eq!(tree.remove("#c"), 1);
+        assert_eq!(focused_id(&mut tree).as_deref(), Some("b"));
+        assert_eq!(
+            tree.query_one("#b", |w| w.is_focused()),
+            Some(true),
+            "focus flag should move too"
+        );
+
+        // Matches inside a removed widget are removed with it
+        assert_eq!(tree.remove("Switch"), 1);
+        assert_eq!(tree.remove("Vertical"), 1);
+        assert_eq!(tree.remove("Screen"), 0, "the root stays");
+        assert_eq!(tree.focusable_count(), 0);
+        assert_eq!(focused_id(&mut tree), None);
+    }
+
+    // =========================================================================
+    // Lifecycle tests
+    // =========================================================================
+
+    type HookLog = std::rc::Rc<std::cell::RefCell<Vec<String>>>;
+
+    /// Records its lifecycle hooks as `"hook:id"`.
+    struct LifecycleWidget {
+        id: &'static str,
+        children: Vec<Box<dyn Widget<()>>>,
+        style: tcss::ComputedStyle,
+        visible: bool,
+        dirty: bool,
+        log: HookLog,
+    }
+
+    impl LifecycleWidget {
+        fn boxed(
+            id: &'static str,
+            log: &HookLog,
+            children: Vec<Box<dyn Widget<()>>>,
+        ) -> Box<dyn Widget<()>> {
+            Box::new(Self {
+                id,
+                children,
+                style: Default::default(),
+                visible: true,
+                dirty: true,
+                log: log.clone(),
+            })
+        }
+
+        fn record(&self, hook: &str) {
+            self.log.borrow_mut().push(format!("{hook}:{}", self.id));
+        }
+    }
+
+    impl Widget<()> for LifecycleWidget {
+        fn render(&self, _canvas: &mut crate::Canvas, _region: crate::Region) {}
+
+        fn desired_size(&self) -> crate::Size {
+            crate::Size::new(1, 1)
+        }
+
+        fn id(&self) -> Option<&str> {
+            Some(self.id)
+        }
+
+        fn set_style(&mut self, style: tcss::ComputedStyle) {
+            self.style = style;
+        }
+
+        fn get_style(&self) -> tcss::ComputedStyle {
+            self.style.clone()
+        }
+
+        fn is_visible(&self) -> bool {
+            self.visible
+        }
+
+        fn set_visible(&mut self, visible: bool) {
+            self.visible = visible;
+            self.dirty = true;
+        }
+
+        fn is_dirty(&self) -> bool {
+            self.dirty
+        }
+
+        fn mark_dirty(&mut self) {
+            self.dirty = true;
+        }
+
+        fn mark_clean(&mut self) {
+            self.dirty = false;
+        }
+
+        fn for_each_child(&mut self, f: &mut dyn FnMut(&mut dyn Widget<()>)) {
+            for child in &mut self.children {
+                f(child.as_mut());
+            }
+        }
+
+        fn child_count(&self) -> usize {
+            self.children.len()
+        }
+
+        fn get_child_mut(&mut self, index: usize) -> Option<&mut (dyn Widget<()> + '_)> {
+            self.children
+                .get_mut(index)
+                .map(|c| c.as_mut() as &mut dyn Widget<()>)
+        }
+
+        fn insert_child(
+            &mut self,
+            index: usize,
+            child: Box<dyn Widget<()>>,
+        ) -> std::result::Result<(), Box<dyn Widget<()>>> {
+            self.children.insert(index, child);
+            Ok(())
+        }
+
+        fn remove_child(&mut self, index: usize) -> Option<Box<dyn Widget<()>>> {
+            Some(self.children.remove(index))
+        }
+
+        fn on_mount(&mut self, ctx: &AppContext<()>) {
+            self.record("mount");
+            ctx.post(());
+        }
+
+        fn on_unmount(&mut self, _ctx: &AppContext<()>) {
+            self.record("unmount");
+        }
+
+        fn on_show(&mut self, _ctx: &AppContext<()>) {
+            self.record("show");
+        }
+
+        fn on_hide(&mut self, _ctx: &AppContext<()>) {
+            self.record("hide");
+        }
+    }
+
+    fn lifecycle_tree(log: &HookLog) -> WidgetTree<()> {
+        let a = LifecycleWidget::boxed("a", log, vec![LifecycleWidget::boxed("a1", log, vec![])]);
+        let b = LifecycleWidget::boxed("b", log, vec![]);
+        WidgetTree::new(LifecycleWidget::boxed("root", log, vec![a, b]))
+    }
+
+    #[test]
+    fn test_attach_and_detach_mount_children_first() {
+        let log = HookLog::default();
+        let mut tree = lifecycle_tree(&log);
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        tree.mount(
+            "#a",
+            LifecycleWidget::boxed("early", &log, vec![]),
+            MountPosition::Last,
+        )
+        .unwrap();
+        assert!(log.borrow().is_empty(), "hooks only run once attached");
+
+        tree.attach(AppContext::new(tx));
+        assert_eq!(
+            log.take(),
+            [
+                "mount:a1",
+                "mount:early",
+                "mount:a",
+                "mount:b",
+                "mount:root"
+            ]
+        );
+        // Messages posted from hooks carry the widget's sender info
+        let first = rx.try_recv().expect("on_mount posted a message");
+        assert_eq!(first.sender_id.as_deref(), Some("a1"));
+
+        tree.detach();
+        assert_eq!(
+            log.take(),
+            [
+                "unmount:a1",
+                "unmount:early",
+                "unmount:a",
+                "unmount:b",
+                "unmount:root"
+            ]
+        );
+        tree.detach();
+        assert!(log.borrow().is_empty(), "a detached tree unmounts nothing");
+    }
+
+    #[test]
+    fn test_mount_and_remove_run_hooks_while_attached() {
+        let log = HookLog::default();
+        let mut tree = lifecycle_tree(&log);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        tree.attach(AppContext::new(tx));
+        log.take();
+
+        let c = LifecycleWidget::boxed("c", &log, vec![LifecycleWidget::boxed("c1", &log, vec![])]);
+        tree.mount("#root", c, MountPosition::Index(1)).unwrap();
+        assert_eq!(log.take(), ["mount:c1", "mount:c"]);
+
+        assert_eq!(tree.remove("#a"), 1);
+        assert_eq!(log.take(), ["unmount:a1", "unmount:a"]);
+    }
+
+    #[test]
+    fn test_show_and_hide_follow_visibility() {
+        let log = HookLog::default();
+        let mut tree = lifecycle_tree(&log);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        tree.attach(AppContext::new(tx));
+        log.take();
+
+        let stylesheet =
+            tcss::parser::parse_stylesheet("#b { visibility: hidden; }").expect("valid CSS");
+        let theme = Theme::new("test", true);
+        let canvas = Canvas::new(10, 5);
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert_eq!(log.take(), ["show:root", "show:a", "show:a1"]);
+
+        // Nothing changed, nothing to report
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert!(log.borrow().is_empty());
+
+        tree.query_one("#a", |w| w.set_visible(false));
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert_eq!(log.take(), ["hide:a", "hide:a1"]);
+
+        tree.query_one("#a", |w| w.set_visible(true));
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert_eq!(log.take(), ["show:a", "show:a1"]);
+
+        // Removed widgets are unmounted rather than hidden, and mounting a
+        // new widget shows it once styled
+        tree.remove("#a");
+        tree.mount(
+            "#root",
+            LifecycleWidget::boxed("d", &log, vec![]),
+            MountPosition::Last,
+        )
+        .unwrap();
+        tree.resolve_dirty_styles(&stylesheet, &theme, &canvas);
+        assert_eq!(log.take(), ["unmount:a1", "unmount:a", "mount:d", "show:d"]);
+    }
+
+    // =========================================================================
+    // Scroll-into-view tests
+    // =========================================================================
+
+    /// A six-row scroll container holding five three-row switches.
+    fn scroll_test_tree() -> WidgetTree<()> {
+        let switches = (0..5)
+            .map(|i| {
+                Box::new(TestSwitch::new(false, |_| ()).with_id(format!("s{i}")))
+                    as Box<dyn Widget<()>>
+            })
+            .collect();
+        let list = crate::VerticalScroll::new(switches).with_id("list");
+        let root = Box::new(crate::widget::screen::Screen::new(vec![
+            Box::new(list) as Box<dyn Widget<()>>
+        ]));
+        let mut tree = WidgetTree::new(root);
+        tree.resize(crate::Size::new(30, 12));
+        let stylesheet = crate::style_resolver::build_stylesheet(
+            tree.root_mut(),
+            "#list { height: 6; } Switch { height: 3; }",
+        )
+        .expect("valid CSS");
+        let theme = Theme::standard_themes()
+            .get("textual-dark")
+            .cloned()
+            .expect("textual-dark theme");
+        tree.resolve_dirty_styles(&stylesheet, &theme, &Canvas::new(30, 12));
+        tree
+    }
+
+    /// Bring `selector` into view, returning whether anything had to scroll.
+    fn scroll_into_view(tree: &mut WidgetTree<()>, selector: &str) -> bool {
+        let selector = parse_simple_selector(selector);
+        let mut paths = Vec::new();
+        collect_matching_paths(tree.root.as_mut(), &selector, &mut Vec::new(), &mut paths);
+        tree.scroll_path_visible(&paths[0], false, false)
+    }
+
+    #[test]
+    fn test_scroll_to_widget_scrolls_only_when_needed() {
+        let mut tree = scroll_test_tree();
+        assert!(!tree.scroll_to_widget("#missing", false, false));
+
+        // Already visible, nothing to do
+        assert!(!scroll_into_view(&mut tree, "#s0"));
+
+        assert!(tree.scroll_to_widget("#s4", false, false));
+        assert!(!scroll_into_view(&mut tree, "#s4"));
+        assert!(!scroll_into_view(&mut tree, "#s3"));
+
+        // The first switch is now above the viewport
+        assert!(scroll_into_view(&mut tree, "#s0"));
+    }
+
+    #[test]
+    fn test_focus_change_scrolls_widget_into_view() {
+        let mut tree = scroll_test_tree();
+
+        for _ in 0..4 {
+            tree.focus_next();
+        }
+        assert_eq!(focused_id(&mut tree).as_deref(), Some("s4"));
+        assert!(!scroll_into_view(&mut tree, "#s4"));
+
+        tree.focus_previous();
+        tree.focus_previous();
+        tree.focus_previous();
+        assert_eq!(focused_id(&mut tree).as_deref(), Some("s1"));
+        assert!(!scroll_into_view(&mut tree, "#s1"));
+        assert!(scroll_into_view(&mut tree, "#s4"));
+    }
+
+    #[test]
+    fn test_hit_path_accounts_for_scrolling() {
+        let mut tree = scroll_test_tree();
+        assert_eq!(tree.hit_path(1, 4), [0, 1]);
+        assert_eq!(tree.hit_path(1, 8), Vec::<usize>::new());
+
+        tree.scroll_to_widget("#s4", false, false);
+        assert_eq!(tree.hit_path(1, 4), [0, 4]);
+    }
+
+    // =========================================================================
+    // Focus chain tests
+    // =========================================================================
+
+    fn switch(id: &str) -> Box<dyn Widget<()>> {
+        Box::new(TestSwitch::new(false, |_| ()).with_id(id))
+    }
+
+    fn focus_tree(children: Vec<Box<dyn Widget<()>>>) -> WidgetTree<()> {
+        let root = Box::new(crate::widget::screen::Screen::new(children));
+        let mut tree = WidgetTree::new(root);
+        tree.set_focus_index(0);
+        tree
+    }
+
+    fn tab_order(tree: &mut WidgetTree<()>, steps: usize) -> Vec<String> {
+        (0..steps)
+            .map(|_| {
+                tree.focus_next();
+                focused_id(tree).unwrap_or_default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_tab_index_orders_focus_chain() {
+        let late = TestSwitch::new(false, |_| ())
+            .with_id("late")
+            .with_tab_index(1);
+        let early = TestSwitch::new(false, |_| ())
+            .with_id("early")
+            .with_tab_index(-1);
+        let mut tree = focus_tree(vec![
+            Box::new(late),
+            switch("a"),
+            Box::new(early),
+            switch("b"),
+        ]);
+
+        assert_eq!(tab_order(&mut tree, 4), ["early", "a", "b", "late"]);
+        tree.focus_previous();
+        assert_eq!(focused_id(&mut tree).as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn test_tab_skips_children_of_unfocusable_containers() {
+        let skipped =
+            crate::Container::new(vec![switch("hidden-from-tab")]).with_can_focus_children(false);
+        let mut tree = focus_tree(vec![switch("a"), Box::new(skipped), switch("b")]);
+
+        assert_eq!(tab_order(&mut tree, 3), ["b", "a", "b"]);
+        // Still focusable directly
+        assert!(tree.focus_by_id("hidden-from-tab"));
+    }
+
+    #[test]
+    fn test_tab_from_outside_the_chain_focuses_its_first_widget() {
+        let skipped =
+            crate::Container::new(vec![switch("hidden-from-tab")]).with_can_focus_children(false);
+        let mut tree = focus_tree(vec![switch("a"), Box::new(skipped), switch("b")]);
+
+        // The first Tab enters the chain at its start, Shift+Tab at its end
+        tree.focus_by_id("hidden-from-tab");
+        assert_eq!(tab_order(&mut tree, 1), ["a"]);
+        tree.focus_by_id("hidden-from-tab");
+        tree.focus_previous();
+        assert_eq!(focused_id(&mut tree).as_deref(), Some("b"));
+    }
+
+    #[test]
+    fn test_focus_trap_cycles_and_restores() {
+        let dialog = crate::Container::new(vec![switch("ok"), switch("cancel")])
+            .with_id("dialog")
+            .with_focus_trap(true);
+        let mut tree = focus_tree(vec![switch("a"), switch("b"), Box::new(dialog)]);
+        tree.focus_by_id("b");
+
+        // The dialog is showing, so Tab moves into it and stays there
+        assert_eq!(tab_order(&mut tree, 3), ["ok", "cancel", "ok"]);
+        assert!(!tree.restore_focus(), "focus is still valid");
+
+        // Hiding the dialog returns focus to where it came from
+        tree.query_one("#dialog", |dialog| {
+            let mut style = dialog.get_style();
+            style.display = tcss::types::Display::None;
+            dialog.set_style(style);
+        });
+        assert!(tree.restore_focus());
+        assert_eq!(focused_id(&mut tree).as_deref(), Some("b"));
+        assert_eq!(tab_order(&mut tree, 2), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_focus_events_report_changes() {
+        let mut tree = focus_tree(vec![switch("a"), switch("b")]);
+        let describe = |events: Vec<FocusEvent>| {
+            events
+                .into_iter()
+                .map(|event| match event {
+                    FocusEvent::FocusGained(info) => format!("+{}", info.id.unwrap()),
+                    FocusEvent::FocusLost(info) => format!("-{}", info.id.unwrap()),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(describe(tree.take_focus_events()), ["+a"]);
+        assert!(tree.take_focus_events().is_empty());
+
+        tree.focus_next();
+        assert_eq!(describe(tree.take_focus_events()), ["-a", "+b"]);
+
+        // Carried over to a rebuilt tree, unchanged focus isn't reported
+        let memory = tree.take_focus_memory();
+        let mut rebuilt = focus_tree(vec![switch("a"), switch("b")]);
+        rebuilt.set_focus_memory(memory);
+        rebuilt.set_focus_index(1);
+        assert!(rebuilt.take_focus_events().is_empty());
+    }
 }
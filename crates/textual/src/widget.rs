@@ -3,6 +3,7 @@ pub mod button;
 pub mod command_palette;
 pub mod header;
 pub mod input;
+pub mod inspector;
 pub mod label;
 pub mod loading_indicator;
 pub mod option_list;
@@ -18,9 +19,12 @@ pub mod tooltip;
 use tcss::{ComputedStyle, StyleOverride, WidgetMeta, WidgetStates};
 
 use crate::{
-    KeyCode, MouseEvent, Size,
+    AppContext, KeyCode, MouseEvent, Size,
     canvas::{Canvas, Region},
-    layouts::Layout,
+    damage::{Invalidation, widget_key},
+    layouts::{Layout, Viewport},
+    mouse::PointerEvent,
+    timer::TimerId,
 };
 
 /// Sender information extracted from a widget.
@@ -90,6 +94,42 @@ pub trait Widget<M> {
     /// The default implementation does nothing.
     fn on_resize(&mut self, _size: Size) {}
 
+    // =========================================================================
+    // Lifecycle
+    // =========================================================================
+
+    /// Called once the widget is attached to a running app: at startup, on
+    /// recomposition, or when mounted with
+    /// [`EventContext::mount`](crate::EventContext::mount). Descendants are
+    /// mounted before their parent.
+    ///
+    /// `ctx` is bound to this widget, so messages it posts carry the
+    /// widget's ID and type. Start [timers](crate::timer) or async loads
+    /// here; timers started through `ctx` are cancelled when the widget is
+    /// removed.
+    fn on_mount(&mut self, _ctx: &AppContext<M>) {}
+
+    /// Called when the widget is detached: removed, replaced by
+    /// recomposition, or the app is exiting. Descendants are unmounted
+    /// before their parent.
+    fn on_unmount(&mut self, _ctx: &AppContext<M>) {}
+
+    /// Called when the widget becomes visible on screen: its `display`,
+    /// `visibility` or runtime visibility (or an ancestor's) changed, or it
+    /// was drawn for the first time.
+    fn on_show(&mut self, _ctx: &AppContext<M>) {}
+
+    /// Called when a shown widget is hidden by `display: none`,
+    /// `visibility: hidden` or [`set_visible`](Self::set_visible) on it or
+    /// an ancestor.
+    fn on_hide(&mut self, _ctx: &AppContext<M>) {}
+
+    /// Called when a [timer](crate::timer) this widget started with
+    /// [`AppContext::start_timer`] or [`AppContext::start_interval`] fires.
+    ///
+    /// Mark the widget dirty if the timer changed what it renders.
+    fn on_timer(&mut self, _timer: TimerId, _ctx: &AppContext<M>) {}
+
     /// Returns the widget's current pseudo-class states (focus, hover, active, disabled).
     ///
     /// Override this in widgets that support interactive states.
@@ -123,16 +163,27 @@ pub trait Widget<M> {
             id: self.id().map(|s| s.to_string()),
             classes: Vec::new(),
             states: self.get_state(),
+            ..Default::default()
         }
     }
 
+    /// Returns the widget's attributes for attribute selectors.
+    ///
+    /// Each pair is an attribute name and its current value, matched by
+    /// selectors such as `Input[type=password]` or `Switch[value=true]`.
+    /// Widgets should mark themselves dirty when a value changes.
+    fn attributes(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+
     // Default style management
 
     /// Returns the default CSS for this widget type.
     ///
     /// Override this method to provide built-in styles for your widget.
-    /// Default CSS has lower precedence than app-level CSS, allowing users
-    /// to override widget defaults in their `App::CSS`.
+    /// Default CSS forms a lower cascade tier than app-level CSS: any
+    /// `App::CSS` rule overrides a default rule for the same property,
+    /// however specific the default's selector.
     ///
     /// The base Widget implementation provides sensible defaults for all widgets,
     /// including scrollbar theming. Override this in subclasses to add widget-specific
@@ -157,6 +208,15 @@ pub trait Widget<M> {
         ""
     }
 
+    /// Whether this widget's `default_css()` is scoped to its own subtree.
+    ///
+    /// Scoped default rules only match widgets of this type and their
+    /// descendants, so a default like `Label { ... }` in a composite widget
+    /// doesn't leak onto labels elsewhere in the app.
+    fn scoped_css(&self) -> bool {
+        false
+    }
+
     /// Returns the base widget CSS that applies to all widgets.
     ///
     /// This is collected once during style resolution and provides defaults
@@ -262,6 +322,23 @@ pub trait Widget<M> {
     /// Called by the style resolver after recomputing the widget's style.
     fn mark_clean(&mut self) {}
 
+    /// Reports what this widget needs redone before the next frame.
+    ///
+    /// Queried by the style resolver before the widget is marked clean.
+    /// The default asks for a repaint of any dirty widget. A repaint is
+    /// escalated to a relayout when the widget's
+    /// [`desired_size`](Self::desired_size) differs from when it was last
+    /// painted, and style changes are compared separately, so a
+    /// layout-affecting property change relayouts too. Widgets whose
+    /// geometry can change some other way should return
+    /// [`Invalidation::Relayout`].
+    ///
+    /// Containers whose `is_dirty` aggregates their children should report
+    /// only their own state here.
+    fn invalidation(&self) -> Option<Invalidation> {
+        self.is_dirty().then_some(Invalidation::Repaint)
+    }
+
     /// Handle a key event and optionally return a message.
     fn on_event(&mut self, _key: KeyCode) -> Option<M> {
         None
@@ -287,6 +364,19 @@ pub trait Widget<M> {
             .map(|msg| (msg, self.sender_info()))
     }
 
+    /// Handle a synthesized mouse event: enter/leave, counted clicks and
+    /// drags.
+    ///
+    /// Delivered in addition to [`on_mouse`](Self::on_mouse), to every
+    /// widget the event concerns rather than by region hit-testing, with
+    /// coordinates relative to this widget. A widget that starts a drag
+    /// keeps receiving `DragMove` and `DragEnd` until the button is
+    /// released, wherever the cursor goes. Returned messages bubble through
+    /// this widget's ancestors.
+    fn on_pointer(&mut self, _event: &PointerEvent) -> Option<M> {
+        None
+    }
+
     /// Returns true if this widget should block mouse events from reaching widgets behind it.
     ///
     /// This is intended for modal overlays (e.g., command palette) that should capture
@@ -329,6 +419,32 @@ pub trait Widget<M> {
         false
     }
 
+    /// Position of this widget in the Tab order.
+    ///
+    /// Tab visits lower values first; widgets with equal values (the
+    /// default is 0) keep tree order. This only affects Tab and Shift+Tab:
+    /// focus indices always follow tree order.
+    fn tab_index(&self) -> i32 {
+        0
+    }
+
+    /// Returns false if Tab should skip this widget's descendants.
+    ///
+    /// They can still be focused explicitly (by ID, index or click).
+    fn can_focus_children(&self) -> bool {
+        true
+    }
+
+    /// Returns true if Tab should cycle inside this widget while it holds
+    /// focus, as for a modal dialog.
+    ///
+    /// Moving focus into a trap from outside remembers where it came from;
+    /// [`WidgetTree::restore_focus`](crate::tree::WidgetTree::restore_focus)
+    /// goes back there once the trap is hidden or removed.
+    fn is_focus_trap(&self) -> bool {
+        false
+    }
+
     // =========================================================================
     // Reactive Attributes (Visibility, Loading, Disabled)
     // =========================================================================
@@ -415,6 +531,53 @@ pub trait Widget<M> {
         None
     }
 
+    /// Inserts `child` before the child at `index` (at the end if `index`
+    /// is past the last child).
+    ///
+    /// Widgets that can't hold a variable number of children return the
+    /// child back. Used by [`WidgetTree::mount`](crate::tree::WidgetTree::mount).
+    fn insert_child(
+        &mut self,
+        _index: usize,
+        child: Box<dyn Widget<M>>,
+    ) -> Result<(), Box<dyn Widget<M>>> {
+        Err(child)
+    }
+
+    /// Removes and returns the child at `index`.
+    ///
+    /// Used by [`WidgetTree::remove`](crate::tree::WidgetTree::remove).
+    fn remove_child(&mut self, _index: usize) -> Option<Box<dyn Widget<M>>> {
+        None
+    }
+
+    /// Where each child is drawn when this widget is rendered in `region`,
+    /// as `(child index, region)` in screen coordinates with any scrolling
+    /// applied.
+    ///
+    /// Lets the tree locate widgets between frames, e.g. to scroll the
+    /// focused widget into view. Children left out count as not placed.
+    fn child_regions(&self, _region: Region, _viewport: Viewport) -> Vec<(usize, Region)> {
+        Vec::new()
+    }
+
+    /// Scroll so that `target`, part of this widget's content, is visible.
+    ///
+    /// `region` is where the widget is rendered and `target` is in screen
+    /// coordinates at the current scroll offset. With `center` the target
+    /// is centered even if it is already visible. Returns how far the
+    /// content moved, or `None` for widgets that don't scroll.
+    fn scroll_to_region(
+        &mut self,
+        _region: Region,
+        _viewport: Viewport,
+        _target: Region,
+        _animate: bool,
+        _center: bool,
+    ) -> Option<(i32, i32)> {
+        None
+    }
+
     /// Handle a message bubbling up from a descendant widget.
     ///
     /// Return `Some(M)` to transform the message, or `None` to pass it through unchanged.
@@ -423,6 +586,23 @@ pub trait Widget<M> {
         None
     }
 
+    /// Handle a message posted to this widget or bubbling up from a
+    /// descendant, with a context for posting follow-up messages.
+    ///
+    /// `ctx` is bound to this widget, so messages it posts name this widget
+    /// as their sender. Messages posted with
+    /// [`AppContext::post_to`](crate::AppContext::post_to) arrive here with
+    /// `envelope.target` set. The default forwards to
+    /// [`handle_message`](Self::handle_message), which is also what runs
+    /// when the tree isn't attached to an app.
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        _ctx: &AppContext<M>,
+    ) -> Option<M> {
+        self.handle_message(envelope)
+    }
+
     /// Returns the widget's optional ID for message tracking.
     ///
     /// Set via `widget.with_id("my-widget")`. Used to identify which widget
@@ -575,6 +755,14 @@ pub trait Widget<M> {
 /// Allow boxed widgets to be used as widgets.
 impl<M> Widget<M> for Box<dyn Widget<M>> {
     fn render(&self, canvas: &mut Canvas, region: Region) {
+        // Boxed widgets are the unit of damage tracking: skip those outside
+        // the damaged area and remember where the others were painted.
+        if !canvas.is_damaged(&region) {
+            return;
+        }
+        if self.as_ref().is_visible() {
+            canvas.record_widget_region(widget_key(self.as_ref()), region, self.desired_size());
+        }
         self.as_ref().render(canvas, region);
     }
 
@@ -611,10 +799,18 @@ impl<M> Widget<M> for Box<dyn Widget<M>> {
         self.as_ref().default_css()
     }
 
+    fn scoped_css(&self) -> bool {
+        self.as_ref().scoped_css()
+    }
+
     fn get_meta(&self) -> WidgetMeta {
         self.as_ref().get_meta()
     }
 
+    fn attributes(&self) -> Vec<(&'static str, String)> {
+        self.as_ref().attributes()
+    }
+
     fn is_dirty(&self) -> bool {
         self.as_ref().is_dirty()
     }
@@ -627,6 +823,10 @@ impl<M> Widget<M> for Box<dyn Widget<M>> {
         self.as_mut().mark_clean();
     }
 
+    fn invalidation(&self) -> Option<Invalidation> {
+        self.as_ref().invalidation()
+    }
+
     fn pre_layout(&mut self, layout: &mut dyn Layout) {
         self.as_mut().pre_layout(layout);
     }
@@ -647,6 +847,10 @@ impl<M> Widget<M> for Box<dyn Widget<M>> {
         self.as_mut().on_mouse_with_sender(event, region)
     }
 
+    fn on_pointer(&mut self, event: &PointerEvent) -> Option<M> {
+        self.as_mut().on_pointer(event)
+    }
+
     fn set_hover(&mut self, is_hovered: bool) -> bool {
         self.as_mut().set_hover(is_hovered)
     }
@@ -667,6 +871,18 @@ impl<M> Widget<M> for Box<dyn Widget<M>> {
         self.as_ref().is_focusable()
     }
 
+    fn tab_index(&self) -> i32 {
+        self.as_ref().tab_index()
+    }
+
+    fn can_focus_children(&self) -> bool {
+        self.as_ref().can_focus_children()
+    }
+
+    fn is_focus_trap(&self) -> bool {
+        self.as_ref().is_focus_trap()
+    }
+
     fn is_visible(&self) -> bool {
         self.as_ref().is_visible()
     }
@@ -715,10 +931,46 @@ impl<M> Widget<M> for Box<dyn Widget<M>> {
         self.as_mut().get_child_mut(index)
     }
 
+    fn insert_child(
+        &mut self,
+        index: usize,
+        child: Box<dyn Widget<M>>,
+    ) -> Result<(), Box<dyn Widget<M>>> {
+        self.as_mut().insert_child(index, child)
+    }
+
+    fn remove_child(&mut self, index: usize) -> Option<Box<dyn Widget<M>>> {
+        self.as_mut().remove_child(index)
+    }
+
+    fn child_regions(&self, region: Region, viewport: Viewport) -> Vec<(usize, Region)> {
+        self.as_ref().child_regions(region, viewport)
+    }
+
+    fn scroll_to_region(
+        &mut self,
+        region: Region,
+        viewport: Viewport,
+        target: Region,
+        animate: bool,
+        center: bool,
+    ) -> Option<(i32, i32)> {
+        self.as_mut()
+            .scroll_to_region(region, viewport, target, animate, center)
+    }
+
     fn handle_message(&mut self, envelope: &mut crate::MessageEnvelope<M>) -> Option<M> {
         self.as_mut().handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        ctx: &AppContext<M>,
+    ) -> Option<M> {
+        self.as_mut().on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.as_ref().id()
     }
@@ -737,6 +989,26 @@ impl<M> Widget<M> for Box<dyn Widget<M>> {
         self.as_mut().on_resize(size);
     }
 
+    fn on_mount(&mut self, ctx: &AppContext<M>) {
+        self.as_mut().on_mount(ctx);
+    }
+
+    fn on_unmount(&mut self, ctx: &AppContext<M>) {
+        self.as_mut().on_unmount(ctx);
+    }
+
+    fn on_show(&mut self, ctx: &AppContext<M>) {
+        self.as_mut().on_show(ctx);
+    }
+
+    fn on_hide(&mut self, ctx: &AppContext<M>) {
+        self.as_mut().on_hide(ctx);
+    }
+
+    fn on_timer(&mut self, timer: TimerId, ctx: &AppContext<M>) {
+        self.as_mut().on_timer(timer, ctx);
+    }
+
     fn for_each_child(&mut self, f: &mut dyn FnMut(&mut dyn Widget<M>)) {
         self.as_mut().for_each_child(f);
     }
@@ -12,6 +12,8 @@
 //! Python Textual's behavior where App is the root of the DOM tree.
 
 use crate::canvas::{Canvas, Region, Size};
+use crate::damage::Invalidation;
+use crate::layouts::Viewport;
 use crate::widget::Widget;
 use crate::{KeyCode, MouseEvent};
 use tcss::{ComputedStyle, StyleOverride, WidgetMeta, WidgetStates};
@@ -87,6 +89,7 @@ App {
             classes: vec![],
             states: WidgetStates::empty(),
             id: None,
+            ..Default::default()
         }
     }
 
@@ -108,6 +111,33 @@ App {
         }
     }
 
+    fn insert_child(
+        &mut self,
+        index: usize,
+        child: Box<dyn Widget<M>>,
+    ) -> Result<(), Box<dyn Widget<M>>> {
+        self.children.insert(index.min(self.children.len()), child);
+        self.is_dirty = true;
+        Ok(())
+    }
+
+    fn remove_child(&mut self, index: usize) -> Option<Box<dyn Widget<M>>> {
+        if index >= self.children.len() {
+            return None;
+        }
+        let child = self.children.remove(index);
+        self.is_dirty = true;
+        Some(child)
+    }
+
+    fn child_regions(&self, region: Region, _viewport: Viewport) -> Vec<(usize, Region)> {
+        // Children are drawn over each other in the full region
+        (0..self.children.len())
+            .filter(|&i| self.children[i].participates_in_layout())
+            .map(|i| (i, region))
+            .collect()
+    }
+
     // Delegate state management
     fn is_dirty(&self) -> bool {
         self.is_dirty || self.children.iter().any(|child| child.is_dirty())
@@ -127,6 +157,11 @@ App {
         }
     }
 
+    fn invalidation(&self) -> Option<Invalidation> {
+        // `is_dirty` also reflects children; they report their own damage.
+        self.is_dirty.then_some(Invalidation::Relayout)
+    }
+
     fn set_style(&mut self, style: ComputedStyle) {
         self.style = style;
     }
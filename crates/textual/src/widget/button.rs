@@ -3,7 +3,7 @@ use std::cell::RefCell;
 use tcss::{ComputedStyle, StyleOverride, WidgetMeta, WidgetStates};
 
 use crate::{
-    Canvas, KeyCode, MouseEvent, MouseEventKind, Region, Size, Widget,
+    Canvas, KeyCode, MouseEvent, MouseEventKind, Region, Size, Widget, damage::Invalidation,
     widget::static_widget::Static,
 };
 
@@ -27,6 +27,16 @@ impl ButtonVariant {
         }
     }
 
+    fn name(self) -> &'static str {
+        match self {
+            ButtonVariant::Default => "default",
+            ButtonVariant::Primary => "primary",
+            ButtonVariant::Success => "success",
+            ButtonVariant::Warning => "warning",
+            ButtonVariant::Error => "error",
+        }
+    }
+
     fn parse(name: &str) -> Option<Self> {
         match name.trim().to_lowercase().as_str() {
             "default" => Some(ButtonVariant::Default),
@@ -52,6 +62,7 @@ pub struct Button<M> {
     action: Option<String>,
     on_press: Option<Box<dyn Fn() -> M>>,
     pending_action: RefCell<Option<String>>,
+    tab_index: i32,
 }
 
 impl<M: 'static> Button<M> {
@@ -73,6 +84,7 @@ impl<M: 'static> Button<M> {
             action: None,
             on_press: None,
             pending_action: RefCell::new(None),
+            tab_index: 0,
         }
     }
 
@@ -107,6 +119,12 @@ impl<M: 'static> Button<M> {
     }
 
     /// Set the button action to dispatch on press.
+    /// Set the button's position in the Tab order (lower comes first).
+    pub fn with_tab_index(mut self, tab_index: i32) -> Self {
+        self.tab_index = tab_index;
+        self
+    }
+
     pub fn with_action(mut self, action: impl Into<String>) -> Self {
         self.action = Some(action.into());
         self
@@ -151,6 +169,7 @@ impl<M: 'static> Button<M> {
             self.inner.remove_class(self.variant.class_name());
             self.variant = parsed;
             self.inner.add_class(self.variant.class_name());
+            self.dirty = true;
         }
     }
 
@@ -393,6 +412,10 @@ Button {
         meta
     }
 
+    fn attributes(&self) -> Vec<(&'static str, String)> {
+        vec![("variant", self.variant.name().to_string())]
+    }
+
     fn get_state(&self) -> WidgetStates {
         let mut states = WidgetStates::empty();
         if self.focused {
@@ -446,6 +469,15 @@ Button {
         self.inner.mark_clean();
     }
 
+    fn invalidation(&self) -> Option<Invalidation> {
+        // Label changes can resize the button; state changes only repaint.
+        if self.inner.is_dirty() {
+            Some(Invalidation::Relayout)
+        } else {
+            self.dirty.then_some(Invalidation::Repaint)
+        }
+    }
+
     fn id(&self) -> Option<&str> {
         self.inner.id()
     }
@@ -530,6 +562,10 @@ Button {
         !self.inner.is_disabled()
     }
 
+    fn tab_index(&self) -> i32 {
+        self.tab_index
+    }
+
     fn focus_nth(&mut self, n: usize) -> bool {
         if n == 0 {
             self.set_focus(true);
@@ -195,6 +195,14 @@ impl<M: 'static> Widget<M> for SearchIcon<M> {
         self.inner.handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        ctx: &crate::AppContext<M>,
+    ) -> Option<M> {
+        self.inner.on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.inner.id()
     }
@@ -417,6 +425,14 @@ impl<M: 'static> Widget<M> for CommandInput<M> {
         self.inner.handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        ctx: &crate::AppContext<M>,
+    ) -> Option<M> {
+        self.inner.on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.inner.id()
     }
@@ -671,6 +687,14 @@ impl<M: 'static> Widget<M> for CommandList<M> {
         self.inner.handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        ctx: &crate::AppContext<M>,
+    ) -> Option<M> {
+        self.inner.on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.inner.id()
     }
@@ -836,8 +860,6 @@ pub struct CommandPalette<M: 'static> {
     results_index: usize,
     list_index: usize,
     loading_index: usize,
-
-    restore_focus: Option<usize>,
 }
 
 impl<M: 'static> CommandPalette<M> {
@@ -929,7 +951,6 @@ impl<M: 'static> CommandPalette<M> {
             results_index: 1,
             list_index: 0,
             loading_index: 1,
-            restore_focus: None,
         };
 
         palette.refresh_results();
@@ -949,10 +970,14 @@ impl<M: 'static> CommandPalette<M> {
         }
     }
 
-    pub fn open_with_focus(&mut self, focus_index: usize) {
-        if !self.visible {
-            self.restore_focus = Some(focus_index);
-        }
+    /// Opens the palette.
+    ///
+    /// The palette is a focus trap, so the widget tree remembers where focus
+    /// was and restores it on close (see
+    /// [`WidgetTree::restore_focus`](crate::tree::WidgetTree::restore_focus));
+    /// `focus_index` is ignored.
+    #[deprecated(note = "use `open`; the widget tree restores focus when the palette closes")]
+    pub fn open_with_focus(&mut self, _focus_index: usize) {
         self.open();
     }
 
@@ -994,12 +1019,12 @@ impl<M: 'static> CommandPalette<M> {
         }
     }
 
+    /// Always `None`: the widget tree restores focus itself once the
+    /// palette closes (see
+    /// [`WidgetTree::restore_focus`](crate::tree::WidgetTree::restore_focus)).
+    #[deprecated(note = "the widget tree restores focus when the palette closes")]
     pub fn take_restore_focus(&mut self) -> Option<usize> {
-        if self.visible {
-            None
-        } else {
-            self.restore_focus.take()
-        }
+        None
     }
 
     pub fn take_events(&mut self) -> Vec<CommandPaletteEvent> {
@@ -1019,14 +1044,9 @@ impl<M: 'static> CommandPalette<M> {
         changed
     }
 
+    /// Does nothing: the busy spinner animates itself once mounted.
+    #[deprecated(note = "the palette's loading indicator animates itself")]
     pub fn tick(&mut self) -> bool {
-        if self.show_busy {
-            if let Some(loading) = self.loading_mut() {
-                loading.tick();
-                self.inner.mark_dirty();
-                return true;
-            }
-        }
         false
     }
 
@@ -1821,6 +1841,11 @@ CommandPalette LoadingIndicator.--visible {
         self.visible
     }
 
+    fn is_focus_trap(&self) -> bool {
+        // Modal: focus returns to where it was once the palette closes
+        self.visible
+    }
+
     fn set_focus(&mut self, is_focused: bool) {
         if self.focused != is_focused {
             self.focused = is_focused;
@@ -1890,6 +1915,14 @@ CommandPalette LoadingIndicator.--visible {
         self.inner.handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        ctx: &crate::AppContext<M>,
+    ) -> Option<M> {
+        self.inner.on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.inner.id()
     }
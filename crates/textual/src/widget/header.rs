@@ -143,6 +143,14 @@ HeaderTitle {
         self.inner.handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        ctx: &crate::AppContext<M>,
+    ) -> Option<M> {
+        self.inner.on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.inner.id()
     }
@@ -302,6 +310,14 @@ HeaderClockSpace {
         self.inner.handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        ctx: &crate::AppContext<M>,
+    ) -> Option<M> {
+        self.inner.on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.inner.id()
     }
@@ -578,6 +594,14 @@ HeaderIcon:hover {
         self.inner.handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        ctx: &crate::AppContext<M>,
+    ) -> Option<M> {
+        self.inner.on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.inner.id()
     }
@@ -862,6 +886,14 @@ Header.-tall {
         self.inner.handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        ctx: &crate::AppContext<M>,
+    ) -> Option<M> {
+        self.inner.on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.inner.id()
     }
@@ -34,7 +34,10 @@ pub struct Input<M> {
     placeholder: Option<String>,
     cursor: usize,
     focused: bool,
+    /// Mask the value, exposed as `type=password` to attribute selectors.
+    password: bool,
     dirty: bool,
+    tab_index: i32,
     _phantom: PhantomData<M>,
 }
 
@@ -48,7 +51,9 @@ impl<M> Input<M> {
             placeholder: None,
             cursor: 0,
             focused: false,
+            password: false,
             dirty: true,
+            tab_index: 0,
             _phantom: PhantomData,
         }
     }
@@ -64,6 +69,12 @@ impl<M> Input<M> {
         self
     }
 
+    /// Set the input's position in the Tab order (lower comes first).
+    pub fn with_tab_index(mut self, tab_index: i32) -> Self {
+        self.tab_index = tab_index;
+        self
+    }
+
     pub fn with_classes(mut self, classes: impl Into<String>) -> Self {
         self.inner = self.inner.with_classes(classes);
         self
@@ -79,6 +90,25 @@ impl<M> Input<M> {
         self
     }
 
+    /// Mask the value with bullets, as for a password field.
+    pub fn with_password(mut self, password: bool) -> Self {
+        self.password = password;
+        self.refresh_display();
+        self
+    }
+
+    pub fn set_password(&mut self, password: bool) {
+        if self.password != password {
+            self.password = password;
+            self.refresh_display();
+            self.dirty = true;
+        }
+    }
+
+    pub fn is_password(&self) -> bool {
+        self.password
+    }
+
     pub fn set_value(&mut self, value: impl Into<String>) {
         self.value = value.into();
         self.cursor = self.cursor.min(grapheme_count(&self.value));
@@ -143,12 +173,19 @@ impl<M> Input<M> {
             return format!("[{cursor_style}] [/]");
         }
 
-        let escaped = escape_markup(&self.value);
+        let masked;
+        let value = if self.password {
+            masked = "•".repeat(grapheme_count(&self.value));
+            &masked
+        } else {
+            &self.value
+        };
+        let escaped = escape_markup(value);
         if !self.focused {
             return escaped;
         }
 
-        let value_graphemes: Vec<&str> = graphemes(&self.value).collect();
+        let value_graphemes: Vec<&str> = graphemes(value).collect();
         let cursor = self.cursor.min(value_graphemes.len());
 
         if cursor >= value_graphemes.len() {
@@ -282,6 +319,11 @@ Input {
         meta
     }
 
+    fn attributes(&self) -> Vec<(&'static str, String)> {
+        let kind = if self.password { "password" } else { "text" };
+        vec![("type", kind.to_string())]
+    }
+
     fn get_state(&self) -> WidgetStates {
         let mut states = WidgetStates::empty();
         if self.focused {
@@ -333,6 +375,10 @@ Input {
         self.is_visible() && !self.is_disabled()
     }
 
+    fn tab_index(&self) -> i32 {
+        self.tab_index
+    }
+
     fn set_focus(&mut self, is_focused: bool) {
         if self.focused != is_focused {
             self.focused = is_focused;
@@ -372,6 +418,14 @@ Input {
         self.inner.handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        ctx: &crate::AppContext<M>,
+    ) -> Option<M> {
+        self.inner.on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.inner.id()
     }
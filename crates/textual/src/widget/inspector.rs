@@ -0,0 +1,687 @@
+//! DOM inspector overlay for debugging styles.
+//!
+//! Apps opt in with [`App::ENABLE_INSPECTOR`](crate::App::ENABLE_INSPECTOR).
+//! The inspector is toggled with [`App::INSPECTOR_BINDING`](crate::App::INSPECTOR_BINDING)
+//! and shows, in a panel docked to the right of the screen:
+//!
+//! - the widget hierarchy with type, id and classes,
+//! - the [`ComputedStyle`] of the widget under the mouse (or the focused
+//!   widget), and
+//! - the TCSS rules that matched it, highest priority first.
+//!
+//! The inspected widget's margin, padding and content areas are highlighted
+//! in place, like a browser's element picker.
+//!
+//! The event loop refreshes the overlay before every frame via [`inspect`],
+//! which walks the tree and re-runs selector matching for the inspected
+//! widget only. When the result changes, only the panel and the old and new
+//! highlights are redrawn.
+
+use std::collections::VecDeque;
+
+use tcss::parser::StyleSheet;
+use tcss::parser::cascade::matching_rules;
+use tcss::types::border::BorderKind;
+use tcss::types::geometry::Unit;
+use tcss::types::{RgbaColor, Scalar, Spacing, Theme};
+use tcss::{ComputedStyle, WidgetMeta, WidgetStates};
+
+use crate::canvas::{Canvas, Region, Size, TextAttributes};
+use crate::damage::widget_key;
+use crate::style_resolver::{Position, focus_path};
+use crate::widget::Widget;
+
+/// One line of the widget hierarchy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InspectorNode {
+    /// Nesting depth (0 for the root).
+    pub depth: usize,
+    /// Selector-like label, e.g. `Button#save.primary`.
+    pub label: String,
+    /// Whether this is the inspected widget.
+    pub selected: bool,
+}
+
+/// Details about the inspected widget.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectedWidget {
+    /// Selector-like label, e.g. `Button#save.primary`.
+    pub label: String,
+    /// Region the widget was last rendered into.
+    pub region: Region,
+    /// The widget's computed style.
+    pub style: ComputedStyle,
+    /// Matched rules as `(selector, specificity, declaration count,
+    /// origin)`, highest priority first. The origin is `source:line`.
+    pub rules: Vec<(String, String, usize, Option<String>)>,
+}
+
+/// Everything the inspector panel displays for one frame.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InspectorSnapshot {
+    pub nodes: Vec<InspectorNode>,
+    pub selected: Option<InspectedWidget>,
+}
+
+/// A widget visited while walking the tree, with what is needed to inspect it.
+struct Candidate {
+    node_index: usize,
+    meta: WidgetMeta,
+    ancestors: Vec<WidgetMeta>,
+    style: ComputedStyle,
+    region: Region,
+}
+
+/// Build a snapshot of the tree under `root`.
+///
+/// The inspected widget is the innermost widget whose last rendered region
+/// contains `pointer`, falling back to the focused widget.
+pub(crate) fn inspect<M>(
+    root: &mut dyn Widget<M>,
+    canvas: &Canvas,
+    stylesheet: &StyleSheet,
+    theme: &Theme,
+    pointer: Option<(i32, i32)>,
+) -> InspectorSnapshot {
+    let mut nodes = Vec::new();
+    let mut hovered: Option<Candidate> = None;
+    let mut focused: Option<Candidate> = None;
+    let mut ancestors = VecDeque::new();
+    let focus = focus_path(root);
+
+    collect(
+        root,
+        canvas,
+        pointer,
+        Position::root(focus.as_deref(), focus.as_deref()),
+        0,
+        &mut ancestors,
+        &mut nodes,
+        &mut hovered,
+        &mut focused,
+    );
+
+    let selected = hovered.or(focused).map(|candidate| {
+        nodes[candidate.node_index].selected = true;
+        let rules = matching_rules(&candidate.meta, &candidate.ancestors, stylesheet, theme)
+            .iter()
+            .rev()
+            .map(|m| {
+                (
+                    m.selector.to_string(),
+                    m.specificity.to_string(),
+                    m.rule.declarations().len(),
+                    m.rule.origin.as_ref().map(ToString::to_string),
+                )
+            })
+            .collect();
+        InspectedWidget {
+            label: label(&candidate.meta),
+            region: candidate.region,
+            style: candidate.style,
+            rules,
+        }
+    });
+
+    InspectorSnapshot { nodes, selected }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn collect<M>(
+    widget: &mut dyn Widget<M>,
+    canvas: &Canvas,
+    pointer: Option<(i32, i32)>,
+    position: Position<'_>,
+    depth: usize,
+    ancestors: &mut VecDeque<WidgetMeta>,
+    nodes: &mut Vec<InspectorNode>,
+    hovered: &mut Option<Candidate>,
+    focused: &mut Option<Candidate>,
+) {
+    if !widget.is_visible() || widget.type_name() == "Inspector" {
+        return;
+    }
+
+    let meta = position.meta(&*widget);
+    let node_index = nodes.len();
+    nodes.push(InspectorNode {
+        depth,
+        label: label(&meta),
+        selected: false,
+    });
+
+    let region = canvas.widget_region(widget_key(widget));
+    let is_hovered = matches!(
+        (region, pointer),
+        (Some(region), Some((x, y))) if region.contains_point(x, y)
+    );
+    let is_focused = meta.states.contains(WidgetStates::FOCUS);
+    if is_hovered || is_focused {
+        let candidate = Candidate {
+            node_index,
+            meta: meta.clone(),
+            ancestors: ancestors.iter().cloned().collect(),
+            style: widget.get_style(),
+            region: region.unwrap_or_default(),
+        };
+        // Later (deeper or overlaying) widgets replace earlier ones.
+        if is_hovered {
+            *hovered = Some(candidate);
+        } else {
+            *focused = Some(candidate);
+        }
+    }
+
+    ancestors.push_front(meta);
+    let siblings = position.child_metas(widget);
+    let mut index = 0;
+    widget.for_each_child(&mut |child| {
+        index += 1;
+        collect(
+            child,
+            canvas,
+            pointer,
+            position.child(&siblings, index - 1),
+            depth + 1,
+            ancestors,
+            nodes,
+            hovered,
+            focused,
+        );
+    });
+    ancestors.pop_front();
+}
+
+fn label(meta: &WidgetMeta) -> String {
+    let mut label = meta.type_name.to_string();
+    if let Some(id) = &meta.id {
+        label.push('#');
+        label.push_str(id);
+    }
+    for class in &meta.classes {
+        label.push('.');
+        label.push_str(class);
+    }
+    label
+}
+
+fn scalar(value: &Scalar) -> String {
+    let suffix = match value.unit {
+        Unit::Cells => "",
+        Unit::Percent => "%",
+        Unit::Width => "w",
+        Unit::Height => "h",
+        Unit::ViewWidth => "vw",
+        Unit::ViewHeight => "vh",
+        Unit::Fraction => "fr",
+        Unit::Auto => return "auto".to_string(),
+    };
+    format!("{}{suffix}", value.value)
+}
+
+fn spacing(value: &Spacing) -> String {
+    [value.top, value.right, value.bottom, value.left]
+        .iter()
+        .map(scalar)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn color(value: &Option<RgbaColor>) -> String {
+    match value {
+        Some(c) if c.a < 1.0 => format!("#{:02x}{:02x}{:02x} {:.0}%", c.r, c.g, c.b, c.a * 100.0),
+        Some(c) => format!("#{:02x}{:02x}{:02x}", c.r, c.g, c.b),
+        None => "-".to_string(),
+    }
+}
+
+fn lower_debug(value: &impl std::fmt::Debug) -> String {
+    format!("{value:?}").to_lowercase()
+}
+
+/// The computed properties shown in the panel, as `(name, value)` pairs.
+pub fn style_properties(style: &ComputedStyle) -> Vec<(&'static str, String)> {
+    let optional = |value: &Option<Scalar>| value.as_ref().map(scalar).unwrap_or("-".into());
+    let border = [
+        style.border.top.kind,
+        style.border.right.kind,
+        style.border.bottom.kind,
+        style.border.left.kind,
+    ]
+    .iter()
+    .map(lower_debug)
+    .collect::<Vec<_>>()
+    .join(" ");
+
+    vec![
+        ("display", lower_debug(&style.display)),
+        ("visibility", lower_debug(&style.visibility)),
+        ("layout", lower_debug(&style.layout)),
+        ("position", lower_debug(&style.position)),
+        (
+            "dock",
+            style.dock.as_ref().map(lower_debug).unwrap_or("-".into()),
+        ),
+        ("width", optional(&style.width)),
+        ("height", optional(&style.height)),
+        ("min-width", optional(&style.min_width)),
+        ("max-width", optional(&style.max_width)),
+        ("min-height", optional(&style.min_height)),
+        ("max-height", optional(&style.max_height)),
+        ("margin", spacing(&style.margin)),
+        ("padding", spacing(&style.padding)),
+        ("border", border),
+        ("box-sizing", lower_debug(&style.box_sizing)),
+        ("color", color(&style.color)),
+        ("background", color(&style.background)),
+        ("text-align", lower_debug(&style.text_align)),
+        (
+            "overflow",
+            format!(
+                "{} {}",
+                lower_debug(&style.overflow_x),
+                lower_debug(&style.overflow_y)
+            ),
+        ),
+        ("opacity", format!("{}", style.opacity)),
+    ]
+}
+
+/// Overlay listing the widget tree, computed style and matched rules.
+pub struct Inspector<M> {
+    open: bool,
+    snapshot: InspectorSnapshot,
+    style: ComputedStyle,
+    dirty: bool,
+    _marker: std::marker::PhantomData<M>,
+}
+
+impl<M> Inspector<M> {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            snapshot: InspectorSnapshot::default(),
+            style: ComputedStyle::default(),
+            dirty: true,
+            _marker: std::marker::PhantomData,
+        }
+    }
+
+    /// Returns true if the overlay is showing.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Show or hide the overlay. Returns the new state.
+    pub fn toggle(&mut self) -> bool {
+        self.open = !self.open;
+        self.dirty = true;
+        self.open
+    }
+
+    /// Replace the displayed snapshot.
+    pub fn set_snapshot(&mut self, snapshot: InspectorSnapshot) {
+        if self.snapshot != snapshot {
+            self.snapshot = snapshot;
+            self.dirty = true;
+        }
+    }
+
+    /// Replace the displayed snapshot, returning the regions of `screen`
+    /// to redraw: none if it didn't change, otherwise the panel and the
+    /// old and new highlights.
+    ///
+    /// Unlike [`set_snapshot`](Self::set_snapshot) this doesn't mark the
+    /// overlay dirty, which would redraw the whole screen.
+    pub(crate) fn refresh(&mut self, snapshot: InspectorSnapshot, screen: Region) -> Vec<Region> {
+        if self.snapshot == snapshot {
+            return Vec::new();
+        }
+        let old = std::mem::replace(&mut self.snapshot, snapshot);
+        let mut damaged = vec![panel_region(screen)];
+        damaged.extend(old.selected.as_ref().map(margin_box));
+        damaged.extend(self.snapshot.selected.as_ref().map(margin_box));
+        damaged
+    }
+
+    /// The currently displayed snapshot.
+    pub fn snapshot(&self) -> &InspectorSnapshot {
+        &self.snapshot
+    }
+
+    fn panel_lines(&self) -> Vec<(String, Option<RgbaColor>, bool)> {
+        let heading = Some(RgbaColor::rgb(255, 200, 80));
+        let muted = Some(RgbaColor::rgb(150, 150, 150));
+        let mut lines = vec![("DOM".to_string(), heading.clone(), true)];
+
+        for node in &self.snapshot.nodes {
+            let marker = if node.selected { "▶ " } else { "  " };
+            let color = if node.selected { heading.clone() } else { None };
+            lines.push((
+                format!("{marker}{}{}", "  ".repeat(node.depth), node.label),
+                color,
+                node.selected,
+            ));
+        }
+
+        let Some(selected) = &self.snapshot.selected else {
+            lines.push((String::new(), None, false));
+            lines.push(("Hover a widget to inspect it".to_string(), muted, false));
+            return lines;
+        };
+
+        let r = selected.region;
+        lines.push((String::new(), None, false));
+        lines.push((
+            format!(
+                "{}  {}x{} at ({}, {})",
+                selected.label, r.width, r.height, r.x, r.y
+            ),
+            heading.clone(),
+            true,
+        ));
+        for (name, value) in style_properties(&selected.style) {
+            lines.push((format!("  {name:<12}{value}"), None, false));
+        }
+
+        lines.push((String::new(), None, false));
+        lines.push((
+            "Matched rules (highest priority first)".to_string(),
+            heading,
+            true,
+        ));
+        if selected.rules.is_empty() {
+            lines.push(("  (none)".to_string(), muted, false));
+        }
+        for (selector, specificity, count, origin) in &selected.rules {
+            let mut line = format!("  {selector} {specificity} · {count} decl");
+            if let Some(origin) = origin {
+                line.push_str(&format!(" · {origin}"));
+            }
+            lines.push((line, None, false));
+        }
+        lines
+    }
+
+    fn highlight(&self, canvas: &mut Canvas, selected: &InspectedWidget) {
+        let style = &selected.style;
+        let r = selected.region;
+        let cells = |s: Scalar| s.value.max(0.0) as i32;
+        let edge = |kind: BorderKind| i32::from(kind != BorderKind::None);
+
+        let padding_box = shrink(
+            r,
+            edge(style.border.top.kind),
+            edge(style.border.right.kind),
+            edge(style.border.bottom.kind),
+            edge(style.border.left.kind),
+        );
+        let content_box = shrink(
+            padding_box,
+            cells(style.padding.top),
+            cells(style.padding.right),
+            cells(style.padding.bottom),
+            cells(style.padding.left),
+        );
+
+        tint(
+            canvas,
+            margin_box(selected),
+            RgbaColor::rgba(246, 178, 107, 0.5),
+        );
+        tint(canvas, padding_box, RgbaColor::rgba(147, 196, 125, 0.5));
+        tint(canvas, content_box, RgbaColor::rgba(111, 168, 220, 0.5));
+    }
+}
+
+/// The inspected widget's region grown by its margin: the highlighted area.
+fn margin_box(selected: &InspectedWidget) -> Region {
+    let margin = &selected.style.margin;
+    let cells = |s: Scalar| s.value.max(0.0) as i32;
+    let r = selected.region;
+    Region::new(
+        r.x - cells(margin.left),
+        r.y - cells(margin.top),
+        r.width + cells(margin.left) + cells(margin.right),
+        r.height + cells(margin.top) + cells(margin.bottom),
+    )
+}
+
+/// Where the panel is drawn within the overlay's `region`.
+fn panel_region(region: Region) -> Region {
+    let width = (region.width * 2 / 5).clamp(30.min(region.width), 60);
+    Region::new(
+        region.x + region.width - width,
+        region.y,
+        width,
+        region.height,
+    )
+}
+
+fn shrink(region: Region, top: i32, right: i32, bottom: i32, left: i32) -> Region {
+    Region::new(
+        region.x + left,
+        region.y + top,
+        (region.width - left - right).max(0),
+        (region.height - top - bottom).max(0),
+    )
+}
+
+/// Blend a translucent background over a region, keeping its text.
+fn tint(canvas: &mut Canvas, region: Region, color: RgbaColor) {
+    let blank = " ".repeat(region.width.max(0) as usize);
+    for y in region.y..region.y + region.height {
+        canvas.put_str(
+            region.x,
+            y,
+            &blank,
+            None,
+            Some(color.clone()),
+            TextAttributes::default(),
+        );
+    }
+}
+
+impl<M> Default for Inspector<M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<M: 'static> Widget<M> for Inspector<M> {
+    fn default_css(&self) -> &'static str {
+        r#"
+Inspector {
+    background: $panel;
+    color: $text;
+}
+"#
+    }
+
+    fn render(&self, canvas: &mut Canvas, region: Region) {
+        if !self.open {
+            return;
+        }
+
+        if let Some(selected) = &self.snapshot.selected {
+            self.highlight(canvas, selected);
+        }
+
+        let panel = panel_region(region);
+        let background = self
+            .style
+            .background
+            .clone()
+            .unwrap_or(RgbaColor::rgb(30, 30, 40));
+        let foreground = self.style.color.clone();
+
+        canvas.push_clip(panel);
+        let blank = " ".repeat(panel.width as usize);
+        let lines = self.panel_lines();
+        for row in 0..panel.height {
+            let y = panel.y + row;
+            canvas.put_str(
+                panel.x,
+                y,
+                &blank,
+                None,
+                Some(background.clone()),
+                TextAttributes::default(),
+            );
+            if let Some((text, color, bold)) = lines.get(row as usize) {
+                let attrs = TextAttributes {
+                    bold: *bold,
+                    ..TextAttributes::default()
+                };
+                canvas.put_str(
+                    panel.x + 1,
+                    y,
+                    text,
+                    color.clone().or(foreground.clone()),
+                    Some(background.clone()),
+                    attrs,
+                );
+            }
+        }
+        canvas.pop_clip();
+    }
+
+    fn desired_size(&self) -> Size {
+        Size::new(0, 0)
+    }
+
+    fn get_meta(&self) -> WidgetMeta {
+        WidgetMeta {
+            type_name: "Inspector",
+            type_names: vec!["Inspector", "Widget", "DOMNode"],
+            id: None,
+            classes: Vec::new(),
+            states: WidgetStates::empty(),
+            ..Default::default()
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Inspector"
+    }
+
+    fn set_style(&mut self, style: ComputedStyle) {
+        self.style = style;
+    }
+
+    fn get_style(&self) -> ComputedStyle {
+        self.style.clone()
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    fn mark_clean(&mut self) {
+        self.dirty = false;
+    }
+
+    fn participates_in_layout(&self) -> bool {
+        false
+    }
+
+    fn as_any(&self) -> Option<&dyn std::any::Any> {
+        Some(self)
+    }
+
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::build_stylesheet;
+    use crate::tree::WidgetTree;
+    use crate::widget::screen::Screen;
+    use crate::{Label, style_resolver::resolve_styles};
+
+    fn inspect_tree(pointer: Option<(i32, i32)>) -> InspectorSnapshot {
+        let label: Box<dyn Widget<()>> = Box::new(Label::new("Hello").with_id("greeting"));
+        let root = Box::new(Screen::new(vec![label]));
+        let mut tree = WidgetTree::new(root);
+        tree.root_mut().on_resize(Size::new(30, 5));
+
+        let stylesheet =
+            build_stylesheet(tree.root_mut(), "#greeting { color: red; padding: 0 1; }").unwrap();
+        let theme = Theme::new("test", true);
+        resolve_styles(tree.root_mut(), &stylesheet, &theme, &mut VecDeque::new());
+
+        let mut canvas = Canvas::new(30, 5);
+        tree.render(&mut canvas, Region::new(0, 0, 30, 5));
+
+        inspect(tree.root_mut(), &canvas, &stylesheet, &theme, pointer)
+    }
+
+    #[test]
+    fn lists_hierarchy_with_labels() {
+        let snapshot = inspect_tree(None);
+        let labels: Vec<(usize, &str)> = snapshot
+            .nodes
+            .iter()
+            .map(|n| (n.depth, n.label.as_str()))
+            .collect();
+
+        assert_eq!(
+            labels,
+            [(0, "Screen.-narrow.-short"), (1, "Label#greeting")]
+        );
+        assert!(snapshot.selected.is_none());
+    }
+
+    #[test]
+    fn hovered_widget_shows_matched_rules() {
+        let snapshot = inspect_tree(Some((1, 0)));
+        let selected = snapshot.selected.expect("label should be inspected");
+
+        assert_eq!(selected.label, "Label#greeting");
+        assert!(snapshot.nodes[1].selected);
+        assert_eq!(
+            selected.rules.first().map(|r| r.0.as_str()),
+            Some("#greeting"),
+            "highest priority rule should be listed first"
+        );
+        assert_eq!(selected.rules[0].3.as_deref(), Some("App.CSS:1"));
+        assert!(
+            selected.rules[1..].iter().all(|rule| rule
+                .3
+                .as_deref()
+                .is_some_and(|o| o.contains(".DEFAULT_CSS:"))),
+            "widget defaults should name their widget: {:?}",
+            selected.rules
+        );
+        assert_eq!(selected.style.color, Some(RgbaColor::rgb(255, 0, 0)));
+    }
+
+    #[test]
+    fn refresh_redraws_only_panel_and_highlights_on_change() {
+        let screen = Region::new(0, 0, 80, 24);
+        let mut inspector = Inspector::<()>::new();
+        inspector.mark_clean();
+
+        let hovered = inspect_tree(Some((1, 0)));
+        let highlight = margin_box(hovered.selected.as_ref().unwrap());
+        assert_eq!(
+            inspector.refresh(hovered.clone(), screen),
+            [panel_region(screen), highlight]
+        );
+        assert!(inspector.refresh(hovered, screen).is_empty());
+        assert!(!inspector.is_dirty());
+
+        // Moving off the widget clears its highlight
+        assert_eq!(
+            inspector.refresh(inspect_tree(None), screen),
+            [panel_region(screen), highlight]
+        );
+    }
+}
@@ -277,6 +277,10 @@ Label {
         self.inner.mark_clean()
     }
 
+    fn invalidation(&self) -> Option<crate::damage::Invalidation> {
+        self.inner.invalidation()
+    }
+
     fn on_event(&mut self, key: crate::KeyCode) -> Option<M> {
         self.inner.on_event(key)
     }
@@ -361,6 +365,14 @@ Label {
         self.inner.handle_message(envelope)
     }
 
+    fn on_message(
+        &mut self,
+        envelope: &mut crate::MessageEnvelope<M>,
+        ctx: &crate::AppContext<M>,
+    ) -> Option<M> {
+        self.inner.on_message(envelope, ctx)
+    }
+
     fn id(&self) -> Option<&str> {
         self.inner.id()
     }
@@ -402,7 +414,8 @@ Label {
     }
 
     fn add_class(&mut self, class: &str) {
-        self.inner.add_class(class);
+        // Not `Static::add_class`, which doesn't mark the widget dirty
+        crate::Widget::add_class(&mut self.inner, class);
     }
 
     fn remove_class(&mut self, class: &str) {
@@ -1,18 +1,28 @@
 //! Loading indicator widget (spinner).
 
 use std::marker::PhantomData;
+use std::time::Duration;
 
 use tcss::types::Visibility;
 use tcss::{ComputedStyle, StyleOverride, WidgetMeta, WidgetStates};
 
 use crate::canvas::{Canvas, Region};
-use crate::{KeyCode, MouseEvent, Size, Widget};
+use crate::damage::Invalidation;
+use crate::timer::{TimerHandle, TimerId};
+use crate::{AppContext, KeyCode, MouseEvent, Size, Widget};
 
 const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
 
+/// How long each spinner frame is shown.
+const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
 /// A simple loading spinner.
+///
+/// Once mounted it animates itself, pausing while hidden.
 pub struct LoadingIndicator<M> {
     frame: usize,
+    /// Advances `frame` while the indicator is mounted.
+    timer: Option<TimerHandle>,
     visible: bool,
     style: ComputedStyle,
     inline_style: StyleOverride,
@@ -26,6 +36,7 @@ impl<M> LoadingIndicator<M> {
     pub fn new() -> Self {
         Self {
             frame: 0,
+            timer: None,
             visible: true,
             style: ComputedStyle::default(),
             inline_style: StyleOverride::default(),
@@ -57,6 +68,8 @@ impl<M> LoadingIndicator<M> {
         }
     }
 
+    /// Advance the spinner by one frame. Mounted indicators call this
+    /// themselves.
     pub fn tick(&mut self) {
         self.frame = (self.frame + 1) % SPINNER_FRAMES.len();
         self.dirty = true;
@@ -104,6 +117,7 @@ LoadingIndicator {
             id: self.id.clone(),
             classes: self.classes.clone(),
             states: self.get_state(),
+            ..Default::default()
         }
     }
 
@@ -153,6 +167,29 @@ LoadingIndicator {
         self.dirty = false;
     }
 
+    fn invalidation(&self) -> Option<Invalidation> {
+        // Spinner frames are a fixed-size glyph swap.
+        self.dirty.then_some(Invalidation::Repaint)
+    }
+
+    fn on_mount(&mut self, ctx: &AppContext<M>) {
+        self.timer = ctx.start_interval(FRAME_INTERVAL);
+    }
+
+    fn on_unmount(&mut self, _ctx: &AppContext<M>) {
+        self.timer = None;
+    }
+
+    fn on_timer(&mut self, timer: TimerId, _ctx: &AppContext<M>) {
+        if self
+            .timer
+            .as_ref()
+            .is_some_and(|handle| handle.id() == timer)
+        {
+            self.tick();
+        }
+    }
+
     fn on_event(&mut self, _key: KeyCode) -> Option<M> {
         None
     }
@@ -559,6 +559,7 @@ Placeholder {
             id: self.id.clone(),
             classes: self.classes.clone(),
             states: WidgetStates::empty(),
+            ..Default::default()
         }
     }
 
@@ -215,6 +215,7 @@ Ruler.-horizontal {
             id: self.id.clone(),
             classes: self.classes.clone(),
             states: WidgetStates::empty(),
+            ..Default::default()
         }
     }
 
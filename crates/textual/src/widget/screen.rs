@@ -17,6 +17,7 @@
 use std::cell::RefCell;
 
 use crate::canvas::{Canvas, Region, Size};
+use crate::damage::Invalidation;
 use crate::layouts;
 use crate::scroll::ScrollState;
 use crate::scrollbar::ScrollBarRender;
@@ -61,6 +62,8 @@ pub struct Screen<M> {
     scrollbar_drag: Option<(bool, i32)>,
     horizontal_breakpoints: &'static [Breakpoint],
     vertical_breakpoints: &'static [Breakpoint],
+    /// Cached layout placements (reused while no layout input changes).
+    layout_cache: layouts::LayoutCache,
 }
 
 impl<M> Screen<M> {
@@ -77,6 +80,7 @@ impl<M> Screen<M> {
             scrollbar_drag: None,
             horizontal_breakpoints: DEFAULT_HORIZONTAL_BREAKPOINTS,
             vertical_breakpoints: DEFAULT_VERTICAL_BREAKPOINTS,
+            layout_cache: layouts::LayoutCache::new(),
         }
     }
 
@@ -146,7 +150,85 @@ impl<M> Screen<M> {
             .collect();
 
         // Dispatch to layout (handles layers internally when needed)
-        layouts::arrange_children_with_layers(&self.style, &children_with_styles, region, viewport)
+        self.layout_cache.arrange(
+            &self.style,
+            &children_with_styles,
+            region,
+            viewport,
+            |children| {
+                layouts::arrange_children_with_layers(&self.style, children, region, viewport)
+            },
+        )
+    }
+
+    /// Lay children out in `inner_region` (the region inside border and
+    /// padding), updating the scroll state. Returns the region left for
+    /// content once scrollbars are placed, and the unscrolled placements.
+    fn layout_content(
+        &self,
+        inner_region: Region,
+        viewport: layouts::Viewport,
+    ) -> (Region, Vec<layouts::WidgetPlacement>) {
+        let initial_placements = self.compute_child_placements(inner_region, viewport);
+        let (virtual_width, virtual_height, _) =
+            self.compute_virtual_size(&initial_placements, inner_region);
+
+        // Do not pad virtual size here; padding can fabricate overflow and force
+        // Screen-level scrollbars even when content fits (differs from Python Textual).
+
+        let show_v_scrollbar = match self.style.overflow_y {
+            Overflow::Scroll => true,
+            Overflow::Hidden => false,
+            Overflow::Auto => virtual_height > inner_region.height,
+        };
+        let show_h_scrollbar = match self.style.overflow_x {
+            Overflow::Scroll => true,
+            Overflow::Hidden => false,
+            Overflow::Auto => virtual_width > inner_region.width,
+        };
+
+        let style = &self.style.scrollbar;
+        let v_size = if show_v_scrollbar
+            || (style.gutter == ScrollbarGutter::Stable && self.style.overflow_y == Overflow::Auto)
+        {
+            style.size.vertical as i32
+        } else {
+            0
+        };
+        let h_size = if show_h_scrollbar {
+            style.size.horizontal as i32
+        } else {
+            0
+        };
+
+        let content_region = Region {
+            x: inner_region.x,
+            y: inner_region.y,
+            width: (inner_region.width - v_size).max(0),
+            height: (inner_region.height - h_size).max(0),
+        };
+
+        let (placements, recomputed) =
+            if show_v_scrollbar && content_region.width < inner_region.width {
+                let new_placements = self.compute_child_placements(content_region, viewport);
+                (new_placements, true)
+            } else {
+                (initial_placements, false)
+            };
+
+        let (final_virtual_width, final_virtual_height, _final_max_from_auto) = if recomputed {
+            self.compute_virtual_size(&placements, content_region)
+        } else {
+            (virtual_width, virtual_height, false)
+        };
+
+        {
+            let mut scroll = self.scroll.borrow_mut();
+            scroll.set_virtual_size(final_virtual_width, final_virtual_height);
+            scroll.set_viewport(content_region.width, content_region.height);
+        }
+
+        (content_region, placements)
     }
 
     fn compute_virtual_size(
@@ -504,65 +586,7 @@ Screen {
 
         // Use the canvas viewport (screen dimensions) for CSS vw/vh units
         let viewport = canvas.viewport();
-
-        let initial_placements = self.compute_child_placements(inner_region, viewport);
-        let (virtual_width, virtual_height, _) =
-            self.compute_virtual_size(&initial_placements, inner_region);
-
-        // Do not pad virtual size here; padding can fabricate overflow and force
-        // Screen-level scrollbars even when content fits (differs from Python Textual).
-
-        let show_v_scrollbar = match self.style.overflow_y {
-            Overflow::Scroll => true,
-            Overflow::Hidden => false,
-            Overflow::Auto => virtual_height > inner_region.height,
-        };
-        let show_h_scrollbar = match self.style.overflow_x {
-            Overflow::Scroll => true,
-            Overflow::Hidden => false,
-            Overflow::Auto => virtual_width > inner_region.width,
-        };
-
-        let style = &self.style.scrollbar;
-        let v_size = if show_v_scrollbar
-            || (style.gutter == ScrollbarGutter::Stable && self.style.overflow_y == Overflow::Auto)
-        {
-            style.size.vertical as i32
-        } else {
-            0
-        };
-        let h_size = if show_h_scrollbar {
-            style.size.horizontal as i32
-        } else {
-            0
-        };
-
-        let content_region = Region {
-            x: inner_region.x,
-            y: inner_region.y,
-            width: (inner_region.width - v_size).max(0),
-            height: (inner_region.height - h_size).max(0),
-        };
-
-        let (placements, recomputed) =
-            if show_v_scrollbar && content_region.width < inner_region.width {
-                let new_placements = self.compute_child_placements(content_region, viewport);
-                (new_placements, true)
-            } else {
-                (initial_placements, false)
-            };
-
-        let (final_virtual_width, final_virtual_height, _final_max_from_auto) = if recomputed {
-            self.compute_virtual_size(&placements, content_region)
-        } else {
-            (virtual_width, virtual_height, false)
-        };
-
-        {
-            let mut scroll = self.scroll.borrow_mut();
-            scroll.set_virtual_size(final_virtual_width, final_virtual_height);
-            scroll.set_viewport(content_region.width, content_region.height);
-        }
+        let (content_region, placements) = self.layout_content(inner_region, viewport);
 
         let (offset_x, offset_y) = {
             let scroll = self.scroll.borrow();
@@ -680,6 +704,7 @@ Screen {
                 .collect(),
             states: WidgetStates::empty(), // Screen typically doesn't have focus/hover itself
             id: None,
+            ..Default::default()
         }
     }
 
@@ -702,6 +727,62 @@ Screen {
         }
     }
 
+    fn insert_child(
+        &mut self,
+        index: usize,
+        child: Box<dyn Widget<M>>,
+    ) -> Result<(), Box<dyn Widget<M>>> {
+        self.children.insert(index.min(self.children.len()), child);
+        self.layout_cache.invalidate();
+        self.is_dirty = true;
+        Ok(())
+    }
+
+    fn remove_child(&mut self, index: usize) -> Option<Box<dyn Widget<M>>> {
+        if index >= self.children.len() {
+            return None;
+        }
+        let child = self.children.remove(index);
+        self.layout_cache.invalidate();
+        self.is_dirty = true;
+        Some(child)
+    }
+
+    fn child_regions(&self, region: Region, viewport: layouts::Viewport) -> Vec<(usize, Region)> {
+        let inner_region = crate::containers::inner_region_for_container(region, &self.style);
+        let (_, placements) = self.layout_content(inner_region, viewport);
+        let scroll = self.scroll.borrow();
+        placements
+            .iter()
+            .map(|p| {
+                let region = crate::containers::scrolled_region(p.region, &scroll);
+                (p.child_index, region)
+            })
+            .collect()
+    }
+
+    fn scroll_to_region(
+        &mut self,
+        region: Region,
+        viewport: layouts::Viewport,
+        target: Region,
+        _animate: bool,
+        center: bool,
+    ) -> Option<(i32, i32)> {
+        let inner_region = crate::containers::inner_region_for_container(region, &self.style);
+        let (content_region, _) = self.layout_content(inner_region, viewport);
+        let moved = crate::containers::scroll_to_show(
+            &mut self.scroll.borrow_mut(),
+            content_region,
+            target,
+            center,
+        );
+        if moved != (0, 0) {
+            self.is_dirty = true;
+        }
+        Some(moved)
+    }
+
     // Delegate state management
     fn is_dirty(&self) -> bool {
         self.is_dirty || self.children.iter().any(|c| c.is_dirty())
@@ -721,6 +802,11 @@ Screen {
         }
     }
 
+    fn invalidation(&self) -> Option<Invalidation> {
+        // `is_dirty` also reflects children; they report their own damage.
+        self.is_dirty.then_some(Invalidation::Relayout)
+    }
+
     fn set_style(&mut self, style: ComputedStyle) {
         self.style = style;
     }
@@ -867,6 +867,7 @@ Static {
             id: self.id.clone(),
             classes: self.classes.clone(),
             states,
+            ..Default::default()
         }
     }
 
@@ -1,12 +1,19 @@
+use std::time::Duration;
+
 use tcss::types::Visibility;
 use tcss::{ComputedStyle, StyleOverride, WidgetStates};
 
 use crate::canvas::TextAttributes;
-use crate::{Canvas, KeyCode, MouseEvent, MouseEventKind, Region, Size, Widget};
+use crate::damage::Invalidation;
+use crate::timer::{TimerHandle, TimerId};
+use crate::{AppContext, Canvas, KeyCode, MouseEvent, MouseEventKind, Region, Size, Widget};
 
 /// Braille spinner animation frames for loading state.
 const SPINNER_FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
 
+/// How long each spinner frame is shown.
+const SPINNER_INTERVAL: Duration = Duration::from_millis(100);
+
 /// A toggle switch widget that produces messages via a callback.
 ///
 /// Supports focus, hover, and active pseudo-class states for CSS styling.
@@ -39,6 +46,10 @@ where
     disabled: bool,
     /// Current frame of the loading spinner animation
     spinner_frame: usize,
+    /// Advances the spinner while mounted; paused unless loading
+    spinner_timer: Option<TimerHandle>,
+    /// Position in the Tab order
+    tab_index: i32,
     on_change: F,
 }
 
@@ -58,6 +69,8 @@ where
             loading: false,
             disabled: false,
             spinner_frame: 0,
+            spinner_timer: None,
+            tab_index: 0,
             on_change,
             style: ComputedStyle::default(),
             inline_style: StyleOverride::default(),
@@ -78,6 +91,12 @@ where
         self
     }
 
+    /// Set the switch's position in the Tab order (lower comes first).
+    pub fn with_tab_index(mut self, tab_index: i32) -> Self {
+        self.tab_index = tab_index;
+        self
+    }
+
     pub fn with_focus(mut self, focused: bool) -> Self {
         if self.focused != focused {
             self.focused = focused;
@@ -140,7 +159,7 @@ where
 
     /// Advance the spinner animation frame.
     ///
-    /// Call this from a timer (e.g., every 100ms) for smooth animation.
+    /// Mounted switches call this themselves while loading.
     pub fn tick_spinner(&mut self) {
         self.spinner_frame = (self.spinner_frame + 1) % SPINNER_FRAMES.len();
         if self.loading {
@@ -262,6 +281,10 @@ where
         }
     }
 
+    fn attributes(&self) -> Vec<(&'static str, String)> {
+        vec![("value", self.value.to_string())]
+    }
+
     fn get_state(&self) -> WidgetStates {
         let mut states = WidgetStates::empty();
         if self.focused {
@@ -291,11 +314,20 @@ where
         self.dirty = false;
     }
 
+    fn invalidation(&self) -> Option<Invalidation> {
+        // Toggling, hover, focus and spinner frames never change the size.
+        self.dirty.then_some(Invalidation::Repaint)
+    }
+
     fn is_focusable(&self) -> bool {
         // Can't focus if invisible, disabled, or loading
         self.visible && !self.disabled && !self.loading
     }
 
+    fn tab_index(&self) -> i32 {
+        self.tab_index
+    }
+
     fn focus_nth(&mut self, n: usize) -> bool {
         if n == 0 {
             self.set_focus(true);
@@ -409,6 +441,36 @@ where
         if self.loading != loading {
             self.loading = loading;
             self.dirty = true;
+            if let Some(timer) = &self.spinner_timer {
+                if loading {
+                    timer.resume();
+                } else {
+                    timer.pause();
+                }
+            }
+        }
+    }
+
+    fn on_mount(&mut self, ctx: &AppContext<M>) {
+        self.spinner_timer = ctx.start_interval(SPINNER_INTERVAL);
+        if !self.loading
+            && let Some(timer) = &self.spinner_timer
+        {
+            timer.pause();
+        }
+    }
+
+    fn on_unmount(&mut self, _ctx: &AppContext<M>) {
+        self.spinner_timer = None;
+    }
+
+    fn on_timer(&mut self, timer: TimerId, _ctx: &AppContext<M>) {
+        if self
+            .spinner_timer
+            .as_ref()
+            .is_some_and(|handle| handle.id() == timer)
+        {
+            self.tick_spinner();
         }
     }
 
@@ -0,0 +1,580 @@
+//! Background workers: cancellable tasks that report their progress to the
+//! app as messages.
+//!
+//! A worker is started with [`AppContext::run_worker`] (async code) or
+//! [`AppContext::run_worker_thread`] (blocking code). Every change of state
+//! is posted through the app's message channel as a [`WorkerStateChanged`],
+//! and a successful worker's result message is posted just before its
+//! `Success` state:
+//!
+//! ```ignore
+//! ctx.run_worker("fetch", "network", true, async move {
+//!     let networks = fetch_networks().await?;
+//!     Ok::<_, FetchError>(Message::NetworksLoaded(networks))
+//! });
+//! ```
+//!
+//! Workers are cancelled when:
+//! - [`WorkerHandle::cancel`] is called
+//! - another exclusive worker starts in the same group, from the same owner
+//! - the widget that started them (from a lifecycle hook) is removed
+//! - the app exits
+//!
+//! [`AppContext::run_worker`]: crate::AppContext::run_worker
+//! [`AppContext::run_worker_thread`]: crate::AppContext::run_worker_thread
+
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use tokio::sync::{Notify, mpsc};
+use tokio::task::JoinHandle;
+
+use crate::message::MessageEnvelope;
+
+/// Identifies a worker for the lifetime of the app.
+pub type WorkerId = u64;
+
+/// The lifecycle of a worker.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WorkerState {
+    /// Created but not started yet.
+    Pending,
+    /// Doing its work.
+    Running,
+    /// Finished; its result message has been posted.
+    Success,
+    /// Failed or panicked, with the error's description.
+    Error(String),
+    /// Cancelled before it finished; any result is discarded.
+    Cancelled,
+}
+
+impl WorkerState {
+    /// Whether the worker has stopped, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            WorkerState::Success | WorkerState::Error(_) | WorkerState::Cancelled
+        )
+    }
+}
+
+impl fmt::Display for WorkerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkerState::Pending => write!(f, "pending"),
+            WorkerState::Running => write!(f, "running"),
+            WorkerState::Success => write!(f, "success"),
+            WorkerState::Error(error) => write!(f, "error: {error}"),
+            WorkerState::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+/// Posted whenever a worker changes state.
+///
+/// Apps using workers convert it into their message type by implementing
+/// `From<WorkerStateChanged>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerStateChanged {
+    /// The worker's id.
+    pub id: WorkerId,
+    /// The name the worker was started with.
+    pub name: String,
+    /// The group the worker was started in.
+    pub group: String,
+    /// The state the worker entered.
+    pub state: WorkerState,
+}
+
+struct WorkerInner {
+    id: WorkerId,
+    name: String,
+    group: String,
+    /// Identity of the widget that started the worker, if any.
+    owner: Option<usize>,
+    state: Mutex<WorkerState>,
+    cancelled: AtomicBool,
+    cancel: Notify,
+}
+
+/// A handle to a running worker.
+///
+/// Dropping the handle does not cancel the worker; call
+/// [`cancel`](Self::cancel) for that.
+#[derive(Clone)]
+pub struct WorkerHandle {
+    inner: Arc<WorkerInner>,
+}
+
+impl WorkerHandle {
+    /// The worker's id.
+    pub fn id(&self) -> WorkerId {
+        self.inner.id
+    }
+
+    /// The name the worker was started with.
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// The group the worker was started in.
+    pub fn group(&self) -> &str {
+        &self.inner.group
+    }
+
+    /// The worker's current state.
+    pub fn state(&self) -> WorkerState {
+        self.lock_state().clone()
+    }
+
+    /// Whether the worker has stopped, successfully or not.
+    pub fn is_finished(&self) -> bool {
+        self.lock_state().is_finished()
+    }
+
+    /// Whether the worker was asked to stop.
+    ///
+    /// Thread workers can't be interrupted, so long-running ones should
+    /// check this and return early.
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::Acquire)
+    }
+
+    /// Ask the worker to stop.
+    ///
+    /// Async workers are dropped at their next `.await`; thread workers run
+    /// on until they return, but their result is discarded. The worker then
+    /// posts its `Cancelled` state. Does nothing if it already finished.
+    pub fn cancel(&self) {
+        if !self.is_finished() && !self.inner.cancelled.swap(true, Ordering::AcqRel) {
+            self.inner.cancel.notify_one();
+        }
+    }
+
+    fn lock_state(&self) -> std::sync::MutexGuard<'_, WorkerState> {
+        self.inner
+            .state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Resolves once the worker is cancelled.
+    async fn cancelled(&self) {
+        while !self.is_cancelled() {
+            self.inner.cancel.notified().await;
+        }
+    }
+}
+
+impl fmt::Debug for WorkerHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorkerHandle")
+            .field("id", &self.id())
+            .field("name", &self.name())
+            .field("group", &self.group())
+            .field("state", &self.state())
+            .finish()
+    }
+}
+
+#[derive(Default)]
+struct WorkerList {
+    next_id: WorkerId,
+    workers: Vec<WorkerHandle>,
+}
+
+/// The workers started by an app.
+///
+/// Shared by every [`AppContext`](crate::AppContext) the app hands out.
+#[derive(Clone, Default)]
+pub struct WorkerManager {
+    list: Arc<Mutex<WorkerList>>,
+}
+
+impl WorkerManager {
+    /// Workers that haven't finished, oldest first.
+    pub fn active(&self) -> Vec<WorkerHandle> {
+        let mut list = self.lock();
+        list.workers.retain(|worker| !worker.is_finished());
+        list.workers.clone()
+    }
+
+    /// Cancel the unfinished workers in `group`, returning how many there
+    /// were.
+    pub fn cancel_group(&self, group: &str) -> usize {
+        self.cancel_where(|worker| worker.group() == group)
+    }
+
+    /// Cancel every unfinished worker.
+    pub fn cancel_all(&self) -> usize {
+        self.cancel_where(|_| true)
+    }
+
+    /// Cancel the workers started by the widget with identity `owner`.
+    pub(crate) fn cancel_owned_by(&self, owner: usize) -> usize {
+        self.cancel_where(|worker| worker.inner.owner == Some(owner))
+    }
+
+    fn cancel_where(&self, mut predicate: impl FnMut(&WorkerHandle) -> bool) -> usize {
+        let cancelled: Vec<_> = self
+            .active()
+            .into_iter()
+            .filter(|worker| predicate(worker))
+            .collect();
+        for worker in &cancelled {
+            worker.cancel();
+        }
+        cancelled.len()
+    }
+
+    /// Register a new worker, first cancelling the owner's workers in the
+    /// same group if it is exclusive.
+    fn register(
+        &self,
+        name: &str,
+        group: &str,
+        exclusive: bool,
+        owner: Option<usize>,
+    ) -> WorkerHandle {
+        if exclusive {
+            self.cancel_where(|worker| worker.group() == group && worker.inner.owner == owner);
+        }
+        let mut list = self.lock();
+        list.workers.retain(|worker| !worker.is_finished());
+        list.next_id += 1;
+        let worker = WorkerHandle {
+            inner: Arc::new(WorkerInner {
+                id: list.next_id,
+                name: name.to_string(),
+                group: group.to_string(),
+                owner,
+                state: Mutex::new(WorkerState::Pending),
+                cancelled: AtomicBool::new(false),
+                cancel: Notify::new(),
+            }),
+        };
+        list.workers.push(worker.clone());
+        worker
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, WorkerList> {
+        self.list.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Where a worker posts its messages, with the sender info of the context
+/// that started it.
+pub(crate) struct WorkerSender<M> {
+    pub(crate) sender: mpsc::UnboundedSender<MessageEnvelope<M>>,
+    pub(crate) sender_id: Option<String>,
+    pub(crate) sender_type: String,
+}
+
+impl<M> WorkerSender<M> {
+    fn post(&self, message: M) {
+        let envelope = MessageEnvelope::new(message, self.sender_id.as_deref(), &self.sender_type);
+        let _ = self.sender.send(envelope);
+    }
+}
+
+impl<M: From<WorkerStateChanged>> WorkerSender<M> {
+    /// Move `worker` to `state` and post the change, unless it already
+    /// finished.
+    fn set_state(&self, worker: &WorkerHandle, state: WorkerState) {
+        {
+            let mut current = worker.lock_state();
+            if current.is_finished() {
+                return;
+            }
+            *current = state.clone();
+        }
+        self.post(M::from(WorkerStateChanged {
+            id: worker.id(),
+            name: worker.name().to_string(),
+            group: worker.group().to_string(),
+            state,
+        }));
+    }
+}
+
+/// Register a worker and spawn the task driving it.
+///
+/// `start` is called once the worker is running and returns the task doing
+/// the work; a result of `Err` (or a panic) ends the worker in the `Error`
+/// state.
+pub(crate) fn spawn_worker<M, S>(
+    manager: &WorkerManager,
+    sender: WorkerSender<M>,
+    name: &str,
+    group: &str,
+    exclusive: bool,
+    owner: Option<usize>,
+    start: S,
+) -> WorkerHandle
+where
+    M: From<WorkerStateChanged> + Send + 'static,
+    S: FnOnce(WorkerHandle) -> JoinHandle<Result<M, String>> + Send + 'static,
+{
+    let worker = manager.register(name, group, exclusive, owner);
+    sender.set_state(&worker, WorkerState::Pending);
+
+    let handle = worker.clone();
+    tokio::spawn(async move {
+        if worker.is_cancelled() {
+            sender.set_state(&worker, WorkerState::Cancelled);
+            return;
+        }
+        sender.set_state(&worker, WorkerState::Running);
+        let task = start(worker.clone());
+        let abort = task.abort_handle();
+        let result = tokio::select! {
+            _ = worker.cancelled() => {
+                abort.abort();
+                None
+            }
+            joined = task => Some(joined.unwrap_or_else(|e| Err(e.to_string()))),
+        };
+        match result {
+            // A result arriving after cancellation is discarded
+            Some(Ok(message)) if !worker.is_cancelled() => {
+                sender.post(message);
+                sender.set_state(&worker, WorkerState::Success);
+            }
+            Some(Err(error)) if !worker.is_cancelled() => {
+                log::debug!("WORKER: {} failed: {}", worker.name(), error);
+                sender.set_state(&worker, WorkerState::Error(error));
+            }
+            _ => sender.set_state(&worker, WorkerState::Cancelled),
+        }
+    });
+    handle
+}
+
+/// Convert an async worker's future into the task `spawn_worker` drives.
+pub(crate) fn spawn_future<M, E, F>(work: F) -> JoinHandle<Result<M, String>>
+where
+    M: Send + 'static,
+    E: fmt::Display,
+    F: Future<Output = Result<M, E>> + Send + 'static,
+{
+    tokio::spawn(async move { work.await.map_err(|e| e.to_string()) })
+}
+
+/// Run a thread worker's closure on tokio's blocking pool.
+pub(crate) fn spawn_thread<M, E, F>(worker: WorkerHandle, work: F) -> JoinHandle<Result<M, String>>
+where
+    M: Send + 'static,
+    E: fmt::Display,
+    F: FnOnce(&WorkerHandle) -> Result<M, E> + Send + 'static,
+{
+    tokio::task::spawn_blocking(move || work(&worker).map_err(|e| e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::{AppContext, Canvas, Container, MountPosition, Region, Size, Widget, WidgetTree};
+
+    #[derive(Debug, PartialEq)]
+    enum Msg {
+        Worker(String, WorkerState),
+        Done(u32),
+    }
+
+    impl From<WorkerStateChanged> for Msg {
+        fn from(changed: WorkerStateChanged) -> Self {
+            Msg::Worker(changed.name, changed.state)
+        }
+    }
+
+    fn context() -> (
+        AppContext<Msg>,
+        mpsc::UnboundedReceiver<MessageEnvelope<Msg>>,
+    ) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (AppContext::new(tx), rx)
+    }
+
+    /// Receive messages until `name` finishes.
+    async fn run_to_end(
+        rx: &mut mpsc::UnboundedReceiver<MessageEnvelope<Msg>>,
+        name: &str,
+    ) -> Vec<Msg> {
+        let mut messages = Vec::new();
+        loop {
+            let message = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+                .await
+                .expect("worker should finish")
+                .unwrap()
+                .message;
+            let finished =
+                matches!(&message, Msg::Worker(n, state) if n == name && state.is_finished());
+            messages.push(message);
+            if finished {
+                return messages;
+            }
+        }
+    }
+
+    fn state(name: &str, state: WorkerState) -> Msg {
+        Msg::Worker(name.to_string(), state)
+    }
+
+    #[tokio::test]
+    async fn test_worker_posts_states_and_result() {
+        let (ctx, mut rx) = context();
+        let ctx = ctx.with_sender_info(Some("loader"), "Loader");
+        let worker = ctx.run_worker("load", "io", false, async { Ok::<_, String>(Msg::Done(7)) });
+
+        let first = rx.recv().await.unwrap();
+        assert_eq!(first.sender_id.as_deref(), Some("loader"));
+        assert_eq!(first.message, state("load", WorkerState::Pending));
+        assert_eq!(
+            run_to_end(&mut rx, "load").await,
+            [
+                state("load", WorkerState::Running),
+                Msg::Done(7),
+                state("load", WorkerState::Success),
+            ]
+        );
+        assert_eq!(worker.state(), WorkerState::Success);
+        assert!(ctx.workers().active().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_worker_errors_and_panics_are_reported() {
+        let (ctx, mut rx) = context();
+        ctx.run_worker("fail", "io", false, async { Err::<Msg, _>("timed out") });
+        assert_eq!(
+            run_to_end(&mut rx, "fail").await.pop(),
+            Some(state("fail", WorkerState::Error("timed out".into())))
+        );
+
+        ctx.run_worker_thread("panic", "io", false, |_| -> Result<Msg, String> {
+            panic!("bad input")
+        });
+        let last = run_to_end(&mut rx, "panic").await.pop();
+        assert!(
+            matches!(&last, Some(Msg::Worker(_, WorkerState::Error(e))) if e.contains("panic")),
+            "{last:?}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_exclusive_worker_cancels_its_group() {
+        let (ctx, mut rx) = context();
+        let old = ctx.run_worker(
+            "old",
+            "search",
+            false,
+            std::future::pending::<Result<Msg, String>>(),
+        );
+        let other = ctx.run_worker(
+            "other",
+            "io",
+            false,
+            std::future::pending::<Result<Msg, String>>(),
+        );
+        ctx.run_worker("new", "search", true, async {
+            Ok::<_, String>(Msg::Done(1))
+        });
+
+        assert_eq!(
+            run_to_end(&mut rx, "old").await.pop(),
+            Some(state("old", WorkerState::Cancelled))
+        );
+        assert!(old.is_cancelled());
+        assert!(!other.is_cancelled());
+        run_to_end(&mut rx, "new").await;
+
+        assert_eq!(ctx.workers().cancel_group("io"), 1);
+        assert_eq!(
+            run_to_end(&mut rx, "other").await.pop(),
+            Some(state("other", WorkerState::Cancelled))
+        );
+        assert_eq!(other.state(), WorkerState::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_thread_worker_discards_its_result() {
+        let (ctx, mut rx) = context();
+        let worker = ctx.run_worker_thread("count", "cpu", false, |worker| {
+            while !worker.is_cancelled() {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            Ok::<_, String>(Msg::Done(1))
+        });
+        assert_eq!(
+            rx.recv().await.unwrap().message,
+            state("count", WorkerState::Pending)
+        );
+        assert_eq!(
+            rx.recv().await.unwrap().message,
+            state("count", WorkerState::Running)
+        );
+
+        assert_eq!(ctx.workers().cancel_all(), 1);
+        assert_eq!(
+            run_to_end(&mut rx, "count").await,
+            [state("count", WorkerState::Cancelled)]
+        );
+        assert!(worker.is_finished());
+    }
+
+    /// Starts a worker that never finishes when mounted.
+    struct Poller {
+        worker: Option<WorkerHandle>,
+    }
+
+    impl Widget<Msg> for Poller {
+        fn render(&self, _canvas: &mut Canvas, _region: Region) {}
+
+        fn desired_size(&self) -> Size {
+            Size::new(1, 1)
+        }
+
+        fn id(&self) -> Option<&str> {
+            Some("poller")
+        }
+
+        fn on_mount(&mut self, ctx: &AppContext<Msg>) {
+            let work = std::future::pending::<Result<Msg, String>>();
+            self.worker = Some(ctx.run_worker("poll", "poll", false, work));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_removing_a_widget_cancels_its_workers() {
+        let (ctx, mut rx) = context();
+        let app_worker = ctx.run_worker(
+            "app",
+            "poll",
+            false,
+            std::future::pending::<Result<Msg, String>>(),
+        );
+        let mut tree = WidgetTree::new(Box::new(Container::new(vec![]).with_id("root")));
+        tree.attach(ctx.clone());
+        tree.mount(
+            "#root",
+            Box::new(Poller { worker: None }),
+            MountPosition::Last,
+        )
+        .unwrap();
+        assert_eq!(ctx.workers().active().len(), 2);
+
+        assert_eq!(tree.remove("#poller"), 1);
+        assert_eq!(
+            run_to_end(&mut rx, "poll").await.pop(),
+            Some(state("poll", WorkerState::Cancelled))
+        );
+        // Workers the app started live until it exits
+        assert!(!app_worker.is_cancelled());
+        tree.detach();
+        assert!(!app_worker.is_cancelled());
+    }
+}
@@ -38,6 +38,7 @@ Screen {
         id: None,
         classes: vec![],
         states: WidgetStates::empty(),
+        ..Default::default()
     };
     let screen_style = compute_style(&screen_meta, &[], &stylesheet, &theme);
 
@@ -69,6 +70,7 @@ Static {
         id: None,
         classes: vec![],
         states: WidgetStates::empty(),
+        ..Default::default()
     };
     let static_style = compute_style(&static_meta, &[], &stylesheet, &theme);
 
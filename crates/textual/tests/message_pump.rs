@@ -5,11 +5,16 @@
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
+use tcss::types::Theme;
 use textual::context::AppContext;
+use textual::layouts::Viewport;
 use textual::message::MessageEnvelope;
+use textual::mouse::PointerTracker;
 use textual::tree::WidgetTree;
 use textual::widget::Widget;
-use textual::{Canvas, Region, Size};
+use textual::{
+    Canvas, KeyModifiers, MouseButton, MouseEvent, MouseEventKind, PointerEvent, Region, Size,
+};
 use tokio::sync::mpsc;
 
 // =============================================================================
@@ -27,6 +32,7 @@ struct TrackingWidget {
 #[derive(Debug, Clone, PartialEq)]
 enum TestMessage {
     Ping,
+    Pong,
 }
 
 impl TrackingWidget {
@@ -80,6 +86,23 @@ impl Widget<TestMessage> for TrackingWidget {
         }
     }
 
+    fn child_regions(&self, region: Region, _viewport: Viewport) -> Vec<(usize, Region)> {
+        // Children are stacked one row each
+        (0..self.children.len())
+            .map(|i| {
+                (
+                    i,
+                    Region::new(region.x, region.y + i as i32, region.width, 1),
+                )
+            })
+            .collect()
+    }
+
+    fn on_pointer(&mut self, event: &PointerEvent) -> Option<TestMessage> {
+        // Only double clicks produce a message
+        event.is_click(2).then_some(TestMessage::Ping)
+    }
+
     fn handle_message(
         &mut self,
         _envelope: &mut MessageEnvelope<TestMessage>,
@@ -229,6 +252,189 @@ fn test_bubbling_stops_on_stop() {
     );
 }
 
+// =============================================================================
+// Unit Tests: Mouse Bubbling Along the Hit Path
+// =============================================================================
+
+/// Root -> [Header, Container -> [Leaf]], with Container on row 1.
+fn mouse_tree(call_log: &Arc<Mutex<Vec<&'static str>>>) -> WidgetTree<TestMessage> {
+    let root = TrackingWidget::new("Root", call_log.clone())
+        .with_child(TrackingWidget::new("Header", call_log.clone()))
+        .with_child(
+            TrackingWidget::new("Container", call_log.clone())
+                .with_child(TrackingWidget::new("Leaf", call_log.clone())),
+        )
+        .boxed();
+    let mut tree = WidgetTree::new(root);
+    tree.resize(Size::new(10, 5));
+    tree
+}
+
+#[test]
+fn test_hit_path_follows_child_regions() {
+    let call_log = Arc::new(Mutex::new(Vec::new()));
+    let mut tree = mouse_tree(&call_log);
+
+    assert_eq!(tree.hit_path(3, 0), vec![0]);
+    assert_eq!(tree.hit_path(3, 1), vec![1, 0]);
+    assert_eq!(tree.hit_path(3, 4), Vec::<usize>::new());
+}
+
+#[test]
+fn test_mouse_message_bubbles_from_sender() {
+    let call_log = Arc::new(Mutex::new(Vec::new()));
+    let mut tree = mouse_tree(&call_log);
+    let hit_path = tree.hit_path(3, 1);
+
+    let envelope = MessageEnvelope::new(TestMessage::Ping, None, "Leaf");
+    tree.bubble_mouse_message(envelope, &hit_path);
+    assert_eq!(*call_log.lock().unwrap(), vec!["Container", "Root"]);
+    call_log.lock().unwrap().clear();
+
+    // A container that produced the message itself (e.g. from its
+    // scrollbar) is skipped along with everything below it
+    let envelope = MessageEnvelope::new(TestMessage::Ping, None, "Container");
+    tree.bubble_mouse_message(envelope, &hit_path);
+    assert_eq!(*call_log.lock().unwrap(), vec!["Root"]);
+    call_log.lock().unwrap().clear();
+
+    // Senders off the hit path don't bubble at all
+    let envelope = MessageEnvelope::new(TestMessage::Ping, None, "Header");
+    tree.bubble_mouse_message(envelope, &hit_path);
+    assert!(call_log.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_pointer_messages_bubble_from_target() {
+    let call_log = Arc::new(Mutex::new(Vec::new()));
+    let mut tree = mouse_tree(&call_log);
+    let mut tracker = PointerTracker::default();
+    let now = std::time::Instant::now();
+    let mut click = |tree: &mut WidgetTree<TestMessage>| {
+        let mut envelopes = Vec::new();
+        for kind in [
+            MouseEventKind::Down(MouseButton::Left),
+            MouseEventKind::Up(MouseButton::Left),
+        ] {
+            let event = MouseEvent {
+                kind,
+                column: 3,
+                row: 1,
+                modifiers: KeyModifiers::NONE,
+            };
+            envelopes.extend(tree.dispatch_pointer(&mut tracker, event, now));
+        }
+        envelopes
+    };
+
+    assert!(click(&mut tree).is_empty());
+    assert!(call_log.lock().unwrap().is_empty());
+
+    let envelopes = click(&mut tree);
+    assert_eq!(envelopes.len(), 1);
+    assert_eq!(envelopes[0].sender_type, "Leaf");
+    assert_eq!(*call_log.lock().unwrap(), vec!["Container", "Root"]);
+}
+
+// =============================================================================
+// Unit Tests: Messages Posted to Widgets
+// =============================================================================
+
+fn theme() -> Theme {
+    Theme::new("test", true)
+}
+
+#[test]
+fn test_targeted_message_reaches_target_then_bubbles() {
+    let call_log = Arc::new(Mutex::new(Vec::new()));
+    let mut tree = mouse_tree(&call_log);
+
+    let envelope = MessageEnvelope::new(TestMessage::Ping, None, "Test").with_target("Leaf");
+    let delivered = tree.deliver_message(envelope, &theme());
+    assert_eq!(*call_log.lock().unwrap(), vec!["Leaf", "Container", "Root"]);
+    assert_eq!(delivered.target.as_deref(), Some("Leaf"));
+    call_log.lock().unwrap().clear();
+
+    // Nothing matches, so only the app will see it
+    let envelope = MessageEnvelope::new(TestMessage::Ping, None, "Test").with_target("#nope");
+    tree.deliver_message(envelope, &theme());
+    assert!(call_log.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_message_targets_use_full_selectors() {
+    let call_log = Arc::new(Mutex::new(Vec::new()));
+    let mut tree = mouse_tree(&call_log);
+    let mut deliver = |target: &str| {
+        let envelope = MessageEnvelope::new(TestMessage::Ping, None, "Test").with_target(target);
+        tree.deliver_message(envelope, &theme());
+        std::mem::take(&mut *call_log.lock().unwrap())
+    };
+
+    assert_eq!(
+        deliver("Root > Container > Leaf"),
+        ["Leaf", "Container", "Root"]
+    );
+    assert_eq!(deliver("Header + Container"), ["Container", "Root"]);
+    assert!(deliver("Root > Leaf").is_empty());
+    assert!(
+        deliver("Leaf >").is_empty(),
+        "invalid selectors match nothing"
+    );
+}
+
+/// Answers `Ping` by posting `Pong` to the root, handling it fully.
+struct Relay;
+
+impl Widget<TestMessage> for Relay {
+    fn render(&self, _canvas: &mut Canvas, _region: Region) {}
+
+    fn desired_size(&self) -> Size {
+        Size::new(1, 1)
+    }
+
+    fn on_message(
+        &mut self,
+        envelope: &mut MessageEnvelope<TestMessage>,
+        ctx: &AppContext<TestMessage>,
+    ) -> Option<TestMessage> {
+        if envelope.message == TestMessage::Ping {
+            ctx.post_to("Root", TestMessage::Pong);
+            envelope.prevent_default();
+            envelope.stop();
+        }
+        None
+    }
+
+    fn type_name(&self) -> &'static str {
+        "Relay"
+    }
+}
+
+#[tokio::test]
+async fn test_message_handlers_post_follow_ups() {
+    let call_log = Arc::new(Mutex::new(Vec::new()));
+    let mut root = TrackingWidget::new("Root", call_log.clone());
+    root.children.push(Box::new(Relay));
+    let mut tree = WidgetTree::new(root.boxed());
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    tree.attach(AppContext::new(tx.clone()));
+
+    AppContext::new(tx).post_to("Relay", TestMessage::Ping);
+    let ping = rx.recv().await.expect("ping should be queued");
+    let ping = tree.deliver_message(ping, &theme());
+    assert!(ping.is_default_prevented(), "the app should skip the ping");
+    assert!(call_log.lock().unwrap().is_empty(), "the relay stopped it");
+
+    let pong = rx.try_recv().expect("relay should post a follow-up");
+    assert_eq!(pong.message, TestMessage::Pong);
+    assert_eq!(pong.sender_type, "Relay");
+    assert_eq!(pong.target.as_deref(), Some("Root"));
+    let pong = tree.deliver_message(pong, &theme());
+    assert!(!pong.is_default_prevented());
+    assert_eq!(*call_log.lock().unwrap(), vec!["Root"]);
+}
+
 // =============================================================================
 // Unit Tests: FocusPath Stability
 // =============================================================================
@@ -66,6 +66,7 @@ Vertical {
         id: meta.id,
         classes: meta.classes,
         states: WidgetStates::empty(),
+        ..Default::default()
     };
 
     let style = compute_style(&tcss_meta, &[], &stylesheet, &theme);
@@ -2,27 +2,26 @@
 //!
 //! Demonstrates the reactive attribute system:
 //! - Loading state with animated spinner
-//! - Simulated async API fetch
+//! - Simulated API fetches run as background workers
 //! - Visibility toggling
 //! - Disabled state
 //!
 //! The workflow:
 //! 1. App starts with switches in loading state (showing spinner)
-//! 2. Spinner animation runs at 100ms intervals
-//! 3. After 2s, simulated API response sets actual values
+//! 2. The switches animate their own spinners while loading
+//! 3. An async worker "fetches" wifi status (2s) and a thread worker
+//!    "fetches" bluetooth status (3s); each posts its result as a message
 //! 4. Third switch demonstrates disabled state
 
 use std::time::Duration;
 
 use textual::{
-    App, AppContext, Center, IntervalHandle, KeyCode, MessageEnvelope, Middle, Result, Switch,
-    Vertical, Widget, log, ui,
+    App, AppContext, Center, KeyCode, MessageEnvelope, Middle, Result, Switch, Vertical, Widget,
+    WorkerState, WorkerStateChanged, log, ui,
 };
 
 #[derive(Debug, Clone)]
 enum Message {
-    /// Animate loading spinners (every 100ms)
-    SpinnerTick,
     /// Simulated API response with wifi status
     WifiLoaded(bool),
     /// Simulated API response with bluetooth status
@@ -34,6 +33,14 @@ enum Message {
     /// User toggled the disabled switch (won't fire - it's disabled!)
     #[allow(dead_code)]
     DisabledToggled(bool),
+    /// A fetch worker changed state
+    Worker(WorkerStateChanged),
+}
+
+impl From<WorkerStateChanged> for Message {
+    fn from(changed: WorkerStateChanged) -> Self {
+        Message::Worker(changed)
+    }
 }
 
 struct ApiApp {
@@ -44,11 +51,7 @@ struct ApiApp {
     // Actual values (set after "API" returns)
     wifi_enabled: bool,
     bluetooth_enabled: bool,
-    // Timer handles
-    spinner_handle: Option<IntervalHandle>,
     ctx: Option<AppContext<Message>>,
-    // Spinner frame counter (shared across all loading widgets)
-    spinner_frame: usize,
     // Focus navigation
     focus_idx: usize,
 }
@@ -61,9 +64,7 @@ impl ApiApp {
             bluetooth_loading: true,
             wifi_enabled: false,
             bluetooth_enabled: false,
-            spinner_handle: None,
             ctx: None,
-            spinner_frame: 0,
             focus_idx: 0,
         }
     }
@@ -81,18 +82,25 @@ impl App for ApiApp {
     fn on_mount(&mut self, ctx: &mut textual::MountContext<Message>) {
         log::info!("App mounted - starting API fetch simulation...");
 
-        // Store context for timer management
         self.ctx = Some(ctx.app_context().clone());
 
-        // Start spinner animation (100ms = smooth animation)
-        let handle = ctx.set_interval(Duration::from_millis(100), || Message::SpinnerTick);
-        self.spinner_handle = Some(handle);
-
-        // Simulate WiFi API call (responds after 2 seconds)
-        ctx.set_timer(Duration::from_secs(2), Message::WifiLoaded(true));
-
-        // Simulate Bluetooth API call (responds after 3 seconds)
-        ctx.set_timer(Duration::from_secs(3), Message::BluetoothLoaded(false));
+        // Simulate WiFi API call (responds after 2 seconds). Workers are
+        // cancelled automatically when the app exits.
+        ctx.run_worker("wifi", "api", false, async {
+            tokio::time::sleep(Duration::from_secs(2)).await;
+            Ok::<_, String>(Message::WifiLoaded(true))
+        });
+
+        // Simulate a blocking Bluetooth driver call (responds after 3 seconds)
+        ctx.run_worker_thread("bluetooth", "api", false, |worker| {
+            for _ in 0..30 {
+                if worker.is_cancelled() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(100));
+            }
+            Ok::<_, String>(Message::BluetoothLoaded(false))
+        });
 
         log::info!("Waiting for API responses...");
     }
@@ -135,12 +143,6 @@ impl App for ApiApp {
         _ctx: &mut textual::EventContext<Self::Message>,
     ) {
         match envelope.message {
-            Message::SpinnerTick => {
-                // Advance the global spinner frame
-                self.spinner_frame = (self.spinner_frame + 1) % 10;
-                // Note: In a real app, you'd call tick_spinner() on actual widget refs.
-                // For now, the compose() recreates widgets each frame anyway.
-            }
             Message::WifiLoaded(status) => {
                 log::info!("WiFi API returned: {}", if status { "ON" } else { "OFF" });
                 self.wifi_loading = false;
@@ -153,13 +155,6 @@ impl App for ApiApp {
                 );
                 self.bluetooth_loading = false;
                 self.bluetooth_enabled = status;
-
-                // Stop spinner animation when all loading is done
-                if !self.wifi_loading && !self.bluetooth_loading {
-                    if self.spinner_handle.take().is_some() {
-                        log::info!("All data loaded - stopping spinner animation");
-                    }
-                }
             }
             Message::WifiToggled(enabled) => {
                 log::info!(
@@ -181,6 +176,15 @@ impl App for ApiApp {
                 // This should never fire because the switch is disabled
                 log::warn!("Disabled switch was somehow toggled! This shouldn't happen.");
             }
+            Message::Worker(changed) => {
+                log::info!("Worker {:?} is {}", changed.name, changed.state);
+                if let WorkerState::Error(_) = changed.state {
+                    match changed.name.as_str() {
+                        "wifi" => self.wifi_loading = false,
+                        _ => self.bluetooth_loading = false,
+                    }
+                }
+            }
         }
     }
 
@@ -191,10 +195,10 @@ impl App for ApiApp {
                     Vertical {
                         // WiFi switch - starts loading, then shows actual state
                         Switch(self.wifi_enabled, Message::WifiToggled,
-                            id: "wifi", loading: self.wifi_loading, spinner_frame: self.spinner_frame)
+                            id: "wifi", loading: self.wifi_loading)
                         // Bluetooth switch - starts loading, then shows actual state
                         Switch(self.bluetooth_enabled, Message::BluetoothToggled,
-                            id: "bluetooth", loading: self.bluetooth_loading, spinner_frame: self.spinner_frame)
+                            id: "bluetooth", loading: self.bluetooth_loading)
                         // Disabled switch - always disabled, shows how disabled state works
                         Switch(false, Message::DisabledToggled, id: "disabled-demo", disabled: true)
                     }